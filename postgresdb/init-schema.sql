@@ -1,7 +1,14 @@
 CREATE TABLE users (
     user_id SERIAL PRIMARY KEY,
     username VARCHAR(255) UNIQUE NOT NULL,
-    password VARCHAR(255) NOT NULL
+    password VARCHAR(255) NOT NULL,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'UTC',
+    -- Sequence number of the last entry of the user in sync_changes.
+    sync_seq BIGINT NOT NULL DEFAULT 0,
+    -- Secret of the calendar feed of the user, NULL if the feed is not enabled.
+    calendar_token CHAR(40) UNIQUE,
+    -- Local part of the inbound email address of the board of the user, set when first requested.
+    inbound_token CHAR(32) UNIQUE
 );
 
 CREATE TABLE sessions (
@@ -14,6 +21,11 @@ CREATE TABLE task_categories (
     category_id VARCHAR(64) PRIMARY KEY,
     user_id INT NOT NULL,
     label VARCHAR(64) NOT NULL,
+    version BIGINT NOT NULL DEFAULT 1,
+    archived_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ,
+    -- Categories are listed in the order they have been created.
+    created_seq BIGSERIAL,
     FOREIGN KEY (user_id) REFERENCES users (user_id)
 );
 
@@ -23,6 +35,195 @@ CREATE TABLE tasks (
     category_id VARCHAR(64) NOT NULL,
     label TEXT NOT NULL,
     description TEXT NOT NULL,
+    start_at TIMESTAMPTZ,
+    due_at TIMESTAMPTZ,
+    priority SMALLINT NOT NULL DEFAULT 1,
+    version BIGINT NOT NULL DEFAULT 1,
+    archived_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ,
+    -- Tasks are listed in the order they have been created.
+    created_seq BIGSERIAL,
+    search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', label), 'A') || setweight(to_tsvector('simple', description), 'B')
+    ) STORED,
     FOREIGN KEY (user_id) REFERENCES users (user_id),
     FOREIGN KEY (category_id) REFERENCES task_categories (category_id)
-);
\ No newline at end of file
+);
+
+CREATE INDEX tasks_due_at_idx ON tasks (user_id, due_at) WHERE due_at IS NOT NULL;
+CREATE INDEX tasks_category_id_idx ON tasks (user_id, category_id, created_seq, task_id) WHERE deleted_at IS NULL;
+CREATE INDEX tasks_deleted_at_idx ON tasks (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX tasks_search_vector_idx ON tasks USING GIN (search_vector);
+
+CREATE TABLE labels (
+    label_id VARCHAR(64) PRIMARY KEY,
+    user_id INT NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    color CHAR(7) NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (user_id)
+);
+
+CREATE TABLE task_labels (
+    task_id VARCHAR(64) NOT NULL,
+    label_id VARCHAR(64) NOT NULL,
+    PRIMARY KEY (task_id, label_id),
+    FOREIGN KEY (task_id) REFERENCES tasks (task_id) ON DELETE CASCADE,
+    FOREIGN KEY (label_id) REFERENCES labels (label_id) ON DELETE CASCADE
+);
+
+CREATE TABLE checklist_items (
+    item_id VARCHAR(64) PRIMARY KEY,
+    task_id VARCHAR(64) NOT NULL,
+    text TEXT NOT NULL,
+    done BOOLEAN NOT NULL DEFAULT FALSE,
+    position INT NOT NULL,
+    FOREIGN KEY (task_id) REFERENCES tasks (task_id) ON DELETE CASCADE
+);
+
+CREATE INDEX checklist_items_task_id_idx ON checklist_items (task_id, position);
+
+CREATE TABLE comments (
+    comment_id VARCHAR(64) PRIMARY KEY,
+    task_id VARCHAR(64) NOT NULL,
+    author_id INT NOT NULL,
+    text TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    edited_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ,
+    search_vector TSVECTOR GENERATED ALWAYS AS (to_tsvector('simple', text)) STORED,
+    FOREIGN KEY (task_id) REFERENCES tasks (task_id) ON DELETE CASCADE,
+    FOREIGN KEY (author_id) REFERENCES users (user_id)
+);
+
+CREATE INDEX comments_task_id_idx ON comments (task_id, created_at);
+CREATE INDEX comments_search_vector_idx ON comments USING GIN (search_vector);
+
+CREATE TABLE comment_revisions (
+    revision_id BIGSERIAL PRIMARY KEY,
+    comment_id VARCHAR(64) NOT NULL,
+    text TEXT NOT NULL,
+    written_at TIMESTAMPTZ NOT NULL,
+    FOREIGN KEY (comment_id) REFERENCES comments (comment_id) ON DELETE CASCADE
+);
+
+CREATE TABLE attachments (
+    attachment_id VARCHAR(64) PRIMARY KEY,
+    task_id VARCHAR(64) NOT NULL,
+    owner_id INT NOT NULL,
+    file_name VARCHAR(255) NOT NULL,
+    content_type VARCHAR(255) NOT NULL,
+    size BIGINT NOT NULL,
+    blob_key VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    FOREIGN KEY (task_id) REFERENCES tasks (task_id),
+    FOREIGN KEY (owner_id) REFERENCES users (user_id)
+);
+
+CREATE INDEX attachments_task_id_idx ON attachments (task_id);
+CREATE INDEX attachments_owner_id_idx ON attachments (owner_id);
+
+-- Task IDs are not foreign keys, so that the history is kept after the task is deleted.
+CREATE TABLE activity (
+    activity_id BIGSERIAL PRIMARY KEY,
+    board_id INT NOT NULL,
+    task_id VARCHAR(64) NOT NULL,
+    actor_id INT NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    from_value TEXT,
+    to_value TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    FOREIGN KEY (board_id) REFERENCES users (user_id),
+    FOREIGN KEY (actor_id) REFERENCES users (user_id)
+);
+
+CREATE INDEX activity_board_id_idx ON activity (board_id, activity_id);
+CREATE INDEX activity_task_id_idx ON activity (task_id, activity_id);
+
+CREATE TABLE views (
+    view_id VARCHAR(64) PRIMARY KEY,
+    board_id INT NOT NULL,
+    owner_id INT NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    filter_query TEXT NOT NULL,
+    sort VARCHAR(16) NOT NULL,
+    descending BOOLEAN NOT NULL,
+    group_by VARCHAR(16) NOT NULL,
+    include_archived BOOLEAN NOT NULL,
+    shared BOOLEAN NOT NULL,
+    FOREIGN KEY (board_id) REFERENCES users (user_id),
+    FOREIGN KEY (owner_id) REFERENCES users (user_id)
+);
+
+CREATE INDEX views_board_id_idx ON views (board_id);
+
+-- Entity IDs are not foreign keys, so that the deletions are logged too.
+CREATE TABLE sync_changes (
+    user_id INT NOT NULL,
+    seq BIGINT NOT NULL,
+    entity_kind VARCHAR(16) NOT NULL,
+    entity_id VARCHAR(64) NOT NULL,
+    PRIMARY KEY (user_id, seq),
+    FOREIGN KEY (user_id) REFERENCES users (user_id)
+);
+
+-- Keys are scoped by the user that has sent the request.
+-- The requests made before logging in, such as registrations, share the scope without a user ID.
+CREATE TABLE idempotency_keys (
+    user_id INT,
+    key VARCHAR(255) NOT NULL,
+    fingerprint CHAR(64) NOT NULL,
+    status SMALLINT,
+    etag VARCHAR(64),
+    body TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    UNIQUE NULLS NOT DISTINCT (key, user_id),
+    FOREIGN KEY (user_id) REFERENCES users (user_id)
+);
+
+CREATE INDEX idempotency_keys_created_at_idx ON idempotency_keys (created_at);
+
+-- Event names are stored comma-separated, an empty string stands for all the events.
+CREATE TABLE webhooks (
+    webhook_id VARCHAR(64) PRIMARY KEY,
+    board_id INT NOT NULL,
+    url VARCHAR(2048) NOT NULL,
+    event_types VARCHAR(255) NOT NULL,
+    secret CHAR(64) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    FOREIGN KEY (board_id) REFERENCES users (user_id)
+);
+
+CREATE INDEX webhooks_board_id_idx ON webhooks (board_id);
+
+CREATE TABLE webhook_deliveries (
+    delivery_id VARCHAR(64) PRIMARY KEY,
+    webhook_id VARCHAR(64) NOT NULL,
+    event_type VARCHAR(64) NOT NULL,
+    payload TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    attempts INT NOT NULL,
+    next_attempt_at TIMESTAMPTZ,
+    last_status_code SMALLINT,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    FOREIGN KEY (webhook_id) REFERENCES webhooks (webhook_id) ON DELETE CASCADE
+);
+
+CREATE INDEX webhook_deliveries_webhook_id_idx ON webhook_deliveries (webhook_id, created_at);
+CREATE INDEX webhook_deliveries_pending_idx ON webhook_deliveries (next_attempt_at)
+    WHERE status = 'pending';
+
+-- Changes of the boards with webhooks, recorded in the same transaction as the change
+-- and deleted once their deliveries are queued. Item IDs are not foreign keys,
+-- so that the deletions are recorded too.
+CREATE TABLE webhook_events (
+    event_id BIGSERIAL PRIMARY KEY,
+    board_id INT NOT NULL,
+    event_type VARCHAR(64) NOT NULL,
+    item_id VARCHAR(64) NOT NULL,
+    from_category_id VARCHAR(64),
+    created_at TIMESTAMPTZ NOT NULL,
+    -- Set while a worker queues the deliveries of the event.
+    claimed_until TIMESTAMPTZ,
+    FOREIGN KEY (board_id) REFERENCES users (user_id)
+);
@@ -2,11 +2,25 @@ use std::sync::Arc;
 
 use rocket::State;
 
-use crate::app::{auth::AuthService, tasks::TasksService};
+use crate::app::{
+    attachments::AttachmentsService, auth::AuthService, calendar::CalendarService,
+    comments::CommentsService, idempotency::IdempotencyService, inbound::InboundEmailService,
+    presence::PresenceTracker, search::SearchService, tasks::TasksService, views::ViewsService,
+    webhooks::WebhooksService,
+};
 
 pub type ContextState = State<Arc<Context>>;
 
 pub struct Context {
     pub auth: Box<AuthService>,
     pub tasks: Box<TasksService>,
+    pub comments: Box<CommentsService>,
+    pub search: Box<SearchService>,
+    pub views: Box<ViewsService>,
+    pub attachments: Arc<AttachmentsService>,
+    pub presence: Arc<PresenceTracker>,
+    pub idempotency: Box<IdempotencyService>,
+    pub calendar: Box<CalendarService>,
+    pub webhooks: Box<WebhooksService>,
+    pub inbound: Box<InboundEmailService>,
 }
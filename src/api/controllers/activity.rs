@@ -0,0 +1,120 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    app::activity::ActivityPage,
+    model::{
+        activity::{ActivityEntry, ActivityId},
+        BoardId, TaskId,
+    },
+};
+
+use super::super::{ApiError, ContextState, NoData, Problem, Response, ResponseBody};
+
+use super::auth::AuthorizedUser;
+
+/// Default number of entries in a page of the board activity feed.
+const DEFAULT_PAGE_SIZE: i64 = 50;
+
+#[derive(Serialize, ToSchema)]
+pub struct Activity {
+    #[schema(value_type = i64)]
+    activity_id: ActivityId,
+    #[schema(value_type = String)]
+    task_id: TaskId,
+    actor_id: i64,
+    /// One of `created`, `label_changed`, `description_changed`, `moved`, `archived`, `deleted`, `restored`.
+    kind: &'static str,
+    /// Value before the change. For `moved`, the ID of the previous category.
+    from: Option<String>,
+    /// Value after the change. For `moved`, the ID of the new category.
+    to: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<&ActivityEntry> for Activity {
+    fn from(entry: &ActivityEntry) -> Self {
+        let values = entry.kind.values();
+
+        Self {
+            activity_id: entry.activity_id,
+            task_id: entry.task_id.clone(),
+            actor_id: entry.actor_id.raw(),
+            kind: entry.kind.as_str(),
+            from: values.map(|(from, _)| from.to_string()),
+            to: values.map(|(_, to)| to.to_string()),
+            created_at: entry.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ActivityFeed {
+    entries: Vec<Activity>,
+    /// Pass as `cursor` to get the next, older page. `None` on the last page.
+    #[schema(value_type = Option<i64>)]
+    next_cursor: Option<ActivityId>,
+}
+
+impl From<ActivityPage> for ActivityFeed {
+    fn from(page: ActivityPage) -> Self {
+        Self {
+            entries: page.entries.iter().map(Activity::from).collect(),
+            next_cursor: page.next_cursor,
+        }
+    }
+}
+
+/// Returns the history of the task, newest first. The history of a deleted task is still available.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The history", body = ResponseBody<Vec<Activity>>),
+        (status = 404, description = "Error code `task_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[get("/tasks/<task_id>/activity")]
+pub async fn get_task_activity(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+) -> Response<Vec<Activity>> {
+    let tasks = &context.tasks;
+
+    match tasks.fetch_task_activity(user.user_id, task_id).await? {
+        Some(entries) => Response::from_data(entries.iter().map(Activity::from).collect()),
+        None => Response::from_error(ApiError::TaskNotFound),
+    }
+}
+
+/// Returns the changes of all the tasks on the board, newest first, a page at a time.
+#[utoipa::path(
+    security(("session" = [])),
+    params(("cursor" = Option<i64>, Query, description = "`next_cursor` of the previous page")),
+    responses(
+        (status = 200, description = "A page of the changes", body = ResponseBody<ActivityFeed>),
+        (status = 403, description = "Error code `forbidden`", body = Problem<NoData>),
+    ),
+)]
+#[get("/boards/<board_id>/activity?<cursor>&<limit>")]
+pub async fn get_board_activity(
+    context: &ContextState,
+    user: AuthorizedUser,
+    board_id: i64,
+    cursor: Option<ActivityId>,
+    limit: Option<i64>,
+) -> Response<ActivityFeed> {
+    let tasks = &context.tasks;
+    let board_id = BoardId::from_raw(board_id);
+
+    if !tasks.can_access_board(user.user_id, board_id) {
+        return Response::from_error(ApiError::Forbidden);
+    }
+
+    let page = tasks
+        .fetch_board_activity(board_id, cursor, limit.unwrap_or(DEFAULT_PAGE_SIZE))
+        .await?;
+
+    Response::from_data(ActivityFeed::from(page))
+}
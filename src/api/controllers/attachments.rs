@@ -0,0 +1,310 @@
+use chrono::{DateTime, Utc};
+use rocket::{
+    data::{self, Capped, FromData},
+    form::{Form, FromForm},
+    fs::TempFile,
+    http::{ContentType, Header},
+    response::Responder,
+    serde::Serialize,
+    tokio::io::AsyncReadExt,
+    Data, Request,
+};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+use crate::{
+    app::attachments::{AttachmentError, Upload},
+    model::attachments::{AttachmentDescription, AttachmentId},
+};
+
+use super::super::{
+    idempotency::{idempotent, IdempotencyKey, Idempotent, IdempotentBody},
+    ApiError, ContextState, NoData, Problem, Response, ResponseBody,
+};
+
+use super::auth::AuthorizedUser;
+
+#[derive(Serialize, ToSchema)]
+pub struct Attachment {
+    #[schema(value_type = String)]
+    attachment_id: AttachmentId,
+    file_name: String,
+    content_type: String,
+    size: i64,
+    created_at: DateTime<Utc>,
+}
+
+impl From<&AttachmentDescription> for Attachment {
+    fn from(attachment: &AttachmentDescription) -> Self {
+        Self {
+            attachment_id: attachment.attachment_id.clone(),
+            file_name: attachment.file_name.clone(),
+            content_type: attachment.content_type.clone(),
+            size: attachment.size,
+            created_at: attachment.created_at,
+        }
+    }
+}
+
+pub(super) fn attachment_api_error(err: &AttachmentError) -> ApiError {
+    match err {
+        AttachmentError::TaskNotFound => ApiError::TaskNotFound,
+        AttachmentError::AttachmentNotFound => ApiError::AttachmentNotFound,
+        AttachmentError::FileTooLarge => ApiError::FileTooLarge,
+        AttachmentError::QuotaExceeded => ApiError::QuotaExceeded,
+        AttachmentError::UnsupportedContentType => ApiError::UnsupportedContentType,
+    }
+}
+
+fn attachment_error<T>(err: AttachmentError) -> Response<T> {
+    Response::from_error(attachment_api_error(&err))
+}
+
+/// Returns the files attached to the task.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The attachments", body = ResponseBody<Vec<Attachment>>),
+        (status = 404, description = "Error code `task_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[get("/tasks/<task_id>/attachments")]
+pub async fn get_attachments(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+) -> Response<Vec<Attachment>> {
+    let attachments = &context.attachments;
+
+    match attachments.fetch_attachments(user.user_id, task_id).await? {
+        Some(list) => Response::from_data(list.iter().map(Attachment::from).collect()),
+        None => Response::from_error(ApiError::TaskNotFound),
+    }
+}
+
+#[derive(FromForm, ToSchema)]
+pub struct AttachmentForm<'r> {
+    #[schema(value_type = String, format = Binary)]
+    file: Capped<TempFile<'r>>,
+}
+
+/// File sent as `file` field of a `multipart/form-data` body.
+pub struct UploadedFile(Upload);
+
+#[rocket::async_trait]
+impl<'r> IdempotentBody<'r> for UploadedFile {
+    async fn read(
+        request: &'r Request<'_>,
+        data: Data<'r>,
+        fingerprint: &mut Sha256,
+    ) -> Result<Self, ApiError> {
+        let form = match Form::<AttachmentForm<'r>>::from_data(request, data).await {
+            data::Outcome::Success(form) => form,
+            data::Outcome::Error((status, errors)) => {
+                return Err(ApiError::from_status(status).cache(request, Some(errors.to_string())))
+            }
+            data::Outcome::Forward((_, status)) => {
+                return Err(ApiError::from_status(status).cache(request, None))
+            }
+        };
+
+        // The file is truncated if it exceeds the limit of the server.
+        if !form.file.is_complete() {
+            return Err(ApiError::FileTooLarge.cache(request, None));
+        }
+
+        let file = &form.file.value;
+
+        let data = read_file(file)
+            .await
+            .map_err(|err| ApiError::BadRequest.cache(request, Some(err.to_string())))?;
+
+        let upload = Upload {
+            file_name: file
+                .raw_name()
+                .map(|name| name.dangerous_unsafe_unsanitized_raw().as_str().to_string())
+                .unwrap_or_default(),
+            content_type: file
+                .content_type()
+                .map(ContentType::to_string)
+                .unwrap_or_default(),
+            data,
+        };
+
+        fingerprint.update(&upload.file_name);
+        fingerprint.update(b"\n");
+        fingerprint.update(&upload.content_type);
+        fingerprint.update(b"\n");
+        fingerprint.update(&upload.data);
+
+        Ok(Self(upload))
+    }
+}
+
+/// Attaches the file sent as `file` field of a `multipart/form-data` body.
+/// The retries with the same `Idempotency-Key` header get the response to the first request.
+#[utoipa::path(
+    security(("session" = [])),
+    params(IdempotencyKey),
+    request_body(content = AttachmentForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "The attachment", body = ResponseBody<Attachment>),
+        (status = 404, description = "Error code `task_not_found`", body = Problem<NoData>),
+        (
+            status = 413,
+            description = "Error code `file_too_large` or `quota_exceeded`",
+            body = Problem<NoData>,
+        ),
+        (
+            status = 415,
+            description = "Error code `unsupported_content_type` if the type is not allowed \
+                or does not match the content",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
+#[post("/tasks/<task_id>/attachments", data = "<file>")]
+pub async fn upload_attachment(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    file: Idempotent<UploadedFile>,
+) -> Response<Attachment> {
+    let (UploadedFile(upload), key) = file.into_parts();
+
+    idempotent(context, Some(user.user_id), key.as_ref(), async {
+        let attachments = &context.attachments;
+
+        match attachments.upload(user.user_id, task_id, upload).await? {
+            Ok(attachment) => Response::from_data(Attachment::from(&attachment)),
+            Err(err) => attachment_error(err),
+        }
+    })
+    .await
+}
+
+async fn read_file(file: &TempFile<'_>) -> anyhow::Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(file.len() as usize);
+    file.open().await?.read_to_end(&mut data).await?;
+    Ok(data)
+}
+
+/// Content of an attached file, served for download.
+#[derive(Responder)]
+pub struct AttachmentContent {
+    data: Vec<u8>,
+    content_type: ContentType,
+    disposition: Header<'static>,
+    nosniff: Header<'static>,
+}
+
+impl AttachmentContent {
+    fn new(attachment: &AttachmentDescription, data: Vec<u8>) -> Self {
+        let content_type =
+            ContentType::parse_flexible(&attachment.content_type).unwrap_or(ContentType::Binary);
+
+        // Quotes and backslashes cannot be escaped reliably in the legacy parameter,
+        // so they are replaced there, and the exact name is sent in the encoded one.
+        let ascii_name: String = attachment
+            .file_name
+            .chars()
+            .map(|c| match c {
+                '"' | '\\' => '_',
+                c if c.is_ascii() => c,
+                _ => '_',
+            })
+            .collect();
+
+        let disposition = format!(
+            "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+            ascii_name,
+            urlencode(&attachment.file_name)
+        );
+
+        Self {
+            data,
+            content_type,
+            disposition: Header::new("Content-Disposition", disposition),
+            nosniff: Header::new("X-Content-Type-Options", "nosniff"),
+        }
+    }
+}
+
+fn urlencode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Downloads the attached file.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (
+            status = 200,
+            description = "The content of the file, with `Content-Disposition: attachment`",
+            content_type = "application/octet-stream",
+        ),
+        (
+            status = 404,
+            description = "Error code `task_not_found` or `attachment_not_found`",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
+#[get("/tasks/<task_id>/attachments/<attachment_id>")]
+pub async fn download_attachment(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    attachment_id: &str,
+) -> Result<AttachmentContent, ApiError> {
+    let attachments = &context.attachments;
+
+    let result = attachments
+        .download(user.user_id, task_id, attachment_id)
+        .await
+        .map_err(|err| {
+            log::error!("Server error: {:?}", err);
+            ApiError::ServerError
+        })?;
+
+    match result {
+        Ok((attachment, data)) => Ok(AttachmentContent::new(&attachment, data)),
+        Err(err) => Err(attachment_api_error(&err)),
+    }
+}
+
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The attachment is deleted", body = ResponseBody<NoData>),
+        (
+            status = 404,
+            description = "Error code `task_not_found` or `attachment_not_found`",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
+#[delete("/tasks/<task_id>/attachments/<attachment_id>")]
+pub async fn delete_attachment(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    attachment_id: &str,
+) -> Response<()> {
+    let attachments = &context.attachments;
+
+    match attachments
+        .delete_attachment(user.user_id, task_id, attachment_id)
+        .await?
+    {
+        Ok(()) => Response::from_data(()),
+        Err(err) => attachment_error(err),
+    }
+}
@@ -6,15 +6,20 @@ use rocket::{
     outcome::try_outcome,
     request::{FromRequest, Outcome},
     serde::{json::Json, Deserialize, Serialize},
-    Request,
+    Data, Request,
 };
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
 
 use crate::{
-    app::auth::{CreateUserError, LoginError},
+    app::auth::{CreateUserError, LoginError, SetTimezoneError},
     model::{SessionToken, UserId},
 };
 
-use super::super::{ContextState, Response};
+use super::super::{
+    idempotency::{idempotent, IdempotencyKey, Idempotent, IdempotentBody},
+    ApiError, ContextState, NoData, Problem, Response, ResponseBody,
+};
 
 struct SessionTokenCookie<'a>(&'a CookieJar<'a>);
 
@@ -83,17 +88,47 @@ impl<'r> FromRequest<'r> for AuthorizedUser {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct LoginParams {
     username: String,
     password: String,
 }
 
-#[derive(Serialize)]
+/// Body of a registration. Only the username tells a retry from a different request,
+/// so that nothing derived from the password is kept with the idempotency key.
+pub struct Registration(LoginParams);
+
+#[rocket::async_trait]
+impl<'r> IdempotentBody<'r> for Registration {
+    async fn read(
+        request: &'r Request<'_>,
+        data: Data<'r>,
+        fingerprint: &mut Sha256,
+    ) -> Result<Self, ApiError> {
+        let params = LoginParams::read(request, data, &mut Sha256::new()).await?;
+        fingerprint.update(&params.username);
+
+        Ok(Registration(params))
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct UserResponse {
     username: String,
 }
 
+/// Logs in and sets the session cookie.
+#[utoipa::path(
+    request_body = LoginParams,
+    responses(
+        (status = 200, description = "The user", body = ResponseBody<UserResponse>),
+        (
+            status = 401,
+            description = "Error code `user_not_found` or `incorrect_password`",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
 #[post("/login", format = "application/json", data = "<user>")]
 pub async fn login(
     context: &ContextState,
@@ -110,33 +145,77 @@ pub async fn login(
                 username: user.username.to_string(),
             })
         }
-        Err(LoginError::UserNotFound) => Response::from_error("user_not_found"),
-        Err(LoginError::IncorrectPassword) => Response::from_error("incorrect_password"),
+        Err(LoginError::UserNotFound) => Response::from_error(ApiError::UserNotFound),
+        Err(LoginError::IncorrectPassword) => Response::from_error(ApiError::IncorrectPassword),
     }
 }
 
+/// Creates the user and logs in.
+///
+/// The retries with the same `Idempotency-Key` header get the response to the first request.
+/// Since a replayed response carries no session cookie, a replayed success logs in
+/// with the credentials of the retry.
+#[utoipa::path(
+    params(IdempotencyKey),
+    request_body = LoginParams,
+    responses(
+        (status = 200, description = "The user", body = ResponseBody<UserResponse>),
+        (status = 409, description = "Error code `user_already_exists`", body = Problem<NoData>),
+        (
+            status = 422,
+            description = "Error code `invalid_username` or `invalid_password`",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
 #[post("/register", format = "application/json", data = "<user>")]
 pub async fn register(
     context: &ContextState,
     jar: &CookieJar<'_>,
-    user: Json<LoginParams>,
+    user: Idempotent<Registration>,
 ) -> Response<UserResponse> {
     let auth = &context.auth;
+    let (Registration(user), key) = user.into_parts();
 
-    match auth.create_user(&user.username, &user.password).await? {
-        Ok((_user_id, token)) => {
-            SessionTokenCookie::new(jar).write(&token);
+    // The client has no session yet, so the key is kept in the scope of the anonymous requests.
+    let response = idempotent(context, None, key.as_ref(), async {
+        match auth.create_user(&user.username, &user.password).await? {
+            Ok((_user_id, token)) => {
+                SessionTokenCookie::new(jar).write(&token);
 
-            Response::from_data(UserResponse {
-                username: user.username.to_string(),
-            })
+                Response::from_data(UserResponse {
+                    username: user.username.to_string(),
+                })
+            }
+            Err(CreateUserError::InvalidUsername) => {
+                Response::from_error(ApiError::InvalidUsername)
+            }
+            Err(CreateUserError::InvalidPassword) => {
+                Response::from_error(ApiError::InvalidPassword)
+            }
+            Err(CreateUserError::UserAlreadyExists) => {
+                Response::from_error(ApiError::UserAlreadyExists)
+            }
+        }
+    })
+    .await;
+
+    if let Response::Replayed(stored) = &response {
+        if stored.status == Status::Ok.code {
+            if let Ok((_user_id, token)) = auth.login_user(&user.username, &user.password).await? {
+                SessionTokenCookie::new(jar).write(&token);
+            }
         }
-        Err(CreateUserError::InvalidUsername) => Response::from_error("invalid_username"),
-        Err(CreateUserError::InvalidPassword) => Response::from_error("invalid_password"),
-        Err(CreateUserError::UserAlreadyExists) => Response::from_error("user_already_exists"),
     }
+
+    response
 }
 
+/// Returns the logged in user.
+#[utoipa::path(
+    security(("session" = [])),
+    responses((status = 200, description = "The user", body = ResponseBody<UserResponse>)),
+)]
 #[get("/user")]
 pub async fn get_user(
     context: &ContextState,
@@ -151,3 +230,56 @@ pub async fn get_user(
 
     Response::from_data(UserResponse { username })
 }
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct UserSettings {
+    /// IANA name of the timezone, such as `Europe/Paris`.
+    timezone: String,
+}
+
+/// Returns the settings of the logged in user.
+#[utoipa::path(
+    security(("session" = [])),
+    responses((status = 200, description = "The settings", body = ResponseBody<UserSettings>)),
+)]
+#[get("/user/settings")]
+pub async fn get_user_settings(
+    context: &ContextState,
+    authorized_user: AuthorizedUser,
+) -> Response<UserSettings> {
+    let auth = &context.auth;
+
+    let timezone = auth.get_timezone(authorized_user.user_id).await?;
+
+    Response::from_data(UserSettings {
+        timezone: timezone.name().to_string(),
+    })
+}
+
+/// Changes the settings of the logged in user.
+#[utoipa::path(
+    security(("session" = [])),
+    request_body = UserSettings,
+    responses(
+        (status = 200, description = "The settings", body = ResponseBody<UserSettings>),
+        (status = 422, description = "Error code `invalid_timezone`", body = Problem<NoData>),
+    ),
+)]
+#[put("/user/settings", format = "application/json", data = "<settings>")]
+pub async fn modify_user_settings(
+    context: &ContextState,
+    authorized_user: AuthorizedUser,
+    settings: Json<UserSettings>,
+) -> Response<UserSettings> {
+    let auth = &context.auth;
+
+    match auth
+        .set_timezone(authorized_user.user_id, &settings.timezone)
+        .await?
+    {
+        Ok(timezone) => Response::from_data(UserSettings {
+            timezone: timezone.name().to_string(),
+        }),
+        Err(SetTimezoneError::InvalidTimezone) => Response::from_error(ApiError::InvalidTimezone),
+    }
+}
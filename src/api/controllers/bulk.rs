@@ -0,0 +1,233 @@
+use rocket::serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::model::{
+    bulk::{BulkAction, BulkError, BulkOperation},
+    tasks::{TaskDescription, TaskPatch},
+    LabelId, TaskCategoryId, TaskId, UserId,
+};
+
+use super::super::{
+    idempotency::{idempotent, IdempotencyKey, Idempotent},
+    ApiError, Context, ContextState, Problem, Response, ResponseBody,
+};
+
+use super::{
+    auth::AuthorizedUser,
+    tasks::{Task, TaskMergePatch},
+};
+
+/// Maximum number of operations in a batch.
+const MAX_BULK_OPERATIONS: usize = 100;
+
+#[derive(Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+#[allow(non_snake_case)]
+pub enum BulkInputOperation {
+    Move {
+        #[schema(value_type = String)]
+        taskId: TaskId,
+        #[schema(value_type = String)]
+        categoryId: TaskCategoryId,
+    },
+    /// Changes the fields present in the merge patch.
+    Update {
+        #[schema(value_type = String)]
+        taskId: TaskId,
+        #[serde(flatten)]
+        patch: TaskMergePatch,
+    },
+    Archive {
+        #[schema(value_type = String)]
+        taskId: TaskId,
+    },
+    /// Moves the task to the trash.
+    Delete {
+        #[schema(value_type = String)]
+        taskId: TaskId,
+    },
+    AddLabel {
+        #[schema(value_type = String)]
+        taskId: TaskId,
+        #[schema(value_type = String)]
+        labelId: LabelId,
+    },
+    RemoveLabel {
+        #[schema(value_type = String)]
+        taskId: TaskId,
+        #[schema(value_type = String)]
+        labelId: LabelId,
+    },
+}
+
+impl BulkInputOperation {
+    /// Returns `None` if the merge patch of an update removes a required field.
+    fn into_operation(self) -> Option<BulkOperation> {
+        let (task_id, action) = match self {
+            BulkInputOperation::Move { taskId, categoryId } => (
+                taskId,
+                BulkAction::Update(TaskPatch {
+                    category_id: Some(categoryId),
+                    ..Default::default()
+                }),
+            ),
+            BulkInputOperation::Update { taskId, patch } => {
+                (taskId, BulkAction::Update(patch.into_task_patch()?))
+            }
+            BulkInputOperation::Archive { taskId } => (taskId, BulkAction::Archive),
+            BulkInputOperation::Delete { taskId } => (taskId, BulkAction::Delete),
+            BulkInputOperation::AddLabel { taskId, labelId } => {
+                (taskId, BulkAction::AddLabel(labelId))
+            }
+            BulkInputOperation::RemoveLabel { taskId, labelId } => {
+                (taskId, BulkAction::RemoveLabel(labelId))
+            }
+        };
+
+        Some(BulkOperation { task_id, action })
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BulkInput {
+    operations: Vec<BulkInputOperation>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BulkTask {
+    #[serde(flatten)]
+    task: Task,
+    deleted: bool,
+}
+
+impl From<&TaskDescription> for BulkTask {
+    fn from(task: &TaskDescription) -> Self {
+        Self {
+            task: Task::from(task),
+            deleted: task.lifecycle.is_trashed(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkItemStatus {
+    Applied,
+    Failed,
+    /// Not applied, since another operation of the batch has failed.
+    Skipped,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BulkItemResult {
+    status: BulkItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_code: Option<ApiError>,
+    /// The state of the task after the operation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    task: Option<BulkTask>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BulkResults {
+    /// Results in the order of the operations.
+    results: Vec<BulkItemResult>,
+}
+
+/// Responds with the failure of the operation at `index`, reporting the other operations as skipped.
+fn bulk_failure(count: usize, index: usize, error: ApiError) -> Response<BulkResults> {
+    let results = (0..count)
+        .map(|i| BulkItemResult {
+            status: if i == index {
+                BulkItemStatus::Failed
+            } else {
+                BulkItemStatus::Skipped
+            },
+            error_code: (i == index).then_some(error),
+            task: None,
+        })
+        .collect();
+
+    Response::from_error_details(ApiError::BulkOperationFailed, BulkResults { results })
+}
+
+/// Applies the operations to the tasks in order. Either all of them are applied,
+/// or none of them is and the result of the failed operation tells why.
+/// The retries with the same `Idempotency-Key` header get the response to the first request.
+#[utoipa::path(
+    security(("session" = [])),
+    params(IdempotencyKey),
+    request_body = BulkInput,
+    responses(
+        (
+            status = 200,
+            description = "The results of the operations",
+            body = ResponseBody<BulkResults>,
+        ),
+        (
+            status = 422,
+            description = "Error code `bulk_operation_failed` with the error code \
+                of the failed operation in its result, or `too_many_operations`",
+            body = Problem<BulkResults>,
+        ),
+    ),
+)]
+#[post("/tasks/bulk", format = "application/json", data = "<data>")]
+pub async fn apply_bulk_operations(
+    context: &ContextState,
+    user: AuthorizedUser,
+    data: Idempotent<BulkInput>,
+) -> Response<BulkResults> {
+    let (data, key) = data.into_parts();
+
+    idempotent(context, Some(user.user_id), key.as_ref(), async {
+        apply_operations(context, user.user_id, data).await
+    })
+    .await
+}
+
+async fn apply_operations(
+    context: &Context,
+    user_id: UserId,
+    data: BulkInput,
+) -> Response<BulkResults> {
+    let count = data.operations.len();
+    if count > MAX_BULK_OPERATIONS {
+        return Response::from_error(ApiError::TooManyOperations);
+    }
+
+    let mut operations = Vec::with_capacity(count);
+    for (index, operation) in data.operations.into_iter().enumerate() {
+        match operation.into_operation() {
+            Some(operation) => operations.push(operation),
+            None => return bulk_failure(count, index, ApiError::InvalidPatch),
+        }
+    }
+
+    let result = context
+        .tasks
+        .apply_bulk_operations(user_id, &operations)
+        .await?;
+
+    match result {
+        Ok(tasks) => Response::from_data(BulkResults {
+            results: tasks
+                .iter()
+                .map(|task| BulkItemResult {
+                    status: BulkItemStatus::Applied,
+                    error_code: None,
+                    task: Some(BulkTask::from(task)),
+                })
+                .collect(),
+        }),
+        Err(failure) => bulk_failure(
+            count,
+            failure.index,
+            match failure.error {
+                BulkError::TaskNotFound => ApiError::TaskNotFound,
+                BulkError::CategoryNotFound => ApiError::CategoryNotFound,
+                BulkError::LabelNotFound => ApiError::LabelNotFound,
+            },
+        ),
+    }
+}
@@ -0,0 +1,166 @@
+use chrono::Utc;
+use rocket::{
+    http::{ContentType, Status},
+    response::{self, Responder},
+    serde::Serialize,
+    Request,
+};
+use utoipa::ToSchema;
+
+use crate::{app::calendar::FeedComponent, model::calendar::CalendarToken};
+
+use super::super::{
+    etag::IfNoneMatch, ApiError, ContextState, NoData, Problem, Response, ResponseBody,
+};
+
+use super::auth::AuthorizedUser;
+
+#[derive(Serialize, ToSchema)]
+pub struct CalendarFeedInfo {
+    /// Path of the feed to subscribe to, or `None` if the feed is not enabled.
+    url: Option<String>,
+}
+
+impl CalendarFeedInfo {
+    fn new(token: Option<&CalendarToken>) -> Self {
+        Self {
+            url: token.map(|token| format!("/api/calendar/{}.ics", token.as_str())),
+        }
+    }
+}
+
+/// Returns the URL of the calendar feed of the user.
+#[utoipa::path(
+    security(("session" = [])),
+    responses((status = 200, description = "The feed", body = ResponseBody<CalendarFeedInfo>)),
+)]
+#[get("/user/calendar")]
+pub async fn get_calendar_feed(
+    context: &ContextState,
+    user: AuthorizedUser,
+) -> Response<CalendarFeedInfo> {
+    let token = context.calendar.feed_token(user.user_id).await?;
+
+    Response::from_data(CalendarFeedInfo::new(token.as_ref()))
+}
+
+/// Enables the calendar feed of the user with a new secret URL. The previous URL stops working.
+#[utoipa::path(
+    security(("session" = [])),
+    responses((status = 200, description = "The new feed", body = ResponseBody<CalendarFeedInfo>)),
+)]
+#[post("/user/calendar")]
+pub async fn reset_calendar_feed(
+    context: &ContextState,
+    user: AuthorizedUser,
+) -> Response<CalendarFeedInfo> {
+    let token = context.calendar.reset_feed(user.user_id).await?;
+
+    Response::from_data(CalendarFeedInfo::new(Some(&token)))
+}
+
+/// Disables the calendar feed of the user.
+#[utoipa::path(
+    security(("session" = [])),
+    responses((status = 200, description = "The disabled feed", body = ResponseBody<CalendarFeedInfo>)),
+)]
+#[delete("/user/calendar")]
+pub async fn revoke_calendar_feed(
+    context: &ContextState,
+    user: AuthorizedUser,
+) -> Response<CalendarFeedInfo> {
+    context.calendar.revoke_feed(user.user_id).await?;
+
+    Response::from_data(CalendarFeedInfo::new(None))
+}
+
+pub enum CalendarResponse {
+    Feed { body: String, etag: String },
+    NotModified { etag: String },
+}
+
+impl<'r> Responder<'r, 'static> for CalendarResponse {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let (mut response, etag) = match self {
+            CalendarResponse::Feed { body, etag } => {
+                let content_type =
+                    ContentType::new("text", "calendar").with_params(("charset", "utf-8"));
+                ((content_type, body).respond_to(request)?, etag)
+            }
+            CalendarResponse::NotModified { etag } => (
+                rocket::Response::build()
+                    .status(Status::NotModified)
+                    .finalize(),
+                etag,
+            ),
+        };
+
+        response.set_raw_header("ETag", etag);
+        // Calendar apps poll the feed, so they have to revalidate their copy every time.
+        response.set_raw_header("Cache-Control", "private, no-cache");
+        Ok(response)
+    }
+}
+
+/// Publishes the tasks with due dates of the user with the secret `<token>.ics` file name
+/// as iCalendar events, or as to-dos if `todo=true`. Requires no session, since calendar apps
+/// subscribe to the URL on their own.
+#[utoipa::path(
+    params(
+        ("file_name" = String, Path, description = "`<token>.ics` from the URL of the feed"),
+        IfNoneMatch,
+    ),
+    responses(
+        (
+            status = 200,
+            description = "The feed, with `ETag` header",
+            content_type = "text/calendar",
+            body = String,
+        ),
+        (status = 304, description = "The feed has not changed since the `ETag` of the client"),
+        (
+            status = 404,
+            description = "Error code `calendar_not_found` if the feed is not enabled or has been reset",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
+#[get("/calendar/<file_name>?<todo>")]
+pub async fn get_calendar(
+    context: &ContextState,
+    file_name: &str,
+    todo: Option<bool>,
+    if_none_match: IfNoneMatch,
+) -> Result<CalendarResponse, ApiError> {
+    let Some(token) = file_name.strip_suffix(".ics") else {
+        return Err(ApiError::CalendarNotFound);
+    };
+
+    let component = if todo.unwrap_or(false) {
+        FeedComponent::Todo
+    } else {
+        FeedComponent::Event
+    };
+
+    let feed = context
+        .calendar
+        .feed(token, component, Utc::now())
+        .await
+        .map_err(|err| {
+            log::error!("Server error: {:?}", err);
+            ApiError::ServerError
+        })?
+        .ok_or(ApiError::CalendarNotFound)?;
+
+    // The feed differs in `DTSTAMP` between requests, so the tag is weak.
+    let etag = format!("W/\"{}\"", feed.fingerprint);
+
+    if if_none_match.matches(&etag) {
+        return Ok(CalendarResponse::NotModified { etag });
+    }
+
+    Ok(CalendarResponse::Feed {
+        body: feed.body,
+        etag,
+    })
+}
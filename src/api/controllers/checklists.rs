@@ -0,0 +1,212 @@
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    app::tasks::ChecklistError,
+    model::checklists::{ChecklistItem, ChecklistItemId, ChecklistItemPatch, ChecklistProgress},
+};
+
+use super::super::{
+    idempotency::{idempotent, IdempotencyKey, Idempotent},
+    ApiError, ContextState, NoData, Problem, Response, ResponseBody,
+};
+
+use super::auth::AuthorizedUser;
+
+/// Progress of the checklist, shown as a badge on the task.
+#[derive(Serialize, ToSchema)]
+pub struct Progress {
+    done: i64,
+    total: i64,
+}
+
+impl From<ChecklistProgress> for Progress {
+    fn from(progress: ChecklistProgress) -> Self {
+        Self {
+            done: progress.done,
+            total: progress.total,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ChecklistItemResponse {
+    #[schema(value_type = String)]
+    item_id: ChecklistItemId,
+    text: String,
+    done: bool,
+    position: i32,
+}
+
+impl From<&ChecklistItem> for ChecklistItemResponse {
+    fn from(item: &ChecklistItem) -> Self {
+        Self {
+            item_id: item.item_id.clone(),
+            text: item.text.clone(),
+            done: item.done,
+            position: item.position,
+        }
+    }
+}
+
+fn checklist_response(
+    result: Result<Vec<ChecklistItem>, ChecklistError>,
+) -> Response<Vec<ChecklistItemResponse>> {
+    match result {
+        Ok(items) => Response::from_data(items.iter().map(ChecklistItemResponse::from).collect()),
+        Err(ChecklistError::TaskNotFound) => Response::from_error(ApiError::TaskNotFound),
+        Err(ChecklistError::ItemNotFound) => Response::from_error(ApiError::ItemNotFound),
+        Err(ChecklistError::InvalidText) => Response::from_error(ApiError::InvalidText),
+    }
+}
+
+/// Returns the checklist of the task in order.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (
+            status = 200,
+            description = "The checklist",
+            body = ResponseBody<Vec<ChecklistItemResponse>>,
+        ),
+        (status = 404, description = "Error code `task_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[get("/tasks/<task_id>/checklist")]
+pub async fn get_checklist(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+) -> Response<Vec<ChecklistItemResponse>> {
+    let tasks = &context.tasks;
+
+    match tasks.fetch_checklist(user.user_id, task_id).await? {
+        Some(items) => Response::from_data(items.iter().map(ChecklistItemResponse::from).collect()),
+        None => Response::from_error(ApiError::TaskNotFound),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ChecklistItemInputData {
+    text: String,
+}
+
+/// Appends the item to the checklist. Responds with the whole checklist.
+#[utoipa::path(
+    security(("session" = [])),
+    params(IdempotencyKey),
+    request_body = ChecklistItemInputData,
+    responses(
+        (
+            status = 200,
+            description = "The checklist",
+            body = ResponseBody<Vec<ChecklistItemResponse>>,
+        ),
+        (status = 404, description = "Error code `task_not_found`", body = Problem<NoData>),
+        (status = 422, description = "Error code `invalid_text`", body = Problem<NoData>),
+    ),
+)]
+#[post(
+    "/tasks/<task_id>/checklist",
+    format = "application/json",
+    data = "<data>"
+)]
+pub async fn add_checklist_item(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    data: Idempotent<ChecklistItemInputData>,
+) -> Response<Vec<ChecklistItemResponse>> {
+    idempotent(context, Some(user.user_id), data.key(), async {
+        let tasks = &context.tasks;
+
+        let result = tasks
+            .add_checklist_item(user.user_id, task_id, &data.text)
+            .await?;
+
+        checklist_response(result)
+    })
+    .await
+}
+
+/// Changes of a checklist item. Absent fields are left unchanged.
+#[derive(Deserialize, ToSchema)]
+pub struct ChecklistItemPatchData {
+    text: Option<String>,
+    done: Option<bool>,
+    position: Option<i32>,
+}
+
+/// Edits, checks/unchecks or moves the item. Responds with the whole checklist.
+#[utoipa::path(
+    security(("session" = [])),
+    request_body = ChecklistItemPatchData,
+    responses(
+        (
+            status = 200,
+            description = "The checklist",
+            body = ResponseBody<Vec<ChecklistItemResponse>>,
+        ),
+        (
+            status = 404,
+            description = "Error code `task_not_found` or `item_not_found`",
+            body = Problem<NoData>,
+        ),
+        (status = 422, description = "Error code `invalid_text`", body = Problem<NoData>),
+    ),
+)]
+#[patch("/tasks/<task_id>/checklist/<item_id>", data = "<data>")]
+pub async fn modify_checklist_item(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    item_id: &str,
+    data: Json<ChecklistItemPatchData>,
+) -> Response<Vec<ChecklistItemResponse>> {
+    let tasks = &context.tasks;
+
+    let data = data.into_inner();
+    let patch = ChecklistItemPatch {
+        text: data.text,
+        done: data.done,
+        position: data.position,
+    };
+
+    let result = tasks
+        .modify_checklist_item(user.user_id, task_id, item_id, &patch)
+        .await?;
+
+    checklist_response(result)
+}
+
+/// Deletes the item. Responds with the remaining checklist.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (
+            status = 200,
+            description = "The checklist",
+            body = ResponseBody<Vec<ChecklistItemResponse>>,
+        ),
+        (
+            status = 404,
+            description = "Error code `task_not_found` or `item_not_found`",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
+#[delete("/tasks/<task_id>/checklist/<item_id>")]
+pub async fn delete_checklist_item(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    item_id: &str,
+) -> Response<Vec<ChecklistItemResponse>> {
+    let tasks = &context.tasks;
+
+    let result = tasks
+        .delete_checklist_item(user.user_id, task_id, item_id)
+        .await?;
+
+    checklist_response(result)
+}
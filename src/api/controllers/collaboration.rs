@@ -0,0 +1,485 @@
+use std::{collections::HashSet, sync::Arc};
+
+use rocket::{
+    futures::{SinkExt, StreamExt},
+    serde::{
+        json::{self, Value},
+        Deserialize, Serialize,
+    },
+    tokio::{select, sync::broadcast::error::RecvError},
+    Shutdown,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::{
+    app::{
+        events::{BoardEvent, EventId, SubscriptionError},
+        presence::{PresenceHandle, Viewer},
+        tasks::{CreateTaskError, ModifyTaskError},
+    },
+    model::{BoardId, TaskId, UserId},
+};
+
+use super::super::{
+    websocket::{WebSocket, WebSocketChannel, WebSocketUpgrade},
+    ApiError, Context, ContextState, NoData, Problem,
+};
+
+use super::{
+    auth::AuthorizedUser,
+    events::{EventPayload, TaskPayload},
+    tasks::TaskInputData,
+};
+
+/// Messages sent by the client over the board channel.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    CreateTask {
+        request_id: String,
+        #[serde(flatten)]
+        data: TaskInputData,
+    },
+    /// Like `PUT /api/tasks/<task_id>`, requires the version the changes are based on.
+    ModifyTask {
+        request_id: String,
+        task_id: TaskId,
+        #[serde(flatten)]
+        data: TaskInputData,
+    },
+    DeleteTask {
+        request_id: String,
+        task_id: TaskId,
+    },
+    /// The user has started editing the task, or stopped editing if `task_id` is null.
+    Editing {
+        task_id: Option<TaskId>,
+    },
+}
+
+#[derive(Serialize)]
+struct ViewerResponse {
+    user_id: i64,
+    username: String,
+    editing_task_id: Option<TaskId>,
+}
+
+/// Messages sent by the server over the board channel.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    /// The command of the client has been applied.
+    /// Contains the state of the task with the version assigned by the server, unless the task was deleted.
+    Ack {
+        request_id: String,
+        event_id: EventId,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        task: Option<TaskPayload>,
+    },
+    /// The command of the client has failed, with the code of the REST API error.
+    /// On `version_conflict` contains the current state of the task.
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+        error_code: ApiError,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        task: Option<TaskPayload>,
+    },
+    /// The board has been changed by another client.
+    Event {
+        event: &'static str,
+        event_id: EventId,
+        data: EventPayload,
+    },
+    /// The users viewing the board.
+    Presence { viewers: Vec<ViewerResponse> },
+    /// Some events have been missed, the client has to reload the board.
+    Reset,
+}
+
+impl From<Viewer> for ViewerResponse {
+    fn from(viewer: Viewer) -> Self {
+        Self {
+            user_id: viewer.user_id.raw(),
+            username: viewer.username,
+            editing_task_id: viewer.editing_task_id,
+        }
+    }
+}
+
+struct BoardChannel {
+    context: Arc<Context>,
+    user_id: UserId,
+    board_id: BoardId,
+    presence: PresenceHandle,
+    /// Events caused by the commands of this client. They are acknowledged instead of being echoed.
+    own_events: HashSet<EventId>,
+}
+
+impl BoardChannel {
+    async fn handle_message(&mut self, text: &str) -> Option<ServerMessage> {
+        let message: ClientMessage = match json::from_str(text) {
+            Ok(message) => message,
+            Err(_) => {
+                // Try to keep the request ID, so that the client can match the error.
+                let request_id = json::from_str::<Value>(text).ok().and_then(|value| {
+                    value
+                        .get("request_id")
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                });
+
+                return Some(ServerMessage::Error {
+                    request_id,
+                    error_code: ApiError::InvalidBody,
+                    task: None,
+                });
+            }
+        };
+
+        let tasks = &self.context.tasks;
+
+        let (request_id, result) = match message {
+            ClientMessage::CreateTask { request_id, data } => {
+                let result = tasks
+                    .create_task(self.user_id, data.to_task_data())
+                    .await
+                    .map(|result| {
+                        result
+                            .map(|(task, event_id)| (event_id, Some(task)))
+                            .map_err(|CreateTaskError::CategoryNotFound| {
+                                ModifyTaskError::CategoryNotFound
+                            })
+                    });
+
+                (request_id, result)
+            }
+            ClientMessage::ModifyTask {
+                request_id,
+                task_id,
+                data,
+            } => {
+                let Some(version) = data.version else {
+                    return Some(ServerMessage::Error {
+                        request_id: Some(request_id),
+                        error_code: ApiError::VersionRequired,
+                        task: None,
+                    });
+                };
+
+                let result = tasks
+                    .modify_task(self.user_id, &task_id, data.to_task_data(), Some(version))
+                    .await
+                    .map(|result| result.map(|(task, event_id)| (event_id, Some(task))));
+
+                (request_id, result)
+            }
+            ClientMessage::DeleteTask {
+                request_id,
+                task_id,
+            } => {
+                let result = tasks
+                    .delete_task(self.user_id, &task_id)
+                    .await
+                    .map(|result| {
+                        result
+                            .map(|event_id| (event_id, None))
+                            .map_err(|_| ModifyTaskError::TaskNotFound)
+                    });
+
+                (request_id, result)
+            }
+            ClientMessage::Editing { task_id } => {
+                self.presence.set_editing(task_id);
+                return None;
+            }
+        };
+
+        Some(match result {
+            Ok(Ok((event_id, task))) => {
+                self.own_events.insert(event_id);
+
+                ServerMessage::Ack {
+                    request_id,
+                    event_id,
+                    task: task.as_ref().map(TaskPayload::from),
+                }
+            }
+            Ok(Err(ModifyTaskError::TaskNotFound)) => ServerMessage::Error {
+                request_id: Some(request_id),
+                error_code: ApiError::TaskNotFound,
+                task: None,
+            },
+            Ok(Err(ModifyTaskError::CategoryNotFound)) => ServerMessage::Error {
+                request_id: Some(request_id),
+                error_code: ApiError::CategoryNotFound,
+                task: None,
+            },
+            Ok(Err(ModifyTaskError::VersionConflict(current))) => ServerMessage::Error {
+                request_id: Some(request_id),
+                error_code: ApiError::VersionConflict,
+                task: Some(TaskPayload::from(current.as_ref())),
+            },
+            Err(err) => {
+                log::error!("Board channel command failed: {:?}", err);
+
+                ServerMessage::Error {
+                    request_id: Some(request_id),
+                    error_code: ApiError::ServerError,
+                    task: None,
+                }
+            }
+        })
+    }
+
+    fn handle_event(&mut self, event: &BoardEvent) -> Option<ServerMessage> {
+        if self.own_events.remove(&event.event_id) {
+            return None;
+        }
+
+        let (name, data) = EventPayload::from_event(&event.kind);
+
+        Some(ServerMessage::Event {
+            event: name,
+            event_id: event.event_id,
+            data,
+        })
+    }
+
+    fn presence_message(&self) -> ServerMessage {
+        let viewers = self.context.presence.viewers(self.board_id);
+
+        ServerMessage::Presence {
+            viewers: viewers.into_iter().map(ViewerResponse::from).collect(),
+        }
+    }
+
+    async fn run(mut self, ws: WebSocket, mut shutdown: Shutdown) -> anyhow::Result<()> {
+        let (mut sink, mut stream) = ws.split();
+
+        let mut subscription = self
+            .context
+            .tasks
+            .subscribe(self.board_id, None)
+            .map_err(|err| anyhow::anyhow!("could not subscribe to the board: {:?}", err))?;
+        let mut presence_changes = self.context.presence.subscribe();
+
+        send(&mut sink, &self.presence_message()).await?;
+
+        loop {
+            let reply = select! {
+                message = stream.next() => match message {
+                    Some(Ok(Message::Text(text))) => self.handle_message(&text).await,
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => None,
+                    Some(Err(err)) => return Err(err.into()),
+                },
+                event = subscription.recv() => match event {
+                    Ok(event) => self.handle_event(&event),
+                    Err(SubscriptionError::MissedEvents) => {
+                        send(&mut sink, &ServerMessage::Reset).await?;
+                        break;
+                    }
+                    Err(SubscriptionError::Closed) => break,
+                },
+                board_id = presence_changes.recv() => match board_id {
+                    Ok(board_id) if board_id == self.board_id => Some(self.presence_message()),
+                    Ok(_) => None,
+                    Err(RecvError::Lagged(_)) => Some(self.presence_message()),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = &mut shutdown => break,
+            };
+
+            if let Some(reply) = reply {
+                send(&mut sink, &reply).await?;
+            }
+        }
+
+        sink.close().await?;
+        Ok(())
+    }
+}
+
+async fn send<S>(sink: &mut S, message: &ServerMessage) -> anyhow::Result<()>
+where
+    S: SinkExt<Message> + Unpin,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    sink.send(Message::Text(json::to_string(message)?)).await?;
+    Ok(())
+}
+
+/// WebSocket channel for collaborative editing of the board.
+///
+/// Clients send commands that modify the tasks of the board and report which task they are editing.
+/// The server acknowledges the commands with the versions assigned to the modified tasks,
+/// and pushes the changes made by other clients and the presence of the viewers of the board.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 101, description = "The connection is upgraded to a WebSocket"),
+        (status = 403, description = "Error code `forbidden`", body = Problem<NoData>),
+    ),
+)]
+#[get("/boards/<board_id>/channel")]
+pub async fn board_channel(
+    context: &ContextState,
+    user: AuthorizedUser,
+    board_id: i64,
+    upgrade: WebSocketUpgrade,
+    shutdown: Shutdown,
+) -> Result<WebSocketChannel, ApiError> {
+    let board_id = BoardId::from_raw(board_id);
+
+    if !context.tasks.can_access_board(user.user_id, board_id) {
+        return Err(ApiError::Forbidden);
+    }
+
+    let username = context
+        .auth
+        .get_username(user.user_id)
+        .await
+        .map_err(|err| {
+            log::error!("Server error: {:?}", err);
+            ApiError::ServerError
+        })?
+        .unwrap_or_default();
+
+    let context = context.inner().clone();
+    let presence = context.presence.join(board_id, user.user_id, username);
+
+    let channel = BoardChannel {
+        context,
+        user_id: user.user_id,
+        board_id,
+        presence,
+        own_events: HashSet::new(),
+    };
+
+    Ok(upgrade.accept(move |ws| async move {
+        if let Err(err) = channel.run(ws, shutdown).await {
+            log::warn!("Board channel closed with an error: {:?}", err);
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use rocket::serde::json::{self, Value};
+
+    use crate::{
+        api::Context, create_context, create_inmemory_repositories, model::UserId, read_environment,
+    };
+
+    use super::{BoardChannel, ServerMessage};
+
+    fn join(context: &Arc<Context>, user_id: UserId) -> BoardChannel {
+        BoardChannel {
+            context: context.clone(),
+            user_id,
+            board_id: user_id,
+            presence: context
+                .presence
+                .join(user_id, user_id, "alice_smith".to_string()),
+            own_events: HashSet::new(),
+        }
+    }
+
+    async fn send(channel: &mut BoardChannel, message: Value) -> Value {
+        let reply = channel.handle_message(&message.to_string()).await;
+
+        json::to_value(reply.expect("the command is answered")).unwrap()
+    }
+
+    fn to_value(message: Option<ServerMessage>) -> Option<Value> {
+        message.map(|message| json::to_value(message).unwrap())
+    }
+
+    #[rocket::async_test]
+    async fn commands_are_acknowledged_and_broadcast() -> anyhow::Result<()> {
+        let context = Arc::new(create_context(
+            create_inmemory_repositories(),
+            &read_environment(),
+        ));
+        let (user_id, _) = context
+            .auth
+            .create_user("alice_smith", "Str0ngPassw0rd!")
+            .await?
+            .unwrap();
+        let (categories, _) = context.tasks.fetch_board(user_id, false, None).await?;
+        let category_id = categories[0].category_id.clone();
+
+        let mut sender = join(&context, user_id);
+        let mut other = join(&context, user_id);
+        let mut subscription = context.tasks.subscribe(user_id, None).unwrap();
+
+        let ack = send(
+            &mut sender,
+            json::json!({
+                "type": "create_task",
+                "request_id": "r1",
+                "categoryId": category_id,
+                "label": "Write docs",
+                "description": "",
+            }),
+        )
+        .await;
+        assert_eq!(ack["type"], "ack");
+        assert_eq!(ack["request_id"], "r1");
+        assert_eq!(ack["task"]["label"], "Write docs");
+        let task_id = ack["task"]["task_id"].as_str().unwrap().to_string();
+
+        // The event is acknowledged to the sender and pushed to the other clients.
+        let event = subscription.recv().await.unwrap();
+        assert_eq!(ack["event_id"], event.event_id);
+        assert!(sender.handle_event(&event).is_none());
+
+        let broadcast = to_value(other.handle_event(&event)).unwrap();
+        assert_eq!(broadcast["type"], "event");
+        assert_eq!(broadcast["event"], "task_created");
+        assert_eq!(broadcast["event_id"], event.event_id);
+        assert_eq!(broadcast["data"]["task"]["task_id"], task_id.as_str());
+
+        // The errors have the codes of the REST API.
+        let modification = |request_id: &str, version: Option<i64>| {
+            let mut message = json::json!({
+                "type": "modify_task",
+                "request_id": request_id,
+                "task_id": task_id,
+                "categoryId": category_id,
+                "label": "Write the docs",
+                "description": "",
+            });
+            if let Some(version) = version {
+                message["version"] = version.into();
+            }
+            message
+        };
+
+        let error = send(&mut sender, modification("r2", None)).await;
+        assert_eq!(error["type"], "error");
+        assert_eq!(error["request_id"], "r2");
+        assert_eq!(error["error_code"], "version_required");
+
+        let error = send(&mut sender, modification("r3", Some(7))).await;
+        assert_eq!(error["error_code"], "version_conflict");
+        assert_eq!(error["task"]["version"], 1);
+
+        let ack = send(&mut sender, modification("r4", Some(1))).await;
+        assert_eq!(ack["type"], "ack");
+        assert_eq!(ack["task"]["version"], 2);
+
+        let error = send(
+            &mut sender,
+            json::json!({ "type": "rename", "request_id": "r5" }),
+        )
+        .await;
+        assert_eq!(error["request_id"], "r5");
+        assert_eq!(error["error_code"], "invalid_body");
+
+        Ok(())
+    }
+}
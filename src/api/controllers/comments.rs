@@ -0,0 +1,227 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    app::comments::CommentError,
+    model::comments::{CommentDescription, CommentId, CommentRevision},
+};
+
+use super::super::{
+    idempotency::{idempotent, IdempotencyKey, Idempotent},
+    ApiError, ContextState, NoData, Problem, Response, ResponseBody,
+};
+
+use super::auth::AuthorizedUser;
+
+#[derive(Serialize, ToSchema)]
+pub struct Comment {
+    #[schema(value_type = String)]
+    comment_id: CommentId,
+    author_id: i64,
+    /// `None` if the comment has been deleted.
+    text: Option<String>,
+    created_at: DateTime<Utc>,
+    edited_at: Option<DateTime<Utc>>,
+    deleted: bool,
+}
+
+impl From<&CommentDescription> for Comment {
+    fn from(comment: &CommentDescription) -> Self {
+        let deleted = comment.deleted_at.is_some();
+
+        Self {
+            comment_id: comment.comment_id.clone(),
+            author_id: comment.author_id.raw(),
+            text: (!deleted).then(|| comment.text.clone()),
+            created_at: comment.created_at,
+            edited_at: comment.edited_at,
+            deleted,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Revision {
+    text: String,
+    written_at: DateTime<Utc>,
+}
+
+impl From<&CommentRevision> for Revision {
+    fn from(revision: &CommentRevision) -> Self {
+        Self {
+            text: revision.text.clone(),
+            written_at: revision.written_at,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CommentInputData {
+    text: String,
+}
+
+fn comment_error<T>(err: CommentError) -> Response<T> {
+    match err {
+        CommentError::TaskNotFound => Response::from_error(ApiError::TaskNotFound),
+        CommentError::CommentNotFound => Response::from_error(ApiError::CommentNotFound),
+        CommentError::NotAuthor => Response::from_error(ApiError::NotAuthor),
+        CommentError::InvalidText => Response::from_error(ApiError::InvalidText),
+    }
+}
+
+/// Returns the comments of the task, oldest first.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The comments", body = ResponseBody<Vec<Comment>>),
+        (status = 404, description = "Error code `task_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[get("/tasks/<task_id>/comments")]
+pub async fn get_comments(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+) -> Response<Vec<Comment>> {
+    let comments = &context.comments;
+
+    match comments.fetch_comments(user.user_id, task_id).await? {
+        Some(thread) => Response::from_data(thread.iter().map(Comment::from).collect()),
+        None => Response::from_error(ApiError::TaskNotFound),
+    }
+}
+
+#[utoipa::path(
+    security(("session" = [])),
+    params(IdempotencyKey),
+    request_body = CommentInputData,
+    responses(
+        (status = 200, description = "The created comment", body = ResponseBody<Comment>),
+        (status = 404, description = "Error code `task_not_found`", body = Problem<NoData>),
+        (status = 422, description = "Error code `invalid_text`", body = Problem<NoData>),
+    ),
+)]
+#[post(
+    "/tasks/<task_id>/comments",
+    format = "application/json",
+    data = "<data>"
+)]
+pub async fn create_comment(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    data: Idempotent<CommentInputData>,
+) -> Response<Comment> {
+    idempotent(context, Some(user.user_id), data.key(), async {
+        let comments = &context.comments;
+
+        match comments
+            .create_comment(user.user_id, task_id, &data.text)
+            .await?
+        {
+            Ok(comment) => Response::from_data(Comment::from(&comment)),
+            Err(err) => comment_error(err),
+        }
+    })
+    .await
+}
+
+/// Edits the comment of the user. The previous text is kept as a revision.
+#[utoipa::path(
+    security(("session" = [])),
+    request_body = CommentInputData,
+    responses(
+        (status = 200, description = "The modified comment", body = ResponseBody<Comment>),
+        (status = 403, description = "Error code `not_author`", body = Problem<NoData>),
+        (
+            status = 404,
+            description = "Error code `task_not_found` or `comment_not_found`",
+            body = Problem<NoData>,
+        ),
+        (status = 422, description = "Error code `invalid_text`", body = Problem<NoData>),
+    ),
+)]
+#[put(
+    "/tasks/<task_id>/comments/<comment_id>",
+    format = "application/json",
+    data = "<data>"
+)]
+pub async fn modify_comment(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    comment_id: &str,
+    data: Json<CommentInputData>,
+) -> Response<Comment> {
+    let comments = &context.comments;
+
+    match comments
+        .modify_comment(user.user_id, task_id, comment_id, &data.text)
+        .await?
+    {
+        Ok(comment) => Response::from_data(Comment::from(&comment)),
+        Err(err) => comment_error(err),
+    }
+}
+
+/// Deletes the comment of the user. The comment stays in the thread without its text.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The comment is deleted", body = ResponseBody<NoData>),
+        (status = 403, description = "Error code `not_author`", body = Problem<NoData>),
+        (
+            status = 404,
+            description = "Error code `task_not_found` or `comment_not_found`",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
+#[delete("/tasks/<task_id>/comments/<comment_id>")]
+pub async fn delete_comment(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    comment_id: &str,
+) -> Response<()> {
+    let comments = &context.comments;
+
+    match comments
+        .delete_comment(user.user_id, task_id, comment_id)
+        .await?
+    {
+        Ok(()) => Response::from_data(()),
+        Err(err) => comment_error(err),
+    }
+}
+
+/// Returns the previous texts of the comment, oldest first.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The revisions", body = ResponseBody<Vec<Revision>>),
+        (
+            status = 404,
+            description = "Error code `task_not_found` or `comment_not_found`",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
+#[get("/tasks/<task_id>/comments/<comment_id>/revisions")]
+pub async fn get_comment_revisions(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    comment_id: &str,
+) -> Response<Vec<Revision>> {
+    let comments = &context.comments;
+
+    match comments
+        .fetch_revisions(user.user_id, task_id, comment_id)
+        .await?
+    {
+        Ok(revisions) => Response::from_data(revisions.iter().map(Revision::from).collect()),
+        Err(err) => comment_error(err),
+    }
+}
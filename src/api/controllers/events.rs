@@ -0,0 +1,225 @@
+use std::convert::Infallible;
+
+use chrono::{DateTime, Utc};
+
+use rocket::{
+    request::{FromRequest, Outcome},
+    response::stream::{Event, EventStream},
+    serde::Serialize,
+    tokio::select,
+    Request, Shutdown,
+};
+
+use crate::{
+    app::events::{BoardEvent, BoardEventKind, EventId, SubscriptionError},
+    model::{
+        tasks::{TaskDescription, Version},
+        BoardId, LabelId, TaskCategoryId, TaskId,
+    },
+};
+
+use super::super::{ApiError, ContextState, NoData, Problem};
+
+use super::{auth::AuthorizedUser, checklists::Progress, labels::Label};
+
+/// The ID of the last event received by the client, sent in `Last-Event-ID` header on reconnection.
+pub struct LastEventId(Option<EventId>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LastEventId {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let last_event_id = request
+            .headers()
+            .get_one("Last-Event-ID")
+            .and_then(|value| value.trim().parse().ok());
+
+        Outcome::Success(LastEventId(last_event_id))
+    }
+}
+
+#[derive(Serialize)]
+pub struct TaskPayload {
+    task_id: TaskId,
+    category_id: TaskCategoryId,
+    label: String,
+    description: String,
+    start_at: Option<DateTime<Utc>>,
+    due_at: Option<DateTime<Utc>>,
+    priority: &'static str,
+    label_ids: Vec<LabelId>,
+    checklist_progress: Progress,
+    version: Version,
+}
+
+impl From<&TaskDescription> for TaskPayload {
+    fn from(task: &TaskDescription) -> Self {
+        Self {
+            task_id: task.task_id.clone(),
+            category_id: task.category_id.clone(),
+            label: task.label.clone(),
+            description: task.description.clone(),
+            start_at: task.start_at,
+            due_at: task.due_at,
+            priority: task.priority.as_str(),
+            label_ids: task.label_ids.clone(),
+            checklist_progress: task.checklist_progress.into(),
+            version: task.version,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum EventPayload {
+    Task {
+        task: TaskPayload,
+    },
+    TaskMoved {
+        task: TaskPayload,
+        from_category_id: TaskCategoryId,
+    },
+    TaskDeleted {
+        task_id: TaskId,
+    },
+    CategoryRemoved {
+        category_id: TaskCategoryId,
+    },
+    Category {
+        category_id: TaskCategoryId,
+        label: String,
+        version: Version,
+    },
+    BoardImported {
+        category_ids: Vec<TaskCategoryId>,
+        task_count: usize,
+    },
+    Label {
+        label: Label,
+    },
+    LabelDeleted {
+        label_id: LabelId,
+    },
+}
+
+impl EventPayload {
+    /// Returns the name of the event and its payload.
+    pub fn from_event(event: &BoardEventKind) -> (&'static str, EventPayload) {
+        let payload = match event {
+            BoardEventKind::TaskCreated(task) | BoardEventKind::TaskUpdated(task) => {
+                EventPayload::Task { task: task.into() }
+            }
+            BoardEventKind::TaskMoved {
+                task,
+                from_category_id,
+            } => EventPayload::TaskMoved {
+                task: task.into(),
+                from_category_id: from_category_id.clone(),
+            },
+            BoardEventKind::TaskDeleted { task_id } => EventPayload::TaskDeleted {
+                task_id: task_id.clone(),
+            },
+            BoardEventKind::CategoryRemoved { category_id } => EventPayload::CategoryRemoved {
+                category_id: category_id.clone(),
+            },
+            BoardEventKind::CategoryCreated(category)
+            | BoardEventKind::CategoryUpdated(category)
+            | BoardEventKind::CategoryRestored(category) => EventPayload::Category {
+                category_id: category.category_id.clone(),
+                label: category.label.clone(),
+                version: category.version,
+            },
+            BoardEventKind::BoardImported {
+                category_ids,
+                task_count,
+            } => EventPayload::BoardImported {
+                category_ids: category_ids.clone(),
+                task_count: *task_count,
+            },
+            BoardEventKind::LabelCreated(label) | BoardEventKind::LabelUpdated(label) => {
+                EventPayload::Label {
+                    label: label.into(),
+                }
+            }
+            BoardEventKind::LabelDeleted { label_id } => EventPayload::LabelDeleted {
+                label_id: label_id.clone(),
+            },
+        };
+
+        (event.name(), payload)
+    }
+}
+
+fn make_event(event: &BoardEvent) -> Event {
+    let (name, payload) = EventPayload::from_event(&event.kind);
+
+    Event::json(&payload)
+        .event(name)
+        .id(event.event_id.to_string())
+}
+
+/// Tells the client that it has missed some events and has to reload the board.
+fn make_reset_event() -> Event {
+    Event::data("{}").event("reset")
+}
+
+/// Streams the changes of the board as server-sent events.
+///
+/// On reconnection the events after `Last-Event-ID` are replayed, or a `reset` event is sent
+/// if they are not available anymore.
+#[utoipa::path(
+    security(("session" = [])),
+    params(("Last-Event-ID" = Option<u64>, Header, description = "ID of the last event received by the client")),
+    responses(
+        (status = 200, description = "The stream of the events", content_type = "text/event-stream", body = String),
+        (status = 403, description = "Error code `forbidden`", body = Problem<NoData>),
+    ),
+)]
+#[get("/boards/<board_id>/events")]
+pub async fn board_events(
+    context: &ContextState,
+    user: AuthorizedUser,
+    board_id: i64,
+    last_event_id: LastEventId,
+    mut shutdown: Shutdown,
+) -> Result<EventStream![Event + 'static], ApiError> {
+    let board_id = BoardId::from_raw(board_id);
+
+    if !context.tasks.can_access_board(user.user_id, board_id) {
+        return Err(ApiError::Forbidden);
+    }
+
+    // If the missed events are not available anymore, the client has to reload the board
+    // and continue with the live events.
+    let (missed_events, mut subscription) = match context.tasks.subscribe(board_id, last_event_id.0)
+    {
+        Ok(subscription) => (false, subscription),
+        Err(_) => match context.tasks.subscribe(board_id, None) {
+            Ok(subscription) => (true, subscription),
+            Err(_) => return Err(ApiError::ServerError),
+        },
+    };
+
+    Ok(EventStream! {
+        if missed_events {
+            yield make_reset_event();
+        }
+
+        loop {
+            let event = select! {
+                event = subscription.recv() => event,
+                _ = &mut shutdown => break,
+            };
+
+            match event {
+                Ok(event) => yield make_event(&event),
+                Err(SubscriptionError::MissedEvents) => {
+                    yield make_reset_event();
+                    break;
+                }
+                Err(SubscriptionError::Closed) => break,
+            }
+        }
+    })
+}
@@ -0,0 +1,541 @@
+use std::{collections::HashMap, iter, vec};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use rocket::{
+    futures::stream::{self, Iter},
+    http::{ContentType, Header},
+    response::{self, stream::ByteStream, Responder},
+    serde::json,
+    Request,
+};
+
+use crate::model::{tasks::TaskPriority, BoardId, LabelId};
+
+use super::super::{ApiError, ContextState, NoData, Problem};
+
+use super::{
+    auth::AuthorizedUser,
+    import::BoardExportDocument,
+    tasks::{make_tasks_board, Task, TaskCategory, TasksBoard},
+};
+
+/// Name of the JSON export format, checked when the export is imported back.
+pub(super) const EXPORT_SCHEMA: &str = "board-export";
+
+/// Version of the JSON export format, incremented on incompatible changes.
+pub(super) const EXPORT_VERSION: u32 = 1;
+
+/// Header of the CSV export, which is also understood by the CSV import.
+const CSV_HEADER: &str = "column,title,description,labels,start date,due date,priority\r\n";
+
+/// Board sent column by column.
+pub struct BoardExport {
+    stream: ByteStream<Iter<ExportChunks>>,
+    content_type: ContentType,
+    disposition: Header<'static>,
+}
+
+impl<'r> Responder<'r, 'r> for BoardExport {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'r> {
+        rocket::Response::build_from(self.stream.respond_to(request)?)
+            .header(self.content_type)
+            .header(self.disposition)
+            .ok()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            "md" => Some(ExportFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+
+    fn content_type(self) -> ContentType {
+        match self {
+            ExportFormat::Csv => ContentType::new("text", "csv").with_params(("charset", "utf-8")),
+            ExportFormat::Json => ContentType::JSON,
+            ExportFormat::Markdown => {
+                ContentType::new("text", "markdown").with_params(("charset", "utf-8"))
+            }
+        }
+    }
+}
+
+fn format_date(date: Option<DateTime<Utc>>) -> String {
+    date.map(|d| d.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) || value.trim() != value {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escapes the characters that would format the text in Markdown.
+fn markdown_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+fn label_names<'a>(task: &Task, labels: &'a HashMap<LabelId, String>) -> Vec<&'a str> {
+    task.label_ids
+        .iter()
+        .filter_map(|id| labels.get(id).map(String::as_str))
+        .collect()
+}
+
+fn csv_column(category: &TaskCategory, labels: &HashMap<LabelId, String>) -> String {
+    let mut chunk = String::new();
+
+    for task in &category.ordered_tasks {
+        let fields = [
+            csv_field(&category.label),
+            csv_field(&task.label),
+            csv_field(&task.description),
+            csv_field(&label_names(task, labels).join("; ")),
+            format_date(task.start_at),
+            format_date(task.due_at),
+            task.priority.to_string(),
+        ];
+
+        chunk.push_str(&fields.join(","));
+        chunk.push_str("\r\n");
+    }
+
+    chunk
+}
+
+fn markdown_column(category: &TaskCategory, labels: &HashMap<LabelId, String>) -> String {
+    let mut chunk = format!("## {}", markdown_text(&category.label));
+    if category.archived {
+        chunk.push_str(" (archived)");
+    }
+    chunk.push_str("\n\n");
+
+    if category.ordered_tasks.is_empty() {
+        chunk.push_str("_No tasks_\n\n");
+        return chunk;
+    }
+
+    for task in &category.ordered_tasks {
+        chunk.push_str(&format!("- **{}**", markdown_text(&task.label)));
+
+        if task.archived {
+            chunk.push_str(" (archived)");
+        }
+        if let Some(due_at) = task.due_at {
+            chunk.push_str(&format!(" · due {}", format_date(Some(due_at))));
+        }
+        if task.priority != TaskPriority::default().as_str() {
+            chunk.push_str(&format!(" · {} priority", task.priority));
+        }
+
+        let names = label_names(task, labels);
+        if !names.is_empty() {
+            let names: Vec<String> = names.iter().map(|name| markdown_text(name)).collect();
+            chunk.push_str(&format!(" · {}", names.join(", ")));
+        }
+        chunk.push('\n');
+
+        for line in task.description.lines().filter(|l| !l.trim().is_empty()) {
+            chunk.push_str(&format!("  {}\n", markdown_text(line)));
+        }
+    }
+
+    chunk.push('\n');
+    chunk
+}
+
+/// Chunks of the exported board: the header, a chunk per column rendered only when it is
+/// requested by the stream, and the footer.
+struct ExportChunks {
+    format: ExportFormat,
+    header: Option<String>,
+    columns: iter::Enumerate<vec::IntoIter<Box<TaskCategory>>>,
+    /// Names of the labels by ID.
+    names: HashMap<LabelId, String>,
+    footer: Option<&'static str>,
+}
+
+impl ExportChunks {
+    fn render_column(&self, index: usize, category: &TaskCategory) -> anyhow::Result<String> {
+        Ok(match self.format {
+            ExportFormat::Csv => csv_column(category, &self.names),
+            ExportFormat::Json => {
+                let separator = if index > 0 { "," } else { "" };
+                format!("{separator}{}", json::to_string(category)?)
+            }
+            ExportFormat::Markdown => markdown_column(category, &self.names),
+        })
+    }
+}
+
+impl Iterator for ExportChunks {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if let Some(header) = self.header.take() {
+            return Some(header.into_bytes());
+        }
+
+        let Some((index, category)) = self.columns.next() else {
+            return self.footer.take().map(|footer| footer.as_bytes().to_vec());
+        };
+
+        match self.render_column(index, &category) {
+            Ok(chunk) => Some(chunk.into_bytes()),
+            Err(err) => {
+                // The response has already started, so the export is cut short.
+                log::error!("Server error: {:?}", err);
+                self.columns = Vec::new().into_iter().enumerate();
+                self.footer = None;
+                None
+            }
+        }
+    }
+}
+
+/// Renders the board in the format. The header is rendered at once, the columns one by one as
+/// the chunks are taken.
+fn render(
+    format: ExportFormat,
+    board: TasksBoard,
+    exported_at: DateTime<Utc>,
+) -> anyhow::Result<ExportChunks> {
+    let names: HashMap<LabelId, String> = board
+        .labels
+        .iter()
+        .map(|label| (label.label_id.clone(), label.name.clone()))
+        .collect();
+
+    let header = match format {
+        ExportFormat::Csv => CSV_HEADER.to_string(),
+        // The fields of `BoardExportDocument`, with the columns appended one by one.
+        ExportFormat::Json => format!(
+            "{{\"schema\":{},\"version\":{},\"exported_at\":{},\"labels\":{},\"ordered_categories\":[",
+            json::to_string(&EXPORT_SCHEMA)?,
+            EXPORT_VERSION,
+            json::to_string(&exported_at)?,
+            json::to_string(&board.labels)?,
+        ),
+        ExportFormat::Markdown => {
+            format!(
+                "# Board export\n\nExported at {}.\n\n",
+                format_date(Some(exported_at))
+            )
+        }
+    };
+    let footer = match format {
+        ExportFormat::Json => "]}",
+        ExportFormat::Csv | ExportFormat::Markdown => "",
+    };
+
+    Ok(ExportChunks {
+        format,
+        header: Some(header),
+        columns: board.ordered_categories.into_iter().enumerate(),
+        names,
+        footer: Some(footer),
+    })
+}
+
+/// Exports the board as `csv`, `json` or `md`. Columns and tasks keep the board order.
+/// Archived columns and tasks are included only if `archived=true`.
+///
+/// The JSON export has the shape of the board returned by `GET /tasks`, preceded by the
+/// `schema` and `version` of the format, and can be imported back with `POST /import/board`.
+#[utoipa::path(
+    security(("session" = [])),
+    params(("format" = String, Query, description = "`csv`, `json` or `md`")),
+    responses(
+        (
+            status = 200,
+            description = "The board as an attachment",
+            content(
+                (BoardExportDocument = "application/json"),
+                (String = "text/csv"),
+                (String = "text/markdown"),
+            ),
+        ),
+        (status = 403, description = "Error code `forbidden`", body = Problem<NoData>),
+        (status = 422, description = "Error code `unknown_format`", body = Problem<NoData>),
+    ),
+)]
+#[get("/boards/<board_id>/export?<format>&<archived>")]
+pub async fn export_board(
+    context: &ContextState,
+    user: AuthorizedUser,
+    board_id: i64,
+    format: &str,
+    archived: Option<bool>,
+) -> Result<BoardExport, ApiError> {
+    let tasks = &context.tasks;
+    let board_id = BoardId::from_raw(board_id);
+
+    let Some(format) = ExportFormat::parse(format) else {
+        return Err(ApiError::UnknownFormat);
+    };
+
+    if !tasks.can_access_board(user.user_id, board_id) {
+        return Err(ApiError::Forbidden);
+    }
+
+    let server_error = |err: anyhow::Error| {
+        log::error!("Server error: {:?}", err);
+        ApiError::ServerError
+    };
+
+    let (categories, task_descriptions) = tasks
+        .fetch_board(board_id, archived.unwrap_or(false), None)
+        .await
+        .map_err(server_error)?;
+    let label_descriptions = tasks.fetch_labels(board_id).await.map_err(server_error)?;
+
+    let board = make_tasks_board(&task_descriptions, &categories, &label_descriptions)
+        .map_err(server_error)?;
+
+    let chunks = render(format, board, Utc::now()).map_err(server_error)?;
+
+    let disposition = format!(
+        "attachment; filename=\"board-{}.{}\"",
+        board_id.raw(),
+        format.extension()
+    );
+
+    Ok(BoardExport {
+        stream: ByteStream(stream::iter(chunks)),
+        content_type: format.content_type(),
+        disposition: Header::new("Content-Disposition", disposition),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{DateTime, TimeZone, Utc};
+    use rocket::serde::json;
+
+    use crate::{
+        app::{
+            attachments::{AttachmentLimits, AttachmentsService},
+            events::EventBus,
+            repositories::TasksRepository,
+            tasks::TasksService,
+        },
+        model::{
+            labels::LabelData,
+            tasks::{TaskData, TaskPriority},
+            UserId,
+        },
+        storage::inmemory,
+    };
+
+    use super::{
+        csv_field, make_tasks_board, render, BoardExportDocument, ExportFormat, EXPORT_SCHEMA,
+        EXPORT_VERSION,
+    };
+
+    const USER_ID: UserId = UserId::from_raw(1);
+    const OTHER_USER_ID: UserId = UserId::from_raw(2);
+
+    fn exported_at() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()
+    }
+
+    fn task_data(label: &str, description: &str, category_id: &str) -> TaskData {
+        TaskData {
+            label: label.to_string(),
+            description: description.to_string(),
+            category_id: category_id.to_string(),
+            start_at: None,
+            due_at: None,
+            priority: Default::default(),
+        }
+    }
+
+    /// Returns the service with a board of two columns, `ToDo` with three tasks and an empty `Done`.
+    async fn setup_board() -> anyhow::Result<TasksService> {
+        let activity = Arc::new(inmemory::InMemoryActivity::new());
+        let sync = Arc::new(inmemory::InMemorySync::new());
+        let tasks = Arc::new(inmemory::InMemoryTasks::new(
+            activity.clone(),
+            sync.clone(),
+            Arc::new(inmemory::InMemoryWebhooks::new()),
+        ));
+        let categories = tasks.add_categories(USER_ID, &["ToDo", "Done"]).await?;
+
+        let attachments = AttachmentsService::new(
+            Arc::new(inmemory::InMemoryAttachments::new()),
+            tasks.clone(),
+            Arc::new(inmemory::InMemoryBlobs::new()),
+            AttachmentLimits::default(),
+        );
+
+        let service = TasksService::new(
+            tasks,
+            Arc::new(EventBus::new()),
+            Arc::new(attachments),
+            activity,
+            sync,
+        );
+
+        let todo = &categories[0].category_id;
+
+        let (first, _) = service
+            .create_task(USER_ID, task_data("Buy milk, eggs", "", todo))
+            .await?
+            .expect("failed to create task");
+        service
+            .create_task(
+                USER_ID,
+                TaskData {
+                    due_at: Some(exported_at()),
+                    priority: TaskPriority::High,
+                    ..task_data("Call *Bob*", "Say \"hi\"\nand bye", todo)
+                },
+            )
+            .await?
+            .expect("failed to create task");
+        service
+            .create_task(USER_ID, task_data("Water plants", "", todo))
+            .await?
+            .expect("failed to create task");
+
+        let label = service
+            .create_label(
+                USER_ID,
+                LabelData {
+                    name: "home".to_string(),
+                    color: "#00ff00".to_string(),
+                },
+            )
+            .await?
+            .unwrap();
+        service
+            .set_label_assigned(USER_ID, &first.task_id, &label.label_id, true)
+            .await?
+            .unwrap();
+
+        Ok(service)
+    }
+
+    async fn export(
+        service: &TasksService,
+        user_id: UserId,
+        format: ExportFormat,
+    ) -> anyhow::Result<String> {
+        let (categories, tasks) = service.fetch_board(user_id, false, None).await?;
+        let labels = service.fetch_labels(user_id).await?;
+        let board = make_tasks_board(&tasks, &categories, &labels)?;
+
+        let chunks = render(format, board, exported_at())?;
+
+        Ok(String::from_utf8(chunks.collect::<Vec<_>>().concat())?)
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("plain text"), "plain text");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\r\nlines"), "\"two\r\nlines\"");
+        assert_eq!(csv_field(" padded"), "\" padded\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[tokio::test]
+    async fn csv_export_lists_tasks_in_board_order() -> anyhow::Result<()> {
+        let service = setup_board().await?;
+
+        assert_eq!(
+            export(&service, USER_ID, ExportFormat::Csv).await?,
+            "column,title,description,labels,start date,due date,priority\r\n\
+            ToDo,\"Buy milk, eggs\",,home,,,normal\r\n\
+            ToDo,Call *Bob*,\"Say \"\"hi\"\"\nand bye\",,,2024-05-01T12:00:00Z,high\r\n\
+            ToDo,Water plants,,,,,normal\r\n"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn markdown_export_escapes_text() -> anyhow::Result<()> {
+        let service = setup_board().await?;
+
+        assert_eq!(
+            export(&service, USER_ID, ExportFormat::Markdown).await?,
+            "# Board export\n\nExported at 2024-05-01T12:00:00Z.\n\n\
+            ## ToDo\n\n\
+            - **Buy milk, eggs** · home\n\
+            - **Call \\*Bob\\*** · due 2024-05-01T12:00:00Z · high priority\n  \
+            Say \"hi\"\n  \
+            and bye\n\
+            - **Water plants**\n\n\
+            ## Done\n\n\
+            _No tasks_\n\n"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn json_export_is_imported_back() -> anyhow::Result<()> {
+        let service = setup_board().await?;
+
+        let text = export(&service, USER_ID, ExportFormat::Json).await?;
+
+        let value: json::Value = json::from_str(&text)?;
+        assert_eq!(value["schema"], EXPORT_SCHEMA);
+        assert_eq!(value["version"], EXPORT_VERSION);
+        assert_eq!(value["exported_at"], "2024-05-01T12:00:00Z");
+
+        let document: BoardExportDocument = json::from_str(&text)?;
+        let report = service
+            .import_board(OTHER_USER_ID, document.into(), false)
+            .await?
+            .unwrap();
+        assert_eq!(report.task_count, 3);
+        assert!(report.warnings.is_empty());
+
+        assert_eq!(
+            export(&service, OTHER_USER_ID, ExportFormat::Csv).await?,
+            export(&service, USER_ID, ExportFormat::Csv).await?
+        );
+
+        Ok(())
+    }
+}
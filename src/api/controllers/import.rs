@@ -0,0 +1,493 @@
+use rocket::{
+    data::{Data, ToByteUnit},
+    serde::{json, Deserialize, Serialize},
+    Request,
+};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    app::import::{
+        self, ImportCard, ImportError, ImportReport, ImportSource, TrelloBoard, TrelloCard,
+        TrelloLabel, TrelloList,
+    },
+    model::{labels::LabelData, tasks::TaskPriority, LabelId, TaskCategoryId},
+};
+
+use super::super::{
+    idempotency::{idempotent, IdempotencyKey, Idempotent, IdempotentBody},
+    ApiError, ContextState, NoData, Problem, Response, ResponseBody,
+};
+
+use super::{
+    auth::AuthorizedUser,
+    export::{EXPORT_SCHEMA, EXPORT_VERSION},
+};
+
+#[derive(Deserialize, ToSchema)]
+pub struct TrelloExportList {
+    id: String,
+    name: String,
+    #[serde(default)]
+    closed: bool,
+    #[serde(default)]
+    pos: f64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TrelloExportLabel {
+    id: String,
+    #[serde(default)]
+    name: String,
+    color: Option<String>,
+}
+
+#[derive(Deserialize, Default, ToSchema)]
+pub struct TrelloExportBadges {
+    #[serde(default)]
+    attachments: usize,
+    #[serde(default)]
+    comments: usize,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[allow(non_snake_case)]
+pub struct TrelloExportCard {
+    idList: String,
+    name: String,
+    #[serde(default)]
+    desc: String,
+    #[serde(default)]
+    closed: bool,
+    #[serde(default)]
+    pos: f64,
+    #[serde(default)]
+    idLabels: Vec<String>,
+    start: Option<String>,
+    due: Option<String>,
+    #[serde(default)]
+    idChecklists: Vec<String>,
+    #[serde(default)]
+    badges: TrelloExportBadges,
+}
+
+/// The parts of a Trello board export that are imported.
+#[derive(Deserialize, ToSchema)]
+pub struct TrelloExport {
+    #[serde(default)]
+    lists: Vec<TrelloExportList>,
+    #[serde(default)]
+    labels: Vec<TrelloExportLabel>,
+    #[serde(default)]
+    cards: Vec<TrelloExportCard>,
+}
+
+impl From<TrelloExport> for TrelloBoard {
+    fn from(export: TrelloExport) -> Self {
+        Self {
+            lists: export
+                .lists
+                .into_iter()
+                .map(|list| TrelloList {
+                    id: list.id,
+                    name: list.name,
+                    closed: list.closed,
+                    pos: list.pos,
+                })
+                .collect(),
+            labels: export
+                .labels
+                .into_iter()
+                .map(|label| TrelloLabel {
+                    id: label.id,
+                    name: label.name,
+                    color: label.color,
+                })
+                .collect(),
+            cards: export
+                .cards
+                .into_iter()
+                .map(|card| TrelloCard {
+                    list_id: card.idList,
+                    name: card.name,
+                    desc: card.desc,
+                    closed: card.closed,
+                    pos: card.pos,
+                    label_ids: card.idLabels,
+                    start: card.start,
+                    due: card.due,
+                    checklist_count: card.idChecklists.len(),
+                    attachment_count: card.badges.attachments,
+                    comment_count: card.badges.comments,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ExportedLabel {
+    #[schema(value_type = String)]
+    label_id: LabelId,
+    name: String,
+    color: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ExportedTask {
+    label: String,
+    #[serde(default)]
+    description: String,
+    start_at: Option<DateTime<Utc>>,
+    due_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    priority: String,
+    #[serde(default)]
+    #[schema(value_type = Vec<String>)]
+    label_ids: Vec<LabelId>,
+    #[serde(default)]
+    archived: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ExportedCategory {
+    label: String,
+    #[serde(default)]
+    archived: bool,
+    #[serde(default)]
+    ordered_tasks: Vec<ExportedTask>,
+}
+
+/// Board exported with `GET /boards/<board_id>/export?format=json`.
+#[derive(Deserialize, ToSchema)]
+pub struct BoardExportDocument {
+    schema: String,
+    version: u32,
+    #[serde(default)]
+    labels: Vec<ExportedLabel>,
+    #[serde(default)]
+    ordered_categories: Vec<ExportedCategory>,
+}
+
+impl From<BoardExportDocument> for ImportSource {
+    /// Archived columns and tasks are not imported.
+    fn from(document: BoardExportDocument) -> Self {
+        let mut source = ImportSource::default();
+
+        for category in document.ordered_categories {
+            if category.archived {
+                source.warnings.push(import::ImportWarning::new(
+                    format!("column \"{}\"", category.label),
+                    "archived column and its tasks were not imported",
+                ));
+                continue;
+            }
+
+            source.columns.push(category.label.clone());
+
+            for task in category.ordered_tasks {
+                let location = format!("task \"{}\"", task.label);
+
+                if task.archived {
+                    source.warnings.push(import::ImportWarning::new(
+                        location,
+                        "archived task was not imported",
+                    ));
+                    continue;
+                }
+
+                let labels = task
+                    .label_ids
+                    .iter()
+                    .filter_map(|id| document.labels.iter().find(|l| &l.label_id == id))
+                    .map(|label| LabelData {
+                        name: label.name.clone(),
+                        color: label.color.clone(),
+                    })
+                    .collect();
+
+                source.cards.push(ImportCard {
+                    column: category.label.clone(),
+                    title: task.label,
+                    description: task.description,
+                    labels,
+                    start_at: task.start_at,
+                    due_at: task.due_at,
+                    priority: TaskPriority::parse(&task.priority).unwrap_or_default(),
+                    location,
+                });
+            }
+        }
+
+        source
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportColumn {
+    #[schema(value_type = Option<String>)]
+    category_id: Option<TaskCategoryId>,
+    label: String,
+    created: bool,
+    task_count: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportLabel {
+    #[schema(value_type = Option<String>)]
+    label_id: Option<LabelId>,
+    name: String,
+    color: String,
+    created: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportWarning {
+    location: String,
+    message: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum ImportResult {
+    Report {
+        dry_run: bool,
+        columns: Vec<ImportColumn>,
+        labels: Vec<ImportLabel>,
+        task_count: usize,
+        warnings: Vec<ImportWarning>,
+    },
+    InvalidCsv {
+        line: usize,
+    },
+    MissingColumn {
+        column: &'static str,
+    },
+}
+
+impl From<ImportReport> for ImportResult {
+    fn from(report: ImportReport) -> Self {
+        ImportResult::Report {
+            dry_run: report.dry_run,
+            columns: report
+                .columns
+                .into_iter()
+                .map(|column| ImportColumn {
+                    category_id: column.category_id,
+                    label: column.label,
+                    created: column.created,
+                    task_count: column.task_count,
+                })
+                .collect(),
+            labels: report
+                .labels
+                .into_iter()
+                .map(|label| ImportLabel {
+                    label_id: label.label_id,
+                    name: label.name,
+                    color: label.color,
+                    created: label.created,
+                })
+                .collect(),
+            task_count: report.task_count,
+            warnings: report
+                .warnings
+                .into_iter()
+                .map(|warning| ImportWarning {
+                    location: warning.location,
+                    message: warning.message,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn import_failure(error: ImportError) -> Response<ImportResult> {
+    match error {
+        ImportError::InvalidCsv { line } => {
+            Response::from_error_details(ApiError::InvalidCsv, ImportResult::InvalidCsv { line })
+        }
+        ImportError::MissingColumn(column) => Response::from_error_details(
+            ApiError::MissingColumn,
+            ImportResult::MissingColumn { column },
+        ),
+        ImportError::TooManyTasks => Response::from_error(ApiError::TooManyTasks),
+    }
+}
+
+/// Whole body of an import request, up to the `import` limit.
+pub struct ImportFile(String);
+
+#[rocket::async_trait]
+impl<'r> IdempotentBody<'r> for ImportFile {
+    async fn read(
+        request: &'r Request<'_>,
+        data: Data<'r>,
+        fingerprint: &mut Sha256,
+    ) -> Result<Self, ApiError> {
+        let limit = request.limits().get("import").unwrap_or(8.mebibytes());
+
+        let body = match data.open(limit).into_string().await {
+            Ok(body) if body.is_complete() => body.into_inner(),
+            Ok(_) => return Err(ApiError::FileTooLarge.cache(request, None)),
+            Err(err) => return Err(ApiError::BadRequest.cache(request, Some(err.to_string()))),
+        };
+
+        fingerprint.update(&body);
+
+        Ok(Self(body))
+    }
+}
+
+async fn import(
+    context: &ContextState,
+    user: &AuthorizedUser,
+    source: ImportSource,
+    dry_run: Option<bool>,
+) -> Response<ImportResult> {
+    let result = context
+        .tasks
+        .import_board(user.user_id, source, dry_run.unwrap_or(false))
+        .await?;
+
+    match result {
+        Ok(report) => Response::from_data(report.into()),
+        Err(error) => import_failure(error),
+    }
+}
+
+/// Imports the lists and the cards of a Trello board export as categories and tasks.
+/// With `dry_run=true` only reports what would be created.
+/// The retries with the same `Idempotency-Key` header get the response to the first request.
+#[utoipa::path(
+    security(("session" = [])),
+    params(IdempotencyKey),
+    request_body = TrelloExport,
+    responses(
+        (
+            status = 200,
+            description = "What has been imported, or would be in a dry run",
+            body = ResponseBody<ImportResult>,
+        ),
+        (status = 413, description = "Error code `file_too_large`", body = Problem<NoData>),
+        (
+            status = 422,
+            description = "Error code `invalid_trello_export` or `too_many_tasks`",
+            body = Problem<ImportResult>,
+        ),
+    ),
+)]
+#[post(
+    "/import/trello?<dry_run>",
+    format = "application/json",
+    data = "<data>"
+)]
+pub async fn import_trello(
+    context: &ContextState,
+    user: AuthorizedUser,
+    dry_run: Option<bool>,
+    data: Idempotent<ImportFile>,
+) -> Response<ImportResult> {
+    let (ImportFile(body), key) = data.into_parts();
+
+    idempotent(context, Some(user.user_id), key.as_ref(), async {
+        let Ok(export) = json::from_str::<TrelloExport>(&body) else {
+            return Response::from_error(ApiError::InvalidTrelloExport);
+        };
+
+        let source = ImportSource::from_trello(export.into());
+        import(context, &user, source, dry_run).await
+    })
+    .await
+}
+
+/// Imports a board exported in JSON format. Archived columns and tasks are not imported.
+/// With `dry_run=true` only reports what would be created.
+/// The retries with the same `Idempotency-Key` header get the response to the first request.
+#[utoipa::path(
+    security(("session" = [])),
+    params(IdempotencyKey),
+    request_body = BoardExportDocument,
+    responses(
+        (
+            status = 200,
+            description = "What has been imported, or would be in a dry run",
+            body = ResponseBody<ImportResult>,
+        ),
+        (status = 413, description = "Error code `file_too_large`", body = Problem<NoData>),
+        (
+            status = 422,
+            description = "Error code `invalid_board_export`, `unsupported_export_version` \
+                or `too_many_tasks`",
+            body = Problem<ImportResult>,
+        ),
+    ),
+)]
+#[post(
+    "/import/board?<dry_run>",
+    format = "application/json",
+    data = "<data>"
+)]
+pub async fn import_exported_board(
+    context: &ContextState,
+    user: AuthorizedUser,
+    dry_run: Option<bool>,
+    data: Idempotent<ImportFile>,
+) -> Response<ImportResult> {
+    let (ImportFile(body), key) = data.into_parts();
+
+    idempotent(context, Some(user.user_id), key.as_ref(), async {
+        let Ok(document) = json::from_str::<BoardExportDocument>(&body) else {
+            return Response::from_error(ApiError::InvalidBoardExport);
+        };
+
+        if document.schema != EXPORT_SCHEMA || document.version != EXPORT_VERSION {
+            return Response::from_error(ApiError::UnsupportedExportVersion);
+        }
+
+        import(context, &user, document.into(), dry_run).await
+    })
+    .await
+}
+
+/// Imports the rows of a CSV file with `column`, `title`, `description`, `labels`, `start date`,
+/// `due date` and `priority` columns as tasks. With `dry_run=true` only reports what would be created.
+/// The retries with the same `Idempotency-Key` header get the response to the first request.
+#[utoipa::path(
+    security(("session" = [])),
+    params(IdempotencyKey),
+    request_body(content = String, content_type = "text/csv"),
+    responses(
+        (
+            status = 200,
+            description = "What has been imported, or would be in a dry run",
+            body = ResponseBody<ImportResult>,
+        ),
+        (status = 413, description = "Error code `file_too_large`", body = Problem<NoData>),
+        (
+            status = 422,
+            description = "Error code `invalid_csv` with the line, `missing_column` with the column, \
+                or `too_many_tasks`",
+            body = Problem<ImportResult>,
+        ),
+    ),
+)]
+#[post("/import/csv?<dry_run>", format = "text/csv", data = "<data>")]
+pub async fn import_csv(
+    context: &ContextState,
+    user: AuthorizedUser,
+    dry_run: Option<bool>,
+    data: Idempotent<ImportFile>,
+) -> Response<ImportResult> {
+    let (ImportFile(body), key) = data.into_parts();
+
+    idempotent(context, Some(user.user_id), key.as_ref(), async {
+        match ImportSource::from_csv(&body) {
+            Ok(source) => import(context, &user, source, dry_run).await,
+            Err(error) => import_failure(error),
+        }
+    })
+    .await
+}
@@ -0,0 +1,163 @@
+use rocket::{
+    data::{Data, Limits},
+    serde::Serialize,
+};
+use utoipa::ToSchema;
+
+use crate::{
+    app::inbound::{InboundEmail, InboundError, InboundTask},
+    model::BoardId,
+};
+
+use super::super::{
+    ApiError, Context, ContextState, NoData, Problem, Response, ResponseBody, MAX_EMAIL_SIZE,
+};
+
+use super::{
+    attachments::{attachment_api_error, Attachment},
+    auth::AuthorizedUser,
+    tasks::Task,
+};
+
+#[derive(Serialize, ToSchema)]
+pub struct InboundAddress {
+    address: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RejectedAttachment {
+    file_name: String,
+    error: ApiError,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct InboundTaskResult {
+    task: Task,
+    attachments: Vec<Attachment>,
+    /// Attachments that are not allowed, too large or over the quota of the board.
+    rejected_attachments: Vec<RejectedAttachment>,
+}
+
+impl From<&InboundTask> for InboundTaskResult {
+    fn from(result: &InboundTask) -> Self {
+        Self {
+            task: Task::from(&result.task),
+            attachments: result.attachments.iter().map(Attachment::from).collect(),
+            rejected_attachments: result
+                .rejected_attachments
+                .iter()
+                .map(|rejected| RejectedAttachment {
+                    file_name: rejected.file_name.clone(),
+                    error: attachment_api_error(&rejected.error),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Returns the address that turns the messages sent to it into tasks of the board.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The address", body = ResponseBody<InboundAddress>),
+        (status = 403, description = "Error code `forbidden`", body = Problem<NoData>),
+    ),
+)]
+#[get("/boards/<board_id>/inbound-email")]
+pub async fn get_inbound_address(
+    context: &ContextState,
+    user: AuthorizedUser,
+    board_id: i64,
+) -> Response<InboundAddress> {
+    let board_id = BoardId::from_raw(board_id);
+    if !context.tasks.can_access_board(user.user_id, board_id) {
+        return Response::from_error(ApiError::Forbidden);
+    }
+
+    let address = context.inbound.address(board_id).await?;
+
+    Response::from_data(InboundAddress { address })
+}
+
+/// Replaces the inbound address of the board. The previous address stops working.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The new address", body = ResponseBody<InboundAddress>),
+        (status = 403, description = "Error code `forbidden`", body = Problem<NoData>),
+    ),
+)]
+#[post("/boards/<board_id>/inbound-email")]
+pub async fn reset_inbound_address(
+    context: &ContextState,
+    user: AuthorizedUser,
+    board_id: i64,
+) -> Response<InboundAddress> {
+    let board_id = BoardId::from_raw(board_id);
+    if !context.tasks.can_access_board(user.user_id, board_id) {
+        return Response::from_error(ApiError::Forbidden);
+    }
+
+    let address = context.inbound.reset_address(board_id).await?;
+
+    Response::from_data(InboundAddress { address })
+}
+
+/// Turns a raw MIME message into a task of the board whose inbound address is among
+/// the recipients. The recipient may also be given in the query, e.g. by a mail provider
+/// that forwards the messages. The address is the credential, so no session is needed.
+#[utoipa::path(
+    request_body(content = String, content_type = "message/rfc822"),
+    responses(
+        (status = 200, description = "The created task", body = ResponseBody<InboundTaskResult>),
+        (status = 404, description = "Error code `unknown_recipient`", body = Problem<NoData>),
+        (status = 413, description = "Error code `message_too_large`", body = Problem<NoData>),
+        (
+            status = 422,
+            description = "Error code `invalid_message`, or `no_category` if the board has no \
+                categories",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
+#[post("/inbound/email?<recipient>", data = "<data>")]
+pub async fn receive_inbound_email(
+    context: &ContextState,
+    limits: &Limits,
+    recipient: Option<String>,
+    data: Data<'_>,
+) -> Response<InboundTaskResult> {
+    let limit = limits.get("email").unwrap_or(MAX_EMAIL_SIZE);
+    let raw = data
+        .open(limit)
+        .into_bytes()
+        .await
+        .map_err(anyhow::Error::from)?;
+    if !raw.is_complete() {
+        return Response::from_error(ApiError::MessageTooLarge);
+    }
+
+    match receive_email(context, &raw, recipient.into_iter().collect()).await? {
+        Ok(result) => Response::from_data(InboundTaskResult::from(&result)),
+        Err(InboundError::InvalidMessage) => Response::from_error(ApiError::InvalidMessage),
+        Err(InboundError::UnknownRecipient) => Response::from_error(ApiError::UnknownRecipient),
+        Err(InboundError::NoCategory) => Response::from_error(ApiError::NoCategory),
+    }
+}
+
+/// Creates a task from a raw message received by the endpoint or the SMTP listener.
+pub async fn receive_email(
+    context: &Context,
+    raw: &[u8],
+    envelope_recipients: Vec<String>,
+) -> anyhow::Result<Result<InboundTask, InboundError>> {
+    let Some(email) = InboundEmail::parse(raw, envelope_recipients) else {
+        return Ok(Err(InboundError::InvalidMessage));
+    };
+
+    let Some(board_id) = context.inbound.find_board(&email.recipients).await? else {
+        return Ok(Err(InboundError::UnknownRecipient));
+    };
+
+    context.tasks.create_task_from_email(board_id, email).await
+}
@@ -0,0 +1,214 @@
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    app::tasks::{AssignLabelError, LabelError},
+    model::{
+        labels::{LabelData, LabelDescription},
+        LabelId,
+    },
+};
+
+use super::super::{
+    etag::entity_tag,
+    idempotency::{idempotent, IdempotencyKey, Idempotent},
+    ApiError, ContextState, NoData, Problem, Response, ResponseBody,
+};
+
+use super::{auth::AuthorizedUser, tasks::Task};
+
+#[derive(Serialize, ToSchema)]
+pub struct Label {
+    #[schema(value_type = String)]
+    pub(super) label_id: LabelId,
+    pub(super) name: String,
+    pub(super) color: String,
+}
+
+impl From<&LabelDescription> for Label {
+    fn from(label: &LabelDescription) -> Self {
+        Self {
+            label_id: label.label_id.clone(),
+            name: label.name.clone(),
+            color: label.color.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LabelInputData {
+    name: String,
+    /// Colour in `#rrggbb` format.
+    color: String,
+}
+
+impl LabelInputData {
+    fn into_label_data(self) -> LabelData {
+        LabelData {
+            name: self.name,
+            color: self.color,
+        }
+    }
+}
+
+fn label_response(result: Result<LabelDescription, LabelError>) -> Response<Label> {
+    match result {
+        Ok(label) => Response::from_data(Label::from(&label)),
+        Err(LabelError::InvalidLabel) => Response::from_error(ApiError::InvalidLabel),
+        Err(LabelError::LabelNotFound) => Response::from_error(ApiError::LabelNotFound),
+    }
+}
+
+/// Returns the label palette of the board.
+#[utoipa::path(
+    security(("session" = [])),
+    responses((status = 200, description = "The labels", body = ResponseBody<Vec<Label>>)),
+)]
+#[get("/labels")]
+pub async fn get_labels(context: &ContextState, user: AuthorizedUser) -> Response<Vec<Label>> {
+    let tasks = &context.tasks;
+
+    let labels = tasks.fetch_labels(user.user_id).await?;
+
+    Response::from_data(labels.iter().map(Label::from).collect())
+}
+
+#[utoipa::path(
+    security(("session" = [])),
+    params(IdempotencyKey),
+    request_body = LabelInputData,
+    responses(
+        (status = 200, description = "The created label", body = ResponseBody<Label>),
+        (status = 422, description = "Error code `invalid_label`", body = Problem<NoData>),
+    ),
+)]
+#[post("/labels", format = "application/json", data = "<data>")]
+pub async fn create_label(
+    context: &ContextState,
+    user: AuthorizedUser,
+    data: Idempotent<LabelInputData>,
+) -> Response<Label> {
+    let (data, key) = data.into_parts();
+
+    idempotent(context, Some(user.user_id), key.as_ref(), async {
+        let tasks = &context.tasks;
+
+        let result = tasks
+            .create_label(user.user_id, data.into_label_data())
+            .await?;
+
+        label_response(result)
+    })
+    .await
+}
+
+#[utoipa::path(
+    security(("session" = [])),
+    request_body = LabelInputData,
+    responses(
+        (status = 200, description = "The modified label", body = ResponseBody<Label>),
+        (status = 404, description = "Error code `label_not_found`", body = Problem<NoData>),
+        (status = 422, description = "Error code `invalid_label`", body = Problem<NoData>),
+    ),
+)]
+#[put("/labels/<label_id>", format = "application/json", data = "<data>")]
+pub async fn modify_label(
+    context: &ContextState,
+    user: AuthorizedUser,
+    label_id: &str,
+    data: Json<LabelInputData>,
+) -> Response<Label> {
+    let tasks = &context.tasks;
+
+    let result = tasks
+        .modify_label(user.user_id, label_id, data.into_inner().into_label_data())
+        .await?;
+
+    label_response(result)
+}
+
+/// Deletes the label from the palette of the board, unassigning it from the tasks.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The label is deleted", body = ResponseBody<NoData>),
+        (status = 404, description = "Error code `label_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[delete("/labels/<label_id>")]
+pub async fn delete_label(
+    context: &ContextState,
+    user: AuthorizedUser,
+    label_id: &str,
+) -> Response<()> {
+    let tasks = &context.tasks;
+
+    match tasks.delete_label(user.user_id, label_id).await? {
+        Ok(()) => Response::from_data(()),
+        Err(_) => Response::from_error(ApiError::LabelNotFound),
+    }
+}
+
+async fn set_label_assigned(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    label_id: &str,
+    assigned: bool,
+) -> Response<Task> {
+    let tasks = &context.tasks;
+
+    let result = tasks
+        .set_label_assigned(user.user_id, task_id, label_id, assigned)
+        .await?;
+
+    match result {
+        Ok(task) => Response::from_data(Task::from(&task)).with_etag(entity_tag(task.version)),
+        Err(AssignLabelError::TaskNotFound) => Response::from_error(ApiError::TaskNotFound),
+        Err(AssignLabelError::LabelNotFound) => Response::from_error(ApiError::LabelNotFound),
+    }
+}
+
+/// Assigns the label to the task.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The task", body = ResponseBody<Task>),
+        (
+            status = 404,
+            description = "Error code `task_not_found` or `label_not_found`",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
+#[put("/tasks/<task_id>/labels/<label_id>")]
+pub async fn assign_label(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    label_id: &str,
+) -> Response<Task> {
+    set_label_assigned(context, user, task_id, label_id, true).await
+}
+
+/// Unassigns the label from the task.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The task", body = ResponseBody<Task>),
+        (
+            status = 404,
+            description = "Error code `task_not_found` or `label_not_found`",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
+#[delete("/tasks/<task_id>/labels/<label_id>")]
+pub async fn unassign_label(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    label_id: &str,
+) -> Response<Task> {
+    set_label_assigned(context, user, task_id, label_id, false).await
+}
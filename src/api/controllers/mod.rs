@@ -1,2 +1,19 @@
+pub mod activity;
+pub mod attachments;
 pub mod auth;
+pub mod bulk;
+pub mod calendar;
+pub mod checklists;
+pub mod collaboration;
+pub mod comments;
+pub mod events;
+pub mod export;
+pub mod import;
+pub mod inbound;
+pub mod labels;
+pub mod search;
+pub mod sync;
 pub mod tasks;
+pub mod trash;
+pub mod views;
+pub mod webhooks;
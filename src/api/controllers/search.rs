@@ -0,0 +1,58 @@
+use rocket::serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    app::search::{SearchError, MAX_SEARCH_RESULTS},
+    model::search::SearchHit,
+};
+
+use super::super::{ApiError, ContextState, NoData, Problem, Response, ResponseBody};
+
+use super::{auth::AuthorizedUser, tasks::Task};
+
+#[derive(Serialize, ToSchema)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    task: Task,
+    rank: f32,
+    /// Fragment of the matched text. The matched words are wrapped in `<mark>` tags.
+    snippet: String,
+}
+
+impl From<&SearchHit> for SearchResult {
+    fn from(hit: &SearchHit) -> Self {
+        Self {
+            task: Task::from(&hit.task),
+            rank: hit.rank,
+            snippet: hit.snippet.clone(),
+        }
+    }
+}
+
+/// Searches the labels, descriptions and comments of the tasks for all the words of `q`.
+/// Returns at most `limit` tasks, the most relevant first.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The matching tasks", body = ResponseBody<Vec<SearchResult>>),
+        (status = 422, description = "Error code `invalid_query`", body = Problem<NoData>),
+    ),
+)]
+#[get("/search?<q>&<limit>")]
+pub async fn search(
+    context: &ContextState,
+    user: AuthorizedUser,
+    q: &str,
+    limit: Option<i64>,
+) -> Response<Vec<SearchResult>> {
+    let search = &context.search;
+
+    let result = search
+        .search(user.user_id, q, limit.unwrap_or(MAX_SEARCH_RESULTS))
+        .await?;
+
+    match result {
+        Ok(hits) => Response::from_data(hits.iter().map(SearchResult::from).collect()),
+        Err(SearchError::InvalidQuery) => Response::from_error(ApiError::InvalidQuery),
+    }
+}
@@ -0,0 +1,256 @@
+use rocket::serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    app::{
+        sync::SyncBatch,
+        tasks::{CreateTaskError, ModifyTaskError},
+    },
+    model::{
+        sync::SyncSeq,
+        tasks::{TaskCategoryDescription, Version},
+        TaskCategoryId, TaskId, UserId,
+    },
+};
+
+use super::super::{
+    idempotency::{idempotent, IdempotencyKey, Idempotent},
+    ApiError, Context, ContextState, NoData, Problem, Response, ResponseBody,
+};
+
+use super::{
+    auth::AuthorizedUser,
+    tasks::{Task, TaskInputData},
+};
+
+/// Maximum number of operations in a batch sent by a client.
+const MAX_SYNC_OPERATIONS: usize = 100;
+
+#[derive(Serialize, ToSchema)]
+pub struct SyncCategory {
+    #[schema(value_type = String)]
+    category_id: TaskCategoryId,
+    label: String,
+    archived: bool,
+    #[schema(value_type = i64)]
+    version: Version,
+}
+
+impl From<&TaskCategoryDescription> for SyncCategory {
+    fn from(category: &TaskCategoryDescription) -> Self {
+        Self {
+            category_id: category.category_id.clone(),
+            label: category.label.clone(),
+            archived: category.lifecycle.is_archived(),
+            version: category.version,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SyncChanges {
+    /// The changes are the whole board, which replaces the copy of the client.
+    reset: bool,
+    categories: Vec<SyncCategory>,
+    tasks: Vec<Task>,
+    #[schema(value_type = Vec<String>)]
+    deleted_category_ids: Vec<TaskCategoryId>,
+    #[schema(value_type = Vec<String>)]
+    deleted_task_ids: Vec<TaskId>,
+    /// Pass as `since` with the next request.
+    #[schema(value_type = i64)]
+    cursor: SyncSeq,
+    /// There are more changes, request them right away.
+    has_more: bool,
+}
+
+impl From<&SyncBatch> for SyncChanges {
+    fn from(batch: &SyncBatch) -> Self {
+        Self {
+            reset: batch.reset,
+            categories: batch.categories.iter().map(SyncCategory::from).collect(),
+            tasks: batch.tasks.iter().map(Task::from).collect(),
+            deleted_category_ids: batch.deleted_category_ids.clone(),
+            deleted_task_ids: batch.deleted_task_ids.clone(),
+            cursor: batch.cursor,
+            has_more: batch.has_more,
+        }
+    }
+}
+
+/// Returns the tasks and the categories changed or deleted since the cursor of the previous request.
+/// Without `since`, returns the whole board.
+#[utoipa::path(
+    security(("session" = [])),
+    params(("since" = Option<i64>, Query, description = "`cursor` of the previous response")),
+    responses((status = 200, description = "The changes", body = ResponseBody<SyncChanges>)),
+)]
+#[get("/sync?<since>")]
+pub async fn get_changes(
+    context: &ContextState,
+    user: AuthorizedUser,
+    since: Option<SyncSeq>,
+) -> Response<SyncChanges> {
+    let batch = context.tasks.fetch_changes(user.user_id, since).await?;
+
+    Response::from_data(SyncChanges::from(&batch))
+}
+
+/// Operation made by a client while it was offline.
+#[derive(Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)]
+pub enum SyncOperation {
+    CreateTask {
+        request_id: String,
+        #[serde(flatten)]
+        data: TaskInputData,
+    },
+    /// Applied only if the task is still in `version`, unless the version is absent.
+    ModifyTask {
+        request_id: String,
+        #[schema(value_type = String)]
+        task_id: TaskId,
+        #[serde(flatten)]
+        data: TaskInputData,
+    },
+    DeleteTask {
+        request_id: String,
+        #[schema(value_type = String)]
+        task_id: TaskId,
+    },
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SyncOperations {
+    operations: Vec<SyncOperation>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Applied,
+    /// The task has been modified since the version of the operation.
+    Conflict,
+    /// The task, or the category it is moved to, does not exist.
+    NotFound,
+    Failed,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct OperationResult {
+    request_id: String,
+    status: OperationStatus,
+    /// The state of the task after the operation, or its current state on conflict.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    task: Option<Task>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SyncResults {
+    results: Vec<OperationResult>,
+}
+
+/// Applies the operations of the client in order. Each operation succeeds or fails on its own,
+/// and a conflict is reported with the current state of the task for the client to resolve.
+/// The retries with the same `Idempotency-Key` header get the response to the first request.
+#[utoipa::path(
+    security(("session" = [])),
+    params(IdempotencyKey),
+    request_body = SyncOperations,
+    responses(
+        (
+            status = 200,
+            description = "The results of the operations",
+            body = ResponseBody<SyncResults>,
+        ),
+        (status = 422, description = "Error code `too_many_operations`", body = Problem<NoData>),
+    ),
+)]
+#[post("/sync", format = "application/json", data = "<data>")]
+pub async fn apply_operations(
+    context: &ContextState,
+    user: AuthorizedUser,
+    data: Idempotent<SyncOperations>,
+) -> Response<SyncResults> {
+    idempotent(context, Some(user.user_id), data.key(), async {
+        apply_batch(context, user.user_id, &data).await
+    })
+    .await
+}
+
+async fn apply_batch(
+    context: &Context,
+    user_id: UserId,
+    data: &SyncOperations,
+) -> Response<SyncResults> {
+    if data.operations.len() > MAX_SYNC_OPERATIONS {
+        return Response::from_error(ApiError::TooManyOperations);
+    }
+
+    let tasks = &context.tasks;
+    let mut results = Vec::with_capacity(data.operations.len());
+
+    for operation in &data.operations {
+        let (request_id, result) = match operation {
+            SyncOperation::CreateTask { request_id, data } => {
+                let result = tasks
+                    .create_task(user_id, data.to_task_data())
+                    .await
+                    .map(|result| {
+                        result.map(|(task, _)| Some(task)).map_err(
+                            |CreateTaskError::CategoryNotFound| ModifyTaskError::CategoryNotFound,
+                        )
+                    });
+
+                (request_id, result)
+            }
+            SyncOperation::ModifyTask {
+                request_id,
+                task_id,
+                data,
+            } => {
+                let result = tasks
+                    .modify_task(user_id, task_id, data.to_task_data(), data.version)
+                    .await
+                    .map(|result| result.map(|(task, _)| Some(task)));
+
+                (request_id, result)
+            }
+            SyncOperation::DeleteTask {
+                request_id,
+                task_id,
+            } => {
+                let result = tasks.delete_task(user_id, task_id).await.map(|result| {
+                    result
+                        .map(|_| None)
+                        .map_err(|_| ModifyTaskError::TaskNotFound)
+                });
+
+                (request_id, result)
+            }
+        };
+
+        let (status, task) = match result {
+            Ok(Ok(task)) => (OperationStatus::Applied, task),
+            Ok(Err(ModifyTaskError::TaskNotFound | ModifyTaskError::CategoryNotFound)) => {
+                (OperationStatus::NotFound, None)
+            }
+            Ok(Err(ModifyTaskError::VersionConflict(current))) => {
+                (OperationStatus::Conflict, Some(*current))
+            }
+            Err(err) => {
+                log::error!("Sync operation failed: {:?}", err);
+                (OperationStatus::Failed, None)
+            }
+        };
+
+        results.push(OperationResult {
+            request_id: request_id.clone(),
+            status,
+            task: task.as_ref().map(Task::from),
+        });
+    }
+
+    Response::from_data(SyncResults { results })
+}
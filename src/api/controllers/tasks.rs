@@ -1,39 +1,98 @@
 use std::collections::HashMap;
 
 use anyhow::anyhow;
-use rocket::serde::{json::Json, Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use rocket::{
+    request::FromParam,
+    serde::{json::Json, Deserialize, Deserializer, Serialize},
+};
+use utoipa::ToSchema;
 
-use crate::model::{
-    tasks::{TaskCategoryDescription, TaskDescription},
-    TaskCategoryId, TaskId,
+use crate::{
+    app::{
+        due_dates::DuePeriod,
+        filters::{parse_filter, FilterSyntaxError},
+        tasks::{ColumnSummary, CreateTaskError, ModifyTaskError, RenameCategoryError, TaskPage},
+    },
+    model::{
+        filters::TaskFilter,
+        labels::LabelDescription,
+        tasks::{
+            TaskCategoryDescription, TaskCursor, TaskData, TaskDescription, TaskPatch,
+            TaskPriority, Version,
+        },
+        BoardId, LabelId, TaskCategoryId, TaskId,
+    },
 };
 
-use super::super::{ContextState, Response};
+use super::super::{
+    etag::{entity_tag, IfMatch},
+    idempotency::{idempotent, IdempotencyKey, Idempotent},
+    ApiError, ContextState, NoData, Problem, Response, ResponseBody,
+};
 
-use super::auth::AuthorizedUser;
+use super::{auth::AuthorizedUser, checklists::Progress, labels::Label, trash::lifecycle_error};
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct Task {
-    task_id: TaskId,
-    label: String,
-    description: String,
+    #[schema(value_type = String)]
+    pub(super) task_id: TaskId,
+    #[schema(value_type = String)]
+    pub(super) category_id: TaskCategoryId,
+    pub(super) label: String,
+    pub(super) description: String,
+    pub(super) start_at: Option<DateTime<Utc>>,
+    pub(super) due_at: Option<DateTime<Utc>>,
+    /// `low`, `normal`, `high` or `urgent`.
+    pub(super) priority: &'static str,
+    #[schema(value_type = Vec<String>)]
+    pub(super) label_ids: Vec<LabelId>,
+    checklist_progress: Progress,
+    pub(super) archived: bool,
+    #[schema(value_type = i64)]
+    version: Version,
+}
+
+impl From<&TaskDescription> for Task {
+    fn from(task: &TaskDescription) -> Self {
+        Self {
+            task_id: task.task_id.clone(),
+            category_id: task.category_id.clone(),
+            label: task.label.clone(),
+            description: task.description.clone(),
+            start_at: task.start_at,
+            due_at: task.due_at,
+            priority: task.priority.as_str(),
+            label_ids: task.label_ids.clone(),
+            checklist_progress: task.checklist_progress.into(),
+            archived: task.lifecycle.is_archived(),
+            version: task.version,
+        }
+    }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct TaskCategory {
+    #[schema(value_type = String)]
     category_id: TaskCategoryId,
-    label: String,
-    ordered_tasks: Vec<Box<Task>>,
+    pub(super) label: String,
+    pub(super) archived: bool,
+    #[schema(value_type = i64)]
+    version: Version,
+    pub(super) ordered_tasks: Vec<Box<Task>>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct TasksBoard {
-    ordered_categories: Vec<Box<TaskCategory>>,
+    pub(super) ordered_categories: Vec<Box<TaskCategory>>,
+    /// The label palette of the board. Tasks refer to the labels by ID.
+    pub(super) labels: Vec<Label>,
 }
 
-fn make_tasks_board(
+pub(super) fn make_tasks_board(
     tasks: &[TaskDescription],
     categories: &[TaskCategoryDescription],
+    labels: &[LabelDescription],
 ) -> anyhow::Result<TasksBoard> {
     let mut ordered_categories: Vec<Box<TaskCategory>> = categories
         .iter()
@@ -41,6 +100,8 @@ fn make_tasks_board(
             Box::new(TaskCategory {
                 category_id: ct.category_id.clone(),
                 label: ct.label.clone(),
+                archived: ct.lifecycle.is_archived(),
+                version: ct.version,
                 ordered_tasks: Vec::new(),
             })
         })
@@ -62,51 +123,335 @@ fn make_tasks_board(
             ));
         };
 
-        ordered_categories[idx].ordered_tasks.push(Box::new(Task {
-            task_id: task.task_id.clone(),
-            label: task.label.clone(),
-            description: task.description.clone(),
-        }));
+        ordered_categories[idx]
+            .ordered_tasks
+            .push(Box::new(Task::from(task)));
     }
 
-    Ok(TasksBoard { ordered_categories })
+    Ok(TasksBoard {
+        ordered_categories,
+        labels: labels.iter().map(Label::from).collect(),
+    })
 }
 
-#[get("/tasks")]
-pub async fn get_tasks(context: &ContextState, user: AuthorizedUser) -> Response<TasksBoard> {
+#[derive(Serialize, ToSchema)]
+pub struct FilterError {
+    /// Position of the error in the filter, in characters.
+    position: usize,
+    message: String,
+}
+
+pub(super) fn invalid_filter(err: FilterSyntaxError) -> Response<FilterError> {
+    Response::from_error_details(
+        ApiError::InvalidFilter,
+        FilterError {
+            position: err.position,
+            message: err.message,
+        },
+    )
+}
+
+/// Returns the board. Archived tasks and categories are included only if `archived=true`.
+/// If `filter` is given, only the tasks that match it are included, see [`parse_filter`] for the syntax.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The board", body = ResponseBody<TasksBoard>),
+        (status = 422, description = "Error code `invalid_filter`", body = Problem<FilterError>),
+    ),
+)]
+#[get("/tasks?<archived>&<filter>")]
+pub async fn get_tasks(
+    context: &ContextState,
+    user: AuthorizedUser,
+    archived: Option<bool>,
+    filter: Option<&str>,
+) -> Result<Response<TasksBoard>, Response<FilterError>> {
+    let filter = filter
+        .map(parse_filter)
+        .transpose()
+        .map_err(invalid_filter)?;
+
+    Ok(fetch_board(context, user, archived.unwrap_or(false), filter.as_ref()).await)
+}
+
+async fn fetch_board(
+    context: &ContextState,
+    user: AuthorizedUser,
+    include_archived: bool,
+    filter: Option<&TaskFilter>,
+) -> Response<TasksBoard> {
     let tasks = &context.tasks;
 
-    let task_descriptions = tasks.fetch_tasks(user.user_id).await?;
-    let category_descriptions = tasks.fetch_categories(user.user_id).await?;
-    let tasks_board = make_tasks_board(&task_descriptions, &category_descriptions)?;
+    let (category_descriptions, task_descriptions) = tasks
+        .fetch_board(user.user_id, include_archived, filter)
+        .await?;
+    let label_descriptions = tasks.fetch_labels(user.user_id).await?;
+    let tasks_board = make_tasks_board(
+        &task_descriptions,
+        &category_descriptions,
+        &label_descriptions,
+    )?;
 
     Response::from_data(tasks_board)
 }
 
-#[post("/tasks", format = "application/json", data = "<data>")]
-pub async fn create_task(
+/// Number of tasks in a page of a column, unless `limit` is given.
+const DEFAULT_TASK_PAGE_SIZE: i64 = 50;
+
+#[derive(Serialize, ToSchema)]
+pub struct CategoryTasks {
+    tasks: Vec<Task>,
+    /// Value of `after` that returns the next page, or `None` if this is the last page.
+    next_cursor: Option<String>,
+}
+
+impl From<TaskPage> for CategoryTasks {
+    fn from(page: TaskPage) -> Self {
+        Self {
+            tasks: page.tasks.iter().map(Task::from).collect(),
+            next_cursor: page.next_cursor.map(|cursor| cursor.to_string()),
+        }
+    }
+}
+
+/// Returns the tasks of the column a page at a time, in the order of the board.
+/// Archived tasks are included only if `archived=true`.
+#[utoipa::path(
+    security(("session" = [])),
+    params(("after" = Option<String>, Query, description = "`next_cursor` of the previous page")),
+    responses(
+        (status = 200, description = "A page of the tasks", body = ResponseBody<CategoryTasks>),
+        (status = 400, description = "Error code `bad_request` if `after` is malformed", body = Problem<NoData>),
+        (status = 404, description = "Error code `category_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[get("/categories/<category_id>/tasks?<after>&<limit>&<archived>")]
+pub async fn get_category_tasks(
     context: &ContextState,
     user: AuthorizedUser,
-    data: Json<TaskInputData>,
-) -> Response<Task> {
+    category_id: &str,
+    after: Option<&str>,
+    limit: Option<i64>,
+    archived: Option<bool>,
+) -> Response<CategoryTasks> {
     let tasks = &context.tasks;
 
-    let task_id = tasks
-        .create_task(
+    let after = match after.map(TaskCursor::parse) {
+        Some(None) => return Response::from_error(ApiError::BadRequest),
+        after => after.flatten(),
+    };
+
+    let page = tasks
+        .fetch_category_tasks(
             user.user_id,
-            &data.label,
-            &data.description,
-            &data.categoryId,
+            category_id,
+            after.as_ref(),
+            limit.unwrap_or(DEFAULT_TASK_PAGE_SIZE),
+            archived.unwrap_or(false),
         )
         .await?;
 
-    Response::from_data(Task {
-        task_id,
-        label: data.label.clone(),
-        description: data.description.clone(),
+    match page {
+        Some(page) => Response::from_data(CategoryTasks::from(page)),
+        None => Response::from_error(ApiError::CategoryNotFound),
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Column {
+    #[schema(value_type = String)]
+    category_id: TaskCategoryId,
+    label: String,
+    archived: bool,
+    #[schema(value_type = i64)]
+    version: Version,
+    task_count: i64,
+}
+
+impl From<&ColumnSummary> for Column {
+    fn from(column: &ColumnSummary) -> Self {
+        Self {
+            category_id: column.category.category_id.clone(),
+            label: column.category.label.clone(),
+            archived: column.category.lifecycle.is_archived(),
+            version: column.category.version,
+            task_count: column.task_count,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Category {
+    #[schema(value_type = String)]
+    category_id: TaskCategoryId,
+    label: String,
+    archived: bool,
+    #[schema(value_type = i64)]
+    version: Version,
+}
+
+impl From<&TaskCategoryDescription> for Category {
+    fn from(category: &TaskCategoryDescription) -> Self {
+        Self {
+            category_id: category.category_id.clone(),
+            label: category.label.clone(),
+            archived: category.lifecycle.is_archived(),
+            version: category.version,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CategoryInputData {
+    label: String,
+}
+
+/// Renames the category.
+#[utoipa::path(
+    security(("session" = [])),
+    request_body = CategoryInputData,
+    responses(
+        (status = 200, description = "The renamed category", body = ResponseBody<Category>),
+        (
+            status = 404,
+            description = "Error code `category_not_found` if the category is missing or in the trash",
+            body = Problem<NoData>,
+        ),
+        (
+            status = 422,
+            description = "Error code `invalid_name` if the label is blank or too long",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
+#[put(
+    "/categories/<category_id>",
+    format = "application/json",
+    data = "<data>"
+)]
+pub async fn rename_category(
+    context: &ContextState,
+    user: AuthorizedUser,
+    category_id: &str,
+    data: Json<CategoryInputData>,
+) -> Response<Category> {
+    let result = context
+        .tasks
+        .rename_category(user.user_id, category_id, &data.label)
+        .await?;
+
+    match result {
+        Ok(category) => Response::from_data(Category::from(&category)),
+        Err(RenameCategoryError::CategoryNotFound) => {
+            Response::from_error(ApiError::CategoryNotFound)
+        }
+        Err(RenameCategoryError::InvalidLabel) => Response::from_error(ApiError::InvalidName),
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BoardSummary {
+    ordered_columns: Vec<Column>,
+}
+
+/// Returns the columns of the board with the number of their tasks, without the tasks themselves.
+/// Archived columns and tasks are included only if `archived=true`.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The columns", body = ResponseBody<BoardSummary>),
+        (status = 403, description = "Error code `forbidden`", body = Problem<NoData>),
+    ),
+)]
+#[get("/boards/<board_id>/summary?<archived>")]
+pub async fn get_board_summary(
+    context: &ContextState,
+    user: AuthorizedUser,
+    board_id: i64,
+    archived: Option<bool>,
+) -> Response<BoardSummary> {
+    let tasks = &context.tasks;
+    let board_id = BoardId::from_raw(board_id);
+
+    if !tasks.can_access_board(user.user_id, board_id) {
+        return Response::from_error(ApiError::Forbidden);
+    }
+
+    let columns = tasks
+        .fetch_board_summary(board_id, archived.unwrap_or(false))
+        .await?;
+
+    Response::from_data(BoardSummary {
+        ordered_columns: columns.iter().map(Column::from).collect(),
+    })
+}
+
+/// Creates the task. The retries with the same `Idempotency-Key` header get the response to the first request.
+#[utoipa::path(
+    security(("session" = [])),
+    params(IdempotencyKey),
+    request_body = TaskInputData,
+    responses(
+        (status = 200, description = "The created task", body = ResponseBody<Task>),
+        (status = 404, description = "Error code `category_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[post("/tasks", format = "application/json", data = "<data>")]
+pub async fn create_task(
+    context: &ContextState,
+    user: AuthorizedUser,
+    data: Idempotent<TaskInputData>,
+) -> Response<Task> {
+    idempotent(context, Some(user.user_id), data.key(), async {
+        let tasks = &context.tasks;
+
+        match tasks.create_task(user.user_id, data.to_task_data()).await? {
+            Ok((task, _)) => {
+                Response::from_data(Task::from(&task)).with_etag(entity_tag(task.version))
+            }
+            Err(CreateTaskError::CategoryNotFound) => {
+                Response::from_error(ApiError::CategoryNotFound)
+            }
+        }
     })
+    .await
+}
+
+/// Returns the task, with its version in `ETag` header.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The task", body = ResponseBody<Task>),
+        (status = 404, description = "Error code `task_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[get("/tasks/<task_id>")]
+pub async fn get_task(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+) -> Response<Task> {
+    let tasks = &context.tasks;
+
+    match tasks.fetch_task(user.user_id, task_id).await? {
+        Some(task) => Response::from_data(Task::from(&task)).with_etag(entity_tag(task.version)),
+        None => Response::from_error(ApiError::TaskNotFound),
+    }
 }
 
+/// Moves the task to the trash, from which it can be restored until it is purged.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The task is in the trash", body = ResponseBody<NoData>),
+        (
+            status = 404,
+            description = "Error code `task_not_found` if the task is missing or already in the trash",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
 #[delete("/tasks/<task_id>")]
 pub async fn delete_task(
     context: &ContextState,
@@ -115,37 +460,281 @@ pub async fn delete_task(
 ) -> Response<()> {
     let tasks = &context.tasks;
 
-    tasks.delete_task(user.user_id, task_id).await?;
+    match tasks.delete_task(user.user_id, task_id).await? {
+        Ok(_) => Response::from_data(()),
+        Err(err) => lifecycle_error(err),
+    }
+}
 
-    Response::from_data(())
+#[derive(Deserialize, Clone, Copy, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+    Urgent,
+}
+
+impl From<Priority> for TaskPriority {
+    fn from(priority: Priority) -> Self {
+        match priority {
+            Priority::Low => TaskPriority::Low,
+            Priority::Normal => TaskPriority::Normal,
+            Priority::High => TaskPriority::High,
+            Priority::Urgent => TaskPriority::Urgent,
+        }
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[allow(non_snake_case)]
 pub struct TaskInputData {
-    categoryId: TaskCategoryId,
-    label: String,
-    description: String,
+    #[schema(value_type = String)]
+    pub(super) categoryId: TaskCategoryId,
+    pub(super) label: String,
+    pub(super) description: String,
+    #[serde(default)]
+    pub(super) startAt: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub(super) dueAt: Option<DateTime<Utc>>,
+    /// `normal` if absent.
+    #[serde(default)]
+    pub(super) priority: Option<Priority>,
+    /// The version of the task the changes are based on. May be sent in `If-Match` header instead.
+    #[schema(value_type = Option<i64>)]
+    pub(super) version: Option<Version>,
+}
+
+impl TaskInputData {
+    pub(super) fn to_task_data(&self) -> TaskData {
+        TaskData {
+            label: self.label.clone(),
+            description: self.description.clone(),
+            category_id: self.categoryId.clone(),
+            start_at: self.startAt,
+            due_at: self.dueAt,
+            priority: self.priority.map(TaskPriority::from).unwrap_or_default(),
+        }
+    }
 }
 
+/// Modifies the task. The version the changes are based on must be provided
+/// either in `If-Match` header or in `version` field.
+#[utoipa::path(
+    security(("session" = [])),
+    params(IfMatch),
+    request_body = TaskInputData,
+    responses(
+        (status = 200, description = "The modified task", body = ResponseBody<Task>),
+        (
+            status = 404,
+            description = "Error code `task_not_found`, or `category_not_found` if the task is moved \
+                to a category that is missing or in the trash",
+            body = Problem<NoData>,
+        ),
+        (
+            status = 412,
+            description = "Error code `version_conflict` with the current state of the task",
+            body = Problem<Task>,
+        ),
+        (status = 428, description = "Error code `version_required`", body = Problem<NoData>),
+    ),
+)]
 #[put("/tasks/<task_id>", format = "application/json", data = "<data>")]
 pub async fn modify_task(
     context: &ContextState,
     user: AuthorizedUser,
     task_id: &str,
+    if_match: IfMatch,
     data: Json<TaskInputData>,
-) -> Response<()> {
+) -> Response<Task> {
     let tasks = &context.tasks;
 
-    tasks
-        .modify_task(
-            user.user_id,
-            task_id,
-            &data.label,
-            &data.description,
-            &data.categoryId,
-        )
+    let expected_version = match (if_match, data.version) {
+        (IfMatch::Version(version), _) => Some(version),
+        (IfMatch::Any, _) => None,
+        (IfMatch::Absent, Some(version)) => Some(version),
+        (IfMatch::Absent, None) => return Response::from_error(ApiError::VersionRequired),
+        (IfMatch::NoVersion, _) => match tasks.fetch_task(user.user_id, task_id).await? {
+            Some(current) => return version_conflict(&current),
+            None => return Response::from_error(ApiError::TaskNotFound),
+        },
+    };
+
+    let result = tasks
+        .modify_task(user.user_id, task_id, data.to_task_data(), expected_version)
+        .await?;
+
+    match result {
+        Ok((task, _)) => Response::from_data(Task::from(&task)).with_etag(entity_tag(task.version)),
+        Err(ModifyTaskError::TaskNotFound) => Response::from_error(ApiError::TaskNotFound),
+        Err(ModifyTaskError::CategoryNotFound) => Response::from_error(ApiError::CategoryNotFound),
+        Err(ModifyTaskError::VersionConflict(current)) => version_conflict(&current),
+    }
+}
+
+/// Responds with the current state of the task, which has been modified concurrently.
+fn version_conflict(current: &TaskDescription) -> Response<Task> {
+    Response::from_error_details(ApiError::VersionConflict, Task::from(current))
+        .with_etag(entity_tag(current.version))
+}
+
+/// Deserializes a field that may be absent, so that an explicit `null` can be told apart from an absent field.
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// JSON Merge Patch (RFC 7396) of a task. Absent fields are left unchanged.
+#[derive(Deserialize, ToSchema)]
+#[allow(non_snake_case)]
+pub struct TaskMergePatch {
+    #[serde(default, deserialize_with = "deserialize_present")]
+    #[schema(value_type = Option<String>)]
+    categoryId: Option<Option<TaskCategoryId>>,
+    #[serde(default, deserialize_with = "deserialize_present")]
+    label: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_present")]
+    description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_present")]
+    startAt: Option<Option<DateTime<Utc>>>,
+    #[serde(default, deserialize_with = "deserialize_present")]
+    dueAt: Option<Option<DateTime<Utc>>>,
+    #[serde(default, deserialize_with = "deserialize_present")]
+    priority: Option<Option<Priority>>,
+}
+
+impl TaskMergePatch {
+    /// Converts the merge patch into [`TaskPatch`].
+    /// Returns `None` if the patch removes a required field. Dates are optional and removed by `null`.
+    pub(super) fn into_task_patch(self) -> Option<TaskPatch> {
+        fn field<T>(value: Option<Option<T>>) -> Result<Option<T>, ()> {
+            match value {
+                None => Ok(None),
+                Some(Some(value)) => Ok(Some(value)),
+                Some(None) => Err(()),
+            }
+        }
+
+        Some(TaskPatch {
+            label: field(self.label).ok()?,
+            description: field(self.description).ok()?,
+            category_id: field(self.categoryId).ok()?,
+            start_at: self.startAt,
+            due_at: self.dueAt,
+            priority: field(self.priority).ok()?.map(TaskPriority::from),
+        })
+    }
+}
+
+/// Changes only the fields present in the merge patch.
+/// If the version is provided in `If-Match` header, the task is modified only if it has that version.
+#[utoipa::path(
+    security(("session" = [])),
+    params(IfMatch),
+    request_body(content = TaskMergePatch, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "The modified task", body = ResponseBody<Task>),
+        (
+            status = 404,
+            description = "Error code `task_not_found`, or `category_not_found` if the task is moved \
+                to a category that is missing or in the trash",
+            body = Problem<NoData>,
+        ),
+        (
+            status = 412,
+            description = "Error code `version_conflict` with the current state of the task",
+            body = Problem<Task>,
+        ),
+        (
+            status = 422,
+            description = "Error code `invalid_patch` if the patch removes a required field",
+            body = Problem<NoData>,
+        ),
+    ),
+)]
+#[patch("/tasks/<task_id>", data = "<data>")]
+pub async fn patch_task(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+    if_match: IfMatch,
+    data: Json<TaskMergePatch>,
+) -> Response<Task> {
+    let tasks = &context.tasks;
+
+    let Some(patch) = data.into_inner().into_task_patch() else {
+        return Response::from_error(ApiError::InvalidPatch);
+    };
+
+    let expected_version = match if_match {
+        IfMatch::Version(version) => Some(version),
+        IfMatch::Absent | IfMatch::Any => None,
+        IfMatch::NoVersion => match tasks.fetch_task(user.user_id, task_id).await? {
+            Some(current) => return version_conflict(&current),
+            None => return Response::from_error(ApiError::TaskNotFound),
+        },
+    };
+
+    // Nothing to change, respond with the current state.
+    if patch.is_empty() {
+        return match tasks.fetch_task(user.user_id, task_id).await? {
+            Some(task) if expected_version.is_none_or(|v| v == task.version) => {
+                Response::from_data(Task::from(&task)).with_etag(entity_tag(task.version))
+            }
+            Some(current) => version_conflict(&current),
+            None => Response::from_error(ApiError::TaskNotFound),
+        };
+    }
+
+    let result = tasks
+        .patch_task(user.user_id, task_id, &patch, expected_version)
+        .await?;
+
+    match result {
+        Ok((task, _)) => Response::from_data(Task::from(&task)).with_etag(entity_tag(task.version)),
+        Err(ModifyTaskError::TaskNotFound) => Response::from_error(ApiError::TaskNotFound),
+        Err(ModifyTaskError::CategoryNotFound) => Response::from_error(ApiError::CategoryNotFound),
+        Err(ModifyTaskError::VersionConflict(current)) => version_conflict(&current),
+    }
+}
+
+impl<'a> FromParam<'a> for DuePeriod {
+    type Error = &'a str;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        match param {
+            "overdue" => Ok(DuePeriod::Overdue),
+            "today" => Ok(DuePeriod::Today),
+            "week" => Ok(DuePeriod::ThisWeek),
+            _ => Err(param),
+        }
+    }
+}
+
+/// Returns the tasks due in the period (`overdue`, `today` or `week`), ordered by due date.
+/// Days and weeks are computed in the timezone of the user.
+/// Ranked below the routes of a task, such as `/tasks/<task_id>/checklist`, whose paths have the same shape.
+#[utoipa::path(
+    security(("session" = [])),
+    params(("period" = String, Path, description = "`overdue`, `today` or `week`")),
+    responses((status = 200, description = "The tasks", body = ResponseBody<Vec<Task>>)),
+)]
+#[get("/tasks/due/<period>", rank = 1)]
+pub async fn get_tasks_due(
+    context: &ContextState,
+    user: AuthorizedUser,
+    period: DuePeriod,
+) -> Response<Vec<Task>> {
+    let timezone = context.auth.get_timezone(user.user_id).await?;
+
+    let task_descriptions = context
+        .tasks
+        .fetch_tasks_due(user.user_id, period, timezone)
         .await?;
 
-    Response::from_data(())
+    Response::from_data(task_descriptions.iter().map(Task::from).collect())
 }
@@ -0,0 +1,232 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    app::tasks::{LifecycleError, StoredItems},
+    model::{
+        tasks::{TaskCategoryDescription, TaskDescription, Version},
+        TaskCategoryId,
+    },
+};
+
+use super::super::{ApiError, ContextState, NoData, Problem, Response, ResponseBody};
+
+use super::{auth::AuthorizedUser, tasks::Task};
+
+pub(super) fn lifecycle_error<T>(err: LifecycleError) -> Response<T> {
+    match err {
+        LifecycleError::TaskNotFound => Response::from_error(ApiError::TaskNotFound),
+        LifecycleError::CategoryNotFound => Response::from_error(ApiError::CategoryNotFound),
+        LifecycleError::CategoryTrashed => Response::from_error(ApiError::CategoryTrashed),
+    }
+}
+
+/// A task in the archive or in the trash.
+#[derive(Serialize, ToSchema)]
+pub struct StoredTask {
+    #[serde(flatten)]
+    task: Task,
+    archived_at: Option<DateTime<Utc>>,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+impl From<&TaskDescription> for StoredTask {
+    fn from(task: &TaskDescription) -> Self {
+        Self {
+            task: Task::from(task),
+            archived_at: task.lifecycle.archived_at,
+            deleted_at: task.lifecycle.deleted_at,
+        }
+    }
+}
+
+/// A category in the archive or in the trash.
+#[derive(Serialize, ToSchema)]
+pub struct StoredCategory {
+    #[schema(value_type = String)]
+    category_id: TaskCategoryId,
+    label: String,
+    #[schema(value_type = i64)]
+    version: Version,
+    archived_at: Option<DateTime<Utc>>,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+impl From<&TaskCategoryDescription> for StoredCategory {
+    fn from(category: &TaskCategoryDescription) -> Self {
+        Self {
+            category_id: category.category_id.clone(),
+            label: category.label.clone(),
+            version: category.version,
+            archived_at: category.lifecycle.archived_at,
+            deleted_at: category.lifecycle.deleted_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StoredItemsResponse {
+    categories: Vec<StoredCategory>,
+    tasks: Vec<StoredTask>,
+}
+
+impl From<&StoredItems> for StoredItemsResponse {
+    fn from(items: &StoredItems) -> Self {
+        Self {
+            categories: items.categories.iter().map(StoredCategory::from).collect(),
+            tasks: items.tasks.iter().map(StoredTask::from).collect(),
+        }
+    }
+}
+
+/// Returns the archived tasks and categories.
+#[utoipa::path(
+    security(("session" = [])),
+    responses((
+        status = 200,
+        description = "The archived items",
+        body = ResponseBody<StoredItemsResponse>,
+    )),
+)]
+#[get("/archive")]
+pub async fn get_archive(
+    context: &ContextState,
+    user: AuthorizedUser,
+) -> Response<StoredItemsResponse> {
+    let archive = context.tasks.fetch_archive(user.user_id).await?;
+
+    Response::from_data(StoredItemsResponse::from(&archive))
+}
+
+/// Returns the trashed tasks and categories. They are purged after the retention period.
+#[utoipa::path(
+    security(("session" = [])),
+    responses((
+        status = 200,
+        description = "The trashed items",
+        body = ResponseBody<StoredItemsResponse>,
+    )),
+)]
+#[get("/trash")]
+pub async fn get_trash(
+    context: &ContextState,
+    user: AuthorizedUser,
+) -> Response<StoredItemsResponse> {
+    let trash = context.tasks.fetch_trash(user.user_id).await?;
+
+    Response::from_data(StoredItemsResponse::from(&trash))
+}
+
+/// Moves the task to the archive, from which it can be restored.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The archived task", body = ResponseBody<StoredTask>),
+        (status = 404, description = "Error code `task_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[post("/tasks/<task_id>/archive")]
+pub async fn archive_task(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+) -> Response<StoredTask> {
+    match context.tasks.archive_task(user.user_id, task_id).await? {
+        Ok(task) => Response::from_data(StoredTask::from(&task)),
+        Err(err) => lifecycle_error(err),
+    }
+}
+
+/// Returns the task from the archive or the trash to the board.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The restored task", body = ResponseBody<StoredTask>),
+        (status = 404, description = "Error code `task_not_found`", body = Problem<NoData>),
+        (status = 409, description = "Error code `category_trashed`", body = Problem<NoData>),
+    ),
+)]
+#[post("/tasks/<task_id>/restore")]
+pub async fn restore_task(
+    context: &ContextState,
+    user: AuthorizedUser,
+    task_id: &str,
+) -> Response<StoredTask> {
+    match context.tasks.restore_task(user.user_id, task_id).await? {
+        Ok(task) => Response::from_data(StoredTask::from(&task)),
+        Err(err) => lifecycle_error(err),
+    }
+}
+
+/// Moves the category to the archive together with its tasks.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The archived category", body = ResponseBody<StoredCategory>),
+        (status = 404, description = "Error code `category_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[post("/categories/<category_id>/archive")]
+pub async fn archive_category(
+    context: &ContextState,
+    user: AuthorizedUser,
+    category_id: &str,
+) -> Response<StoredCategory> {
+    match context
+        .tasks
+        .archive_category(user.user_id, category_id)
+        .await?
+    {
+        Ok(category) => Response::from_data(StoredCategory::from(&category)),
+        Err(err) => lifecycle_error(err),
+    }
+}
+
+/// Moves the category to the trash together with its tasks.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The trashed category", body = ResponseBody<StoredCategory>),
+        (status = 404, description = "Error code `category_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[delete("/categories/<category_id>")]
+pub async fn delete_category(
+    context: &ContextState,
+    user: AuthorizedUser,
+    category_id: &str,
+) -> Response<StoredCategory> {
+    match context
+        .tasks
+        .delete_category(user.user_id, category_id)
+        .await?
+    {
+        Ok(category) => Response::from_data(StoredCategory::from(&category)),
+        Err(err) => lifecycle_error(err),
+    }
+}
+
+/// Returns the category from the archive or the trash to the board, with the tasks trashed together with it.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The restored category", body = ResponseBody<StoredCategory>),
+        (status = 404, description = "Error code `category_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[post("/categories/<category_id>/restore")]
+pub async fn restore_category(
+    context: &ContextState,
+    user: AuthorizedUser,
+    category_id: &str,
+) -> Response<StoredCategory> {
+    match context
+        .tasks
+        .restore_category(user.user_id, category_id)
+        .await?
+    {
+        Ok(category) => Response::from_data(StoredCategory::from(&category)),
+        Err(err) => lifecycle_error(err),
+    }
+}
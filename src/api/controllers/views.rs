@@ -0,0 +1,327 @@
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    app::views::{ViewError, ViewGroup},
+    model::views::{ViewData, ViewDescription, ViewGrouping, ViewId, ViewSort},
+};
+
+use super::super::{
+    idempotency::{idempotent, IdempotencyKey, Idempotent},
+    ApiError, ContextState, NoData, Problem, Response, ResponseBody,
+};
+
+use super::{
+    auth::AuthorizedUser,
+    tasks::{invalid_filter, FilterError, Task},
+};
+
+/// Response of the endpoints that validate the filter of a view.
+/// An invalid filter is reported with the position of the error.
+type ViewResponse<T> = Result<Response<T>, Response<FilterError>>;
+
+#[derive(Serialize, ToSchema)]
+pub struct View {
+    #[schema(value_type = String)]
+    view_id: ViewId,
+    owner_id: i64,
+    name: String,
+    filter: String,
+    /// `board`, `due_at`, `priority` or `label`.
+    sort: &'static str,
+    descending: bool,
+    /// `none`, `column`, `priority` or `label`.
+    group_by: &'static str,
+    include_archived: bool,
+    shared: bool,
+}
+
+impl From<&ViewDescription> for View {
+    fn from(view: &ViewDescription) -> Self {
+        Self {
+            view_id: view.view_id.clone(),
+            owner_id: view.owner_id.raw(),
+            name: view.data.name.clone(),
+            filter: view.data.filter.clone(),
+            sort: view.data.sort.as_str(),
+            descending: view.data.descending,
+            group_by: view.data.group_by.as_str(),
+            include_archived: view.data.include_archived,
+            shared: view.data.shared,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ViewTaskGroup {
+    /// ID of the category or the label, or the name of the priority, depending on `group_by` of the view.
+    /// `null` if the view is not grouped, or for the tasks without labels.
+    key: Option<String>,
+    tasks: Vec<Task>,
+}
+
+impl From<&ViewGroup> for ViewTaskGroup {
+    fn from(group: &ViewGroup) -> Self {
+        Self {
+            key: group.key.clone(),
+            tasks: group.tasks.iter().map(Task::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ViewTasks {
+    view: View,
+    groups: Vec<ViewTaskGroup>,
+}
+
+#[derive(Deserialize, Clone, Copy, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Sort {
+    Board,
+    DueAt,
+    Priority,
+    Label,
+}
+
+impl From<Sort> for ViewSort {
+    fn from(sort: Sort) -> Self {
+        match sort {
+            Sort::Board => ViewSort::Board,
+            Sort::DueAt => ViewSort::DueAt,
+            Sort::Priority => ViewSort::Priority,
+            Sort::Label => ViewSort::Label,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Grouping {
+    None,
+    Column,
+    Priority,
+    Label,
+}
+
+impl From<Grouping> for ViewGrouping {
+    fn from(grouping: Grouping) -> Self {
+        match grouping {
+            Grouping::None => ViewGrouping::None,
+            Grouping::Column => ViewGrouping::Column,
+            Grouping::Priority => ViewGrouping::Priority,
+            Grouping::Label => ViewGrouping::Label,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[allow(non_snake_case)]
+pub struct ViewInputData {
+    name: String,
+    /// Filter query. Empty if absent, which matches all the tasks.
+    #[serde(default)]
+    filter: String,
+    /// `board` if absent.
+    #[serde(default)]
+    sort: Option<Sort>,
+    #[serde(default)]
+    descending: bool,
+    /// `none` if absent.
+    #[serde(default)]
+    groupBy: Option<Grouping>,
+    #[serde(default)]
+    includeArchived: bool,
+    #[serde(default)]
+    shared: bool,
+}
+
+impl ViewInputData {
+    fn into_view_data(self) -> ViewData {
+        ViewData {
+            name: self.name,
+            filter: self.filter,
+            sort: self.sort.map(ViewSort::from).unwrap_or_default(),
+            descending: self.descending,
+            group_by: self.groupBy.map(ViewGrouping::from).unwrap_or_default(),
+            include_archived: self.includeArchived,
+            shared: self.shared,
+        }
+    }
+}
+
+fn view_response<V, T>(
+    result: anyhow::Result<Result<V, ViewError>>,
+    make_data: impl FnOnce(V) -> T,
+) -> ViewResponse<T> {
+    match result {
+        Ok(Ok(value)) => Ok(Response::from_data(make_data(value))),
+        Ok(Err(ViewError::InvalidFilter(err))) => Err(invalid_filter(err)),
+        Ok(Err(ViewError::ViewNotFound)) => Ok(Response::from_error(ApiError::ViewNotFound)),
+        Ok(Err(ViewError::NotOwner)) => Ok(Response::from_error(ApiError::NotOwner)),
+        Ok(Err(ViewError::InvalidName)) => Ok(Response::from_error(ApiError::InvalidName)),
+        Err(err) => Ok(Response::ServerError(err.into())),
+    }
+}
+
+/// Returns the views of the board that the user owns or that are shared with the board.
+#[utoipa::path(
+    security(("session" = [])),
+    responses((status = 200, description = "The views", body = ResponseBody<Vec<View>>)),
+)]
+#[get("/views")]
+pub async fn get_views(context: &ContextState, user: AuthorizedUser) -> Response<Vec<View>> {
+    let board_id = context.tasks.user_board(user.user_id);
+
+    let views = context.views.fetch_views(user.user_id, board_id).await?;
+
+    Response::from_data(views.iter().map(View::from).collect())
+}
+
+/// Saves a new view of the board owned by the user.
+#[utoipa::path(
+    security(("session" = [])),
+    params(IdempotencyKey),
+    request_body = ViewInputData,
+    responses(
+        (status = 200, description = "The created view", body = ResponseBody<View>),
+        (
+            status = 422,
+            description = "Error code `invalid_filter` with the position of the error, or `invalid_name`",
+            body = Problem<FilterError>,
+        ),
+    ),
+)]
+#[post("/views", format = "application/json", data = "<data>")]
+pub async fn create_view(
+    context: &ContextState,
+    user: AuthorizedUser,
+    data: Idempotent<ViewInputData>,
+) -> ViewResponse<View> {
+    let (data, key) = data.into_parts();
+
+    idempotent(context, Some(user.user_id), key.as_ref(), async {
+        let board_id = context.tasks.user_board(user.user_id);
+
+        let result = context
+            .views
+            .create_view(user.user_id, board_id, data.into_view_data())
+            .await;
+
+        view_response(result, |view| View::from(&view))
+    })
+    .await
+}
+
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The view", body = ResponseBody<View>),
+        (status = 404, description = "Error code `view_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[get("/views/<view_id>")]
+pub async fn get_view(
+    context: &ContextState,
+    user: AuthorizedUser,
+    view_id: &str,
+) -> ViewResponse<View> {
+    let board_id = context.tasks.user_board(user.user_id);
+
+    let result = context
+        .views
+        .fetch_view(user.user_id, board_id, view_id)
+        .await;
+
+    view_response(result, |view| View::from(&view))
+}
+
+/// Replaces the view. Only the owner may change it.
+#[utoipa::path(
+    security(("session" = [])),
+    request_body = ViewInputData,
+    responses(
+        (status = 200, description = "The modified view", body = ResponseBody<View>),
+        (status = 403, description = "Error code `not_owner`", body = Problem<NoData>),
+        (status = 404, description = "Error code `view_not_found`", body = Problem<NoData>),
+        (
+            status = 422,
+            description = "Error code `invalid_filter` with the position of the error, or `invalid_name`",
+            body = Problem<FilterError>,
+        ),
+    ),
+)]
+#[put("/views/<view_id>", format = "application/json", data = "<data>")]
+pub async fn modify_view(
+    context: &ContextState,
+    user: AuthorizedUser,
+    view_id: &str,
+    data: Json<ViewInputData>,
+) -> ViewResponse<View> {
+    let board_id = context.tasks.user_board(user.user_id);
+
+    let result = context
+        .views
+        .modify_view(
+            user.user_id,
+            board_id,
+            view_id,
+            data.into_inner().into_view_data(),
+        )
+        .await;
+
+    view_response(result, |view| View::from(&view))
+}
+
+/// Deletes the view. Only the owner may delete it.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The view is deleted", body = ResponseBody<NoData>),
+        (status = 403, description = "Error code `not_owner`", body = Problem<NoData>),
+        (status = 404, description = "Error code `view_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[delete("/views/<view_id>")]
+pub async fn delete_view(
+    context: &ContextState,
+    user: AuthorizedUser,
+    view_id: &str,
+) -> ViewResponse<()> {
+    let board_id = context.tasks.user_board(user.user_id);
+
+    let result = context
+        .views
+        .delete_view(user.user_id, board_id, view_id)
+        .await;
+
+    view_response(result, |_| ())
+}
+
+/// Returns the tasks that match the filter of the view, sorted and grouped as the view says.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The tasks", body = ResponseBody<ViewTasks>),
+        (status = 404, description = "Error code `view_not_found`", body = Problem<NoData>),
+        (status = 422, description = "Error code `invalid_filter`", body = Problem<FilterError>),
+    ),
+)]
+#[get("/views/<view_id>/tasks")]
+pub async fn get_view_tasks(
+    context: &ContextState,
+    user: AuthorizedUser,
+    view_id: &str,
+) -> ViewResponse<ViewTasks> {
+    let board_id = context.tasks.user_board(user.user_id);
+
+    let result = context
+        .views
+        .run_view(user.user_id, board_id, view_id)
+        .await;
+
+    view_response(result, |(view, groups)| ViewTasks {
+        view: View::from(&view),
+        groups: groups.iter().map(ViewTaskGroup::from).collect(),
+    })
+}
@@ -0,0 +1,346 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::{json, Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    app::{
+        events::BoardEventKind,
+        repositories::WebhookRenderer,
+        webhooks::{WebhookError, TEST_EVENT_TYPE, WEBHOOK_EVENT_TYPES},
+    },
+    model::{
+        webhooks::{DeliveryId, WebhookDelivery, WebhookDescription, WebhookId},
+        BoardId,
+    },
+};
+
+use super::super::{
+    idempotency::{idempotent, IdempotencyKey, Idempotent},
+    ApiError, ContextState, NoData, Problem, Response, ResponseBody,
+};
+
+use super::{auth::AuthorizedUser, events::EventPayload};
+
+const DEFAULT_DELIVERIES_LIMIT: i64 = 50;
+const MAX_DELIVERIES_LIMIT: i64 = 200;
+
+/// Body of the requests sent to the webhooks.
+#[derive(Serialize)]
+struct WebhookPayload<T> {
+    event: &'static str,
+    board_id: i64,
+    created_at: DateTime<Utc>,
+    data: T,
+}
+
+#[derive(Serialize)]
+struct TestEventData<'a> {
+    webhook_id: &'a str,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Webhook {
+    #[schema(value_type = String)]
+    webhook_id: WebhookId,
+    url: String,
+    /// Empty if all the events are delivered.
+    event_types: Vec<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<&WebhookDescription> for Webhook {
+    fn from(webhook: &WebhookDescription) -> Self {
+        Self {
+            webhook_id: webhook.webhook_id.clone(),
+            url: webhook.data.url.clone(),
+            event_types: webhook.data.event_types.clone(),
+            created_at: webhook.data.created_at,
+        }
+    }
+}
+
+/// Webhook returned on creation, the only time the secret is shown.
+#[derive(Serialize, ToSchema)]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    webhook: Webhook,
+    secret: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Delivery {
+    #[schema(value_type = String)]
+    delivery_id: DeliveryId,
+    event_type: String,
+    status: &'static str,
+    attempts: u32,
+    next_attempt_at: Option<DateTime<Utc>>,
+    last_status_code: Option<u16>,
+    last_error: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<&WebhookDelivery> for Delivery {
+    fn from(delivery: &WebhookDelivery) -> Self {
+        Self {
+            delivery_id: delivery.delivery_id.clone(),
+            event_type: delivery.event_type.clone(),
+            status: delivery.status.as_str(),
+            attempts: delivery.attempts,
+            next_attempt_at: delivery.next_attempt_at,
+            last_status_code: delivery.last_status_code,
+            last_error: delivery.last_error.clone(),
+            created_at: delivery.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TestDelivery {
+    #[schema(value_type = String)]
+    delivery_id: DeliveryId,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[allow(non_snake_case)]
+pub struct WebhookInputData {
+    url: String,
+    /// Names of the events to deliver. All the events are delivered if empty or absent.
+    #[serde(default)]
+    eventTypes: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UnknownEventType {
+    event_type: String,
+    #[schema(value_type = Vec<String>)]
+    supported: &'static [&'static str],
+}
+
+type CreateWebhookResponse = Result<Response<CreatedWebhook>, Response<UnknownEventType>>;
+
+fn webhook_response<T>(result: Result<T, WebhookError>) -> Response<T> {
+    match result {
+        Ok(data) => Response::from_data(data),
+        Err(WebhookError::WebhookNotFound) => Response::from_error(ApiError::WebhookNotFound),
+        Err(WebhookError::InvalidUrl) => Response::from_error(ApiError::InvalidUrl),
+        Err(WebhookError::UnknownEventType(_)) => Response::from_error(ApiError::UnknownEventType),
+        Err(WebhookError::TooManyWebhooks) => Response::from_error(ApiError::TooManyWebhooks),
+    }
+}
+
+/// Returns the webhooks of the board, without their secrets.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (
+            status = 200,
+            description = "The webhooks of the board",
+            body = ResponseBody<Vec<Webhook>>,
+        ),
+        (status = 403, description = "Error code `forbidden`", body = Problem<NoData>),
+    ),
+)]
+#[get("/boards/<board_id>/webhooks")]
+pub async fn get_webhooks(
+    context: &ContextState,
+    user: AuthorizedUser,
+    board_id: i64,
+) -> Response<Vec<Webhook>> {
+    let board_id = BoardId::from_raw(board_id);
+
+    if !context.tasks.can_access_board(user.user_id, board_id) {
+        return Response::from_error(ApiError::Forbidden);
+    }
+
+    let webhooks = context.webhooks.fetch_webhooks(board_id).await?;
+
+    Response::from_data(webhooks.iter().map(Webhook::from).collect())
+}
+
+/// Registers a webhook. The response carries the secret that the deliveries are signed with,
+/// which is not shown again.
+#[utoipa::path(
+    security(("session" = [])),
+    params(IdempotencyKey),
+    request_body = WebhookInputData,
+    responses(
+        (status = 200, description = "The created webhook", body = ResponseBody<CreatedWebhook>),
+        (status = 403, description = "Error code `forbidden`", body = Problem<NoData>),
+        (
+            status = 422,
+            description = "Error code `invalid_url`, `too_many_webhooks`, or `unknown_event_type` \
+                with the supported events",
+            body = Problem<UnknownEventType>,
+        ),
+    ),
+)]
+#[post(
+    "/boards/<board_id>/webhooks",
+    format = "application/json",
+    data = "<data>"
+)]
+pub async fn create_webhook(
+    context: &ContextState,
+    user: AuthorizedUser,
+    board_id: i64,
+    data: Idempotent<WebhookInputData>,
+) -> CreateWebhookResponse {
+    let board_id = BoardId::from_raw(board_id);
+
+    if !context.tasks.can_access_board(user.user_id, board_id) {
+        return Ok(Response::from_error(ApiError::Forbidden));
+    }
+
+    let (data, key) = data.into_parts();
+
+    idempotent(context, Some(user.user_id), key.as_ref(), async {
+        let result = context
+            .webhooks
+            .create_webhook(board_id, &data.url, data.eventTypes, Utc::now())
+            .await;
+
+        match result {
+            Ok(Err(WebhookError::UnknownEventType(event_type))) => {
+                Err(Response::from_error_details(
+                    ApiError::UnknownEventType,
+                    UnknownEventType {
+                        event_type,
+                        supported: WEBHOOK_EVENT_TYPES,
+                    },
+                ))
+            }
+            Ok(result) => Ok(webhook_response(result.map(|webhook| CreatedWebhook {
+                webhook: Webhook::from(&webhook),
+                secret: webhook.data.secret,
+            }))),
+            Err(err) => Ok(Response::ServerError(err.into())),
+        }
+    })
+    .await
+}
+
+/// Deletes the webhook. Its pending deliveries are dropped.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The webhook is deleted", body = ResponseBody<NoData>),
+        (status = 403, description = "Error code `forbidden`", body = Problem<NoData>),
+        (status = 404, description = "Error code `webhook_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[delete("/boards/<board_id>/webhooks/<webhook_id>")]
+pub async fn delete_webhook(
+    context: &ContextState,
+    user: AuthorizedUser,
+    board_id: i64,
+    webhook_id: &str,
+) -> Response<()> {
+    let board_id = BoardId::from_raw(board_id);
+
+    if !context.tasks.can_access_board(user.user_id, board_id) {
+        return Response::from_error(ApiError::Forbidden);
+    }
+
+    let result = context
+        .webhooks
+        .delete_webhook(board_id, webhook_id)
+        .await?;
+
+    webhook_response(result)
+}
+
+/// Returns the most recent deliveries of the webhook, newest first.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The deliveries", body = ResponseBody<Vec<Delivery>>),
+        (status = 403, description = "Error code `forbidden`", body = Problem<NoData>),
+        (status = 404, description = "Error code `webhook_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[get("/boards/<board_id>/webhooks/<webhook_id>/deliveries?<limit>")]
+pub async fn get_webhook_deliveries(
+    context: &ContextState,
+    user: AuthorizedUser,
+    board_id: i64,
+    webhook_id: &str,
+    limit: Option<i64>,
+) -> Response<Vec<Delivery>> {
+    let board_id = BoardId::from_raw(board_id);
+
+    if !context.tasks.can_access_board(user.user_id, board_id) {
+        return Response::from_error(ApiError::Forbidden);
+    }
+
+    let limit = limit
+        .unwrap_or(DEFAULT_DELIVERIES_LIMIT)
+        .clamp(1, MAX_DELIVERIES_LIMIT);
+
+    let result = context
+        .webhooks
+        .fetch_deliveries(board_id, webhook_id, limit)
+        .await?;
+
+    webhook_response(result.map(|deliveries| deliveries.iter().map(Delivery::from).collect()))
+}
+
+/// Queues a `ping` event to the webhook, regardless of its event filter.
+#[utoipa::path(
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "The queued delivery", body = ResponseBody<TestDelivery>),
+        (status = 403, description = "Error code `forbidden`", body = Problem<NoData>),
+        (status = 404, description = "Error code `webhook_not_found`", body = Problem<NoData>),
+    ),
+)]
+#[post("/boards/<board_id>/webhooks/<webhook_id>/test")]
+pub async fn send_test_event(
+    context: &ContextState,
+    user: AuthorizedUser,
+    board_id: i64,
+    webhook_id: &str,
+) -> Response<TestDelivery> {
+    let board_id = BoardId::from_raw(board_id);
+
+    if !context.tasks.can_access_board(user.user_id, board_id) {
+        return Response::from_error(ApiError::Forbidden);
+    }
+
+    let now = Utc::now();
+    let payload = json::to_string(&WebhookPayload {
+        event: TEST_EVENT_TYPE,
+        board_id: board_id.raw(),
+        created_at: now,
+        data: TestEventData { webhook_id },
+    })
+    .map_err(anyhow::Error::from)?;
+
+    let result = context
+        .webhooks
+        .send_test_event(board_id, webhook_id, &payload, now)
+        .await?;
+
+    webhook_response(result.map(|delivery_id| TestDelivery { delivery_id }))
+}
+
+/// Renders the deliveries of the board events with the payloads of the event stream.
+pub struct JsonWebhookRenderer;
+
+impl WebhookRenderer for JsonWebhookRenderer {
+    fn render(
+        &self,
+        board_id: BoardId,
+        event: &BoardEventKind,
+        created_at: DateTime<Utc>,
+    ) -> anyhow::Result<String> {
+        let (name, data) = EventPayload::from_event(event);
+
+        Ok(json::to_string(&WebhookPayload {
+            event: name,
+            board_id: board_id.raw(),
+            created_at,
+            data,
+        })?)
+    }
+}
@@ -0,0 +1,535 @@
+use rocket::{
+    http::{ContentType, Status},
+    response::{self, Responder},
+    serde::{json, Serialize, Serializer},
+    Request,
+};
+use utoipa::{
+    openapi::{schema::SchemaType, ObjectBuilder, RefOr, Schema, Type},
+    PartialSchema, ToSchema,
+};
+
+/// Declares [`ApiError`] and [`ApiError::ALL`] from the same list of variants.
+macro_rules! api_errors {
+    ($($variant:ident,)*) => {
+        /// Error of a request. Its code is stable and part of the API, so clients may match on it,
+        /// while the message may change.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum ApiError {
+            $($variant,)*
+        }
+
+        impl ApiError {
+            /// Every error, in the order of declaration.
+            pub const ALL: &'static [ApiError] = &[$(ApiError::$variant,)*];
+        }
+    };
+}
+
+api_errors! {
+    BadRequest,
+    InvalidBody,
+    InvalidIdempotencyKey,
+    BodyTooLarge,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    WebSocketRequired,
+    ServerError,
+
+    InvalidUsername,
+    InvalidPassword,
+    UserAlreadyExists,
+    UserNotFound,
+    IncorrectPassword,
+    InvalidTimezone,
+
+    TaskNotFound,
+    CategoryNotFound,
+    CategoryTrashed,
+    InvalidText,
+    InvalidFilter,
+    InvalidPatch,
+    VersionRequired,
+    VersionConflict,
+    TooManyOperations,
+    BulkOperationFailed,
+    RequestInProgress,
+    IdempotencyKeyReused,
+
+    LabelNotFound,
+    InvalidName,
+    InvalidLabel,
+    ItemNotFound,
+    CommentNotFound,
+    NotAuthor,
+    ViewNotFound,
+    NotOwner,
+    InvalidQuery,
+
+    AttachmentNotFound,
+    FileTooLarge,
+    QuotaExceeded,
+    UnsupportedContentType,
+
+    InvalidTrelloExport,
+    InvalidBoardExport,
+    UnsupportedExportVersion,
+    InvalidCsv,
+    MissingColumn,
+    TooManyTasks,
+    UnknownFormat,
+
+    CalendarNotFound,
+    WebhookNotFound,
+    InvalidUrl,
+    UnknownEventType,
+    TooManyWebhooks,
+
+    InvalidMessage,
+    MessageTooLarge,
+    UnknownRecipient,
+    NoCategory,
+}
+
+impl ApiError {
+    /// Returns the status, the code and the message of the error.
+    fn describe(self) -> (Status, &'static str, &'static str) {
+        use Status as S;
+
+        match self {
+            ApiError::BadRequest => (S::BadRequest, "bad_request", "The request is malformed."),
+            ApiError::InvalidBody => (
+                S::UnprocessableEntity,
+                "invalid_body",
+                "The body of the request is malformed or lacks required fields.",
+            ),
+            ApiError::InvalidIdempotencyKey => (
+                S::BadRequest,
+                "invalid_idempotency_key",
+                "The idempotency key must be 1 to 255 visible ASCII characters.",
+            ),
+            ApiError::BodyTooLarge => (
+                S::PayloadTooLarge,
+                "body_too_large",
+                "The body of the request is too large.",
+            ),
+            ApiError::Unauthorized => (
+                S::Unauthorized,
+                "unauthorized",
+                "The request requires a session. Log in to get one.",
+            ),
+            ApiError::Forbidden => (
+                S::Forbidden,
+                "forbidden",
+                "The board is not accessible to the user.",
+            ),
+            ApiError::NotFound => (S::NotFound, "not_found", "The resource does not exist."),
+            ApiError::WebSocketRequired => (
+                S::UpgradeRequired,
+                "websocket_required",
+                "The endpoint only accepts WebSocket connections.",
+            ),
+            ApiError::ServerError => (
+                S::InternalServerError,
+                "server_error",
+                "The server failed to handle the request.",
+            ),
+
+            ApiError::InvalidUsername => (
+                S::UnprocessableEntity,
+                "invalid_username",
+                "The username does not meet the requirements.",
+            ),
+            ApiError::InvalidPassword => (
+                S::UnprocessableEntity,
+                "invalid_password",
+                "The password does not meet the requirements.",
+            ),
+            ApiError::UserAlreadyExists => (
+                S::Conflict,
+                "user_already_exists",
+                "A user with the username already exists.",
+            ),
+            ApiError::UserNotFound => (
+                S::Unauthorized,
+                "user_not_found",
+                "There is no user with the username.",
+            ),
+            ApiError::IncorrectPassword => (
+                S::Unauthorized,
+                "incorrect_password",
+                "The password is incorrect.",
+            ),
+            ApiError::InvalidTimezone => (
+                S::UnprocessableEntity,
+                "invalid_timezone",
+                "The timezone is not a known IANA timezone.",
+            ),
+
+            ApiError::TaskNotFound => (S::NotFound, "task_not_found", "The task does not exist."),
+            ApiError::CategoryNotFound => (
+                S::NotFound,
+                "category_not_found",
+                "The category does not exist.",
+            ),
+            ApiError::CategoryTrashed => (
+                S::Conflict,
+                "category_trashed",
+                "The category of the task is in the trash. Restore it first.",
+            ),
+            ApiError::InvalidText => (
+                S::UnprocessableEntity,
+                "invalid_text",
+                "The text is empty or too long.",
+            ),
+            ApiError::InvalidFilter => (
+                S::UnprocessableEntity,
+                "invalid_filter",
+                "The filter has a syntax error.",
+            ),
+            ApiError::InvalidPatch => (
+                S::UnprocessableEntity,
+                "invalid_patch",
+                "The patch is not a valid merge patch of the task.",
+            ),
+            ApiError::VersionRequired => (
+                S::PreconditionRequired,
+                "version_required",
+                "The request must give the version of the task in `If-Match` header or the body.",
+            ),
+            ApiError::VersionConflict => (
+                S::PreconditionFailed,
+                "version_conflict",
+                "The task has been modified since the given version.",
+            ),
+            ApiError::TooManyOperations => (
+                S::UnprocessableEntity,
+                "too_many_operations",
+                "The request has too many operations.",
+            ),
+            ApiError::BulkOperationFailed => (
+                S::UnprocessableEntity,
+                "bulk_operation_failed",
+                "An operation failed, so none of them were applied.",
+            ),
+            ApiError::RequestInProgress => (
+                S::Conflict,
+                "request_in_progress",
+                "A request with the same idempotency key is still in progress.",
+            ),
+            ApiError::IdempotencyKeyReused => (
+                S::UnprocessableEntity,
+                "idempotency_key_reused",
+                "The idempotency key was used for a different request.",
+            ),
+
+            ApiError::LabelNotFound => {
+                (S::NotFound, "label_not_found", "The label does not exist.")
+            }
+            ApiError::InvalidName => (
+                S::UnprocessableEntity,
+                "invalid_name",
+                "The name is empty or too long.",
+            ),
+            ApiError::InvalidLabel => (
+                S::UnprocessableEntity,
+                "invalid_label",
+                "The name or the colour of the label is invalid.",
+            ),
+            ApiError::ItemNotFound => (
+                S::NotFound,
+                "item_not_found",
+                "The checklist item does not exist.",
+            ),
+            ApiError::CommentNotFound => (
+                S::NotFound,
+                "comment_not_found",
+                "The comment does not exist.",
+            ),
+            ApiError::NotAuthor => (
+                S::Forbidden,
+                "not_author",
+                "Only the author of the comment may change it.",
+            ),
+            ApiError::ViewNotFound => (S::NotFound, "view_not_found", "The view does not exist."),
+            ApiError::NotOwner => (
+                S::Forbidden,
+                "not_owner",
+                "Only the owner of the view may change it.",
+            ),
+            ApiError::InvalidQuery => (
+                S::UnprocessableEntity,
+                "invalid_query",
+                "The search query is empty or too long.",
+            ),
+
+            ApiError::AttachmentNotFound => (
+                S::NotFound,
+                "attachment_not_found",
+                "The attachment does not exist.",
+            ),
+            ApiError::FileTooLarge => (
+                S::PayloadTooLarge,
+                "file_too_large",
+                "The file is larger than allowed.",
+            ),
+            ApiError::QuotaExceeded => (
+                S::PayloadTooLarge,
+                "quota_exceeded",
+                "The attachments of the board would exceed its storage quota.",
+            ),
+            ApiError::UnsupportedContentType => (
+                S::UnsupportedMediaType,
+                "unsupported_content_type",
+                "The type of the file is not allowed.",
+            ),
+
+            ApiError::InvalidTrelloExport => (
+                S::UnprocessableEntity,
+                "invalid_trello_export",
+                "The file is not a Trello board export.",
+            ),
+            ApiError::InvalidBoardExport => (
+                S::UnprocessableEntity,
+                "invalid_board_export",
+                "The file is not a board exported by this API.",
+            ),
+            ApiError::UnsupportedExportVersion => (
+                S::UnprocessableEntity,
+                "unsupported_export_version",
+                "The board was exported with an unsupported version of the format.",
+            ),
+            ApiError::InvalidCsv => (
+                S::UnprocessableEntity,
+                "invalid_csv",
+                "The CSV file is malformed.",
+            ),
+            ApiError::MissingColumn => (
+                S::UnprocessableEntity,
+                "missing_column",
+                "The CSV file lacks a required column.",
+            ),
+            ApiError::TooManyTasks => (
+                S::UnprocessableEntity,
+                "too_many_tasks",
+                "The file has too many tasks to import.",
+            ),
+            ApiError::UnknownFormat => (
+                S::UnprocessableEntity,
+                "unknown_format",
+                "The format is not supported.",
+            ),
+
+            ApiError::CalendarNotFound => (
+                S::NotFound,
+                "calendar_not_found",
+                "The calendar feed is not enabled or has been reset.",
+            ),
+            ApiError::WebhookNotFound => (
+                S::NotFound,
+                "webhook_not_found",
+                "The webhook does not exist.",
+            ),
+            ApiError::InvalidUrl => (
+                S::UnprocessableEntity,
+                "invalid_url",
+                "The URL of the webhook must be an absolute HTTP or HTTPS URL of a public host.",
+            ),
+            ApiError::UnknownEventType => (
+                S::UnprocessableEntity,
+                "unknown_event_type",
+                "The event type is not supported.",
+            ),
+            ApiError::TooManyWebhooks => (
+                S::UnprocessableEntity,
+                "too_many_webhooks",
+                "The board has the maximum number of webhooks.",
+            ),
+
+            ApiError::InvalidMessage => (
+                S::UnprocessableEntity,
+                "invalid_message",
+                "The message is not a valid MIME message.",
+            ),
+            ApiError::MessageTooLarge => (
+                S::PayloadTooLarge,
+                "message_too_large",
+                "The message is larger than allowed.",
+            ),
+            ApiError::UnknownRecipient => (
+                S::NotFound,
+                "unknown_recipient",
+                "No recipient of the message is the inbound address of a board.",
+            ),
+            ApiError::NoCategory => (
+                S::UnprocessableEntity,
+                "no_category",
+                "The board has no category to add the task to.",
+            ),
+        }
+    }
+
+    pub fn status(self) -> Status {
+        self.describe().0
+    }
+
+    pub fn code(self) -> &'static str {
+        self.describe().1
+    }
+
+    pub fn message(self) -> &'static str {
+        self.describe().2
+    }
+
+    /// Returns the error that a catcher reports for the status.
+    pub fn from_status(status: Status) -> Self {
+        match status.code {
+            401 => ApiError::Unauthorized,
+            403 => ApiError::Forbidden,
+            404 => ApiError::NotFound,
+            413 => ApiError::BodyTooLarge,
+            422 => ApiError::InvalidBody,
+            426 => ApiError::WebSocketRequired,
+            400..=499 => ApiError::BadRequest,
+            _ => ApiError::ServerError,
+        }
+    }
+
+    /// Makes the error the one reported by the catcher if the request fails in a guard.
+    pub fn cache(self, request: &Request<'_>, detail: Option<String>) -> Self {
+        request.local_cache(|| CachedError(Some((self, detail))));
+        self
+    }
+}
+
+impl Serialize for ApiError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+impl PartialSchema for ApiError {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(SchemaType::Type(Type::String))
+            .enum_values(Some(ApiError::ALL.iter().map(|error| error.code())))
+            .into()
+    }
+}
+
+impl ToSchema for ApiError {}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        Problem::<()>::new(self).respond_to(request)
+    }
+}
+
+/// Error cached by a guard, since the catchers only get the status.
+struct CachedError(Option<(ApiError, Option<String>)>);
+
+/// Body of the error responses, an RFC 7807 problem document with the code of the error
+/// and the data that describes it, if any.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Problem<T> {
+    /// `urn:tasks:error:` followed by the code.
+    #[serde(rename = "type")]
+    problem_type: String,
+    /// Message of the error.
+    title: &'static str,
+    status: u16,
+    code: ApiError,
+    /// Explanation of this occurrence of the error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    /// Data that describes the error, such as the invalid fields.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<T>,
+}
+
+impl<T> Problem<T> {
+    pub fn new(error: ApiError) -> Self {
+        Self {
+            problem_type: format!("urn:tasks:error:{}", error.code()),
+            title: error.message(),
+            status: error.status().code,
+            code: error,
+            detail: None,
+            details: None,
+        }
+    }
+
+    pub fn with_detail(mut self, detail: String) -> Self {
+        self.detail = Some(detail);
+        self
+    }
+
+    pub fn with_details(mut self, details: T) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn error(&self) -> ApiError {
+        self.code
+    }
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Problem<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let body = json::to_string(&self).map_err(|err| {
+            log::error!("Server error: {:?}", err);
+            Status::InternalServerError
+        })?;
+
+        (self.code.status(), (problem_json(), body)).respond_to(request)
+    }
+}
+
+pub fn problem_json() -> ContentType {
+    ContentType::new("application", "problem+json")
+}
+
+/// Reports the errors of the requests that fail before reaching a handler, such as a missing
+/// session, an unknown route or a malformed body, as problem documents.
+#[catch(default)]
+pub fn default_catcher(status: Status, request: &Request<'_>) -> Problem<()> {
+    match &request.local_cache(|| CachedError(None)).0 {
+        Some((error, detail)) if error.status() == status => {
+            let problem = Problem::new(*error);
+            match detail {
+                Some(detail) => problem.with_detail(detail.clone()),
+                None => problem,
+            }
+        }
+        _ => Problem::new(ApiError::from_status(status)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn codes_are_unique() {
+        let codes: HashSet<_> = ApiError::ALL.iter().map(|error| error.code()).collect();
+        assert_eq!(codes.len(), ApiError::ALL.len());
+    }
+
+    #[test]
+    fn errors_have_error_statuses() {
+        for error in ApiError::ALL {
+            assert!(error.status().code >= 400, "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn catchers_report_the_status() {
+        for code in [400, 401, 403, 404, 413, 422, 426, 500] {
+            let status = Status::from_code(code).unwrap();
+            assert_eq!(ApiError::from_status(status).status(), status);
+        }
+    }
+}
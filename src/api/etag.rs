@@ -0,0 +1,132 @@
+use std::convert::Infallible;
+
+use rocket::{
+    request::{FromRequest, Outcome},
+    Request,
+};
+use utoipa::{
+    openapi::{
+        path::{Parameter, ParameterBuilder, ParameterIn},
+        Required,
+    },
+    IntoParams, PartialSchema,
+};
+
+use crate::model::tasks::Version;
+
+/// Formats the version of an entity as a strong entity tag.
+pub fn entity_tag(version: Version) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Condition of `If-Match` request header.
+pub enum IfMatch {
+    /// The header is not present.
+    Absent,
+    /// `If-Match: *`, any version matches.
+    Any,
+    /// The version listed in the header.
+    Version(Version),
+    /// The header lists no entity tag that can be a version, so no version matches.
+    NoVersion,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(header) = request.headers().get_one("If-Match") else {
+            return Outcome::Success(IfMatch::Absent);
+        };
+
+        if header.trim() == "*" {
+            return Outcome::Success(IfMatch::Any);
+        }
+
+        // A task has a single current version, so only the first version in the list is used.
+        let version = header.split(',').find_map(|tag| {
+            // Weak tags never match in `If-Match`.
+            let tag = tag.trim().strip_prefix('"')?.strip_suffix('"')?;
+            tag.parse().ok()
+        });
+
+        Outcome::Success(match version {
+            Some(version) => IfMatch::Version(version),
+            None => IfMatch::NoVersion,
+        })
+    }
+}
+
+impl IntoParams for IfMatch {
+    fn into_params(_: impl Fn() -> Option<ParameterIn>) -> Vec<Parameter> {
+        vec![header_param(
+            "If-Match",
+            "The version the changes are based on, as returned in `ETag` header.",
+        )]
+    }
+}
+
+/// Entity tags listed in `If-None-Match` request header.
+pub enum IfNoneMatch {
+    Absent,
+    /// `If-None-Match: *`, any current representation matches.
+    Any,
+    Tags(Vec<String>),
+}
+
+impl IfNoneMatch {
+    /// Returns true if the entity tag matches the header by the weak comparison,
+    /// so the client already has the representation.
+    pub fn matches(&self, etag: &str) -> bool {
+        let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+
+        match self {
+            IfNoneMatch::Absent => false,
+            IfNoneMatch::Any => true,
+            IfNoneMatch::Tags(tags) => tags.iter().any(|tag| opaque(tag) == opaque(etag)),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfNoneMatch {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(header) = request.headers().get_one("If-None-Match") else {
+            return Outcome::Success(IfNoneMatch::Absent);
+        };
+
+        if header.trim() == "*" {
+            return Outcome::Success(IfNoneMatch::Any);
+        }
+
+        Outcome::Success(IfNoneMatch::Tags(
+            header
+                .split(',')
+                .map(|tag| tag.trim().to_string())
+                .collect(),
+        ))
+    }
+}
+
+impl IntoParams for IfNoneMatch {
+    fn into_params(_: impl Fn() -> Option<ParameterIn>) -> Vec<Parameter> {
+        vec![header_param(
+            "If-None-Match",
+            "`ETag` of the representation the client has. If it is current, \
+             the response is `304 Not Modified` without a body.",
+        )]
+    }
+}
+
+fn header_param(name: &str, description: &str) -> Parameter {
+    ParameterBuilder::new()
+        .name(name)
+        .parameter_in(ParameterIn::Header)
+        .required(Required::False)
+        .description(Some(description))
+        .schema(Some(String::schema()))
+        .build()
+}
@@ -0,0 +1,238 @@
+use std::future::Future;
+
+use rocket::{
+    data::{self, FromData, Limits},
+    serde::{json, DeserializeOwned},
+    Data, Request,
+};
+use sha2::{Digest, Sha256};
+use utoipa::{
+    openapi::{
+        path::{Parameter, ParameterBuilder, ParameterIn},
+        Required,
+    },
+    IntoParams, PartialSchema,
+};
+
+use crate::{
+    app::idempotency::{Reservation, MAX_KEY_LENGTH},
+    model::{idempotency::StoredResponse, UserId},
+};
+
+use super::{ApiError, Context, Response};
+
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Idempotency key of a request, with the hash of the request it is sent with.
+pub struct IdempotencyKey {
+    key: String,
+    fingerprint: String,
+}
+
+impl IntoParams for IdempotencyKey {
+    fn into_params(_: impl Fn() -> Option<ParameterIn>) -> Vec<Parameter> {
+        vec![ParameterBuilder::new()
+            .name(IDEMPOTENCY_KEY_HEADER)
+            .parameter_in(ParameterIn::Header)
+            .required(Required::False)
+            .description(Some(
+                "Retries with the same key get the response to the first request \
+                 instead of repeating it.",
+            ))
+            .schema(Some(String::schema()))
+            .build()]
+    }
+}
+
+/// Body of a request that may carry `Idempotency-Key` header, JSON unless the endpoint reads it otherwise.
+pub struct Idempotent<T> {
+    data: T,
+    key: Option<IdempotencyKey>,
+}
+
+impl<T> Idempotent<T> {
+    pub fn key(&self) -> Option<&IdempotencyKey> {
+        self.key.as_ref()
+    }
+
+    pub fn into_parts(self) -> (T, Option<IdempotencyKey>) {
+        (self.data, self.key)
+    }
+}
+
+impl<T> std::ops::Deref for Idempotent<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.data
+    }
+}
+
+/// Body read by [`Idempotent`].
+#[rocket::async_trait]
+pub trait IdempotentBody<'r>: Sized {
+    /// Reads the body, adding the content that tells a retry from a different request to the fingerprint.
+    /// Fails with the error the catcher reports, after caching it.
+    async fn read(
+        request: &'r Request<'_>,
+        data: Data<'r>,
+        fingerprint: &mut Sha256,
+    ) -> Result<Self, ApiError>;
+}
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned + Send> IdempotentBody<'r> for T {
+    async fn read(
+        request: &'r Request<'_>,
+        data: Data<'r>,
+        fingerprint: &mut Sha256,
+    ) -> Result<Self, ApiError> {
+        let limit = request.limits().get("json").unwrap_or(Limits::JSON);
+
+        let body = match data.open(limit).into_string().await {
+            Ok(body) if body.is_complete() => body.into_inner(),
+            Ok(_) => return Err(ApiError::BodyTooLarge.cache(request, None)),
+            Err(err) => return Err(ApiError::BadRequest.cache(request, Some(err.to_string()))),
+        };
+
+        fingerprint.update(&body);
+
+        match json::from_str(&body) {
+            Ok(data) => Ok(data),
+            Err(err) => Err(ApiError::InvalidBody.cache(request, Some(err.to_string()))),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: IdempotentBody<'r>> FromData<'r> for Idempotent<T> {
+    type Error = ApiError;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let key = request.headers().get_one(IDEMPOTENCY_KEY_HEADER);
+
+        if key.is_some_and(|key| {
+            key.is_empty()
+                || key.len() > MAX_KEY_LENGTH
+                || !key.bytes().all(|b| b.is_ascii_graphic())
+        }) {
+            let error = ApiError::InvalidIdempotencyKey.cache(request, None);
+            return data::Outcome::Error((error.status(), error));
+        }
+
+        // A retry is the same request only if it has the same target and the same body.
+        let mut hasher = Sha256::new();
+        hasher.update(request.method().as_str());
+        hasher.update(b"\n");
+        hasher.update(request.uri().to_string());
+        hasher.update(b"\n");
+
+        let data = match T::read(request, data, &mut hasher).await {
+            Ok(data) => data,
+            Err(error) => return data::Outcome::Error((error.status(), error)),
+        };
+
+        let key = key.map(|key| IdempotencyKey {
+            key: key.to_string(),
+            fingerprint: hex::encode(hasher.finalize()),
+        });
+
+        data::Outcome::Success(Idempotent { data, key })
+    }
+}
+
+/// Response that can be stored and replayed to the retries of the request.
+pub trait Replayable: Sized {
+    /// Returns `None` if the response must not be replayed, such as a server error.
+    fn stored(&self) -> Option<StoredResponse>;
+
+    fn replayed(response: StoredResponse) -> Self;
+
+    fn failure(error: ApiError) -> Self;
+
+    fn server_error(err: anyhow::Error) -> Self;
+}
+
+impl<T: rocket::serde::Serialize> Replayable for Response<T> {
+    fn stored(&self) -> Option<StoredResponse> {
+        self.stored()
+    }
+
+    fn replayed(response: StoredResponse) -> Self {
+        Response::Replayed(response)
+    }
+
+    fn failure(error: ApiError) -> Self {
+        Response::from_error(error)
+    }
+
+    fn server_error(err: anyhow::Error) -> Self {
+        Response::ServerError(err.into())
+    }
+}
+
+impl<T: rocket::serde::Serialize, E: rocket::serde::Serialize> Replayable
+    for Result<Response<T>, Response<E>>
+{
+    fn stored(&self) -> Option<StoredResponse> {
+        match self {
+            Ok(response) => response.stored(),
+            Err(response) => response.stored(),
+        }
+    }
+
+    fn replayed(response: StoredResponse) -> Self {
+        Ok(Response::Replayed(response))
+    }
+
+    fn failure(error: ApiError) -> Self {
+        Ok(Response::from_error(error))
+    }
+
+    fn server_error(err: anyhow::Error) -> Self {
+        Ok(Response::ServerError(err.into()))
+    }
+}
+
+/// Handles the request once per idempotency key of the user, or of the clients that have not
+/// logged in if `user_id` is `None`, replaying the stored response to the retries.
+/// Without a key, the request is handled every time.
+pub async fn idempotent<R: Replayable>(
+    context: &Context,
+    user_id: Option<UserId>,
+    key: Option<&IdempotencyKey>,
+    handler: impl Future<Output = R>,
+) -> R {
+    let Some(key) = key else {
+        return handler.await;
+    };
+
+    let idempotency = &context.idempotency;
+
+    match idempotency
+        .reserve(user_id, &key.key, &key.fingerprint)
+        .await
+    {
+        Ok(Reservation::Reserved) => {}
+        Ok(Reservation::Replay(response)) => return R::replayed(response),
+        Ok(Reservation::InProgress) => return R::failure(ApiError::RequestInProgress),
+        Ok(Reservation::KeyReused) => return R::failure(ApiError::IdempotencyKeyReused),
+        Err(err) => return R::server_error(err),
+    }
+
+    let response = handler.await;
+
+    let result = match response.stored() {
+        Some(stored) => idempotency.complete(user_id, &key.key, &stored).await,
+        None => idempotency.release(user_id, &key.key).await,
+    };
+
+    if let Err(err) = result {
+        log::error!(
+            "Could not store the response for the idempotency key: {:?}",
+            err
+        );
+    }
+
+    response
+}
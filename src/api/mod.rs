@@ -1,25 +1,128 @@
 use std::sync::Arc;
 
-use rocket::{Build, Rocket};
+use rocket::{
+    data::{ByteUnit, Limits},
+    Build, Config, Rocket, Route,
+};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 mod context;
 pub mod controllers;
+mod error;
+mod etag;
+mod idempotency;
+mod openapi;
 mod response;
+mod smtp;
+mod websocket;
 
 pub use context::{Context, ContextState};
-pub use response::Response;
+pub use controllers::webhooks::JsonWebhookRenderer;
+pub use error::{ApiError, Problem};
+pub use response::{NoData, Response, ResponseBody};
+pub use smtp::serve_smtp;
 
-/// Creates [`Rocket`] object that serves API requests using the provided context.
-pub fn initialize_api(context: Arc<Context>) -> Rocket<Build> {
-    let api_routes = routes![
+use openapi::ApiDoc;
+
+/// Maximum size of a message received by the inbound email gateway.
+pub const MAX_EMAIL_SIZE: ByteUnit = ByteUnit::Mebibyte(25);
+
+/// Returns the routes of the API, which are mounted at `/api`.
+pub(crate) fn api_routes() -> Vec<Route> {
+    routes![
         controllers::auth::login,
         controllers::auth::register,
         controllers::auth::get_user,
+        controllers::auth::get_user_settings,
+        controllers::auth::modify_user_settings,
+        controllers::calendar::get_calendar_feed,
+        controllers::calendar::reset_calendar_feed,
+        controllers::calendar::revoke_calendar_feed,
+        controllers::calendar::get_calendar,
         controllers::tasks::get_tasks,
+        controllers::tasks::get_task,
+        controllers::tasks::get_category_tasks,
+        controllers::tasks::get_board_summary,
+        controllers::tasks::rename_category,
+        controllers::tasks::get_tasks_due,
         controllers::tasks::create_task,
         controllers::tasks::delete_task,
         controllers::tasks::modify_task,
-    ];
+        controllers::tasks::patch_task,
+        controllers::bulk::apply_bulk_operations,
+        controllers::import::import_trello,
+        controllers::import::import_csv,
+        controllers::import::import_exported_board,
+        controllers::export::export_board,
+        controllers::search::search,
+        controllers::sync::get_changes,
+        controllers::sync::apply_operations,
+        controllers::views::get_views,
+        controllers::views::create_view,
+        controllers::views::get_view,
+        controllers::views::modify_view,
+        controllers::views::delete_view,
+        controllers::views::get_view_tasks,
+        controllers::trash::get_archive,
+        controllers::trash::get_trash,
+        controllers::trash::archive_task,
+        controllers::trash::restore_task,
+        controllers::trash::archive_category,
+        controllers::trash::delete_category,
+        controllers::trash::restore_category,
+        controllers::labels::get_labels,
+        controllers::labels::create_label,
+        controllers::labels::modify_label,
+        controllers::labels::delete_label,
+        controllers::labels::assign_label,
+        controllers::labels::unassign_label,
+        controllers::checklists::get_checklist,
+        controllers::checklists::add_checklist_item,
+        controllers::checklists::modify_checklist_item,
+        controllers::checklists::delete_checklist_item,
+        controllers::comments::get_comments,
+        controllers::comments::create_comment,
+        controllers::comments::modify_comment,
+        controllers::comments::delete_comment,
+        controllers::comments::get_comment_revisions,
+        controllers::attachments::get_attachments,
+        controllers::attachments::upload_attachment,
+        controllers::attachments::download_attachment,
+        controllers::attachments::delete_attachment,
+        controllers::activity::get_task_activity,
+        controllers::activity::get_board_activity,
+        controllers::events::board_events,
+        controllers::webhooks::get_webhooks,
+        controllers::webhooks::create_webhook,
+        controllers::webhooks::delete_webhook,
+        controllers::webhooks::get_webhook_deliveries,
+        controllers::webhooks::send_test_event,
+        controllers::inbound::get_inbound_address,
+        controllers::inbound::reset_inbound_address,
+        controllers::inbound::receive_inbound_email,
+        controllers::collaboration::board_channel,
+    ]
+}
+
+/// Creates [`Rocket`] object that serves API requests using the provided context.
+pub fn initialize_api(context: Arc<Context>) -> Rocket<Build> {
+    // Uploaded files are limited by the attachment limits. The rest of the form is small.
+    // Imported boards and inbound messages may be much larger than the other JSON bodies.
+    let max_file_size = ByteUnit::from(context.attachments.limits().max_file_size.max(0) as u64);
+    let limits = Limits::default()
+        .limit("file", max_file_size)
+        .limit("data-form", max_file_size + ByteUnit::Mebibyte(1))
+        .limit("import", ByteUnit::Mebibyte(8))
+        .limit("email", MAX_EMAIL_SIZE);
+    let figment = Config::figment().merge(("limits", limits));
 
-    rocket::build().manage(context).mount("/api", api_routes)
+    rocket::custom(figment)
+        .manage(context)
+        .mount("/api", api_routes())
+        .register("/", catchers![error::default_catcher])
+        .mount(
+            "/",
+            SwaggerUi::new("/api/docs/<_..>").url("/api/openapi.json", ApiDoc::openapi()),
+        )
 }
@@ -0,0 +1,321 @@
+use utoipa::{
+    openapi::{
+        path::Operation,
+        security::{ApiKey, ApiKeyValue, SecurityScheme},
+        ContentBuilder, Ref, RefOr, ResponseBuilder,
+    },
+    Modify, OpenApi,
+};
+
+use super::controllers::{
+    activity, attachments, auth, bulk, calendar, checklists, collaboration, comments, events,
+    export, import, inbound, labels, search, sync, tasks, trash, views, webhooks,
+};
+
+/// OpenAPI document of the API, generated from the routes and the types of their bodies.
+/// The paths are relative to `/api`.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Tasks API",
+        description = "Successful JSON responses are wrapped in `ResponseBody`, whose `data` holds \
+            the result. Errors are RFC 7807 `application/problem+json` documents whose `code` is \
+            stable, with the status of the error; the codes of an operation are listed in the \
+            descriptions of its responses. The operations that require the session cookie respond \
+            with `unauthorized` without it, and any operation may fail with `server_error`."
+    ),
+    servers((url = "/api")),
+    modifiers(&SessionCookie, &ProblemDocuments),
+    paths(
+        auth::login,
+        auth::register,
+        auth::get_user,
+        auth::get_user_settings,
+        auth::modify_user_settings,
+        calendar::get_calendar_feed,
+        calendar::reset_calendar_feed,
+        calendar::revoke_calendar_feed,
+        calendar::get_calendar,
+        tasks::get_tasks,
+        tasks::get_task,
+        tasks::get_category_tasks,
+        tasks::get_board_summary,
+        tasks::rename_category,
+        tasks::get_tasks_due,
+        tasks::create_task,
+        tasks::delete_task,
+        tasks::modify_task,
+        tasks::patch_task,
+        bulk::apply_bulk_operations,
+        import::import_trello,
+        import::import_csv,
+        import::import_exported_board,
+        export::export_board,
+        search::search,
+        sync::get_changes,
+        sync::apply_operations,
+        views::get_views,
+        views::create_view,
+        views::get_view,
+        views::modify_view,
+        views::delete_view,
+        views::get_view_tasks,
+        trash::get_archive,
+        trash::get_trash,
+        trash::archive_task,
+        trash::restore_task,
+        trash::archive_category,
+        trash::delete_category,
+        trash::restore_category,
+        labels::get_labels,
+        labels::create_label,
+        labels::modify_label,
+        labels::delete_label,
+        labels::assign_label,
+        labels::unassign_label,
+        checklists::get_checklist,
+        checklists::add_checklist_item,
+        checklists::modify_checklist_item,
+        checklists::delete_checklist_item,
+        comments::get_comments,
+        comments::create_comment,
+        comments::modify_comment,
+        comments::delete_comment,
+        comments::get_comment_revisions,
+        attachments::get_attachments,
+        attachments::upload_attachment,
+        attachments::download_attachment,
+        attachments::delete_attachment,
+        activity::get_task_activity,
+        activity::get_board_activity,
+        events::board_events,
+        webhooks::get_webhooks,
+        webhooks::create_webhook,
+        webhooks::delete_webhook,
+        webhooks::get_webhook_deliveries,
+        webhooks::send_test_event,
+        inbound::get_inbound_address,
+        inbound::reset_inbound_address,
+        inbound::receive_inbound_email,
+        collaboration::board_channel,
+    )
+)]
+pub struct ApiDoc;
+
+/// Session cookie set by `/login` and `/register`.
+struct SessionCookie;
+
+impl Modify for SessionCookie {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+
+        components.add_security_scheme(
+            "session",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("session"))),
+        );
+    }
+}
+
+/// Serves the error responses as problem documents, and documents the response to the requests
+/// of the operations that require a session without one.
+struct ProblemDocuments;
+
+impl Modify for ProblemDocuments {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.delete,
+                &mut item.patch,
+            ];
+
+            for operation in operations.into_iter().flatten() {
+                document_problems(operation);
+            }
+        }
+    }
+}
+
+fn document_problems(operation: &mut Operation) {
+    let responses = &mut operation.responses.responses;
+
+    for (status, response) in responses.iter_mut() {
+        let RefOr::T(response) = response else {
+            continue;
+        };
+
+        if status.starts_with(['4', '5']) {
+            if let Some(content) = response.content.shift_remove("application/json") {
+                response
+                    .content
+                    .insert("application/problem+json".to_string(), content);
+            }
+        }
+    }
+
+    if operation.security.is_some() && !responses.contains_key("401") {
+        let content = ContentBuilder::new()
+            .schema(Some(Ref::from_schema_name("Problem_NoData")))
+            .build();
+        let response = ResponseBuilder::new()
+            .description("Error code `unauthorized` if the session cookie is missing or expired")
+            .content("application/problem+json", content)
+            .build();
+
+        responses.insert("401".to_string(), response.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use rocket::serde::json::{self, Value};
+    use utoipa::OpenApi;
+
+    use super::super::api_routes;
+    use super::ApiDoc;
+
+    const METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+
+    /// Method, path in OpenAPI form and the names of the path and query parameters.
+    type Operation = (String, String, BTreeSet<String>);
+
+    fn param_name(segment: &str) -> Option<&str> {
+        let name = segment.strip_prefix('<')?.strip_suffix('>')?;
+        Some(name.trim_end_matches(".."))
+    }
+
+    fn route_operations() -> BTreeSet<Operation> {
+        api_routes()
+            .iter()
+            .map(|route| {
+                let path = route.uri.path();
+                let mut params = BTreeSet::new();
+
+                let path = path
+                    .split('/')
+                    .map(|segment| match param_name(segment) {
+                        Some(name) => {
+                            params.insert(name.to_string());
+                            format!("{{{}}}", name)
+                        }
+                        None => segment.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/");
+
+                let query = route.uri.query().unwrap_or("");
+                params.extend(query.split('&').filter_map(param_name).map(String::from));
+
+                (route.method.as_str().to_lowercase(), path, params)
+            })
+            .collect()
+    }
+
+    fn spec() -> Value {
+        json::from_str(&ApiDoc::openapi().to_json().unwrap()).unwrap()
+    }
+
+    fn spec_operations(spec: &Value) -> BTreeSet<Operation> {
+        let mut operations = BTreeSet::new();
+
+        for (path, item) in spec["paths"].as_object().unwrap() {
+            for method in METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+
+                let params = operation["parameters"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|param| param["in"] == "path" || param["in"] == "query")
+                    .map(|param| param["name"].as_str().unwrap().to_string())
+                    .collect();
+
+                operations.insert((method.to_string(), path.clone(), params));
+            }
+        }
+
+        operations
+    }
+
+    fn collect_refs<'a>(value: &'a Value, refs: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object {
+                    match (key.as_str(), value) {
+                        ("$ref", Value::String(reference)) => refs.push(reference),
+                        _ => collect_refs(value, refs),
+                    }
+                }
+            }
+            Value::Array(array) => array.iter().for_each(|value| collect_refs(value, refs)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn spec_matches_routes() {
+        let routes = route_operations();
+        let documented = spec_operations(&spec());
+
+        let undocumented: Vec<_> = routes.difference(&documented).collect();
+        let stale: Vec<_> = documented.difference(&routes).collect();
+
+        assert!(
+            undocumented.is_empty() && stale.is_empty(),
+            "routes missing from the spec: {:?}\noperations of the spec without routes: {:?}",
+            undocumented,
+            stale
+        );
+    }
+
+    #[test]
+    fn schema_references_resolve() {
+        let spec = spec();
+        let mut refs = Vec::new();
+        collect_refs(&spec, &mut refs);
+
+        let unresolved: Vec<_> = refs
+            .into_iter()
+            .filter(|reference| {
+                let name = reference.trim_start_matches("#/components/schemas/");
+                spec["components"]["schemas"].get(name).is_none()
+            })
+            .collect();
+
+        assert!(
+            unresolved.is_empty(),
+            "unresolved references: {:?}",
+            unresolved
+        );
+    }
+
+    /// Fields typed with an alias of a primitive, such as `TaskId`, become references to
+    /// a component named after the primitive unless they are annotated with `value_type`.
+    #[test]
+    fn no_primitive_components() {
+        let spec = spec();
+        let primitives: Vec<_> = spec["components"]["schemas"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .filter(|(_, schema)| {
+                ["string", "integer", "number", "boolean"]
+                    .contains(&schema["type"].as_str().unwrap_or(""))
+                    && schema.get("enum").is_none()
+            })
+            .map(|(name, _)| name)
+            .collect();
+
+        assert!(
+            primitives.is_empty(),
+            "primitive components: {:?}",
+            primitives
+        );
+    }
+}
@@ -1,34 +1,53 @@
 use std::{convert::Infallible, error::Error, ops::FromResidual};
 
 use rocket::{
-    http::Status,
+    http::{ContentType, Status, StatusClass},
     response,
-    serde::{json::Json, Serialize},
+    serde::{
+        json::{self, Json},
+        Serialize,
+    },
     Request,
 };
+use utoipa::ToSchema;
+
+use crate::model::idempotency::StoredResponse;
+
+use super::error::{problem_json, ApiError, Problem};
 
 #[derive(Debug)]
 pub enum Response<T> {
     Success(Json<ResponseBody<T>>),
-    #[allow(unused)]
-    Unauthorized,
-    #[allow(unused)]
-    BadRequest,
-    ServerError(Box<dyn Error>),
+    /// An error, optionally carrying data that describes it.
+    Error(Problem<T>),
+    /// A response with `ETag` header.
+    Tagged(Box<Response<T>>, String),
+    ServerError(Box<dyn Error + Send + Sync>),
+    /// The stored response to an earlier request with the same idempotency key.
+    Replayed(StoredResponse),
 }
 
-#[derive(Debug, Serialize)]
+/// Envelope of every successful JSON response. The errors are [`Problem`] documents.
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ResponseBody<T> {
+    /// Always empty. Kept for the clients written before the errors became problem documents.
     error_code: &'static str,
     data: Option<T>,
 }
 
+/// Data of the responses that carry none. Only documents `ResponseBody<()>` and `Problem<()>`.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct NoData;
+
 impl<T> Response<T> {
-    pub fn from_error(error_code: &'static str) -> Self {
-        Self::Success(Json(ResponseBody {
-            error_code,
-            data: None,
-        }))
+    pub fn from_error(error: ApiError) -> Self {
+        Self::Error(Problem::new(error))
+    }
+
+    /// Returns the error with the data that describes it.
+    pub fn from_error_details(error: ApiError, details: T) -> Self {
+        Self::Error(Problem::new(error).with_details(details))
     }
 
     pub fn from_data(data: T) -> Self {
@@ -37,17 +56,63 @@ impl<T> Response<T> {
             data: Some(data),
         }))
     }
+
+    pub fn with_etag(self, etag: String) -> Self {
+        Self::Tagged(Box::new(self), etag)
+    }
+
+    /// Returns the response to store for replaying, or `None` if there is no body to replay.
+    pub fn stored(&self) -> Option<StoredResponse>
+    where
+        T: Serialize,
+    {
+        match self {
+            Response::Success(r) => Some(StoredResponse {
+                status: Status::Ok.code,
+                etag: None,
+                body: json::to_string(&r.0).ok()?,
+            }),
+            Response::Error(problem) => Some(StoredResponse {
+                status: problem.error().status().code,
+                etag: None,
+                body: json::to_string(problem).ok()?,
+            }),
+            Response::Tagged(r, etag) => Some(StoredResponse {
+                etag: Some(etag.clone()),
+                ..r.stored()?
+            }),
+            Response::Replayed(stored) => Some(stored.clone()),
+            Response::ServerError(_) => None,
+        }
+    }
 }
 
 impl<'r, 'o: 'r, T: Serialize> response::Responder<'r, 'o> for Response<T> {
     fn respond_to(self, request: &'r Request<'_>) -> response::Result<'o> {
         match self {
             Response::Success(r) => r.respond_to(request),
-            Response::Unauthorized => Status::Unauthorized.respond_to(request),
-            Response::BadRequest => Status::BadRequest.respond_to(request),
+            Response::Error(problem) => problem.respond_to(request),
+            Response::Tagged(r, etag) => {
+                let mut response = r.respond_to(request)?;
+                response.set_raw_header("ETag", etag);
+                Ok(response)
+            }
             Response::ServerError(err) => {
                 log::error!("Server error: {:?}", err);
-                Status::InternalServerError.respond_to(request)
+                ApiError::ServerError.respond_to(request)
+            }
+            Response::Replayed(stored) => {
+                let status = Status::from_code(stored.status).unwrap_or(Status::Ok);
+                let content_type = match status.class() {
+                    StatusClass::Success => ContentType::JSON,
+                    _ => problem_json(),
+                };
+                let mut response = (status, (content_type, stored.body)).respond_to(request)?;
+                if let Some(etag) = stored.etag {
+                    response.set_raw_header("ETag", etag);
+                }
+                response.set_raw_header("Idempotent-Replayed", "true");
+                Ok(response)
             }
         }
     }
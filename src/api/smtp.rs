@@ -0,0 +1,490 @@
+use std::{sync::Arc, time::Duration};
+
+use rocket::tokio::{
+    self,
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpListener,
+    sync::Semaphore,
+};
+
+use crate::{app::inbound::InboundError, model::tasks::TaskId};
+
+use super::{controllers::inbound::receive_email, Context, MAX_EMAIL_SIZE};
+
+/// Maximum length of a command line, and of the chunks the message lines are read in.
+const MAX_LINE_LENGTH: u64 = 1000;
+
+const MAX_RECIPIENTS: usize = 100;
+
+/// Maximum number of SMTP sessions handled at the same time. Further clients are turned away
+/// with a temporary failure, so that they retry later.
+const MAX_CONNECTIONS: usize = 64;
+
+/// How long the client may stay silent before the connection is closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Receiver of the messages accepted by the SMTP listener.
+#[rocket::async_trait]
+trait Mailbox: Send + Sync {
+    /// Domain the listener introduces itself with.
+    fn domain(&self) -> &str;
+
+    /// Whether the messages to the recipient are accepted.
+    async fn accepts(&self, recipient: &str) -> anyhow::Result<bool>;
+
+    async fn deliver(
+        &self,
+        raw: &[u8],
+        recipients: Vec<String>,
+    ) -> anyhow::Result<Result<TaskId, InboundError>>;
+}
+
+#[rocket::async_trait]
+impl Mailbox for Context {
+    fn domain(&self) -> &str {
+        self.inbound.domain()
+    }
+
+    async fn accepts(&self, recipient: &str) -> anyhow::Result<bool> {
+        let board_id = self.inbound.find_board(&[recipient.to_string()]).await?;
+
+        Ok(board_id.is_some())
+    }
+
+    async fn deliver(
+        &self,
+        raw: &[u8],
+        recipients: Vec<String>,
+    ) -> anyhow::Result<Result<TaskId, InboundError>> {
+        let result = receive_email(self, raw, recipients).await?;
+
+        Ok(result.map(|created| created.task.task_id))
+    }
+}
+
+/// Accepts the messages to the inbound addresses of the boards over plain SMTP on `address`.
+/// The listener is meant to sit behind a mail server or a relay, so it neither offers TLS
+/// nor authenticates the clients: the inbound address itself is the credential.
+pub async fn serve_smtp(context: Arc<Context>, address: String) {
+    let listener = match TcpListener::bind(&address).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            log::error!("Could not listen for SMTP on {}: {}", address, err);
+            return;
+        }
+    };
+
+    log::info!("Listening for SMTP on {}", address);
+
+    let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                log::warn!("Could not accept an SMTP connection: {}", err);
+                continue;
+            }
+        };
+
+        let permit = connections.clone().try_acquire_owned();
+        let context = context.clone();
+        tokio::spawn(async move {
+            let (reader, mut writer) = stream.into_split();
+
+            let Ok(_permit) = permit else {
+                log::warn!(
+                    "Turning away the SMTP connection from {}: too many connections",
+                    peer
+                );
+                let _ = reply(
+                    &mut writer,
+                    "421 4.7.0 Too many connections, try again later",
+                )
+                .await;
+                return;
+            };
+
+            if let Err(err) = run_session(&*context, BufReader::new(reader), writer).await {
+                log::debug!("SMTP session with {} ended: {}", peer, err);
+            }
+        });
+    }
+}
+
+#[derive(Default)]
+struct Transaction {
+    /// Whether `MAIL FROM` has been received. The sender itself is not used.
+    started: bool,
+    recipients: Vec<String>,
+}
+
+async fn run_session<R, W>(
+    mailbox: &dyn Mailbox,
+    mut reader: R,
+    mut writer: W,
+) -> anyhow::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut greeted = false;
+    let mut transaction = Transaction::default();
+
+    reply(
+        &mut writer,
+        &format!("220 {} ESMTP ready", mailbox.domain()),
+    )
+    .await?;
+
+    loop {
+        let Some(line) = read_line(&mut reader).await? else {
+            return Ok(());
+        };
+
+        if !line.ends_with(b"\n") {
+            reply(&mut writer, "500 5.5.2 Line too long").await?;
+            return Ok(());
+        }
+
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim_end_matches(['\r', '\n']);
+        let (verb, args) = line.split_once(' ').unwrap_or((line, ""));
+
+        match verb.to_ascii_uppercase().as_str() {
+            "EHLO" => {
+                greeted = true;
+                transaction = Transaction::default();
+
+                let response = format!(
+                    "250-{}\r\n250-SIZE {}\r\n250 8BITMIME",
+                    mailbox.domain(),
+                    MAX_EMAIL_SIZE.as_u64()
+                );
+                reply(&mut writer, &response).await?;
+            }
+            "HELO" => {
+                greeted = true;
+                transaction = Transaction::default();
+
+                reply(&mut writer, &format!("250 {}", mailbox.domain())).await?;
+            }
+            "MAIL" => {
+                if !greeted || transaction.started {
+                    reply(&mut writer, "503 5.5.1 Bad sequence of commands").await?;
+                    continue;
+                }
+
+                let Some((_, params)) = parse_path(args, "FROM:") else {
+                    reply(&mut writer, "501 5.5.4 Syntax: MAIL FROM:<address>").await?;
+                    continue;
+                };
+
+                let declared_size = params
+                    .split_whitespace()
+                    .filter_map(|param| param.split_once('='))
+                    .find(|(key, _)| key.eq_ignore_ascii_case("SIZE"))
+                    .and_then(|(_, size)| size.parse::<u64>().ok());
+
+                if declared_size.is_some_and(|size| size > MAX_EMAIL_SIZE.as_u64()) {
+                    reply(&mut writer, "552 5.3.4 Message size exceeds the limit").await?;
+                    continue;
+                }
+
+                transaction.started = true;
+                reply(&mut writer, "250 2.1.0 OK").await?;
+            }
+            "RCPT" => {
+                if !transaction.started {
+                    reply(&mut writer, "503 5.5.1 Bad sequence of commands").await?;
+                    continue;
+                }
+
+                let Some((recipient, _)) = parse_path(args, "TO:") else {
+                    reply(&mut writer, "501 5.5.4 Syntax: RCPT TO:<address>").await?;
+                    continue;
+                };
+
+                if transaction.recipients.len() >= MAX_RECIPIENTS {
+                    reply(&mut writer, "452 4.5.3 Too many recipients").await?;
+                    continue;
+                }
+
+                let response = match mailbox.accepts(&recipient).await {
+                    Ok(true) => {
+                        transaction.recipients.push(recipient);
+                        "250 2.1.5 OK"
+                    }
+                    Ok(false) => "550 5.1.1 No such board",
+                    Err(err) => {
+                        log::error!("Could not look up an inbound address: {:?}", err);
+                        "451 4.3.0 Temporary failure, try again later"
+                    }
+                };
+                reply(&mut writer, response).await?;
+            }
+            "DATA" => {
+                if transaction.recipients.is_empty() {
+                    reply(&mut writer, "503 5.5.1 Bad sequence of commands").await?;
+                    continue;
+                }
+
+                reply(&mut writer, "354 End data with <CR><LF>.<CR><LF>").await?;
+
+                let recipients = std::mem::take(&mut transaction).recipients;
+                let Some(raw) = read_message(&mut reader).await? else {
+                    reply(&mut writer, "552 5.3.4 Message size exceeds the limit").await?;
+                    continue;
+                };
+
+                let response = match mailbox.deliver(&raw, recipients).await {
+                    Ok(Ok(task_id)) => format!("250 2.0.0 OK: created task {}", task_id),
+                    Ok(Err(InboundError::InvalidMessage)) => {
+                        "554 5.6.0 Malformed message".to_string()
+                    }
+                    Ok(Err(InboundError::UnknownRecipient)) => {
+                        "550 5.1.1 No such board".to_string()
+                    }
+                    Ok(Err(InboundError::NoCategory)) => {
+                        "554 5.3.0 The board has no category for new tasks".to_string()
+                    }
+                    Err(err) => {
+                        log::error!("Could not create a task from a message: {:?}", err);
+                        "451 4.3.0 Temporary failure, try again later".to_string()
+                    }
+                };
+                reply(&mut writer, &response).await?;
+            }
+            "RSET" => {
+                transaction = Transaction::default();
+                reply(&mut writer, "250 2.0.0 OK").await?;
+            }
+            "NOOP" => reply(&mut writer, "250 2.0.0 OK").await?,
+            "QUIT" => {
+                reply(&mut writer, "221 2.0.0 Bye").await?;
+                return Ok(());
+            }
+            _ => reply(&mut writer, "502 5.5.2 Command not implemented").await?,
+        }
+    }
+}
+
+async fn reply<W: AsyncWrite + Unpin>(writer: &mut W, response: &str) -> anyhow::Result<()> {
+    writer.write_all(response.as_bytes()).await?;
+    writer.write_all(b"\r\n").await?;
+    writer.flush().await?;
+
+    Ok(())
+}
+
+/// Reads a line of at most [`MAX_LINE_LENGTH`] bytes, including the line break,
+/// or returns `None` once the connection is closed.
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    let mut limited = (&mut *reader).take(MAX_LINE_LENGTH);
+    let read = limited.read_until(b'\n', &mut line);
+
+    match tokio::time::timeout(IDLE_TIMEOUT, read).await?? {
+        0 => Ok(None),
+        _ => Ok(Some(line)),
+    }
+}
+
+/// Reads the message up to the terminating `.` line and removes the dot stuffing.
+/// Returns `None` if the message exceeds [`MAX_EMAIL_SIZE`]; the rest of it is discarded.
+async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut message = Vec::new();
+    let mut too_large = false;
+    let mut at_line_start = true;
+
+    loop {
+        let Some(chunk) = read_line(reader).await? else {
+            anyhow::bail!("connection closed during DATA");
+        };
+
+        if at_line_start && (chunk == b".\r\n" || chunk == b".\n") {
+            break;
+        }
+
+        let content = if at_line_start && chunk.starts_with(b".") {
+            &chunk[1..]
+        } else {
+            &chunk[..]
+        };
+        at_line_start = chunk.ends_with(b"\n");
+
+        if message.len() + content.len() > MAX_EMAIL_SIZE.as_u64() as usize {
+            too_large = true;
+            message.clear();
+        }
+        if !too_large {
+            message.extend_from_slice(content);
+        }
+    }
+
+    Ok((!too_large).then_some(message))
+}
+
+/// Splits `FROM:<address> PARAMS` into the address and the parameters.
+fn parse_path<'a>(args: &'a str, prefix: &str) -> Option<(String, &'a str)> {
+    let args = args.trim_start();
+    let rest = match (args.get(..prefix.len()), args.get(prefix.len()..)) {
+        (Some(start), Some(rest)) if start.eq_ignore_ascii_case(prefix) => rest.trim_start(),
+        _ => return None,
+    };
+
+    let (path, params) = match rest.strip_prefix('<') {
+        Some(rest) => rest.split_once('>')?,
+        None => rest.split_once(' ').unwrap_or((rest, "")),
+    };
+
+    Some((path.trim().to_string(), params.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use rocket::tokio::io::{duplex, AsyncReadExt, AsyncWriteExt, BufReader};
+
+    use crate::{app::inbound::InboundError, model::tasks::TaskId};
+
+    use super::{run_session, Mailbox};
+
+    const BOARD_ADDRESS: &str = "board@tasks.example.com";
+
+    #[derive(Default)]
+    struct FakeMailbox {
+        delivered: Mutex<Vec<(Vec<u8>, Vec<String>)>>,
+    }
+
+    #[rocket::async_trait]
+    impl Mailbox for FakeMailbox {
+        fn domain(&self) -> &str {
+            "tasks.example.com"
+        }
+
+        async fn accepts(&self, recipient: &str) -> anyhow::Result<bool> {
+            Ok(recipient == BOARD_ADDRESS)
+        }
+
+        async fn deliver(
+            &self,
+            raw: &[u8],
+            recipients: Vec<String>,
+        ) -> anyhow::Result<Result<TaskId, InboundError>> {
+            self.delivered
+                .lock()
+                .unwrap()
+                .push((raw.to_vec(), recipients));
+
+            Ok(Ok("task1".to_string()))
+        }
+    }
+
+    async fn run(mailbox: &FakeMailbox, input: &str) -> anyhow::Result<String> {
+        let (client, server) = duplex(64 * 1024);
+        let (server_reader, server_writer) = rocket::tokio::io::split(server);
+        let (mut client_reader, mut client_writer) = rocket::tokio::io::split(client);
+
+        client_writer.write_all(input.as_bytes()).await?;
+        client_writer.shutdown().await?;
+
+        run_session(mailbox, BufReader::new(server_reader), server_writer).await?;
+
+        let mut output = String::new();
+        client_reader.read_to_string(&mut output).await?;
+
+        Ok(output)
+    }
+
+    fn reply_codes(output: &str) -> Vec<&str> {
+        output
+            .lines()
+            .filter(|line| line.as_bytes().get(3) != Some(&b'-'))
+            .map(|line| &line[..3])
+            .collect()
+    }
+
+    #[rocket::async_test]
+    async fn message_is_delivered() -> anyhow::Result<()> {
+        let mailbox = FakeMailbox::default();
+
+        let output = run(
+            &mailbox,
+            "EHLO client.example.com\r\n\
+            MAIL FROM:<bob@example.com> SIZE=120\r\n\
+            RCPT TO:<nobody@tasks.example.com>\r\n\
+            RCPT TO:<board@tasks.example.com>\r\n\
+            DATA\r\n\
+            Subject: Hello\r\n\
+            \r\n\
+            ..leading dot\r\n\
+            .\r\n\
+            QUIT\r\n",
+        )
+        .await?;
+
+        assert_eq!(
+            reply_codes(&output),
+            vec!["220", "250", "250", "550", "250", "354", "250", "221"]
+        );
+        assert!(output.contains("created task task1"));
+
+        let delivered = mailbox.delivered.lock().unwrap();
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].0, b"Subject: Hello\r\n\r\n.leading dot\r\n");
+        assert_eq!(delivered[0].1, vec![BOARD_ADDRESS]);
+
+        Ok(())
+    }
+
+    #[rocket::async_test]
+    async fn commands_out_of_sequence_are_rejected() -> anyhow::Result<()> {
+        let mailbox = FakeMailbox::default();
+
+        let output = run(
+            &mailbox,
+            "MAIL FROM:<bob@example.com>\r\n\
+            HELO client.example.com\r\n\
+            DATA\r\n\
+            MAIL FROM:<bob@example.com> SIZE=999999999999\r\n\
+            RCPT TO:<board@tasks.example.com>\r\n\
+            VRFY board\r\n\
+            QUIT\r\n",
+        )
+        .await?;
+
+        assert_eq!(
+            reply_codes(&output),
+            vec!["220", "503", "250", "503", "552", "503", "502", "221"]
+        );
+        assert!(mailbox.delivered.lock().unwrap().is_empty());
+
+        Ok(())
+    }
+
+    #[rocket::async_test]
+    async fn malformed_paths_are_rejected() -> anyhow::Result<()> {
+        let mailbox = FakeMailbox::default();
+
+        let output = run(
+            &mailbox,
+            "HELO client.example.com\r\n\
+            MAIL FROMÖ<bob@example.com>\r\n\
+            MAIL FR\r\n\
+            MAIL FROM:<bob@example.com>\r\n\
+            RCPT TOÖ<board@tasks.example.com>\r\n\
+            RCPT TO:<board@tasks.example.com\r\n\
+            QUIT\r\n",
+        )
+        .await?;
+
+        assert_eq!(
+            reply_codes(&output),
+            vec!["220", "250", "501", "501", "250", "501", "501", "221"]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use std::{future::Future, io, pin::Pin};
+
+use rocket::{
+    data::{IoHandler, IoStream},
+    http::Status,
+    request::{FromRequest, Outcome},
+    response::{self, Responder},
+    Request,
+};
+use tokio_tungstenite::{
+    tungstenite::{handshake::derive_accept_key, protocol::Role},
+    WebSocketStream,
+};
+
+pub type WebSocket = WebSocketStream<IoStream>;
+
+type WebSocketHandler =
+    Box<dyn FnOnce(WebSocket) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+
+/// Request guard for WebSocket handshake requests (RFC 6455).
+pub struct WebSocketUpgrade {
+    accept_key: String,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for WebSocketUpgrade {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let headers = request.headers();
+
+        let header_contains = |name: &str, token: &str| {
+            headers
+                .get(name)
+                .flat_map(|value| value.split(','))
+                .any(|value| value.trim().eq_ignore_ascii_case(token))
+        };
+
+        let is_upgrade = header_contains("Connection", "upgrade")
+            && header_contains("Upgrade", "websocket")
+            && headers.get_one("Sec-WebSocket-Version") == Some("13");
+
+        match headers.get_one("Sec-WebSocket-Key") {
+            Some(key) if is_upgrade => Outcome::Success(WebSocketUpgrade {
+                accept_key: derive_accept_key(key.trim().as_bytes()),
+            }),
+            _ => Outcome::Error((Status::UpgradeRequired, ())),
+        }
+    }
+}
+
+impl WebSocketUpgrade {
+    /// Accepts the connection, running the handler once the connection has been upgraded.
+    pub fn accept<F, Fut>(self, handler: F) -> WebSocketChannel
+    where
+        F: FnOnce(WebSocket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        WebSocketChannel {
+            accept_key: self.accept_key,
+            handler: Box::new(move |ws| Box::pin(handler(ws))),
+        }
+    }
+}
+
+pub struct WebSocketChannel {
+    accept_key: String,
+    handler: WebSocketHandler,
+}
+
+impl<'r> Responder<'r, 'static> for WebSocketChannel {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
+        // Rocket sets the status and `Connection`/`Upgrade` headers itself when upgrading.
+        rocket::Response::build()
+            .raw_header("Sec-WebSocket-Accept", self.accept_key.clone())
+            .upgrade("websocket", self)
+            .ok()
+    }
+}
+
+#[rocket::async_trait]
+impl IoHandler for WebSocketChannel {
+    async fn io(self: Pin<Box<Self>>, io: IoStream) -> io::Result<()> {
+        let channel = Pin::into_inner(self);
+
+        let ws = WebSocketStream::from_raw_socket(io, Role::Server, None).await;
+        (channel.handler)(ws).await;
+
+        Ok(())
+    }
+}
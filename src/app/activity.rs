@@ -0,0 +1,12 @@
+use crate::model::activity::{ActivityEntry, ActivityId};
+
+/// Maximum number of entries in a page of the board activity feed.
+pub const MAX_ACTIVITY_PAGE_SIZE: i64 = 100;
+
+/// A page of the board activity feed, newest first.
+#[derive(Debug)]
+pub struct ActivityPage {
+    pub entries: Vec<ActivityEntry>,
+    /// Cursor of the next, older page, or `None` if this is the last page.
+    pub next_cursor: Option<ActivityId>,
+}
@@ -0,0 +1,435 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::model::{attachments::AttachmentDescription, tasks, UserId};
+
+use super::repositories::{AttachmentsRepository, BlobStore, TasksRepository};
+
+/// Checks that the content of a file is of the declared content type.
+type ContentCheck = fn(&[u8]) -> bool;
+
+/// Content types that may be attached, with the checks of the content.
+const ALLOWED_CONTENT_TYPES: &[(&str, ContentCheck)] = &[
+    ("image/png", |data| data.starts_with(b"\x89PNG\r\n\x1a\n")),
+    ("image/jpeg", |data| data.starts_with(b"\xff\xd8\xff")),
+    ("image/gif", |data| {
+        data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a")
+    }),
+    ("image/webp", |data| {
+        data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP"
+    }),
+    ("application/pdf", |data| data.starts_with(b"%PDF-")),
+    ("application/zip", |data| data.starts_with(b"PK\x03\x04")),
+    ("text/plain", is_text),
+    ("text/markdown", is_text),
+    ("text/csv", is_text),
+];
+
+fn is_text(data: &[u8]) -> bool {
+    std::str::from_utf8(data).is_ok()
+}
+
+/// Size limits of the attached files.
+#[derive(Debug, Clone, Copy)]
+pub struct AttachmentLimits {
+    /// Maximum size of a single file in bytes.
+    pub max_file_size: i64,
+    /// Maximum total size of the files of a user in bytes.
+    pub user_quota: i64,
+}
+
+impl Default for AttachmentLimits {
+    fn default() -> Self {
+        const MIB: i64 = 1024 * 1024;
+
+        Self {
+            max_file_size: 10 * MIB,
+            user_quota: 100 * MIB,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AttachmentError {
+    TaskNotFound,
+    AttachmentNotFound,
+    FileTooLarge,
+    QuotaExceeded,
+    /// The content type is not allowed or does not match the content.
+    UnsupportedContentType,
+}
+
+/// A file being attached to a task.
+#[derive(Debug)]
+pub struct Upload {
+    pub file_name: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+pub struct AttachmentsService {
+    attachments: Arc<dyn AttachmentsRepository>,
+    tasks: Arc<dyn TasksRepository>,
+    blobs: Arc<dyn BlobStore>,
+    limits: AttachmentLimits,
+}
+
+impl AttachmentsService {
+    pub fn new(
+        attachments: Arc<dyn AttachmentsRepository>,
+        tasks: Arc<dyn TasksRepository>,
+        blobs: Arc<dyn BlobStore>,
+        limits: AttachmentLimits,
+    ) -> Self {
+        Self {
+            attachments,
+            tasks,
+            blobs,
+            limits,
+        }
+    }
+
+    pub fn limits(&self) -> AttachmentLimits {
+        self.limits
+    }
+
+    /// Returns the attachments of the task, or `None` if there is no such task.
+    pub async fn fetch_attachments(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Option<Vec<AttachmentDescription>>> {
+        if self.tasks.fetch_task(user_id, task_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(self.attachments.fetch_attachments(task_id).await?))
+    }
+
+    pub async fn upload(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        upload: Upload,
+    ) -> anyhow::Result<Result<AttachmentDescription, AttachmentError>> {
+        let size = upload.data.len() as i64;
+
+        if size > self.limits.max_file_size {
+            return Ok(Err(AttachmentError::FileTooLarge));
+        }
+
+        let content_type = essence(&upload.content_type);
+        let content_matches = ALLOWED_CONTENT_TYPES
+            .iter()
+            .find(|(allowed, _)| *allowed == content_type)
+            .is_some_and(|(_, check)| check(&upload.data));
+
+        if !content_matches {
+            return Ok(Err(AttachmentError::UnsupportedContentType));
+        }
+
+        if self.tasks.fetch_task(user_id, task_id).await?.is_none() {
+            return Ok(Err(AttachmentError::TaskNotFound));
+        }
+
+        // Fail early, before storing the blob. The quota is enforced when the attachment is added.
+        if self.attachments.total_size(user_id).await? + size > self.limits.user_quota {
+            return Ok(Err(AttachmentError::QuotaExceeded));
+        }
+
+        let attachment_id = tasks::generate_random_task_id();
+        let attachment = AttachmentDescription {
+            blob_key: format!("attachments/{}", attachment_id),
+            attachment_id,
+            task_id: task_id.to_string(),
+            owner_id: user_id,
+            file_name: sanitize_file_name(&upload.file_name),
+            content_type,
+            size,
+            created_at: Utc::now(),
+        };
+
+        self.blobs
+            .put(&attachment.blob_key, &attachment.content_type, upload.data)
+            .await?;
+
+        if !self
+            .attachments
+            .create_attachment(&attachment, self.limits.user_quota)
+            .await?
+        {
+            self.delete_blob(&attachment).await;
+            return Ok(Err(AttachmentError::QuotaExceeded));
+        }
+
+        Ok(Ok(attachment))
+    }
+
+    /// Returns the attachment with its content.
+    pub async fn download(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        attachment_id: &str,
+    ) -> anyhow::Result<Result<(AttachmentDescription, Vec<u8>), AttachmentError>> {
+        if self.tasks.fetch_task(user_id, task_id).await?.is_none() {
+            return Ok(Err(AttachmentError::TaskNotFound));
+        }
+
+        let Some(attachment) = self
+            .attachments
+            .fetch_attachment(task_id, attachment_id)
+            .await?
+        else {
+            return Ok(Err(AttachmentError::AttachmentNotFound));
+        };
+
+        let Some(data) = self.blobs.get(&attachment.blob_key).await? else {
+            return Err(anyhow::anyhow!(
+                "blob {} of attachment {} is missing",
+                attachment.blob_key,
+                attachment.attachment_id
+            ));
+        };
+
+        Ok(Ok((attachment, data)))
+    }
+
+    pub async fn delete_attachment(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        attachment_id: &str,
+    ) -> anyhow::Result<Result<(), AttachmentError>> {
+        if self.tasks.fetch_task(user_id, task_id).await?.is_none() {
+            return Ok(Err(AttachmentError::TaskNotFound));
+        }
+
+        let Some(attachment) = self
+            .attachments
+            .delete_attachment(task_id, attachment_id)
+            .await?
+        else {
+            return Ok(Err(AttachmentError::AttachmentNotFound));
+        };
+
+        self.delete_blob(&attachment).await;
+
+        Ok(Ok(()))
+    }
+
+    /// Deletes the attachments of the task with their blobs. Called before the task is deleted.
+    pub async fn delete_task_attachments(&self, task_id: &str) -> anyhow::Result<()> {
+        let attachments = self.attachments.delete_task_attachments(task_id).await?;
+
+        for attachment in &attachments {
+            self.delete_blob(attachment).await;
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the blob of a deleted attachment. A blob that could not be deleted is only wasted space,
+    /// so the failure is logged instead of failing the request.
+    async fn delete_blob(&self, attachment: &AttachmentDescription) {
+        if let Err(err) = self.blobs.delete(&attachment.blob_key).await {
+            log::warn!(
+                "Could not delete blob {} of attachment {}: {:?}",
+                attachment.blob_key,
+                attachment.attachment_id,
+                err
+            );
+        }
+    }
+}
+
+/// Returns the content type without parameters, e.g. `text/plain` for `text/plain; charset=utf-8`.
+fn essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Keeps only the last component of the file name and removes control characters.
+fn sanitize_file_name(file_name: &str) -> String {
+    const MAX_LENGTH: usize = 255;
+
+    let base_name = file_name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let sanitized: String = base_name
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_LENGTH)
+        .collect();
+    let sanitized = sanitized.trim();
+
+    if sanitized.is_empty() || sanitized == "." || sanitized == ".." {
+        "file".to_string()
+    } else {
+        sanitized.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        app::repositories::{BlobStore, TasksRepository},
+        model::{
+            activity::{ActivityKind, ActivityRecord},
+            tasks::TaskData,
+            UserId,
+        },
+        storage::inmemory,
+    };
+
+    use super::{AttachmentError, AttachmentLimits, AttachmentsService, Upload};
+
+    const USER_ID: UserId = UserId::from_raw(1);
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n0123456789";
+
+    struct Setup {
+        service: AttachmentsService,
+        blobs: Arc<inmemory::InMemoryBlobs>,
+        task_id: String,
+    }
+
+    async fn setup_attachments_service(limits: AttachmentLimits) -> anyhow::Result<Setup> {
+        let tasks = Arc::new(inmemory::InMemoryTasks::new(
+            Arc::new(inmemory::InMemoryActivity::new()),
+            Arc::new(inmemory::InMemorySync::new()),
+            Arc::new(inmemory::InMemoryWebhooks::new()),
+        ));
+        let blobs = Arc::new(inmemory::InMemoryBlobs::new());
+
+        let categories = tasks.add_categories(USER_ID, &["ToDo"]).await?;
+        let task_id = tasks
+            .create_task(
+                USER_ID,
+                &TaskData {
+                    label: "task".to_string(),
+                    description: "description".to_string(),
+                    category_id: categories[0].category_id.clone(),
+                    start_at: None,
+                    due_at: None,
+                    priority: Default::default(),
+                },
+                &ActivityRecord::now(USER_ID, USER_ID, vec![ActivityKind::Created]),
+            )
+            .await?;
+
+        let service = AttachmentsService::new(
+            Arc::new(inmemory::InMemoryAttachments::new()),
+            tasks,
+            blobs.clone(),
+            limits,
+        );
+
+        Ok(Setup {
+            service,
+            blobs,
+            task_id,
+        })
+    }
+
+    fn png_upload() -> Upload {
+        Upload {
+            file_name: "../screenshots/screen.png".to_string(),
+            content_type: "image/png".to_string(),
+            data: PNG.to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn upload_download_and_delete_with_task() -> anyhow::Result<()> {
+        let setup = setup_attachments_service(AttachmentLimits::default()).await?;
+
+        let attachment = setup
+            .service
+            .upload(USER_ID, &setup.task_id, png_upload())
+            .await?
+            .unwrap();
+        assert_eq!(attachment.file_name, "screen.png");
+        assert_eq!(attachment.size, PNG.len() as i64);
+
+        let (_, data) = setup
+            .service
+            .download(USER_ID, &setup.task_id, &attachment.attachment_id)
+            .await?
+            .unwrap();
+        assert_eq!(data, PNG);
+
+        setup
+            .service
+            .delete_task_attachments(&setup.task_id)
+            .await?;
+        assert_eq!(setup.blobs.get(&attachment.blob_key).await?, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn content_type_must_match_content() -> anyhow::Result<()> {
+        let setup = setup_attachments_service(AttachmentLimits::default()).await?;
+
+        for (content_type, data) in [
+            ("image/png", b"<script>alert(1)</script>".to_vec()),
+            ("text/html", b"<p>hello</p>".to_vec()),
+        ] {
+            let upload = Upload {
+                file_name: "file".to_string(),
+                content_type: content_type.to_string(),
+                data,
+            };
+
+            let result = setup
+                .service
+                .upload(USER_ID, &setup.task_id, upload)
+                .await?;
+            assert!(matches!(
+                result,
+                Err(AttachmentError::UnsupportedContentType)
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn size_limits_are_enforced() -> anyhow::Result<()> {
+        let limits = AttachmentLimits {
+            max_file_size: PNG.len() as i64,
+            user_quota: PNG.len() as i64 * 2,
+        };
+        let setup = setup_attachments_service(limits).await?;
+
+        let mut too_large = png_upload();
+        too_large.data.push(0);
+        let result = setup
+            .service
+            .upload(USER_ID, &setup.task_id, too_large)
+            .await?;
+        assert!(matches!(result, Err(AttachmentError::FileTooLarge)));
+
+        for _ in 0..2 {
+            setup
+                .service
+                .upload(USER_ID, &setup.task_id, png_upload())
+                .await?
+                .unwrap();
+        }
+
+        let result = setup
+            .service
+            .upload(USER_ID, &setup.task_id, png_upload())
+            .await?;
+        assert!(matches!(result, Err(AttachmentError::QuotaExceeded)));
+
+        Ok(())
+    }
+}
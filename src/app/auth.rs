@@ -1,8 +1,13 @@
 use std::{future::Future, pin::Pin, sync::Arc};
 
+use chrono_tz::Tz;
+
 use crate::model::{SessionToken, UserId};
 
-use super::repositories::{SessionsRepository, TasksRepository, UsersRepositry};
+use super::{
+    events::{BoardEventKind, EventBus},
+    repositories::{SessionsRepository, TasksRepository, UsersRepositry},
+};
 
 pub struct AuthService {
     sessions: Arc<dyn SessionsRepository + Send + Sync>,
@@ -26,28 +31,38 @@ pub enum CreateUserError {
     UserAlreadyExists,
 }
 
+#[derive(Debug)]
+pub enum SetTimezoneError {
+    InvalidTimezone,
+}
+
 impl AuthService {
     pub fn new(
         sessions: Arc<dyn SessionsRepository>,
         users: Arc<dyn UsersRepositry>,
         tasks: Arc<dyn TasksRepository>,
+        events: Arc<EventBus>,
     ) -> Self {
         let on_created_user: OnCreatedUserCb = Box::new(move |user_id| {
             async fn add_user_default_categories(
                 tasks: &dyn TasksRepository,
+                events: &EventBus,
                 user_id: UserId,
             ) -> anyhow::Result<()> {
                 const DEFAULT_CATEGORIES: &[&str] = &["ToDo", "In progress", "Completed"];
 
-                tasks.add_categories(user_id, DEFAULT_CATEGORIES).await?;
+                for category in tasks.add_categories(user_id, DEFAULT_CATEGORIES).await? {
+                    events.publish(user_id, BoardEventKind::CategoryCreated(category));
+                }
 
                 Ok(())
             }
 
             let tasks_c = tasks.clone();
+            let events_c = events.clone();
 
             Box::pin(async move {
-                add_user_default_categories(tasks_c.as_ref(), user_id)
+                add_user_default_categories(tasks_c.as_ref(), events_c.as_ref(), user_id)
                     .await
                     .expect("add_user_default_categories");
             })
@@ -104,14 +119,35 @@ impl AuthService {
         self.users.get_username(user_id).await
     }
 
+    /// Returns the timezone configured by the user, or UTC if the user has not configured any.
+    pub async fn get_timezone(&self, user_id: UserId) -> anyhow::Result<Tz> {
+        let timezone = self.users.get_timezone(user_id).await?;
+
+        Ok(timezone.and_then(|tz| tz.parse().ok()).unwrap_or(Tz::UTC))
+    }
+
+    /// Sets the timezone of the user. The timezone must be an IANA timezone name, e.g. `Europe/Berlin`.
+    pub async fn set_timezone(
+        &self,
+        user_id: UserId,
+        timezone: &str,
+    ) -> anyhow::Result<Result<Tz, SetTimezoneError>> {
+        let Ok(tz) = timezone.parse::<Tz>() else {
+            return Ok(Err(SetTimezoneError::InvalidTimezone));
+        };
+
+        self.users.set_timezone(user_id, tz.name()).await?;
+
+        Ok(Ok(tz))
+    }
+
     pub async fn login_user(
         &self,
         username: &str,
         password: &str,
     ) -> anyhow::Result<Result<(UserId, SessionToken), LoginError>> {
         // Find the user by username.
-        let Some((user_id, actual_password)) =
-            self.users.find_user_with_password(username).await?
+        let Some((user_id, actual_password)) = self.users.find_user_with_password(username).await?
         else {
             return Ok(Err(LoginError::UserNotFound));
         };
@@ -177,7 +213,10 @@ mod tests {
     use std::sync::Arc;
 
     use crate::{
-        app::auth::{CreateUserError, LoginError},
+        app::{
+            auth::{CreateUserError, LoginError},
+            events::{BoardEventKind, EventBus},
+        },
         model::{SessionToken, UserId},
         storage::inmemory,
     };
@@ -331,7 +370,12 @@ mod tests {
         AuthService::new(
             Arc::new(inmemory::InMemorySessions::new()),
             Arc::new(inmemory::InMemoryUsers::new()),
-            Arc::new(inmemory::InMemoryTasks::new()),
+            Arc::new(inmemory::InMemoryTasks::new(
+                Arc::new(inmemory::InMemoryActivity::new()),
+                Arc::new(inmemory::InMemorySync::new()),
+                Arc::new(inmemory::InMemoryWebhooks::new()),
+            )),
+            Arc::new(EventBus::new()),
         )
     }
 
@@ -343,7 +387,12 @@ mod tests {
         AuthService::new(
             Arc::new(inmemory::InMemorySessions::new()),
             Arc::new(users),
-            Arc::new(inmemory::InMemoryTasks::new()),
+            Arc::new(inmemory::InMemoryTasks::new(
+                Arc::new(inmemory::InMemoryActivity::new()),
+                Arc::new(inmemory::InMemorySync::new()),
+                Arc::new(inmemory::InMemoryWebhooks::new()),
+            )),
+            Arc::new(EventBus::new()),
         )
     }
 
@@ -530,6 +579,42 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn default_categories_of_created_user_are_announced() -> anyhow::Result<()> {
+        let events = Arc::new(EventBus::new());
+        let auth = AuthService::new(
+            Arc::new(inmemory::InMemorySessions::new()),
+            Arc::new(inmemory::InMemoryUsers::new()),
+            Arc::new(inmemory::InMemoryTasks::new(
+                Arc::new(inmemory::InMemoryActivity::new()),
+                Arc::new(inmemory::InMemorySync::new()),
+                Arc::new(inmemory::InMemoryWebhooks::new()),
+            )),
+            events.clone(),
+        );
+
+        let (user_id, _) = auth
+            .create_user("user123", "ABc123456@")
+            .await?
+            .expect("failed to create user");
+
+        // The events are replayed from the start.
+        let mut subscription = events.subscribe(user_id, Some(0)).unwrap();
+
+        for label in ["ToDo", "In progress", "Completed"] {
+            let event = subscription.recv().await.unwrap();
+
+            assert_eq!(event.board_id, user_id);
+            assert!(
+                matches!(&event.kind, BoardEventKind::CategoryCreated(c) if c.label == label),
+                "unexpected event {:?}",
+                event.kind
+            );
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn create_user_many() -> anyhow::Result<()> {
         let auth = setup_inmemory_auth_service();
@@ -0,0 +1,344 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::model::{
+    calendar::CalendarToken,
+    tasks::{TaskDescription, TaskPriority, INITIAL_VERSION},
+    UserId,
+};
+
+use super::repositories::{TasksRepository, UsersRepositry};
+
+/// Maximum length of a content line in octets, excluding the line break (RFC 5545, section 3.1).
+const MAX_LINE_LENGTH: usize = 75;
+
+/// Calendar component that a task with a due date is published as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedComponent {
+    /// `VEVENT`, shown by all calendar apps. The event lasts from the start to the due date of the task.
+    Event,
+    /// `VTODO`, shown by the apps that support to-dos.
+    Todo,
+}
+
+/// Rendered iCalendar feed.
+#[derive(Debug)]
+pub struct CalendarFeed {
+    pub body: String,
+    /// Hash of the feed content, which changes only when the published tasks change.
+    pub fingerprint: String,
+}
+
+pub struct CalendarService {
+    users: Arc<dyn UsersRepositry>,
+    tasks: Arc<dyn TasksRepository>,
+}
+
+impl CalendarService {
+    pub fn new(users: Arc<dyn UsersRepositry>, tasks: Arc<dyn TasksRepository>) -> Self {
+        Self { users, tasks }
+    }
+
+    /// Returns the token of the calendar feed of the user, or `None` if the feed is not enabled.
+    pub async fn feed_token(&self, user_id: UserId) -> anyhow::Result<Option<CalendarToken>> {
+        self.users.get_calendar_token(user_id).await
+    }
+
+    /// Enables the calendar feed of the user with a new token. The previous token stops working.
+    pub async fn reset_feed(&self, user_id: UserId) -> anyhow::Result<CalendarToken> {
+        let token = CalendarToken::generate_random();
+        self.users.set_calendar_token(user_id, Some(&token)).await?;
+
+        Ok(token)
+    }
+
+    /// Disables the calendar feed of the user.
+    pub async fn revoke_feed(&self, user_id: UserId) -> anyhow::Result<()> {
+        self.users.set_calendar_token(user_id, None).await
+    }
+
+    /// Returns the feed of the active tasks with due dates of the user with the token,
+    /// or `None` if no feed has the token.
+    pub async fn feed(
+        &self,
+        token: &str,
+        component: FeedComponent,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Option<CalendarFeed>> {
+        let Some(token) = CalendarToken::from_str(token) else {
+            return Ok(None);
+        };
+
+        let Some(user_id) = self.users.find_user_by_calendar_token(&token).await? else {
+            return Ok(None);
+        };
+
+        let categories = self.tasks.fetch_categories(user_id).await?;
+
+        let mut tasks: Vec<TaskDescription> = self
+            .tasks
+            .fetch_tasks(user_id)
+            .await?
+            .into_iter()
+            .filter(|t| {
+                t.due_at.is_some()
+                    && t.lifecycle.is_active()
+                    && categories
+                        .iter()
+                        .any(|c| c.category_id == t.category_id && c.lifecycle.is_active())
+            })
+            .collect();
+
+        // Stable order, so that the fingerprint does not depend on the storage.
+        tasks.sort_by(|a, b| (a.due_at, &a.task_id).cmp(&(b.due_at, &b.task_id)));
+
+        Ok(Some(render_feed(&tasks, component, now)))
+    }
+}
+
+/// Renders the calendar. `now` is used only for `DTSTAMP` properties,
+/// which are left out of the fingerprint.
+fn render_feed(
+    tasks: &[TaskDescription],
+    component: FeedComponent,
+    now: DateTime<Utc>,
+) -> CalendarFeed {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//tasks//calendar feed//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        "X-WR-CALNAME:Tasks".to_string(),
+    ];
+
+    for task in tasks {
+        let Some(due_at) = task.due_at else {
+            continue;
+        };
+
+        // DTSTART must precede the end of the event and the due date of the to-do.
+        let start_at = task.start_at.filter(|&start_at| start_at < due_at);
+
+        let name = match component {
+            FeedComponent::Event => "VEVENT",
+            FeedComponent::Todo => "VTODO",
+        };
+
+        lines.push(format!("BEGIN:{name}"));
+        lines.push(format!("UID:{}@tasks", task.task_id));
+        lines.push(format!("DTSTAMP:{}", format_date_time(now)));
+
+        match component {
+            FeedComponent::Event => {
+                lines.push(format!(
+                    "DTSTART:{}",
+                    format_date_time(start_at.unwrap_or(due_at))
+                ));
+                if start_at.is_some() {
+                    lines.push(format!("DTEND:{}", format_date_time(due_at)));
+                }
+            }
+            FeedComponent::Todo => {
+                if let Some(start_at) = start_at {
+                    lines.push(format!("DTSTART:{}", format_date_time(start_at)));
+                }
+                lines.push(format!("DUE:{}", format_date_time(due_at)));
+                lines.push("STATUS:NEEDS-ACTION".to_string());
+            }
+        }
+
+        lines.push(format!("SUMMARY:{}", escape_text(&task.label)));
+        if !task.description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape_text(&task.description)));
+        }
+        lines.push(format!("PRIORITY:{}", ical_priority(task.priority)));
+        lines.push(format!("SEQUENCE:{}", task.version - INITIAL_VERSION));
+        lines.push(format!("END:{name}"));
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut hasher = Sha256::new();
+    for line in lines.iter().filter(|l| !l.starts_with("DTSTAMP:")) {
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+    }
+
+    CalendarFeed {
+        body: lines.iter().map(|line| fold_line(line)).collect(),
+        fingerprint: hex::encode(hasher.finalize()),
+    }
+}
+
+/// Formats the time in UTC, e.g. `20240501T120000Z`.
+fn format_date_time(date_time: DateTime<Utc>) -> String {
+    date_time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Maps the priority onto the iCalendar scale, where 1 is the highest and 9 the lowest.
+fn ical_priority(priority: TaskPriority) -> u8 {
+    match priority {
+        TaskPriority::Urgent => 1,
+        TaskPriority::High => 3,
+        TaskPriority::Normal => 5,
+        TaskPriority::Low => 9,
+    }
+}
+
+/// Escapes a TEXT value (RFC 5545, section 3.3.11). Control characters other than
+/// line breaks and tabs are not allowed, so they are dropped.
+fn escape_text(value: &str) -> String {
+    let value = value.replace("\r\n", "\n").replace('\r', "\n");
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push('\t'),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Terminates the content line with CRLF, folding it into lines of at most 75 octets
+/// (RFC 5545, section 3.1). Continuation lines start with a space, and multi-octet
+/// characters are never split.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_LENGTH * 3 + 2);
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+
+        folded.push(c);
+        length += c.len_utf8();
+    }
+
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{TimeDelta, TimeZone, Utc};
+
+    use crate::{
+        app::repositories::{TasksRepository, UsersRepositry},
+        model::{
+            activity::{ActivityKind, ActivityRecord},
+            tasks::{TaskData, TaskPriority},
+        },
+        storage::inmemory,
+    };
+
+    use super::{escape_text, fold_line, CalendarService, FeedComponent};
+
+    #[test]
+    fn text_is_escaped_and_folded() {
+        assert_eq!(
+            escape_text("a,b;c\\d\r\ne\u{7}"),
+            "a\\,b\\;c\\\\d\\ne".to_string()
+        );
+
+        let line = format!("SUMMARY:{}", "ж".repeat(40));
+        let folded = fold_line(&line);
+        let parts: Vec<&str> = folded.trim_end_matches("\r\n").split("\r\n").collect();
+
+        assert_eq!(parts.len(), 2);
+        assert!(parts.iter().all(|part| part.len() <= 75));
+        assert!(parts[1].starts_with(' '));
+        assert_eq!(folded.replace("\r\n ", ""), format!("{line}\r\n"));
+    }
+
+    #[tokio::test]
+    async fn feed_publishes_tasks_with_due_dates() -> anyhow::Result<()> {
+        let users = Arc::new(inmemory::InMemoryUsers::new());
+        let user_id = users.create_user("alice", "password").await?;
+
+        let tasks = Arc::new(inmemory::InMemoryTasks::new(
+            Arc::new(inmemory::InMemoryActivity::new()),
+            Arc::new(inmemory::InMemorySync::new()),
+            Arc::new(inmemory::InMemoryWebhooks::new()),
+        ));
+        let categories = tasks.add_categories(user_id, &["ToDo"]).await?;
+
+        let due_at = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut data = TaskData {
+            label: "Pay rent, on time".to_string(),
+            description: String::new(),
+            category_id: categories[0].category_id.clone(),
+            start_at: Some(due_at - TimeDelta::hours(2)),
+            due_at: Some(due_at),
+            priority: TaskPriority::High,
+        };
+        let activity = ActivityRecord::now(user_id, user_id, vec![ActivityKind::Created]);
+        let task_id = tasks.create_task(user_id, &data, &activity).await?;
+
+        data.label = "No due date".to_string();
+        data.due_at = None;
+        tasks.create_task(user_id, &data, &activity).await?;
+
+        let service = CalendarService::new(users, tasks);
+        let now = Utc::now();
+
+        let token = service.reset_feed(user_id).await?;
+        assert_eq!(service.feed_token(user_id).await?, Some(token.clone()));
+
+        let feed = service
+            .feed(token.as_str(), FeedComponent::Event, now)
+            .await?
+            .unwrap();
+        assert!(feed.body.contains(&format!("UID:{task_id}@tasks\r\n")));
+        assert!(feed.body.contains("DTSTART:20240501T100000Z\r\n"));
+        assert!(feed.body.contains("DTEND:20240501T120000Z\r\n"));
+        assert!(feed.body.contains("SUMMARY:Pay rent\\, on time\r\n"));
+        assert!(!feed.body.contains("No due date"));
+
+        let later = service
+            .feed(
+                token.as_str(),
+                FeedComponent::Event,
+                now + TimeDelta::hours(1),
+            )
+            .await?
+            .unwrap();
+        assert_ne!(later.body, feed.body);
+        assert_eq!(later.fingerprint, feed.fingerprint);
+
+        let todos = service
+            .feed(token.as_str(), FeedComponent::Todo, now)
+            .await?
+            .unwrap();
+        assert!(todos.body.contains("BEGIN:VTODO\r\n"));
+        assert!(todos.body.contains("DUE:20240501T120000Z\r\n"));
+
+        let new_token = service.reset_feed(user_id).await?;
+        assert!(service
+            .feed(token.as_str(), FeedComponent::Event, now)
+            .await?
+            .is_none());
+
+        service.revoke_feed(user_id).await?;
+        assert!(service
+            .feed(new_token.as_str(), FeedComponent::Event, now)
+            .await?
+            .is_none());
+        assert_eq!(service.feed_token(user_id).await?, None);
+
+        Ok(())
+    }
+}
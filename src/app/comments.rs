@@ -0,0 +1,301 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::model::{
+    comments::{CommentDescription, CommentRevision},
+    UserId,
+};
+
+use super::repositories::{CommentsRepository, TasksRepository};
+
+#[derive(Debug)]
+pub enum CommentError {
+    TaskNotFound,
+    CommentNotFound,
+    /// Only the author may edit or delete a comment.
+    NotAuthor,
+    /// The text of the comment is blank.
+    InvalidText,
+}
+
+pub struct CommentsService {
+    comments: Arc<dyn CommentsRepository>,
+    tasks: Arc<dyn TasksRepository>,
+}
+
+impl CommentsService {
+    pub fn new(comments: Arc<dyn CommentsRepository>, tasks: Arc<dyn TasksRepository>) -> Self {
+        Self { comments, tasks }
+    }
+
+    /// Returns the thread of the task, including the deleted comments, or `None` if there is no such task.
+    pub async fn fetch_comments(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Option<Vec<CommentDescription>>> {
+        if self.tasks.fetch_task(user_id, task_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(self.comments.fetch_comments(task_id).await?))
+    }
+
+    pub async fn create_comment(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        text: &str,
+    ) -> anyhow::Result<Result<CommentDescription, CommentError>> {
+        if text.trim().is_empty() {
+            return Ok(Err(CommentError::InvalidText));
+        }
+
+        if self.tasks.fetch_task(user_id, task_id).await?.is_none() {
+            return Ok(Err(CommentError::TaskNotFound));
+        }
+
+        let comment_id = self
+            .comments
+            .create_comment(task_id, user_id, text, Utc::now())
+            .await?;
+
+        self.fetch_existing_comment(task_id, &comment_id).await
+    }
+
+    /// Replaces the text of the comment, keeping the previous text as a revision.
+    pub async fn modify_comment(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        comment_id: &str,
+        text: &str,
+    ) -> anyhow::Result<Result<CommentDescription, CommentError>> {
+        if text.trim().is_empty() {
+            return Ok(Err(CommentError::InvalidText));
+        }
+
+        if let Err(err) = self.check_author(user_id, task_id, comment_id).await? {
+            return Ok(Err(err));
+        }
+
+        if !self
+            .comments
+            .update_comment(task_id, comment_id, text, Utc::now())
+            .await?
+        {
+            return Ok(Err(CommentError::CommentNotFound));
+        }
+
+        self.fetch_existing_comment(task_id, comment_id).await
+    }
+
+    /// Marks the comment as deleted. The comment stays in the thread without its text.
+    pub async fn delete_comment(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        comment_id: &str,
+    ) -> anyhow::Result<Result<(), CommentError>> {
+        if let Err(err) = self.check_author(user_id, task_id, comment_id).await? {
+            return Ok(Err(err));
+        }
+
+        if !self
+            .comments
+            .delete_comment(task_id, comment_id, Utc::now())
+            .await?
+        {
+            return Ok(Err(CommentError::CommentNotFound));
+        }
+
+        Ok(Ok(()))
+    }
+
+    /// Returns the previous revisions of the comment, oldest first.
+    pub async fn fetch_revisions(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        comment_id: &str,
+    ) -> anyhow::Result<Result<Vec<CommentRevision>, CommentError>> {
+        if self.tasks.fetch_task(user_id, task_id).await?.is_none() {
+            return Ok(Err(CommentError::TaskNotFound));
+        }
+
+        match self.comments.fetch_comment(task_id, comment_id).await? {
+            Some(comment) if comment.deleted_at.is_none() => {}
+            _ => return Ok(Err(CommentError::CommentNotFound)),
+        }
+
+        Ok(Ok(self.comments.fetch_revisions(comment_id).await?))
+    }
+
+    /// Checks that the comment exists, is not deleted and has been written by the user.
+    async fn check_author(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        comment_id: &str,
+    ) -> anyhow::Result<Result<(), CommentError>> {
+        if self.tasks.fetch_task(user_id, task_id).await?.is_none() {
+            return Ok(Err(CommentError::TaskNotFound));
+        }
+
+        let Some(comment) = self.comments.fetch_comment(task_id, comment_id).await? else {
+            return Ok(Err(CommentError::CommentNotFound));
+        };
+
+        if comment.deleted_at.is_some() {
+            return Ok(Err(CommentError::CommentNotFound));
+        }
+
+        if comment.author_id != user_id {
+            return Ok(Err(CommentError::NotAuthor));
+        }
+
+        Ok(Ok(()))
+    }
+
+    async fn fetch_existing_comment(
+        &self,
+        task_id: &str,
+        comment_id: &str,
+    ) -> anyhow::Result<Result<CommentDescription, CommentError>> {
+        Ok(self
+            .comments
+            .fetch_comment(task_id, comment_id)
+            .await?
+            .ok_or(CommentError::CommentNotFound))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::Utc;
+
+    use crate::{
+        app::repositories::{CommentsRepository, TasksRepository},
+        model::{
+            activity::{ActivityKind, ActivityRecord},
+            tasks::TaskData,
+            UserId,
+        },
+        storage::inmemory,
+    };
+
+    use super::{CommentError, CommentsService};
+
+    const USER_ID: UserId = UserId::from_raw(1);
+    const OTHER_USER_ID: UserId = UserId::from_raw(2);
+
+    async fn setup_comments_service(
+    ) -> anyhow::Result<(CommentsService, Arc<inmemory::InMemoryComments>, String)> {
+        let tasks = Arc::new(inmemory::InMemoryTasks::new(
+            Arc::new(inmemory::InMemoryActivity::new()),
+            Arc::new(inmemory::InMemorySync::new()),
+            Arc::new(inmemory::InMemoryWebhooks::new()),
+        ));
+        let comments = Arc::new(inmemory::InMemoryComments::new());
+
+        let categories = tasks.add_categories(USER_ID, &["ToDo"]).await?;
+        let task_id = tasks
+            .create_task(
+                USER_ID,
+                &TaskData {
+                    label: "task".to_string(),
+                    description: "description".to_string(),
+                    category_id: categories[0].category_id.clone(),
+                    start_at: None,
+                    due_at: None,
+                    priority: Default::default(),
+                },
+                &ActivityRecord::now(USER_ID, USER_ID, vec![ActivityKind::Created]),
+            )
+            .await?;
+
+        let service = CommentsService::new(comments.clone(), tasks);
+
+        Ok((service, comments, task_id))
+    }
+
+    #[tokio::test]
+    async fn edit_keeps_previous_revisions() -> anyhow::Result<()> {
+        let (service, _, task_id) = setup_comments_service().await?;
+
+        let comment = service
+            .create_comment(USER_ID, &task_id, "first")
+            .await?
+            .unwrap();
+        service
+            .modify_comment(USER_ID, &task_id, &comment.comment_id, "second")
+            .await?
+            .unwrap();
+        let edited = service
+            .modify_comment(USER_ID, &task_id, &comment.comment_id, "third")
+            .await?
+            .unwrap();
+
+        assert_eq!(edited.text, "third");
+        assert!(edited.edited_at.is_some());
+
+        let revisions = service
+            .fetch_revisions(USER_ID, &task_id, &comment.comment_id)
+            .await?
+            .unwrap();
+        let texts: Vec<&str> = revisions.iter().map(|r| r.text.as_str()).collect();
+        assert_eq!(texts, ["first", "second"]);
+        assert_eq!(revisions[0].written_at, comment.created_at);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn only_author_may_change_comment() -> anyhow::Result<()> {
+        let (service, comments, task_id) = setup_comments_service().await?;
+
+        let comment_id = comments
+            .create_comment(&task_id, OTHER_USER_ID, "not yours", Utc::now())
+            .await?;
+
+        let result = service
+            .modify_comment(USER_ID, &task_id, &comment_id, "mine")
+            .await?;
+        assert!(matches!(result, Err(CommentError::NotAuthor)));
+
+        let result = service
+            .delete_comment(USER_ID, &task_id, &comment_id)
+            .await?;
+        assert!(matches!(result, Err(CommentError::NotAuthor)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn deleted_comment_stays_in_thread() -> anyhow::Result<()> {
+        let (service, _, task_id) = setup_comments_service().await?;
+
+        let comment = service
+            .create_comment(USER_ID, &task_id, "oops")
+            .await?
+            .unwrap();
+        service
+            .delete_comment(USER_ID, &task_id, &comment.comment_id)
+            .await?
+            .unwrap();
+
+        let thread = service.fetch_comments(USER_ID, &task_id).await?.unwrap();
+        assert_eq!(thread.len(), 1);
+        assert!(thread[0].deleted_at.is_some());
+
+        let result = service
+            .modify_comment(USER_ID, &task_id, &comment.comment_id, "fixed")
+            .await?;
+        assert!(matches!(result, Err(CommentError::CommentNotFound)));
+
+        Ok(())
+    }
+}
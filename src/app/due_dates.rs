@@ -0,0 +1,131 @@
+use chrono::{DateTime, Datelike, Days, NaiveDate, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Period of due dates, relative to the current time in the timezone of the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuePeriod {
+    /// Due dates in the past.
+    Overdue,
+    /// Due dates within the current day.
+    Today,
+    /// Due dates within the current week, starting on Monday.
+    ThisWeek,
+}
+
+impl DuePeriod {
+    /// Returns the range `[from, to)` of due dates in the period.
+    /// `from` is `None` if the range is unbounded from below.
+    pub fn range(self, now: DateTime<Utc>, timezone: Tz) -> (Option<DateTime<Utc>>, DateTime<Utc>) {
+        let today = now.with_timezone(&timezone).date_naive();
+
+        match self {
+            DuePeriod::Overdue => (None, now),
+            DuePeriod::Today => (
+                Some(start_of_day(today, timezone)),
+                start_of_day(today + Days::new(1), timezone),
+            ),
+            DuePeriod::ThisWeek => {
+                let monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+
+                (
+                    Some(start_of_day(monday, timezone)),
+                    start_of_day(monday + Days::new(7), timezone),
+                )
+            }
+        }
+    }
+}
+
+/// Returns the first instant of the day in the timezone.
+///
+/// A day does not always start at midnight: if the clocks are moved forward at midnight,
+/// the day starts when the clocks are moved.
+fn start_of_day(date: NaiveDate, timezone: Tz) -> DateTime<Utc> {
+    const STEP: TimeDelta = TimeDelta::minutes(15);
+    const MAX_STEPS: i32 = 24 * 4;
+
+    let midnight = date.and_time(Default::default());
+
+    let mut local = midnight;
+    for _ in 0..MAX_STEPS {
+        if let Some(start) = timezone.from_local_datetime(&local).earliest() {
+            return start.with_timezone(&Utc);
+        }
+
+        local += STEP;
+    }
+
+    // Unreachable for real timezones, which never skip a whole day.
+    Utc.from_utc_datetime(&midnight)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+    use chrono_tz::Tz;
+
+    use super::DuePeriod;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn overdue_is_before_now() {
+        let now = utc("2024-03-10T12:00:00Z");
+
+        assert_eq!(DuePeriod::Overdue.range(now, Tz::UTC), (None, now));
+    }
+
+    #[test]
+    fn today_in_user_timezone() {
+        // It is still March 9 in New York.
+        let now = utc("2024-03-10T03:00:00Z");
+
+        let (from, to) = DuePeriod::Today.range(now, chrono_tz::America::New_York);
+
+        assert_eq!(from, Some(utc("2024-03-09T05:00:00Z")));
+        assert_eq!(to, utc("2024-03-10T05:00:00Z"));
+    }
+
+    #[test]
+    fn today_is_shorter_when_dst_starts() {
+        // Clocks are moved forward at 2:00 on March 10 in New York, so the day lasts 23 hours.
+        let now = utc("2024-03-10T12:00:00Z");
+
+        let (from, to) = DuePeriod::Today.range(now, chrono_tz::America::New_York);
+
+        assert_eq!(from, Some(utc("2024-03-10T05:00:00Z")));
+        assert_eq!(to, utc("2024-03-11T04:00:00Z"));
+    }
+
+    #[test]
+    fn day_starting_after_midnight_gap() {
+        // Clocks were moved forward at midnight on March 31 2024 in Beirut, so the day started at 1:00.
+        let now = utc("2024-03-31T12:00:00Z");
+        let beirut = chrono_tz::Asia::Beirut;
+
+        let (from, _) = DuePeriod::Today.range(now, beirut);
+
+        assert_eq!(
+            from,
+            Some(
+                beirut
+                    .with_ymd_and_hms(2024, 3, 31, 1, 0, 0)
+                    .unwrap()
+                    .with_timezone(&Utc)
+            )
+        );
+    }
+
+    #[test]
+    fn this_week_starts_on_monday() {
+        // Thursday.
+        let now = utc("2024-06-13T10:00:00Z");
+
+        let (from, to) = DuePeriod::ThisWeek.range(now, Tz::UTC);
+
+        assert_eq!(from, Some(utc("2024-06-10T00:00:00Z")));
+        assert_eq!(to, utc("2024-06-17T00:00:00Z"));
+    }
+}
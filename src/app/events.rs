@@ -0,0 +1,301 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::broadcast;
+
+use crate::model::{
+    labels::LabelDescription,
+    tasks::{TaskCategoryDescription, TaskDescription},
+    BoardId, LabelId, TaskCategoryId, TaskId,
+};
+
+/// Identifier of a published event. Identifiers are assigned in the order
+/// the events are published and are unique within the lifetime of the process.
+pub type EventId = u64;
+
+#[derive(Debug, Clone)]
+pub enum BoardEventKind {
+    TaskCreated(TaskDescription),
+    TaskUpdated(TaskDescription),
+    TaskMoved {
+        task: TaskDescription,
+        from_category_id: TaskCategoryId,
+    },
+    /// The task has been deleted, archived or trashed.
+    TaskDeleted {
+        task_id: TaskId,
+    },
+    CategoryCreated(TaskCategoryDescription),
+    CategoryUpdated(TaskCategoryDescription),
+    /// The category has been archived or trashed together with its tasks.
+    CategoryRemoved {
+        category_id: TaskCategoryId,
+    },
+    /// The category has been restored together with its tasks. The board has to be reloaded.
+    CategoryRestored(TaskCategoryDescription),
+    /// Categories, labels and tasks have been imported. The board has to be reloaded.
+    BoardImported {
+        category_ids: Vec<TaskCategoryId>,
+        task_count: usize,
+    },
+    LabelCreated(LabelDescription),
+    LabelUpdated(LabelDescription),
+    /// The label has been deleted and unassigned from all the tasks of the board.
+    LabelDeleted {
+        label_id: LabelId,
+    },
+}
+
+impl BoardEventKind {
+    /// Name of the event, as seen by the clients and the webhooks.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoardEventKind::TaskCreated(_) => "task_created",
+            BoardEventKind::TaskUpdated(_) => "task_updated",
+            BoardEventKind::TaskMoved { .. } => "task_moved",
+            BoardEventKind::TaskDeleted { .. } => "task_deleted",
+            BoardEventKind::CategoryCreated(_) => "category_created",
+            BoardEventKind::CategoryUpdated(_) => "category_updated",
+            BoardEventKind::CategoryRemoved { .. } => "category_removed",
+            BoardEventKind::CategoryRestored(_) => "category_restored",
+            BoardEventKind::BoardImported { .. } => "board_imported",
+            BoardEventKind::LabelCreated(_) => "label_created",
+            BoardEventKind::LabelUpdated(_) => "label_updated",
+            BoardEventKind::LabelDeleted { .. } => "label_deleted",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BoardEvent {
+    pub event_id: EventId,
+    pub board_id: BoardId,
+    pub kind: BoardEventKind,
+}
+
+#[derive(Debug)]
+pub enum SubscriptionError {
+    /// Some events of the board were dropped before the subscriber could receive them.
+    /// The subscriber has to reload the board.
+    MissedEvents,
+    Closed,
+}
+
+struct EventBusState {
+    next_event_id: EventId,
+    replay_buffer: VecDeque<Arc<BoardEvent>>,
+}
+
+/// In-process bus that delivers events of all boards to the subscribers.
+///
+/// The most recent events are kept in a bounded replay buffer, so that a subscriber
+/// that has reconnected can receive the events it missed.
+pub struct EventBus {
+    sender: broadcast::Sender<Arc<BoardEvent>>,
+    replay_capacity: usize,
+    state: Mutex<EventBusState>,
+}
+
+impl EventBus {
+    const CHANNEL_CAPACITY: usize = 256;
+    const DEFAULT_REPLAY_CAPACITY: usize = 1024;
+
+    pub fn new() -> Self {
+        Self::with_replay_capacity(Self::DEFAULT_REPLAY_CAPACITY)
+    }
+
+    pub fn with_replay_capacity(replay_capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(Self::CHANNEL_CAPACITY);
+
+        Self {
+            sender,
+            replay_capacity,
+            state: Mutex::new(EventBusState {
+                next_event_id: 1,
+                replay_buffer: VecDeque::with_capacity(replay_capacity),
+            }),
+        }
+    }
+
+    pub fn publish(&self, board_id: BoardId, kind: BoardEventKind) -> EventId {
+        let mut state = self.state.lock().unwrap();
+
+        let event_id = state.next_event_id;
+        state.next_event_id += 1;
+
+        let event = Arc::new(BoardEvent {
+            event_id,
+            board_id,
+            kind,
+        });
+
+        if state.replay_buffer.len() == self.replay_capacity {
+            state.replay_buffer.pop_front();
+        }
+        state.replay_buffer.push_back(event.clone());
+
+        // The event is sent while the lock is held, so that the subscribers
+        // never observe the events out of order.
+        // Sending fails only if there are no subscribers, which is fine.
+        let _ = self.sender.send(event);
+
+        event_id
+    }
+
+    /// Subscribes to the events of the board.
+    ///
+    /// If `last_event_id` is provided, the events of the board published after it
+    /// are replayed first. Returns [`SubscriptionError::MissedEvents`] if those events
+    /// are no longer in the replay buffer.
+    pub fn subscribe(
+        &self,
+        board_id: BoardId,
+        last_event_id: Option<EventId>,
+    ) -> Result<BoardSubscription, SubscriptionError> {
+        let state = self.state.lock().unwrap();
+
+        // Subscribe while holding the lock, so that no event is lost
+        // between the replay and the live events.
+        let receiver = self.sender.subscribe();
+
+        let mut replay = VecDeque::new();
+
+        if let Some(last_event_id) = last_event_id {
+            let latest_event_id = state.next_event_id - 1;
+            let oldest_event_id = state
+                .replay_buffer
+                .front()
+                .map_or(state.next_event_id, |e| e.event_id);
+
+            if last_event_id > latest_event_id || last_event_id + 1 < oldest_event_id {
+                return Err(SubscriptionError::MissedEvents);
+            }
+
+            replay.extend(
+                state
+                    .replay_buffer
+                    .iter()
+                    .filter(|e| e.event_id > last_event_id && e.board_id == board_id)
+                    .cloned(),
+            );
+        }
+
+        Ok(BoardSubscription {
+            board_id,
+            replay,
+            receiver,
+        })
+    }
+}
+
+pub struct BoardSubscription {
+    board_id: BoardId,
+    replay: VecDeque<Arc<BoardEvent>>,
+    receiver: broadcast::Receiver<Arc<BoardEvent>>,
+}
+
+impl BoardSubscription {
+    /// Waits for the next event of the board.
+    pub async fn recv(&mut self) -> Result<Arc<BoardEvent>, SubscriptionError> {
+        if let Some(event) = self.replay.pop_front() {
+            return Ok(event);
+        }
+
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if event.board_id == self.board_id => return Ok(event),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    return Err(SubscriptionError::MissedEvents)
+                }
+                Err(broadcast::error::RecvError::Closed) => return Err(SubscriptionError::Closed),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{
+        tasks::{TaskDescription, INITIAL_VERSION},
+        BoardId,
+    };
+
+    use super::{BoardEventKind, EventBus, SubscriptionError};
+
+    const BOARD_ID: BoardId = BoardId::from_raw(1);
+    const OTHER_BOARD_ID: BoardId = BoardId::from_raw(2);
+
+    fn task_created(task_id: &str) -> BoardEventKind {
+        BoardEventKind::TaskCreated(TaskDescription {
+            task_id: task_id.to_string(),
+            label: "label".to_string(),
+            description: "description".to_string(),
+            category_id: "category".to_string(),
+            start_at: None,
+            due_at: None,
+            priority: Default::default(),
+            label_ids: Vec::new(),
+            checklist_progress: Default::default(),
+            lifecycle: Default::default(),
+            version: INITIAL_VERSION,
+        })
+    }
+
+    #[tokio::test]
+    async fn live_events_of_other_boards_are_skipped() -> anyhow::Result<()> {
+        let bus = EventBus::new();
+
+        let mut subscription = bus.subscribe(BOARD_ID, None).unwrap();
+
+        bus.publish(OTHER_BOARD_ID, task_created("a"));
+        let event_id = bus.publish(BOARD_ID, task_created("b"));
+
+        let event = subscription.recv().await.unwrap();
+        assert_eq!(event.event_id, event_id);
+        assert_eq!(event.board_id, BOARD_ID);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replay_after_last_event_id() -> anyhow::Result<()> {
+        let bus = EventBus::new();
+
+        let first = bus.publish(BOARD_ID, task_created("a"));
+        bus.publish(OTHER_BOARD_ID, task_created("b"));
+        let third = bus.publish(BOARD_ID, task_created("c"));
+
+        let mut subscription = bus.subscribe(BOARD_ID, Some(first)).unwrap();
+        let fourth = bus.publish(BOARD_ID, task_created("d"));
+
+        assert_eq!(subscription.recv().await.unwrap().event_id, third);
+        assert_eq!(subscription.recv().await.unwrap().event_id, fourth);
+
+        Ok(())
+    }
+
+    #[test]
+    fn replay_of_evicted_events_is_reported() {
+        let bus = EventBus::with_replay_capacity(2);
+
+        let first = bus.publish(BOARD_ID, task_created("a"));
+        let second = bus.publish(BOARD_ID, task_created("b"));
+        bus.publish(BOARD_ID, task_created("c"));
+        let latest = bus.publish(BOARD_ID, task_created("d"));
+
+        assert!(matches!(
+            bus.subscribe(BOARD_ID, Some(first)),
+            Err(SubscriptionError::MissedEvents)
+        ));
+        assert!(bus.subscribe(BOARD_ID, Some(second)).is_ok());
+
+        // The event ID is from the future, e.g. it was issued before a restart.
+        assert!(matches!(
+            bus.subscribe(BOARD_ID, Some(latest + 1)),
+            Err(SubscriptionError::MissedEvents)
+        ));
+    }
+}
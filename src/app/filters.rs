@@ -0,0 +1,376 @@
+use chrono::{Datelike, NaiveDate, TimeDelta};
+
+use crate::model::{
+    filters::{Comparison, DueDate, DueFilter, TaskFilter},
+    tasks::TaskPriority,
+};
+
+/// Maximum length of a filter query in characters.
+const MAX_FILTER_LENGTH: usize = 1000;
+
+/// Maximum nesting of parentheses and negations.
+const MAX_DEPTH: usize = 32;
+
+/// Maximum offset of a relative due date, in days.
+const MAX_OFFSET_DAYS: i64 = 100 * 365;
+
+#[derive(Debug, PartialEq)]
+pub struct FilterSyntaxError {
+    /// Position of the error in the query, in characters.
+    pub position: usize,
+    pub message: String,
+}
+
+fn error(position: usize, message: impl Into<String>) -> FilterSyntaxError {
+    FilterSyntaxError {
+        position,
+        message: message.into(),
+    }
+}
+
+/// Parses a filter query.
+///
+/// The query is a sequence of terms separated by whitespace, all of which have to match.
+/// Terms can be negated with `-`, combined with `OR` and grouped with parentheses:
+///
+/// - `label:<name>`: the task has the label;
+/// - `column:<name>`: the task is in the column;
+/// - `priority:<op><priority>`, e.g. `priority:>=high`;
+/// - `due:<op><date>`, where the date is `YYYY-MM-DD` in UTC or an offset from now
+///   such as `12h`, `7d`, `-2w`; `due:none` matches the tasks without a due date;
+/// - `archived`: the task is archived.
+///
+/// `<op>` is one of `<`, `<=`, `=`, `>=`, `>` and defaults to `=`.
+/// Values with spaces are quoted: `column:"In progress"`. A blank query matches every task.
+pub fn parse_filter(query: &str) -> Result<TaskFilter, FilterSyntaxError> {
+    let chars: Vec<char> = query.chars().collect();
+
+    if chars.len() > MAX_FILTER_LENGTH {
+        return Err(error(MAX_FILTER_LENGTH, "the filter is too long"));
+    }
+
+    let mut parser = Parser {
+        chars,
+        position: 0,
+        depth: 0,
+    };
+
+    parser.skip_whitespace();
+    if parser.peek().is_none() {
+        return Ok(TaskFilter::And(Vec::new()));
+    }
+
+    let filter = parser.parse_or()?;
+
+    match parser.peek() {
+        Some(c) => Err(error(parser.position, format!("unexpected '{}'", c))),
+        None => Ok(filter),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let start = self.position;
+
+        while self.peek().is_some_and(&f) {
+            self.position += 1;
+        }
+
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn at_or(&self) -> bool {
+        self.chars[self.position..].starts_with(&['O', 'R'])
+            && self
+                .chars
+                .get(self.position + 2)
+                .is_none_or(|&c| c.is_whitespace() || c == '(' || c == '-')
+    }
+
+    fn parse_or(&mut self) -> Result<TaskFilter, FilterSyntaxError> {
+        let mut alternatives = vec![self.parse_and()?];
+
+        while self.at_or() {
+            self.position += 2;
+            alternatives.push(self.parse_and()?);
+        }
+
+        Ok(if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            TaskFilter::Or(alternatives)
+        })
+    }
+
+    /// Parses the terms up to `OR`, a closing parenthesis or the end of the query.
+    fn parse_and(&mut self) -> Result<TaskFilter, FilterSyntaxError> {
+        let mut terms = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+
+            if self.peek().is_none_or(|c| c == ')') || self.at_or() {
+                break;
+            }
+
+            terms.push(self.parse_unary()?);
+        }
+
+        match terms.len() {
+            0 => Err(error(self.position, "expected a filter")),
+            1 => Ok(terms.remove(0)),
+            _ => Ok(TaskFilter::And(terms)),
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<TaskFilter, FilterSyntaxError> {
+        let start = self.position;
+
+        match self.peek() {
+            Some('-') | Some('(') if self.depth == MAX_DEPTH => {
+                Err(error(start, "the filter is nested too deeply"))
+            }
+            Some('-') => {
+                self.position += 1;
+                self.depth += 1;
+                let filter = self.parse_unary()?;
+                self.depth -= 1;
+
+                Ok(TaskFilter::Not(Box::new(filter)))
+            }
+            Some('(') => {
+                self.position += 1;
+                self.depth += 1;
+                let filter = self.parse_or()?;
+                self.depth -= 1;
+
+                if self.peek() != Some(')') {
+                    return Err(error(start, "unclosed parenthesis"));
+                }
+                self.position += 1;
+
+                Ok(filter)
+            }
+            _ => self.parse_term(),
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<TaskFilter, FilterSyntaxError> {
+        let start = self.position;
+        let name = self
+            .take_while(|c| c.is_alphanumeric() || c == '_')
+            .to_lowercase();
+
+        if name.is_empty() {
+            return Err(match self.peek() {
+                Some(c) if !c.is_whitespace() => error(start, format!("unexpected '{}'", c)),
+                _ => error(start, "expected a filter"),
+            });
+        }
+
+        if self.peek() != Some(':') {
+            return match name.as_str() {
+                "archived" => Ok(TaskFilter::Archived),
+                _ => Err(error(start, format!("unknown filter '{}'", name))),
+            };
+        }
+        self.position += 1;
+
+        match name.as_str() {
+            "label" => Ok(TaskFilter::Label(self.parse_value()?.1)),
+            "column" => Ok(TaskFilter::Column(self.parse_value()?.1)),
+            "priority" => {
+                let comparison = self.parse_comparison().unwrap_or(Comparison::Equal);
+                let (position, value) = self.parse_value()?;
+
+                let priority = TaskPriority::parse(&value.to_lowercase())
+                    .ok_or_else(|| error(position, format!("unknown priority '{}'", value)))?;
+
+                Ok(TaskFilter::Priority(comparison, priority))
+            }
+            "due" => self.parse_due(),
+            _ => Err(error(start, format!("unknown field '{}'", name))),
+        }
+    }
+
+    fn parse_due(&mut self) -> Result<TaskFilter, FilterSyntaxError> {
+        let comparison = self.parse_comparison();
+        let (position, value) = self.parse_value()?;
+
+        if comparison.is_none() && value.eq_ignore_ascii_case("none") {
+            return Ok(TaskFilter::Due(DueFilter::None));
+        }
+
+        let date = parse_due_date(&value).ok_or_else(|| {
+            error(
+                position,
+                format!(
+                    "invalid date '{}', expected YYYY-MM-DD or an offset such as 7d",
+                    value
+                ),
+            )
+        })?;
+
+        let comparison = match (comparison, date) {
+            (None | Some(Comparison::Equal), DueDate::Relative(_)) => {
+                return Err(error(
+                    position,
+                    "an offset needs a comparison, such as due:<7d",
+                ))
+            }
+            (comparison, _) => comparison.unwrap_or(Comparison::Equal),
+        };
+
+        Ok(TaskFilter::Due(DueFilter::Compare(comparison, date)))
+    }
+
+    fn parse_comparison(&mut self) -> Option<Comparison> {
+        const OPERATORS: [(&str, Comparison); 5] = [
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+            ("=", Comparison::Equal),
+        ];
+
+        let rest = &self.chars[self.position..];
+
+        let (operator, comparison) = OPERATORS.into_iter().find(|(operator, _)| {
+            rest.iter()
+                .copied()
+                .take(operator.len())
+                .eq(operator.chars())
+        })?;
+
+        self.position += operator.len();
+
+        Some(comparison)
+    }
+
+    /// Parses a bare or quoted value, returning its position.
+    fn parse_value(&mut self) -> Result<(usize, String), FilterSyntaxError> {
+        let start = self.position;
+
+        let value = if self.peek() == Some('"') {
+            self.position += 1;
+            let value = self.take_while(|c| c != '"');
+
+            if self.peek() != Some('"') {
+                return Err(error(start, "unclosed quote"));
+            }
+            self.position += 1;
+
+            value
+        } else {
+            self.take_while(|c| !c.is_whitespace() && c != '(' && c != ')')
+        };
+
+        if value.is_empty() {
+            return Err(error(start, "expected a value"));
+        }
+
+        Ok((start, value))
+    }
+}
+
+fn parse_due_date(value: &str) -> Option<DueDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return (1..=9999)
+            .contains(&date.year())
+            .then_some(DueDate::Date(date));
+    }
+
+    let unit = value.chars().last()?;
+    let amount: i64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+
+    let hours = match unit {
+        'h' => amount,
+        'd' => amount.checked_mul(24)?,
+        'w' => amount.checked_mul(24 * 7)?,
+        _ => return None,
+    };
+
+    (hours.abs() <= MAX_OFFSET_DAYS * 24).then(|| DueDate::Relative(TimeDelta::hours(hours)))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeDelta};
+
+    use crate::model::{
+        filters::{Comparison, DueDate, DueFilter, TaskFilter},
+        tasks::TaskPriority,
+    };
+
+    use super::parse_filter;
+
+    #[test]
+    fn terms_are_combined() {
+        assert_eq!(
+            parse_filter(r#"label:bug priority:>=HIGH due:<7d column:"In progress" -archived"#),
+            Ok(TaskFilter::And(vec![
+                TaskFilter::Label("bug".to_string()),
+                TaskFilter::Priority(Comparison::GreaterOrEqual, TaskPriority::High),
+                TaskFilter::Due(DueFilter::Compare(
+                    Comparison::Less,
+                    DueDate::Relative(TimeDelta::days(7))
+                )),
+                TaskFilter::Column("In progress".to_string()),
+                TaskFilter::Not(Box::new(TaskFilter::Archived)),
+            ]))
+        );
+
+        assert_eq!(
+            parse_filter("(label:bug OR label:defect) -due:none due:2024-03-10"),
+            Ok(TaskFilter::And(vec![
+                TaskFilter::Or(vec![
+                    TaskFilter::Label("bug".to_string()),
+                    TaskFilter::Label("defect".to_string()),
+                ]),
+                TaskFilter::Not(Box::new(TaskFilter::Due(DueFilter::None))),
+                TaskFilter::Due(DueFilter::Compare(
+                    Comparison::Equal,
+                    DueDate::Date(NaiveDate::from_ymd_opt(2024, 3, 10).unwrap())
+                )),
+            ]))
+        );
+
+        assert_eq!(parse_filter("  "), Ok(TaskFilter::And(Vec::new())));
+    }
+
+    #[test]
+    fn syntax_errors_point_to_the_position() {
+        let cases = [
+            ("label:", 6),
+            ("label:bug priority:>=hgh", 21),
+            (r#"column:"In progress"#, 7),
+            ("label:bug (due:none", 10),
+            ("label:bug)", 9),
+            ("label:bug OR", 12),
+            ("due:7d", 4),
+            ("due:<tomorrow", 5),
+            ("assignee:me", 0),
+            ("-  archived", 1),
+            ("größe", 0),
+        ];
+
+        for (query, position) in cases {
+            let err = parse_filter(query).unwrap_err();
+            assert_eq!(err.position, position, "{}: {}", query, err.message);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::sync::Arc;
+
+use chrono::{TimeDelta, Utc};
+
+use crate::model::{
+    idempotency::{IdempotencyRecord, StoredResponse},
+    UserId,
+};
+
+use super::repositories::IdempotencyRepository;
+
+/// Maximum length of an idempotency key.
+pub const MAX_KEY_LENGTH: usize = 255;
+
+/// How long a reserved key waits for the response. Past that, the request is taken as abandoned,
+/// such as by a server that has stopped, and a retry reserves the key again.
+const RESERVATION_LEASE: TimeDelta = TimeDelta::minutes(5);
+
+/// What to do with a request that has an idempotency key.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Reservation {
+    /// The key is new, handle the request and [`IdempotencyService::complete`] the key.
+    Reserved,
+    /// The request has been handled already, respond the same way.
+    Replay(StoredResponse),
+    /// The request with the key is still being handled, for less than [`RESERVATION_LEASE`].
+    InProgress,
+    /// The key has been used for a different request.
+    KeyReused,
+}
+
+pub struct IdempotencyService {
+    keys: Arc<dyn IdempotencyRepository>,
+    /// How long the responses are replayed.
+    ttl: TimeDelta,
+    lease: TimeDelta,
+}
+
+impl IdempotencyService {
+    pub fn new(keys: Arc<dyn IdempotencyRepository>, ttl: TimeDelta) -> Self {
+        Self {
+            keys,
+            ttl,
+            lease: RESERVATION_LEASE,
+        }
+    }
+
+    /// Reserves the key of the user for the request with the fingerprint,
+    /// unless the key has been used within the TTL. The keys sent before logging in,
+    /// without a user, are kept apart from those of the users.
+    pub async fn reserve(
+        &self,
+        user_id: Option<UserId>,
+        key: &str,
+        fingerprint: &str,
+    ) -> anyhow::Result<Reservation> {
+        let now = Utc::now();
+
+        let record = IdempotencyRecord {
+            user_id,
+            key: key.to_string(),
+            fingerprint: fingerprint.to_string(),
+            response: None,
+            created_at: now,
+        };
+
+        let Some(existing) = self
+            .keys
+            .reserve_key(&record, now - self.ttl, now - self.lease)
+            .await?
+        else {
+            return Ok(Reservation::Reserved);
+        };
+
+        Ok(if existing.fingerprint != fingerprint {
+            Reservation::KeyReused
+        } else {
+            match existing.response {
+                Some(response) => Reservation::Replay(response),
+                None => Reservation::InProgress,
+            }
+        })
+    }
+
+    /// Stores the response to the request with the reserved key.
+    pub async fn complete(
+        &self,
+        user_id: Option<UserId>,
+        key: &str,
+        response: &StoredResponse,
+    ) -> anyhow::Result<()> {
+        self.keys.complete_key(user_id, key, response).await
+    }
+
+    /// Frees the reserved key when the request has failed, so that it can be retried.
+    pub async fn release(&self, user_id: Option<UserId>, key: &str) -> anyhow::Result<()> {
+        self.keys.release_key(user_id, key).await
+    }
+
+    /// Deletes the keys older than the TTL. Returns the number of deleted keys.
+    pub async fn purge_expired(&self) -> anyhow::Result<u64> {
+        self.keys.delete_expired_keys(Utc::now() - self.ttl).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::TimeDelta;
+
+    use crate::{
+        model::{idempotency::StoredResponse, UserId},
+        storage::inmemory,
+    };
+
+    use super::{IdempotencyService, Reservation};
+
+    const USER_ID: Option<UserId> = Some(UserId::from_raw(1));
+    const OTHER_USER_ID: Option<UserId> = Some(UserId::from_raw(2));
+
+    fn response() -> StoredResponse {
+        StoredResponse {
+            status: 200,
+            etag: Some("\"1\"".to_string()),
+            body: "{}".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn response_is_replayed_for_the_same_request() -> anyhow::Result<()> {
+        let service = IdempotencyService::new(
+            Arc::new(inmemory::InMemoryIdempotency::new()),
+            TimeDelta::hours(1),
+        );
+
+        let reservation = service.reserve(USER_ID, "key", "a").await?;
+        assert_eq!(reservation, Reservation::Reserved);
+        assert_eq!(
+            service.reserve(USER_ID, "key", "a").await?,
+            Reservation::InProgress
+        );
+
+        service.complete(USER_ID, "key", &response()).await?;
+        assert_eq!(
+            service.reserve(USER_ID, "key", "a").await?,
+            Reservation::Replay(response())
+        );
+        assert_eq!(
+            service.reserve(USER_ID, "key", "b").await?,
+            Reservation::KeyReused
+        );
+
+        // Keys are scoped by user.
+        assert_eq!(
+            service.reserve(OTHER_USER_ID, "key", "b").await?,
+            Reservation::Reserved
+        );
+
+        service.release(OTHER_USER_ID, "key").await?;
+        assert_eq!(
+            service.reserve(OTHER_USER_ID, "key", "c").await?,
+            Reservation::Reserved
+        );
+
+        // And so are the keys sent before logging in.
+        assert_eq!(
+            service.reserve(None, "key", "d").await?,
+            Reservation::Reserved
+        );
+        service.complete(None, "key", &response()).await?;
+        assert_eq!(
+            service.reserve(None, "key", "d").await?,
+            Reservation::Replay(response())
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn abandoned_reservations_are_taken_over() -> anyhow::Result<()> {
+        let service = IdempotencyService {
+            keys: Arc::new(inmemory::InMemoryIdempotency::new()),
+            ttl: TimeDelta::hours(1),
+            lease: TimeDelta::zero(),
+        };
+
+        // The request with the first reservation never completes.
+        service.reserve(USER_ID, "key", "a").await?;
+
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        assert_eq!(
+            service.reserve(USER_ID, "key", "a").await?,
+            Reservation::Reserved
+        );
+
+        // Completed requests are replayed for the whole TTL.
+        service.complete(USER_ID, "key", &response()).await?;
+
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        assert_eq!(
+            service.reserve(USER_ID, "key", "a").await?,
+            Reservation::Replay(response())
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn expired_keys_are_reused() -> anyhow::Result<()> {
+        let service = IdempotencyService::new(
+            Arc::new(inmemory::InMemoryIdempotency::new()),
+            TimeDelta::zero(),
+        );
+
+        service.reserve(USER_ID, "key", "a").await?;
+        service.complete(USER_ID, "key", &response()).await?;
+
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        assert_eq!(
+            service.reserve(USER_ID, "key", "b").await?,
+            Reservation::Reserved
+        );
+
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        assert_eq!(service.purge_expired().await?, 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,747 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::model::{
+    imports::{BoardImport, ImportRef, ImportedItems, ImportedTask},
+    labels::{LabelData, LabelDescription},
+    tasks::{TaskCategoryDescription, TaskPriority},
+    LabelId, TaskCategoryId,
+};
+
+/// Maximum number of tasks created by a single import.
+pub const MAX_IMPORT_TASKS: usize = 1000;
+
+/// Column of the cards whose column is not given.
+const DEFAULT_COLUMN: &str = "Imported";
+
+/// Colour of the labels that have no colour of their own.
+const DEFAULT_LABEL_COLOR: &str = "#b3bac5";
+
+/// Maximum length of the names of categories and labels.
+const MAX_NAME_LENGTH: usize = 64;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ImportError {
+    /// The file is not a well-formed CSV. Contains the line of the error.
+    InvalidCsv { line: usize },
+    /// The header of the CSV file has no column with this name.
+    MissingColumn(&'static str),
+    /// The import would create more than [`MAX_IMPORT_TASKS`] tasks.
+    TooManyTasks,
+}
+
+/// Part of the source that could not be imported as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportWarning {
+    /// Where the problem is, e.g. `line 3` or `card "Title"`.
+    pub location: String,
+    pub message: String,
+}
+
+impl ImportWarning {
+    pub fn new(location: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            location: location.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Card of the source, which becomes a task.
+#[derive(Debug, Clone)]
+pub struct ImportCard {
+    pub column: String,
+    pub title: String,
+    pub description: String,
+    pub labels: Vec<LabelData>,
+    pub start_at: Option<DateTime<Utc>>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub priority: TaskPriority,
+    /// Where the card is in the source, used by the warnings.
+    pub location: String,
+}
+
+/// Board read from a Trello export or a CSV file.
+#[derive(Debug, Clone, Default)]
+pub struct ImportSource {
+    /// Names of the columns in the board order, including the ones without cards.
+    pub columns: Vec<String>,
+    pub cards: Vec<ImportCard>,
+    pub warnings: Vec<ImportWarning>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrelloList {
+    pub id: String,
+    pub name: String,
+    pub closed: bool,
+    pub pos: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrelloLabel {
+    pub id: String,
+    pub name: String,
+    /// Name of the colour, e.g. `green` or `sky_dark`.
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrelloCard {
+    pub list_id: String,
+    pub name: String,
+    pub desc: String,
+    pub closed: bool,
+    pub pos: f64,
+    pub label_ids: Vec<String>,
+    pub start: Option<String>,
+    pub due: Option<String>,
+    pub checklist_count: usize,
+    pub attachment_count: usize,
+    pub comment_count: usize,
+}
+
+/// Board of a Trello JSON export.
+#[derive(Debug, Clone, Default)]
+pub struct TrelloBoard {
+    pub lists: Vec<TrelloList>,
+    pub labels: Vec<TrelloLabel>,
+    pub cards: Vec<TrelloCard>,
+}
+
+/// Column of the board that receives imported cards.
+#[derive(Debug, Clone)]
+pub struct ImportedColumn {
+    /// `None` if the category is created by the import and the import is a dry run.
+    pub category_id: Option<TaskCategoryId>,
+    pub label: String,
+    /// The category does not exist yet.
+    pub created: bool,
+    pub task_count: usize,
+}
+
+/// Label assigned to imported tasks.
+#[derive(Debug, Clone)]
+pub struct ImportedLabel {
+    /// `None` if the label is created by the import and the import is a dry run.
+    pub label_id: Option<LabelId>,
+    pub name: String,
+    pub color: String,
+    /// The label does not exist yet.
+    pub created: bool,
+}
+
+/// What an import creates, or would create in a dry run.
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub columns: Vec<ImportedColumn>,
+    pub labels: Vec<ImportedLabel>,
+    pub task_count: usize,
+    pub warnings: Vec<ImportWarning>,
+}
+
+/// Import mapped onto the existing categories and labels of a board.
+pub(super) struct ImportPlan {
+    pub import: BoardImport,
+    report: ImportReport,
+    columns: Vec<ImportRef<TaskCategoryId>>,
+    labels: Vec<ImportRef<LabelId>>,
+}
+
+impl ImportPlan {
+    /// Maps the source onto the board. Columns and labels are matched to the active categories
+    /// and the labels of the board by name, ignoring case, and created if there is no match.
+    pub fn new(
+        source: ImportSource,
+        categories: &[TaskCategoryDescription],
+        labels: &[LabelDescription],
+    ) -> Result<Self, ImportError> {
+        if source.cards.len() > MAX_IMPORT_TASKS {
+            return Err(ImportError::TooManyTasks);
+        }
+
+        let mut plan = ImportPlan {
+            import: BoardImport::default(),
+            report: ImportReport {
+                warnings: source.warnings,
+                ..Default::default()
+            },
+            columns: Vec::new(),
+            labels: Vec::new(),
+        };
+
+        let mut columns_by_name: HashMap<String, usize> = HashMap::new();
+        let mut labels_by_name: HashMap<String, usize> = HashMap::new();
+
+        let column_names = source
+            .columns
+            .iter()
+            .map(|name| (name.as_str(), "board".to_string()))
+            .chain(
+                source
+                    .cards
+                    .iter()
+                    .map(|card| (card.column.as_str(), card.location.clone())),
+            );
+
+        for (name, location) in column_names {
+            let key = truncate(name).to_lowercase();
+
+            if columns_by_name.contains_key(&key) {
+                continue;
+            }
+
+            let name = plan.shorten(name, &location, "column");
+
+            let existing = categories
+                .iter()
+                .find(|c| c.lifecycle.is_active() && c.label.to_lowercase() == key);
+
+            let column = match existing {
+                Some(category) => ImportRef::Existing(category.category_id.clone()),
+                None => {
+                    plan.import.categories.push(name.clone());
+                    ImportRef::New(plan.import.categories.len() - 1)
+                }
+            };
+
+            columns_by_name.insert(key, plan.columns.len());
+            plan.report.columns.push(ImportedColumn {
+                category_id: existing.map(|c| c.category_id.clone()),
+                label: existing.map_or(name, |c| c.label.clone()),
+                created: existing.is_none(),
+                task_count: 0,
+            });
+            plan.columns.push(column);
+        }
+
+        for card in source.cards {
+            let column_index = columns_by_name[&truncate(&card.column).to_lowercase()];
+            plan.report.columns[column_index].task_count += 1;
+
+            let mut task_labels = Vec::new();
+
+            for label in card.labels {
+                let key = truncate(&label.name).to_lowercase();
+
+                let label_index = match labels_by_name.get(&key) {
+                    Some(&index) => index,
+                    None => {
+                        let name = plan.shorten(&label.name, &card.location, "label");
+                        let existing = labels.iter().find(|l| l.name.to_lowercase() == key);
+
+                        let label_ref = match existing {
+                            Some(existing) => ImportRef::Existing(existing.label_id.clone()),
+                            None => {
+                                plan.import.labels.push(LabelData {
+                                    name: name.clone(),
+                                    color: label.color.clone(),
+                                });
+                                ImportRef::New(plan.import.labels.len() - 1)
+                            }
+                        };
+
+                        plan.report.labels.push(ImportedLabel {
+                            label_id: existing.map(|l| l.label_id.clone()),
+                            name: existing.map_or(name, |l| l.name.clone()),
+                            color: existing.map_or(label.color, |l| l.color.clone()),
+                            created: existing.is_none(),
+                        });
+                        plan.labels.push(label_ref);
+                        labels_by_name.insert(key, plan.labels.len() - 1);
+                        plan.labels.len() - 1
+                    }
+                };
+
+                task_labels.push(plan.labels[label_index].clone());
+            }
+
+            plan.import.tasks.push(ImportedTask {
+                category: plan.columns[column_index].clone(),
+                label: card.title,
+                description: card.description,
+                start_at: card.start_at,
+                due_at: card.due_at,
+                priority: card.priority,
+                labels: task_labels,
+            });
+        }
+
+        plan.report.task_count = plan.import.tasks.len();
+        Ok(plan)
+    }
+
+    /// Returns the report of the plan, with the IDs of the created items if it has been carried out.
+    pub fn into_report(self, items: Option<&ImportedItems>) -> ImportReport {
+        let mut report = self.report;
+        report.dry_run = items.is_none();
+
+        if let Some(items) = items {
+            for (column, column_ref) in report.columns.iter_mut().zip(&self.columns) {
+                column.category_id = Some(items.category_id(column_ref).to_string());
+            }
+
+            for (label, label_ref) in report.labels.iter_mut().zip(&self.labels) {
+                label.label_id = Some(items.label_id(label_ref).to_string());
+            }
+        }
+
+        report
+    }
+
+    /// Truncates a name of a category or a label that does not fit, warning about it.
+    fn shorten(&mut self, name: &str, location: &str, what: &str) -> String {
+        if name.trim().chars().count() > MAX_NAME_LENGTH {
+            self.report.warnings.push(ImportWarning::new(
+                location,
+                format!(
+                    "{what} name is longer than {MAX_NAME_LENGTH} characters and was truncated"
+                ),
+            ));
+        }
+
+        truncate(name)
+    }
+}
+
+/// Trims the name of a category or a label and cuts it to the maximum length.
+fn truncate(name: &str) -> String {
+    name.trim().chars().take(MAX_NAME_LENGTH).collect()
+}
+
+/// Parses a date in RFC 3339 format or a bare `YYYY-MM-DD` date, which is taken as midnight UTC.
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(value) {
+        return Some(date_time.to_utc());
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date_time| date_time.and_utc())
+}
+
+/// Returns the `#rrggbb` colour of a Trello colour name.
+fn trello_color(name: &str) -> Option<&'static str> {
+    // Dark and light shades are mapped onto the base colour.
+    let base = name
+        .strip_suffix("_dark")
+        .or_else(|| name.strip_suffix("_light"))
+        .unwrap_or(name);
+
+    Some(match base {
+        "green" => "#61bd4f",
+        "yellow" => "#f2d600",
+        "orange" => "#ff9f1a",
+        "red" => "#eb5a46",
+        "purple" => "#c377e0",
+        "blue" => "#0079bf",
+        "sky" => "#00c2e0",
+        "lime" => "#51e898",
+        "pink" => "#ff78cb",
+        "black" => "#344563",
+        _ => return None,
+    })
+}
+
+impl ImportSource {
+    /// Maps a Trello board. Closed lists and cards are skipped, as well as the cards of unknown lists.
+    /// Checklists, attachments and comments are not imported.
+    pub fn from_trello(board: TrelloBoard) -> Self {
+        let mut source = ImportSource::default();
+
+        let mut lists = board.lists;
+        lists.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+
+        let mut list_positions: HashMap<&str, usize> = HashMap::new();
+
+        for list in &lists {
+            if list.closed {
+                source.warnings.push(ImportWarning::new(
+                    format!("list \"{}\"", list.name),
+                    "archived list and its cards were not imported",
+                ));
+            } else if list.name.trim().is_empty() {
+                source.warnings.push(ImportWarning::new(
+                    format!("list {}", list.id),
+                    format!("list has no name, its cards were imported to \"{DEFAULT_COLUMN}\""),
+                ));
+                list_positions.insert(&list.id, source.columns.len());
+                source.columns.push(DEFAULT_COLUMN.to_string());
+            } else {
+                list_positions.insert(&list.id, source.columns.len());
+                source.columns.push(list.name.clone());
+            }
+        }
+
+        let labels: HashMap<&str, &TrelloLabel> =
+            board.labels.iter().map(|l| (l.id.as_str(), l)).collect();
+
+        let mut cards = board.cards;
+        cards.sort_by(|a, b| {
+            let a_list = list_positions.get(a.list_id.as_str());
+            let b_list = list_positions.get(b.list_id.as_str());
+            a_list.cmp(&b_list).then(a.pos.total_cmp(&b.pos))
+        });
+
+        let mut archived_cards = 0;
+        let (mut checklists, mut attachments, mut comments) = (0, 0, 0);
+
+        for card in cards {
+            let location = format!("card \"{}\"", card.name);
+
+            if card.closed {
+                archived_cards += 1;
+                continue;
+            }
+
+            let Some(&column) = list_positions.get(card.list_id.as_str()) else {
+                // The cards of the archived lists are covered by the warnings about the lists.
+                if !lists.iter().any(|l| l.closed && l.id == card.list_id) {
+                    source.warnings.push(ImportWarning::new(
+                        location,
+                        "card of an unknown list was not imported",
+                    ));
+                }
+                continue;
+            };
+
+            if card.name.trim().is_empty() {
+                source.warnings.push(ImportWarning::new(
+                    location,
+                    "card has no name and was not imported",
+                ));
+                continue;
+            }
+
+            let mut card_labels: Vec<LabelData> = Vec::new();
+
+            for label_id in &card.label_ids {
+                let Some(label) = labels.get(label_id.as_str()) else {
+                    source.warnings.push(ImportWarning::new(
+                        &location,
+                        format!("unknown label {label_id} was not assigned"),
+                    ));
+                    continue;
+                };
+
+                let color = label.color.as_deref().and_then(trello_color);
+
+                // Labels without a name are known by their colour in Trello.
+                let name = match (label.name.trim(), &label.color) {
+                    ("", Some(color_name)) => color_name.clone(),
+                    ("", None) => {
+                        source.warnings.push(ImportWarning::new(
+                            &location,
+                            "label without a name or a colour was not assigned",
+                        ));
+                        continue;
+                    }
+                    (name, _) => name.to_string(),
+                };
+
+                card_labels.push(LabelData {
+                    name,
+                    color: color.unwrap_or(DEFAULT_LABEL_COLOR).to_string(),
+                });
+            }
+
+            let mut date = |value: Option<&str>, what: &str| {
+                let value = value?;
+                let date = parse_date(value);
+                if date.is_none() {
+                    source.warnings.push(ImportWarning::new(
+                        &location,
+                        format!("{what} date \"{value}\" was not recognized"),
+                    ));
+                }
+                date
+            };
+
+            let start_at = date(card.start.as_deref(), "start");
+            let due_at = date(card.due.as_deref(), "due");
+
+            checklists += card.checklist_count;
+            attachments += card.attachment_count;
+            comments += card.comment_count;
+
+            source.cards.push(ImportCard {
+                column: source.columns[column].clone(),
+                title: card.name,
+                description: card.desc,
+                labels: card_labels,
+                start_at,
+                due_at,
+                priority: TaskPriority::default(),
+                location,
+            });
+        }
+
+        for (count, what) in [
+            (archived_cards, "archived cards"),
+            (checklists, "checklists"),
+            (attachments, "attachments"),
+            (comments, "comments"),
+        ] {
+            if count > 0 {
+                source.warnings.push(ImportWarning::new(
+                    "board",
+                    format!("{what} were not imported: {count}"),
+                ));
+            }
+        }
+
+        source
+    }
+
+    /// Parses a CSV file with a header row. The `column` and `title` columns are required,
+    /// `description`, `labels`, `start date`, `due date` and `priority` are optional
+    /// and the others are ignored.
+    /// Labels are separated by commas or semicolons.
+    pub fn from_csv(text: &str) -> Result<Self, ImportError> {
+        let mut records = parse_csv_records(text.strip_prefix('\u{feff}').unwrap_or(text))?;
+
+        if records.is_empty() {
+            return Err(ImportError::MissingColumn("title"));
+        }
+
+        let (_, header) = records.remove(0);
+        let mut source = ImportSource::default();
+
+        let find = |names: &[&str]| {
+            header.iter().position(|field| {
+                let field = field.trim().to_lowercase();
+                names.contains(&field.as_str())
+            })
+        };
+
+        let column = find(&["column", "list"]).ok_or(ImportError::MissingColumn("column"))?;
+        let title = find(&["title", "name"]).ok_or(ImportError::MissingColumn("title"))?;
+        let description = find(&["description"]);
+        let labels = find(&["labels", "label"]);
+        let start = find(&["start date", "start", "start_date"]);
+        let due = find(&["due date", "due", "due_date"]);
+        let priority = find(&["priority"]);
+
+        let known = [
+            Some(column),
+            Some(title),
+            description,
+            labels,
+            start,
+            due,
+            priority,
+        ];
+
+        for (index, field) in header.iter().enumerate() {
+            if !known.contains(&Some(index)) {
+                source.warnings.push(ImportWarning::new(
+                    "line 1",
+                    format!("column \"{}\" was ignored", field.trim()),
+                ));
+            }
+        }
+
+        for (line, record) in records {
+            let location = format!("line {line}");
+            let field = |index: Option<usize>| {
+                index
+                    .and_then(|i| record.get(i))
+                    .map_or("", |value| value.trim())
+            };
+
+            // Blank lines are skipped silently.
+            if record.iter().all(|value| value.trim().is_empty()) {
+                continue;
+            }
+
+            if field(Some(title)).is_empty() {
+                source.warnings.push(ImportWarning::new(
+                    location,
+                    "row has no title and was not imported",
+                ));
+                continue;
+            }
+
+            let column_name = match field(Some(column)) {
+                "" => {
+                    source.warnings.push(ImportWarning::new(
+                        &location,
+                        format!("row has no column and was imported to \"{DEFAULT_COLUMN}\""),
+                    ));
+                    DEFAULT_COLUMN
+                }
+                name => name,
+            };
+
+            let mut date = |index: Option<usize>, what: &str| match field(index) {
+                "" => None,
+                value => {
+                    let date = parse_date(value);
+                    if date.is_none() {
+                        source.warnings.push(ImportWarning::new(
+                            &location,
+                            format!("{what} date \"{value}\" was not recognized"),
+                        ));
+                    }
+                    date
+                }
+            };
+
+            let start_at = date(start, "start");
+            let due_at = date(due, "due");
+
+            let priority = match field(priority) {
+                "" => TaskPriority::default(),
+                value => TaskPriority::parse(&value.to_lowercase()).unwrap_or_else(|| {
+                    source.warnings.push(ImportWarning::new(
+                        &location,
+                        format!("priority \"{value}\" was not recognized"),
+                    ));
+                    TaskPriority::default()
+                }),
+            };
+
+            source.cards.push(ImportCard {
+                column: column_name.to_string(),
+                title: field(Some(title)).to_string(),
+                description: field(description).to_string(),
+                labels: field(labels)
+                    .split([',', ';'])
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(|name| LabelData {
+                        name: name.to_string(),
+                        color: DEFAULT_LABEL_COLOR.to_string(),
+                    })
+                    .collect(),
+                start_at,
+                due_at,
+                priority,
+                location,
+            });
+        }
+
+        Ok(source)
+    }
+}
+
+/// Splits a CSV text into records as described by RFC 4180, with the line each record starts on.
+/// Quoted fields may contain delimiters, line breaks and doubled quotes.
+fn parse_csv_records(text: &str) -> Result<Vec<(usize, Vec<String>)>, ImportError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    let mut record_line = 1;
+
+    let mut chars = text.chars().peekable();
+    let mut quoted = false;
+    // The field has been quoted, so only a delimiter or a line break may follow.
+    let mut closed = false;
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => {
+                    quoted = false;
+                    closed = true;
+                }
+                c => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+
+        match c {
+            ',' => {
+                record.push(std::mem::take(&mut field));
+                closed = false;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push((record_line, std::mem::take(&mut record)));
+                closed = false;
+                line += 1;
+                record_line = line;
+            }
+            '"' if field.is_empty() && !closed => quoted = true,
+            _ if closed => return Err(ImportError::InvalidCsv { line }),
+            c => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(ImportError::InvalidCsv { line: record_line });
+    }
+
+    if !field.is_empty() || !record.is_empty() || closed {
+        record.push(field);
+        records.push((record_line, record));
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImportError, ImportSource};
+
+    #[test]
+    fn csv_is_parsed_with_warnings() {
+        let text = "Column,Title,Description,Labels,Due date,Owner\r\n\
+            ToDo,\"Buy milk, eggs\",\"Two lines\nof \"\"text\"\"\",home; urgent,2024-05-01,bob\r\n\
+            Done,,no title,,,\r\n\
+            ,Orphan,,,someday,\r\n";
+
+        let source = ImportSource::from_csv(text).unwrap();
+
+        assert_eq!(source.cards.len(), 2);
+
+        let card = &source.cards[0];
+        assert_eq!(card.column, "ToDo");
+        assert_eq!(card.title, "Buy milk, eggs");
+        assert_eq!(card.description, "Two lines\nof \"text\"");
+        assert_eq!(
+            card.labels
+                .iter()
+                .map(|l| l.name.as_str())
+                .collect::<Vec<_>>(),
+            ["home", "urgent"]
+        );
+        assert_eq!(
+            card.due_at.unwrap().to_rfc3339(),
+            "2024-05-01T00:00:00+00:00"
+        );
+
+        assert_eq!(source.cards[1].column, "Imported");
+        assert_eq!(source.cards[1].due_at, None);
+
+        let locations: Vec<&str> = source
+            .warnings
+            .iter()
+            .map(|w| w.location.as_str())
+            .collect();
+        assert_eq!(locations, ["line 1", "line 4", "line 5", "line 5"]);
+
+        assert_eq!(
+            ImportSource::from_csv("column,title\nToDo,\"unterminated").unwrap_err(),
+            ImportError::InvalidCsv { line: 2 }
+        );
+        assert_eq!(
+            ImportSource::from_csv("column,description\n").unwrap_err(),
+            ImportError::MissingColumn("title")
+        );
+    }
+}
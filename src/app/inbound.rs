@@ -0,0 +1,274 @@
+use std::sync::Arc;
+
+use mail_parser::{Address, MessageParser, MimeHeaders};
+
+use crate::model::{
+    attachments::AttachmentDescription, inbound::InboundToken, tasks::TaskDescription, BoardId,
+};
+
+use super::{
+    attachments::{AttachmentError, Upload},
+    repositories::UsersRepositry,
+};
+
+/// Maximum number of attachments of a message that are attached to the task. The rest are dropped.
+pub const MAX_ATTACHMENTS: usize = 20;
+
+/// Label of the task created from a message without a subject.
+const NO_SUBJECT_LABEL: &str = "(no subject)";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum InboundError {
+    /// The message is not an RFC 5322 message.
+    InvalidMessage,
+    /// None of the recipients is the inbound address of a board.
+    UnknownRecipient,
+    /// The board has no active category to put the task in.
+    NoCategory,
+}
+
+/// Message sent to the inbound address of a board.
+#[derive(Debug)]
+pub struct InboundEmail {
+    /// Addresses the message was delivered to.
+    pub recipients: Vec<String>,
+    pub subject: String,
+    /// Plain text body, or the HTML body converted to text if the message has no plain text body.
+    pub text: String,
+    pub attachments: Vec<Upload>,
+}
+
+impl InboundEmail {
+    /// Parses a raw MIME message. The envelope recipients, e.g. from SMTP `RCPT TO` commands,
+    /// are used if given, since the headers do not list the `Bcc` recipients. Otherwise the recipients
+    /// are read from `To`, `Cc` and `Delivered-To` headers.
+    pub fn parse(raw: &[u8], envelope_recipients: Vec<String>) -> Option<Self> {
+        let message = MessageParser::default().parse(raw)?;
+
+        // A message without any header is not a message, even though the parser accepts it.
+        if message.headers().is_empty() {
+            return None;
+        }
+
+        let recipients = if envelope_recipients.is_empty() {
+            let delivered_to = message
+                .header_values("Delivered-To")
+                .filter_map(|value| value.as_text())
+                .map(str::to_string);
+
+            [message.to(), message.cc()]
+                .into_iter()
+                .flatten()
+                .flat_map(Address::iter)
+                .filter_map(|addr| addr.address().map(str::to_string))
+                .chain(delivered_to)
+                .collect()
+        } else {
+            envelope_recipients
+        };
+
+        let text = message
+            .body_text(0)
+            .map(|text| text.replace("\r\n", "\n").trim_end().to_string())
+            .unwrap_or_default();
+
+        let attachments = message
+            .attachments()
+            .take(MAX_ATTACHMENTS)
+            .map(|part| Upload {
+                file_name: part.attachment_name().unwrap_or("attachment").to_string(),
+                content_type: part
+                    .content_type()
+                    .map(|ct| match ct.subtype() {
+                        Some(subtype) => format!("{}/{}", ct.ctype(), subtype),
+                        None => ct.ctype().to_string(),
+                    })
+                    .unwrap_or_else(|| "application/octet-stream".to_string()),
+                data: part.contents().to_vec(),
+            })
+            .collect();
+
+        Some(Self {
+            recipients,
+            subject: message.subject().unwrap_or_default().trim().to_string(),
+            text,
+            attachments,
+        })
+    }
+
+    /// Label of the task created from the message.
+    pub fn label(&self) -> &str {
+        if self.subject.is_empty() {
+            NO_SUBJECT_LABEL
+        } else {
+            &self.subject
+        }
+    }
+}
+
+/// Attachment of a message that could not be attached to the task.
+#[derive(Debug)]
+pub struct RejectedAttachment {
+    pub file_name: String,
+    pub error: AttachmentError,
+}
+
+/// Task created from a message.
+#[derive(Debug)]
+pub struct InboundTask {
+    pub task: TaskDescription,
+    pub attachments: Vec<AttachmentDescription>,
+    pub rejected_attachments: Vec<RejectedAttachment>,
+}
+
+/// Inbound email addresses of the boards.
+pub struct InboundEmailService {
+    users: Arc<dyn UsersRepositry>,
+    /// Domain of the inbound addresses, which is handled by the SMTP listener or the mail provider.
+    domain: String,
+}
+
+impl InboundEmailService {
+    pub fn new(users: Arc<dyn UsersRepositry>, domain: &str) -> Self {
+        Self {
+            users,
+            domain: domain.to_string(),
+        }
+    }
+
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Returns the inbound address of the board, assigning it on the first request.
+    pub async fn address(&self, board_id: BoardId) -> anyhow::Result<String> {
+        let token = self
+            .users
+            .init_inbound_token(board_id, &InboundToken::generate_random())
+            .await?;
+
+        Ok(self.format_address(&token))
+    }
+
+    /// Replaces the inbound address of the board. The previous address stops working.
+    pub async fn reset_address(&self, board_id: BoardId) -> anyhow::Result<String> {
+        let token = InboundToken::generate_random();
+        self.users.set_inbound_token(board_id, &token).await?;
+
+        Ok(self.format_address(&token))
+    }
+
+    /// Returns the board whose inbound address is the first one among the recipients.
+    /// Only the local part is compared, so that the messages forwarded by a mail provider
+    /// from another domain are accepted. A `+suffix` of the local part is ignored.
+    pub async fn find_board(&self, recipients: &[String]) -> anyhow::Result<Option<BoardId>> {
+        for recipient in recipients {
+            let local_part = recipient
+                .trim()
+                .trim_start_matches('<')
+                .split('@')
+                .next()
+                .unwrap_or_default();
+            let local_part = local_part.split('+').next().unwrap_or_default();
+
+            let Some(token) = InboundToken::from_str(local_part) else {
+                continue;
+            };
+
+            if let Some(board_id) = self.users.find_user_by_inbound_token(&token).await? {
+                return Ok(Some(board_id));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn format_address(&self, token: &InboundToken) -> String {
+        format!("{}@{}", token.as_str(), self.domain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        app::repositories::UsersRepositry, model::inbound::InboundToken, storage::inmemory,
+    };
+
+    use super::{InboundEmail, InboundEmailService};
+
+    const MESSAGE: &str = "From: Bob <bob@example.com>\r\n\
+        To: Board <board@tasks.example.com>\r\n\
+        Subject: =?UTF-8?B?0J/RgNC40LLQtdGC?= and hello\r\n\
+        MIME-Version: 1.0\r\n\
+        Content-Type: multipart/mixed; boundary=\"b1\"\r\n\
+        \r\n\
+        --b1\r\n\
+        Content-Type: text/plain; charset=utf-8\r\n\
+        Content-Transfer-Encoding: quoted-printable\r\n\
+        \r\n\
+        Please review the =E2=80=9Cplan=E2=80=9D.\r\n\
+        \r\n\
+        --b1\r\n\
+        Content-Type: image/png\r\n\
+        Content-Disposition: attachment; filename=\"dot.png\"\r\n\
+        Content-Transfer-Encoding: base64\r\n\
+        \r\n\
+        iVBORw0KGgo=\r\n\
+        --b1--\r\n";
+
+    #[test]
+    fn message_is_parsed() {
+        let email = InboundEmail::parse(MESSAGE.as_bytes(), Vec::new()).unwrap();
+
+        assert_eq!(email.recipients, vec!["board@tasks.example.com"]);
+        assert_eq!(email.label(), "Привет and hello");
+        assert_eq!(email.text, "Please review the “plan”.");
+        assert_eq!(email.attachments.len(), 1);
+        assert_eq!(email.attachments[0].file_name, "dot.png");
+        assert_eq!(email.attachments[0].content_type, "image/png");
+        assert_eq!(email.attachments[0].data, b"\x89PNG\r\n\x1a\n");
+
+        let email = InboundEmail::parse(
+            b"Subject: \r\n\r\nbody",
+            vec!["envelope@tasks.example.com".to_string()],
+        )
+        .unwrap();
+        assert_eq!(email.recipients, vec!["envelope@tasks.example.com"]);
+        assert_eq!(email.label(), "(no subject)");
+
+        assert!(InboundEmail::parse(b"", Vec::new()).is_none());
+    }
+
+    #[tokio::test]
+    async fn board_is_found_by_address() -> anyhow::Result<()> {
+        let users = Arc::new(inmemory::InMemoryUsers::new());
+        let board_id = users.create_user("alice", "password").await?;
+
+        let service = InboundEmailService::new(users, "tasks.example.com");
+
+        let address = service.address(board_id).await?;
+        assert!(address.ends_with("@tasks.example.com"));
+        assert_eq!(service.address(board_id).await?, address);
+
+        let token = address.split('@').next().unwrap();
+        let recipients = vec![
+            "someone@example.com".to_string(),
+            format!("{}+urgent@forwarder.example.com", token.to_uppercase()),
+        ];
+        assert_eq!(service.find_board(&recipients).await?, Some(board_id));
+
+        let new_address = service.reset_address(board_id).await?;
+        assert_ne!(new_address, address);
+        assert_eq!(service.find_board(&recipients).await?, None);
+
+        let unknown = format!(
+            "{}@tasks.example.com",
+            InboundToken::generate_random().as_str()
+        );
+        assert_eq!(service.find_board(&[unknown]).await?, None);
+
+        Ok(())
+    }
+}
@@ -1,3 +1,18 @@
+pub mod activity;
+pub mod attachments;
 pub mod auth;
+pub mod calendar;
+pub mod comments;
+pub mod due_dates;
+pub mod events;
+pub mod filters;
+pub mod idempotency;
+pub mod import;
+pub mod inbound;
+pub mod presence;
 pub mod repositories;
+pub mod search;
+pub mod sync;
 pub mod tasks;
+pub mod views;
+pub mod webhooks;
@@ -0,0 +1,201 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use tokio::sync::broadcast;
+
+use crate::model::{BoardId, TaskId, UserId};
+
+pub type ConnectionId = u64;
+
+/// A user viewing a board.
+#[derive(Debug, Clone)]
+pub struct Viewer {
+    pub user_id: UserId,
+    pub username: String,
+    /// The task the user is currently editing, if any.
+    pub editing_task_id: Option<TaskId>,
+}
+
+/// Keeps track of the users viewing each board.
+///
+/// A user may view the same board from several connections, so viewers are tracked
+/// per connection. Every change of the presence of a board is announced to the subscribers.
+pub struct PresenceTracker {
+    next_connection_id: AtomicU64,
+    boards: Mutex<HashMap<BoardId, HashMap<ConnectionId, Viewer>>>,
+    sender: broadcast::Sender<BoardId>,
+}
+
+impl PresenceTracker {
+    const CHANNEL_CAPACITY: usize = 256;
+
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(Self::CHANNEL_CAPACITY);
+
+        Self {
+            next_connection_id: AtomicU64::new(1),
+            boards: Mutex::new(HashMap::new()),
+            sender,
+        }
+    }
+
+    /// Registers the user as a viewer of the board.
+    /// The user stops being a viewer when the returned handle is dropped.
+    pub fn join(
+        self: &Arc<Self>,
+        board_id: BoardId,
+        user_id: UserId,
+        username: String,
+    ) -> PresenceHandle {
+        let connection_id = self.next_connection_id.fetch_add(1, Ordering::Relaxed);
+
+        self.boards
+            .lock()
+            .unwrap()
+            .entry(board_id)
+            .or_default()
+            .insert(
+                connection_id,
+                Viewer {
+                    user_id,
+                    username,
+                    editing_task_id: None,
+                },
+            );
+
+        self.notify(board_id);
+
+        PresenceHandle {
+            tracker: self.clone(),
+            board_id,
+            connection_id,
+        }
+    }
+
+    /// Returns the viewers of the board ordered by connection.
+    pub fn viewers(&self, board_id: BoardId) -> Vec<Viewer> {
+        let boards = self.boards.lock().unwrap();
+
+        let Some(connections) = boards.get(&board_id) else {
+            return Vec::new();
+        };
+
+        let mut viewers: Vec<(ConnectionId, Viewer)> = connections
+            .iter()
+            .map(|(&id, viewer)| (id, viewer.clone()))
+            .collect();
+        viewers.sort_by_key(|(id, _)| *id);
+
+        viewers.into_iter().map(|(_, viewer)| viewer).collect()
+    }
+
+    /// Subscribes to the presence changes. The receiver gets IDs of the boards whose presence has changed.
+    pub fn subscribe(&self) -> broadcast::Receiver<BoardId> {
+        self.sender.subscribe()
+    }
+
+    fn set_editing(&self, board_id: BoardId, connection_id: ConnectionId, task_id: Option<TaskId>) {
+        let changed = {
+            let mut boards = self.boards.lock().unwrap();
+
+            match boards
+                .get_mut(&board_id)
+                .and_then(|connections| connections.get_mut(&connection_id))
+            {
+                Some(viewer) if viewer.editing_task_id != task_id => {
+                    viewer.editing_task_id = task_id;
+                    true
+                }
+                _ => false,
+            }
+        };
+
+        if changed {
+            self.notify(board_id);
+        }
+    }
+
+    fn leave(&self, board_id: BoardId, connection_id: ConnectionId) {
+        {
+            let mut boards = self.boards.lock().unwrap();
+
+            if let Some(connections) = boards.get_mut(&board_id) {
+                connections.remove(&connection_id);
+
+                if connections.is_empty() {
+                    boards.remove(&board_id);
+                }
+            }
+        }
+
+        self.notify(board_id);
+    }
+
+    fn notify(&self, board_id: BoardId) {
+        // Sending fails only if there are no subscribers, which is fine.
+        let _ = self.sender.send(board_id);
+    }
+}
+
+/// Presence of a user on a board through a single connection.
+pub struct PresenceHandle {
+    tracker: Arc<PresenceTracker>,
+    board_id: BoardId,
+    connection_id: ConnectionId,
+}
+
+impl PresenceHandle {
+    /// Sets the task the user is editing, or `None` if the user is not editing any task.
+    pub fn set_editing(&self, task_id: Option<TaskId>) {
+        self.tracker
+            .set_editing(self.board_id, self.connection_id, task_id);
+    }
+}
+
+impl Drop for PresenceHandle {
+    fn drop(&mut self) {
+        self.tracker.leave(self.board_id, self.connection_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::model::{BoardId, UserId};
+
+    use super::PresenceTracker;
+
+    const BOARD_ID: BoardId = BoardId::from_raw(1);
+    const USER_ID: UserId = UserId::from_raw(1);
+
+    #[test]
+    fn viewers_join_edit_and_leave() {
+        let tracker = Arc::new(PresenceTracker::new());
+        let mut changes = tracker.subscribe();
+
+        let first = tracker.join(BOARD_ID, USER_ID, "user123".to_string());
+        let second = tracker.join(BOARD_ID, USER_ID, "user123".to_string());
+        assert_eq!(tracker.viewers(BOARD_ID).len(), 2);
+
+        second.set_editing(Some("task".to_string()));
+        let viewers = tracker.viewers(BOARD_ID);
+        assert_eq!(viewers[0].editing_task_id, None);
+        assert_eq!(viewers[1].editing_task_id.as_deref(), Some("task"));
+
+        drop(second);
+        drop(first);
+        assert!(tracker.viewers(BOARD_ID).is_empty());
+
+        // Two joins, one edit and two leaves.
+        for _ in 0..5 {
+            assert_eq!(changes.try_recv().unwrap(), BOARD_ID);
+        }
+        assert!(changes.try_recv().is_err());
+    }
+}
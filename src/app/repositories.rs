@@ -1,8 +1,32 @@
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+
 use crate::model::{
-    tasks::{TaskCategoryDescription, TaskDescription},
-    SessionToken, TaskId, UserId,
+    activity::{ActivityEntry, ActivityId, ActivityRecord},
+    attachments::AttachmentDescription,
+    bulk::{BulkChange, BulkFailure, BulkOperation},
+    calendar::CalendarToken,
+    checklists::{ChecklistItem, ChecklistItemId, ChecklistItemPatch},
+    comments::{CommentDescription, CommentId, CommentRevision},
+    filters::TaskFilter,
+    idempotency::{IdempotencyRecord, StoredResponse},
+    imports::{BoardImport, ImportedItems},
+    inbound::InboundToken,
+    labels::{LabelData, LabelDescription},
+    lifecycle::Lifecycle,
+    search::SearchHit,
+    sync::{SyncChange, SyncSeq},
+    tasks::{TaskCategoryDescription, TaskCursor, TaskData, TaskDescription, TaskPatch, Version},
+    views::{ViewData, ViewDescription, ViewId},
+    webhooks::{
+        DeliveryId, WebhookData, WebhookDelivery, WebhookDescription, WebhookEvent, WebhookEventId,
+        WebhookId,
+    },
+    BoardId, LabelId, SessionToken, TaskCategoryId, TaskId, UserId,
 };
 
+use super::events::BoardEventKind;
+
 #[async_trait]
 pub trait SessionsRepository: Send + Sync {
     async fn get_authorized_user_id(&self, token: &SessionToken) -> anyhow::Result<Option<UserId>>;
@@ -22,39 +46,543 @@ pub trait UsersRepositry: Send + Sync {
         &self,
         username: &str,
     ) -> anyhow::Result<Option<(UserId, String)>>;
+
+    /// Returns the IANA name of the timezone configured by the user.
+    async fn get_timezone(&self, user_id: UserId) -> anyhow::Result<Option<String>>;
+
+    async fn set_timezone(&self, user_id: UserId, timezone: &str) -> anyhow::Result<()>;
+
+    async fn get_calendar_token(&self, user_id: UserId) -> anyhow::Result<Option<CalendarToken>>;
+
+    /// Replaces the token of the calendar feed of the user, or revokes the feed if `token` is `None`.
+    async fn set_calendar_token(
+        &self,
+        user_id: UserId,
+        token: Option<&CalendarToken>,
+    ) -> anyhow::Result<()>;
+
+    /// Returns the user whose calendar feed has the token.
+    async fn find_user_by_calendar_token(
+        &self,
+        token: &CalendarToken,
+    ) -> anyhow::Result<Option<UserId>>;
+
+    /// Sets the inbound email token of the user unless one is set already.
+    /// Returns the token in effect.
+    async fn init_inbound_token(
+        &self,
+        user_id: UserId,
+        token: &InboundToken,
+    ) -> anyhow::Result<InboundToken>;
+
+    /// Replaces the inbound email token of the user.
+    async fn set_inbound_token(&self, user_id: UserId, token: &InboundToken) -> anyhow::Result<()>;
+
+    async fn find_user_by_inbound_token(
+        &self,
+        token: &InboundToken,
+    ) -> anyhow::Result<Option<UserId>>;
 }
 
+/// Tasks, categories and labels of the users.
+///
+/// The methods that change tasks or categories append them to the change log of the user
+/// (see [`SyncRepository`]), record the given activity and, if the board of the user has webhooks,
+/// record the events to send to them (see [`WebhooksRepository::claim_events`])
+/// in the same transaction as the change.
 #[async_trait]
 pub trait TasksRepository: Send + Sync {
+    /// Returns the tasks of the user, except the trashed ones.
     async fn fetch_tasks(&self, user_id: UserId) -> anyhow::Result<Vec<TaskDescription>>;
 
-    async fn create_task(
+    /// Returns the tasks of the user, except the trashed ones, that match the filter.
+    /// Relative due dates of the filter are counted from `now`.
+    async fn fetch_filtered_tasks(
+        &self,
+        user_id: UserId,
+        filter: &TaskFilter,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<TaskDescription>>;
+
+    /// Returns at most `limit` tasks of the category with their positions, in the order they have
+    /// been created like in [`Self::fetch_tasks`], starting after the position `after`.
+    /// Trashed tasks are skipped, and so are the archived ones unless `include_archived` is set.
+    async fn fetch_category_tasks(
         &self,
         user_id: UserId,
-        label: &str,
-        description: &str,
         category_id: &str,
+        after: Option<&TaskCursor>,
+        limit: i64,
+        include_archived: bool,
+    ) -> anyhow::Result<Vec<(TaskCursor, TaskDescription)>>;
+
+    /// Returns the number of tasks in each category that has any, counted like in [`Self::fetch_category_tasks`].
+    async fn count_category_tasks(
+        &self,
+        user_id: UserId,
+        include_archived: bool,
+    ) -> anyhow::Result<Vec<(TaskCategoryId, i64)>>;
+
+    /// Returns the task, or `None` if there is no such task or it is trashed.
+    async fn fetch_task(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Option<TaskDescription>>;
+
+    /// Returns the active tasks that are due in `[from, to)` ordered by the due date.
+    /// If `from` is `None`, the range is unbounded from below.
+    async fn fetch_tasks_due(
+        &self,
+        user_id: UserId,
+        from: Option<DateTime<Utc>>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<TaskDescription>>;
+
+    async fn create_task(
+        &self,
+        user_id: UserId,
+        task: &TaskData,
+        activity: &ActivityRecord,
     ) -> anyhow::Result<TaskId>;
 
-    async fn modify_task(
+    /// Writes the changed fields of the task if its version is `expected_version`
+    /// or if `expected_version` is `None`, incrementing the version of the task.
+    /// Returns the new state of the task, or `None` if there is no such task or its version does not match.
+    async fn update_task(
         &self,
         user_id: UserId,
         task_id: &str,
-        label: &str,
-        description: &str,
-        category_id: &str,
-    ) -> anyhow::Result<()>;
+        patch: &TaskPatch,
+        expected_version: Option<Version>,
+        activity: &ActivityRecord,
+    ) -> anyhow::Result<Option<TaskDescription>>;
 
+    /// Permanently deletes the task. The change log is left as is, since the task is already in the trash.
     async fn delete_task(&self, user_id: UserId, task_id: &str) -> anyhow::Result<()>;
 
+    /// Moves the task to the board, the archive or the trash, incrementing its version.
+    /// Returns the new state of the task, or `None` if there is no such task.
+    async fn set_task_lifecycle(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        lifecycle: Lifecycle,
+        activity: &ActivityRecord,
+    ) -> anyhow::Result<Option<TaskDescription>>;
+
+    /// Applies the operations in order, all or none of them, recording their activity on the board.
+    /// Returns the state of the task before and after each operation, or the first operation that cannot be applied.
+    async fn apply_bulk_operations(
+        &self,
+        user_id: UserId,
+        board_id: BoardId,
+        operations: &[BulkOperation],
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Result<Vec<BulkChange>, BulkFailure>>;
+
+    async fn fetch_trashed_tasks(&self, user_id: UserId) -> anyhow::Result<Vec<TaskDescription>>;
+
+    /// Returns the tasks of all the users trashed before `deleted_before`,
+    /// including the tasks of the categories trashed before it.
+    async fn fetch_expired_tasks(
+        &self,
+        deleted_before: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<(UserId, TaskId)>>;
+
+    /// Returns the categories of the user, except the trashed ones,
+    /// in the order they have been created.
     async fn fetch_categories(
         &self,
         user_id: UserId,
     ) -> anyhow::Result<Vec<TaskCategoryDescription>>;
 
+    /// Changes the label of the category, incrementing its version.
+    /// Returns the new state of the category, or `None` if there is no such category or it is trashed.
+    async fn rename_category(
+        &self,
+        user_id: UserId,
+        category_id: &str,
+        label: &str,
+    ) -> anyhow::Result<Option<TaskCategoryDescription>>;
+
+    /// Moves the category to the board, the archive or the trash, incrementing its version.
+    /// Trashing the category trashes its tasks at the same time, and restoring it from the trash
+    /// restores the tasks trashed with it. Returns `None` if there is no such category.
+    async fn set_category_lifecycle(
+        &self,
+        user_id: UserId,
+        category_id: &str,
+        lifecycle: Lifecycle,
+    ) -> anyhow::Result<Option<TaskCategoryDescription>>;
+
+    async fn fetch_trashed_categories(
+        &self,
+        user_id: UserId,
+    ) -> anyhow::Result<Vec<TaskCategoryDescription>>;
+
+    /// Permanently deletes the categories trashed before `deleted_before`, which must have no tasks left.
+    /// Returns the number of deleted categories.
+    async fn delete_expired_categories(&self, deleted_before: DateTime<Utc>)
+        -> anyhow::Result<u64>;
+
     async fn add_categories(
         &self,
         user_id: UserId,
         labels: &[&str],
     ) -> anyhow::Result<Vec<TaskCategoryDescription>>;
+
+    /// Creates the categories, the labels and the tasks of the import in a single transaction,
+    /// recording the activity for each of the tasks.
+    /// The existing categories and labels it refers to must belong to the user.
+    async fn import_board(
+        &self,
+        user_id: UserId,
+        import: &BoardImport,
+        activity: &ActivityRecord,
+    ) -> anyhow::Result<ImportedItems>;
+
+    async fn fetch_labels(&self, user_id: UserId) -> anyhow::Result<Vec<LabelDescription>>;
+
+    async fn fetch_label(
+        &self,
+        user_id: UserId,
+        label_id: &str,
+    ) -> anyhow::Result<Option<LabelDescription>>;
+
+    async fn create_label(&self, user_id: UserId, label: &LabelData) -> anyhow::Result<LabelId>;
+
+    /// Returns false if there is no such label.
+    async fn update_label(
+        &self,
+        user_id: UserId,
+        label_id: &str,
+        label: &LabelData,
+    ) -> anyhow::Result<bool>;
+
+    /// Deletes the label, unassigning it from the tasks and incrementing their versions.
+    /// Returns false if there is no such label.
+    async fn delete_label(&self, user_id: UserId, label_id: &str) -> anyhow::Result<bool>;
+
+    /// Assigns the label to the task or unassigns it, incrementing the version of the task
+    /// if the assignment has changed. The label must exist.
+    /// Returns the new state of the task, or `None` if there is no such task.
+    async fn set_label_assigned(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        label_id: &str,
+        assigned: bool,
+    ) -> anyhow::Result<Option<TaskDescription>>;
+
+    /// Returns the checklist of the task ordered by position.
+    async fn fetch_checklist(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Vec<ChecklistItem>>;
+
+    /// Appends the item to the checklist of the task, incrementing the version of the task.
+    async fn add_checklist_item(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        text: &str,
+    ) -> anyhow::Result<ChecklistItemId>;
+
+    /// Changes the item, incrementing the version of the task. Returns false if there is no such item.
+    async fn update_checklist_item(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        item_id: &str,
+        patch: &ChecklistItemPatch,
+    ) -> anyhow::Result<bool>;
+
+    /// Deletes the item, shifting the following items and incrementing the version of the task.
+    /// Returns false if there is no such item.
+    async fn delete_checklist_item(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        item_id: &str,
+    ) -> anyhow::Result<bool>;
+}
+
+/// Comments of tasks. Task IDs are unique across users, so the tasks are not scoped by user here.
+#[async_trait]
+pub trait CommentsRepository: Send + Sync {
+    /// Returns the comments of the task, including the deleted ones, ordered by creation time.
+    async fn fetch_comments(&self, task_id: &str) -> anyhow::Result<Vec<CommentDescription>>;
+
+    async fn fetch_comment(
+        &self,
+        task_id: &str,
+        comment_id: &str,
+    ) -> anyhow::Result<Option<CommentDescription>>;
+
+    async fn create_comment(
+        &self,
+        task_id: &str,
+        author_id: UserId,
+        text: &str,
+        created_at: DateTime<Utc>,
+    ) -> anyhow::Result<CommentId>;
+
+    /// Replaces the text of the comment, keeping the previous text as a revision.
+    /// Returns false if there is no such comment.
+    async fn update_comment(
+        &self,
+        task_id: &str,
+        comment_id: &str,
+        text: &str,
+        edited_at: DateTime<Utc>,
+    ) -> anyhow::Result<bool>;
+
+    /// Marks the comment as deleted. Returns false if there is no such comment.
+    async fn delete_comment(
+        &self,
+        task_id: &str,
+        comment_id: &str,
+        deleted_at: DateTime<Utc>,
+    ) -> anyhow::Result<bool>;
+
+    /// Returns the previous revisions of the comment, oldest first.
+    async fn fetch_revisions(&self, comment_id: &str) -> anyhow::Result<Vec<CommentRevision>>;
+}
+
+/// Full-text search over the labels and descriptions of tasks and the texts of their comments.
+#[async_trait]
+pub trait SearchRepository: Send + Sync {
+    /// Returns at most `limit` tasks of the user, except the trashed ones, that contain all the words
+    /// of the query in their label and description or in one of their comments, most relevant first.
+    async fn search_tasks(
+        &self,
+        user_id: UserId,
+        query: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<SearchHit>>;
+}
+
+/// Saved views of boards.
+#[async_trait]
+pub trait ViewsRepository: Send + Sync {
+    /// Returns the views of the board of all the users, ordered by name.
+    async fn fetch_views(&self, board_id: BoardId) -> anyhow::Result<Vec<ViewDescription>>;
+
+    async fn fetch_view(&self, view_id: &str) -> anyhow::Result<Option<ViewDescription>>;
+
+    async fn create_view(
+        &self,
+        board_id: BoardId,
+        owner_id: UserId,
+        data: &ViewData,
+    ) -> anyhow::Result<ViewId>;
+
+    /// Returns false if there is no such view.
+    async fn update_view(&self, view_id: &str, data: &ViewData) -> anyhow::Result<bool>;
+
+    /// Returns false if there is no such view.
+    async fn delete_view(&self, view_id: &str) -> anyhow::Result<bool>;
+}
+
+/// Idempotency keys of the requests with the responses to replay on retries.
+#[async_trait]
+pub trait IdempotencyRepository: Send + Sync {
+    /// Saves the record, unless the user already has a record with the key created at or after `expired_before`,
+    /// or, while its request is being handled, at or after `abandoned_before`. Returns the existing record in that case.
+    /// Expired and abandoned records are replaced.
+    async fn reserve_key(
+        &self,
+        record: &IdempotencyRecord,
+        expired_before: DateTime<Utc>,
+        abandoned_before: DateTime<Utc>,
+    ) -> anyhow::Result<Option<IdempotencyRecord>>;
+
+    /// Attaches the response to the record of the key.
+    async fn complete_key(
+        &self,
+        user_id: Option<UserId>,
+        key: &str,
+        response: &StoredResponse,
+    ) -> anyhow::Result<()>;
+
+    /// Deletes the record of the key, so that the request can be made again.
+    async fn release_key(&self, user_id: Option<UserId>, key: &str) -> anyhow::Result<()>;
+
+    /// Returns the number of deleted records.
+    async fn delete_expired_keys(&self, expired_before: DateTime<Utc>) -> anyhow::Result<u64>;
+}
+
+/// Webhooks of the boards and the queue of their deliveries.
+#[async_trait]
+pub trait WebhooksRepository: Send + Sync {
+    async fn fetch_webhooks(&self, board_id: BoardId) -> anyhow::Result<Vec<WebhookDescription>>;
+
+    async fn fetch_webhook(&self, webhook_id: &str) -> anyhow::Result<Option<WebhookDescription>>;
+
+    async fn create_webhook(
+        &self,
+        board_id: BoardId,
+        data: &WebhookData,
+    ) -> anyhow::Result<WebhookId>;
+
+    /// Deletes the webhook with its deliveries. Returns false if there is no such webhook.
+    async fn delete_webhook(&self, webhook_id: &str) -> anyhow::Result<bool>;
+
+    /// Queues a pending delivery of the event, to be attempted at `now`.
+    async fn enqueue_delivery(
+        &self,
+        webhook_id: &str,
+        event_type: &str,
+        payload: &str,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<DeliveryId>;
+
+    /// Returns at most `limit` pending deliveries due at `now`, oldest first, and postpones
+    /// their next attempt to `lease_until`, so that they are not claimed twice while being sent.
+    async fn claim_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<WebhookDelivery>>;
+
+    /// Saves the status and the outcome of the last attempt of the delivery.
+    async fn update_delivery(&self, delivery: &WebhookDelivery) -> anyhow::Result<()>;
+
+    /// Returns at most `limit` deliveries of the webhook, newest first.
+    async fn fetch_deliveries(
+        &self,
+        webhook_id: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<WebhookDelivery>>;
+
+    /// Deletes the delivered and dead-lettered deliveries created before `created_before`.
+    /// Returns the number of deleted deliveries.
+    async fn delete_finished_deliveries(
+        &self,
+        created_before: DateTime<Utc>,
+    ) -> anyhow::Result<u64>;
+
+    /// Returns at most `limit` recorded events that are not claimed at `now`, oldest first,
+    /// and claims them until `lease_until`, so that their deliveries are not queued twice.
+    async fn claim_events(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<WebhookEvent>>;
+
+    /// Deletes the event once its deliveries are queued.
+    async fn delete_event(&self, event_id: WebhookEventId) -> anyhow::Result<()>;
+}
+
+/// Sends the HTTP requests of webhook deliveries.
+#[async_trait]
+pub trait WebhookSender: Send + Sync {
+    /// Returns whether the host of the URL resolves only to the addresses that the requests
+    /// may reach. Returns false if the host cannot be resolved.
+    async fn is_allowed_url(&self, url: &Url) -> bool;
+
+    /// Posts the JSON body with the headers and returns the status code of the response.
+    /// Fails if there is no response, e.g. the connection is refused or times out.
+    async fn post(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+        body: &str,
+    ) -> anyhow::Result<u16>;
+}
+
+/// Renders the JSON bodies of the webhook deliveries.
+pub trait WebhookRenderer: Send + Sync {
+    fn render(
+        &self,
+        board_id: BoardId,
+        event: &BoardEventKind,
+        created_at: DateTime<Utc>,
+    ) -> anyhow::Result<String>;
+}
+
+/// Log of the changes of the tasks and the categories of each user, read by offline clients to catch up.
+#[async_trait]
+pub trait SyncRepository: Send + Sync {
+    /// Returns at most `limit` entries of the user after `since`, ordered by sequence number.
+    async fn fetch_changes(
+        &self,
+        user_id: UserId,
+        since: SyncSeq,
+        limit: i64,
+    ) -> anyhow::Result<Vec<SyncChange>>;
+
+    /// Returns the sequence number of the last entry of the user, or 0 if the log is empty.
+    async fn latest_seq(&self, user_id: UserId) -> anyhow::Result<SyncSeq>;
+}
+
+/// History of the changes of tasks. Entries are kept after the task is deleted.
+#[async_trait]
+pub trait ActivityRepository: Send + Sync {
+    /// Returns the history of the task on the board, newest first.
+    async fn fetch_task_activity(
+        &self,
+        board_id: BoardId,
+        task_id: &str,
+    ) -> anyhow::Result<Vec<ActivityEntry>>;
+
+    /// Returns at most `limit` entries of the board older than `before`, newest first.
+    async fn fetch_board_activity(
+        &self,
+        board_id: BoardId,
+        before: Option<ActivityId>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<ActivityEntry>>;
+}
+
+#[async_trait]
+pub trait AttachmentsRepository: Send + Sync {
+    /// Returns the attachments of the task ordered by creation time.
+    async fn fetch_attachments(&self, task_id: &str) -> anyhow::Result<Vec<AttachmentDescription>>;
+
+    async fn fetch_attachment(
+        &self,
+        task_id: &str,
+        attachment_id: &str,
+    ) -> anyhow::Result<Option<AttachmentDescription>>;
+
+    /// Returns the total size of the files of the user in bytes.
+    async fn total_size(&self, owner_id: UserId) -> anyhow::Result<i64>;
+
+    /// Adds the attachment if the total size of the files of its owner stays within `quota` bytes.
+    /// Returns false if the quota would be exceeded.
+    async fn create_attachment(
+        &self,
+        attachment: &AttachmentDescription,
+        quota: i64,
+    ) -> anyhow::Result<bool>;
+
+    /// Returns the deleted attachment, or `None` if there is no such attachment.
+    async fn delete_attachment(
+        &self,
+        task_id: &str,
+        attachment_id: &str,
+    ) -> anyhow::Result<Option<AttachmentDescription>>;
+
+    /// Deletes all the attachments of the task, returning them.
+    async fn delete_task_attachments(
+        &self,
+        task_id: &str,
+    ) -> anyhow::Result<Vec<AttachmentDescription>>;
+}
+
+/// Storage of binary objects, such as attached files.
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    async fn put(&self, key: &str, content_type: &str, data: Vec<u8>) -> anyhow::Result<()>;
+
+    /// Returns the content of the blob, or `None` if there is no such blob.
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>>;
+
+    /// Deletes the blob. Deleting a missing blob is not an error.
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
 }
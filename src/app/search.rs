@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use crate::model::{search::SearchHit, UserId};
+
+use super::repositories::SearchRepository;
+
+/// Maximum number of tasks returned by a search.
+pub const MAX_SEARCH_RESULTS: i64 = 100;
+
+/// Maximum length of a search query in characters.
+const MAX_QUERY_LENGTH: usize = 256;
+
+#[derive(Debug)]
+pub enum SearchError {
+    /// The query is blank or too long.
+    InvalidQuery,
+}
+
+pub struct SearchService {
+    search: Arc<dyn SearchRepository>,
+}
+
+impl SearchService {
+    pub fn new(search: Arc<dyn SearchRepository>) -> Self {
+        Self { search }
+    }
+
+    /// Returns the tasks of the user's board that contain all the words of the query
+    /// in their label, description or comments, most relevant first. Trashed tasks are not searched.
+    pub async fn search(
+        &self,
+        user_id: UserId,
+        query: &str,
+        limit: i64,
+    ) -> anyhow::Result<Result<Vec<SearchHit>, SearchError>> {
+        let query = query.trim();
+
+        if query.is_empty() || query.chars().count() > MAX_QUERY_LENGTH {
+            return Ok(Err(SearchError::InvalidQuery));
+        }
+
+        let limit = limit.clamp(1, MAX_SEARCH_RESULTS);
+
+        Ok(Ok(self.search.search_tasks(user_id, query, limit).await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::Utc;
+
+    use crate::{
+        app::repositories::{CommentsRepository, TasksRepository},
+        model::{
+            activity::{ActivityKind, ActivityRecord},
+            lifecycle::Lifecycle,
+            tasks::TaskData,
+            UserId,
+        },
+        storage::inmemory,
+    };
+
+    use super::{SearchError, SearchService, MAX_SEARCH_RESULTS};
+
+    const USER_ID: UserId = UserId::from_raw(1);
+    const OTHER_USER_ID: UserId = UserId::from_raw(2);
+
+    struct Setup {
+        service: SearchService,
+        tasks: Arc<inmemory::InMemoryTasks>,
+        comments: Arc<inmemory::InMemoryComments>,
+        category_id: String,
+    }
+
+    impl Setup {
+        async fn create_task(
+            &self,
+            user_id: UserId,
+            label: &str,
+            description: &str,
+        ) -> anyhow::Result<String> {
+            self.tasks
+                .create_task(
+                    user_id,
+                    &TaskData {
+                        label: label.to_string(),
+                        description: description.to_string(),
+                        category_id: self.category_id.clone(),
+                        start_at: None,
+                        due_at: None,
+                        priority: Default::default(),
+                    },
+                    &ActivityRecord::now(user_id, user_id, vec![ActivityKind::Created]),
+                )
+                .await
+        }
+    }
+
+    async fn setup_search_service() -> anyhow::Result<Setup> {
+        let tasks = Arc::new(inmemory::InMemoryTasks::new(
+            Arc::new(inmemory::InMemoryActivity::new()),
+            Arc::new(inmemory::InMemorySync::new()),
+            Arc::new(inmemory::InMemoryWebhooks::new()),
+        ));
+        let comments = Arc::new(inmemory::InMemoryComments::new());
+
+        let categories = tasks.add_categories(USER_ID, &["ToDo"]).await?;
+
+        let service = SearchService::new(Arc::new(inmemory::InMemorySearch::new(
+            tasks.clone(),
+            comments.clone(),
+        )));
+
+        Ok(Setup {
+            service,
+            tasks,
+            comments,
+            category_id: categories[0].category_id.clone(),
+        })
+    }
+
+    #[tokio::test]
+    async fn search_ranks_and_highlights_matches() -> anyhow::Result<()> {
+        let setup = setup_search_service().await?;
+
+        let in_description = setup
+            .create_task(USER_ID, "Release", "Update the invoice template")
+            .await?;
+        let in_label = setup
+            .create_task(USER_ID, "Invoice template", "For the new clients")
+            .await?;
+        let in_comment = setup.create_task(USER_ID, "Billing", "").await?;
+        setup
+            .comments
+            .create_comment(
+                &in_comment,
+                USER_ID,
+                "Use the old invoice template",
+                Utc::now(),
+            )
+            .await?;
+        setup
+            .create_task(OTHER_USER_ID, "Invoice template", "Not on this board")
+            .await?;
+        setup
+            .create_task(USER_ID, "Invoice", "Only one of the words")
+            .await?;
+
+        let hits = setup
+            .service
+            .search(USER_ID, "TEMPLATE invoice", MAX_SEARCH_RESULTS)
+            .await?
+            .unwrap();
+
+        let task_ids: Vec<&str> = hits.iter().map(|h| h.task.task_id.as_str()).collect();
+        assert_eq!(task_ids, [&in_label, &in_description, &in_comment]);
+        assert_eq!(
+            hits[0].snippet,
+            "<mark>Invoice</mark> <mark>template</mark> For the new clients"
+        );
+        assert_eq!(
+            hits[2].snippet,
+            "Use the old <mark>invoice</mark> <mark>template</mark>"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn search_skips_trashed_tasks_and_deleted_comments() -> anyhow::Result<()> {
+        let setup = setup_search_service().await?;
+
+        let trashed = setup.create_task(USER_ID, "Quarterly report", "").await?;
+        setup
+            .tasks
+            .set_task_lifecycle(
+                USER_ID,
+                &trashed,
+                Lifecycle::default().trashed(Utc::now()),
+                &ActivityRecord::now(USER_ID, USER_ID, vec![ActivityKind::Deleted]),
+            )
+            .await?;
+
+        let commented = setup.create_task(USER_ID, "Other", "").await?;
+        let comment_id = setup
+            .comments
+            .create_comment(&commented, USER_ID, "quarterly numbers", Utc::now())
+            .await?;
+        setup
+            .comments
+            .delete_comment(&commented, &comment_id, Utc::now())
+            .await?;
+
+        let hits = setup
+            .service
+            .search(USER_ID, "quarterly", MAX_SEARCH_RESULTS)
+            .await?
+            .unwrap();
+        assert!(hits.is_empty());
+
+        let result = setup
+            .service
+            .search(USER_ID, "  ", MAX_SEARCH_RESULTS)
+            .await?;
+        assert!(matches!(result, Err(SearchError::InvalidQuery)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use crate::model::{
+    sync::SyncSeq,
+    tasks::{TaskCategoryDescription, TaskDescription},
+    TaskCategoryId, TaskId,
+};
+
+/// Maximum number of change log entries read by a single sync request.
+pub const MAX_SYNC_CHANGES: i64 = 500;
+
+/// Changes of the board of a user since a cursor of the change log.
+#[derive(Debug, Default)]
+pub struct SyncBatch {
+    /// The batch contains the whole board instead of the changes,
+    /// since the client has no cursor or the cursor is unknown.
+    /// The client has to replace its copy of the board.
+    pub reset: bool,
+    /// Current state of the changed categories, including the archived ones.
+    pub categories: Vec<TaskCategoryDescription>,
+    /// Current state of the changed tasks, including the archived ones.
+    pub tasks: Vec<TaskDescription>,
+    /// Categories that have been moved to the trash or deleted.
+    pub deleted_category_ids: Vec<TaskCategoryId>,
+    /// Tasks that have been moved to the trash or deleted.
+    pub deleted_task_ids: Vec<TaskId>,
+    /// Cursor to pass with the next request.
+    pub cursor: SyncSeq,
+    /// There are more changes after the cursor.
+    pub has_more: bool,
+}
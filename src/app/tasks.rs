@@ -1,58 +1,2359 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+
+use chrono::{TimeDelta, Utc};
+use chrono_tz::Tz;
 
 use crate::model::{
-    tasks::{TaskCategoryDescription, TaskDescription},
-    TaskId, UserId,
+    activity::{task_changes, ActivityEntry, ActivityId, ActivityKind, ActivityRecord},
+    bulk::{BulkAction, BulkFailure, BulkOperation},
+    checklists::{ChecklistItem, ChecklistItemPatch},
+    filters::TaskFilter,
+    labels::{LabelData, LabelDescription},
+    lifecycle::Lifecycle,
+    sync::{SyncEntity, SyncSeq},
+    tasks::{TaskCategoryDescription, TaskCursor, TaskData, TaskDescription, TaskPatch, Version},
+    BoardId, TaskCategoryId, TaskId, UserId,
+};
+
+use super::{
+    activity::{ActivityPage, MAX_ACTIVITY_PAGE_SIZE},
+    attachments::AttachmentsService,
+    due_dates::DuePeriod,
+    events::{BoardEventKind, BoardSubscription, EventBus, EventId, SubscriptionError},
+    import::{ImportError, ImportPlan, ImportReport, ImportSource},
+    inbound::{InboundEmail, InboundError, InboundTask, RejectedAttachment},
+    repositories::{ActivityRepository, SyncRepository, TasksRepository},
+    sync::{SyncBatch, MAX_SYNC_CHANGES},
 };
 
-use super::repositories::TasksRepository;
+#[derive(Debug)]
+pub enum CreateTaskError {
+    /// The category does not exist or is in the trash.
+    CategoryNotFound,
+}
+
+#[derive(Debug)]
+pub enum ModifyTaskError {
+    TaskNotFound,
+    /// The task is moved to a category that does not exist or is in the trash.
+    CategoryNotFound,
+    /// The task has been modified since the expected version. Contains the current state of the task.
+    VersionConflict(Box<TaskDescription>),
+}
+
+#[derive(Debug)]
+pub enum LabelError {
+    /// The name is blank or too long, or the colour is not in `#rrggbb` format.
+    InvalidLabel,
+    LabelNotFound,
+}
+
+#[derive(Debug)]
+pub enum AssignLabelError {
+    TaskNotFound,
+    LabelNotFound,
+}
+
+#[derive(Debug)]
+pub enum ChecklistError {
+    TaskNotFound,
+    ItemNotFound,
+    /// The text of the item is blank.
+    InvalidText,
+}
+
+#[derive(Debug)]
+pub enum RenameCategoryError {
+    CategoryNotFound,
+    /// The label is blank or too long.
+    InvalidLabel,
+}
+
+#[derive(Debug)]
+pub enum LifecycleError {
+    TaskNotFound,
+    CategoryNotFound,
+    /// The task cannot be restored while its category is in the trash.
+    CategoryTrashed,
+}
+
+/// Tasks and categories in the archive or in the trash.
+#[derive(Debug, Default)]
+pub struct StoredItems {
+    pub categories: Vec<TaskCategoryDescription>,
+    pub tasks: Vec<TaskDescription>,
+}
+
+/// Maximum length of the label of a category, in characters.
+pub const MAX_CATEGORY_LABEL_LENGTH: usize = 64;
+
+/// Maximum number of tasks in a page of a column.
+pub const MAX_TASK_PAGE_SIZE: i64 = 200;
+
+/// Tasks of a column, a page at a time.
+#[derive(Debug)]
+pub struct TaskPage {
+    pub tasks: Vec<TaskDescription>,
+    /// Cursor of the next page, or `None` if this is the last page.
+    pub next_cursor: Option<TaskCursor>,
+}
+
+/// Category of a board with the number of its tasks.
+#[derive(Debug)]
+pub struct ColumnSummary {
+    pub category: TaskCategoryDescription,
+    pub task_count: i64,
+}
 
 pub struct TasksService {
     tasks: Arc<dyn TasksRepository>,
+    events: Arc<EventBus>,
+    attachments: Arc<AttachmentsService>,
+    activity: Arc<dyn ActivityRepository>,
+    sync: Arc<dyn SyncRepository>,
 }
 
 impl TasksService {
-    pub fn new(tasks: Arc<dyn TasksRepository>) -> Self {
-        Self { tasks }
+    pub fn new(
+        tasks: Arc<dyn TasksRepository>,
+        events: Arc<EventBus>,
+        attachments: Arc<AttachmentsService>,
+        activity: Arc<dyn ActivityRepository>,
+        sync: Arc<dyn SyncRepository>,
+    ) -> Self {
+        Self {
+            tasks,
+            events,
+            attachments,
+            activity,
+            sync,
+        }
     }
 
-    pub async fn fetch_tasks(&self, user_id: UserId) -> anyhow::Result<Vec<TaskDescription>> {
-        self.tasks.fetch_tasks(user_id).await
+    /// Returns the board the user works with.
+    pub fn user_board(&self, user_id: UserId) -> BoardId {
+        user_id
     }
 
-    pub async fn create_task(
+    /// Returns true if the user has access to the board.
+    pub fn can_access_board(&self, user_id: UserId, board_id: BoardId) -> bool {
+        self.user_board(user_id) == board_id
+    }
+
+    /// Returns the categories and the tasks of the board, including the archived ones if requested.
+    /// If the filter is given, only the tasks that match it are returned.
+    pub async fn fetch_board(
+        &self,
+        user_id: UserId,
+        include_archived: bool,
+        filter: Option<&TaskFilter>,
+    ) -> anyhow::Result<(Vec<TaskCategoryDescription>, Vec<TaskDescription>)> {
+        let mut categories = self.tasks.fetch_categories(user_id).await?;
+        let mut tasks = match filter {
+            Some(filter) => {
+                self.tasks
+                    .fetch_filtered_tasks(user_id, filter, Utc::now())
+                    .await?
+            }
+            None => self.tasks.fetch_tasks(user_id).await?,
+        };
+
+        if !include_archived {
+            categories.retain(|c| c.lifecycle.is_active());
+            tasks.retain(|t| {
+                t.lifecycle.is_active() && categories.iter().any(|c| c.category_id == t.category_id)
+            });
+        }
+
+        Ok((categories, tasks))
+    }
+
+    /// Returns the categories of the board with the number of their tasks, without loading the tasks.
+    pub async fn fetch_board_summary(
+        &self,
+        user_id: UserId,
+        include_archived: bool,
+    ) -> anyhow::Result<Vec<ColumnSummary>> {
+        let mut categories = self.tasks.fetch_categories(user_id).await?;
+        if !include_archived {
+            categories.retain(|c| c.lifecycle.is_active());
+        }
+
+        let counts: HashMap<TaskCategoryId, i64> = HashMap::from_iter(
+            self.tasks
+                .count_category_tasks(user_id, include_archived)
+                .await?,
+        );
+
+        Ok(categories
+            .into_iter()
+            .map(|category| ColumnSummary {
+                task_count: counts.get(&category.category_id).copied().unwrap_or(0),
+                category,
+            })
+            .collect())
+    }
+
+    /// Returns a page of the tasks of the category, in the order of the board, that follows `cursor`.
+    /// Returns `None` if there is no such category, or it is archived and the archived items are not requested.
+    pub async fn fetch_category_tasks(
         &self,
         user_id: UserId,
-        label: &str,
-        description: &str,
         category_id: &str,
-    ) -> anyhow::Result<TaskId> {
-        self.tasks
-            .create_task(user_id, label, description, category_id)
-            .await
+        cursor: Option<&TaskCursor>,
+        limit: i64,
+        include_archived: bool,
+    ) -> anyhow::Result<Option<TaskPage>> {
+        let categories = self.tasks.fetch_categories(user_id).await?;
+
+        if !categories
+            .iter()
+            .any(|c| c.category_id == category_id && (include_archived || c.lifecycle.is_active()))
+        {
+            return Ok(None);
+        }
+
+        let limit = limit.clamp(1, MAX_TASK_PAGE_SIZE);
+
+        // One more task tells whether there is a next page.
+        let mut tasks = self
+            .tasks
+            .fetch_category_tasks(user_id, category_id, cursor, limit + 1, include_archived)
+            .await?;
+
+        let next_cursor = if tasks.len() as i64 > limit {
+            tasks.truncate(limit as usize);
+            tasks.last().map(|(cursor, _)| cursor.clone())
+        } else {
+            None
+        };
+
+        Ok(Some(TaskPage {
+            tasks: tasks.into_iter().map(|(_, task)| task).collect(),
+            next_cursor,
+        }))
+    }
+
+    pub async fn fetch_task(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Option<TaskDescription>> {
+        self.tasks.fetch_task(user_id, task_id).await
+    }
+
+    /// Returns the tasks due in the period, which is relative to the current time in the timezone.
+    pub async fn fetch_tasks_due(
+        &self,
+        user_id: UserId,
+        period: DuePeriod,
+        timezone: Tz,
+    ) -> anyhow::Result<Vec<TaskDescription>> {
+        let (from, to) = period.range(Utc::now(), timezone);
+
+        self.tasks.fetch_tasks_due(user_id, from, to).await
+    }
+
+    pub async fn create_task(
+        &self,
+        user_id: UserId,
+        data: TaskData,
+    ) -> anyhow::Result<Result<(TaskDescription, EventId), CreateTaskError>> {
+        if !self.has_category(user_id, &data.category_id).await? {
+            return Ok(Err(CreateTaskError::CategoryNotFound));
+        }
+
+        let activity = self.activity(user_id, vec![ActivityKind::Created]);
+        let task_id = self.tasks.create_task(user_id, &data, &activity).await?;
+
+        let task = data.into_description(task_id);
+
+        let event_id = self.publish(user_id, BoardEventKind::TaskCreated(task.clone()));
+
+        Ok(Ok((task, event_id)))
     }
 
+    /// Replaces all the data of the task, returning its new state.
+    ///
+    /// If `expected_version` is provided, the task is modified only if it has not been modified
+    /// since that version.
     pub async fn modify_task(
         &self,
         user_id: UserId,
         task_id: &str,
+        data: TaskData,
+        expected_version: Option<Version>,
+    ) -> anyhow::Result<Result<(TaskDescription, EventId), ModifyTaskError>> {
+        self.patch_task(user_id, task_id, &data.into_patch(), expected_version)
+            .await
+    }
+
+    /// Changes only the provided fields of the task, returning its new state.
+    ///
+    /// If `expected_version` is provided, the task is modified only if it has not been modified
+    /// since that version. Otherwise, the changes are applied on top of the concurrent modifications.
+    pub async fn patch_task(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        patch: &TaskPatch,
+        expected_version: Option<Version>,
+    ) -> anyhow::Result<Result<(TaskDescription, EventId), ModifyTaskError>> {
+        const MAX_ATTEMPTS: usize = 5;
+
+        for _ in 0..MAX_ATTEMPTS {
+            let Some(previous) = self.tasks.fetch_task(user_id, task_id).await? else {
+                return Ok(Err(ModifyTaskError::TaskNotFound));
+            };
+
+            if expected_version.is_some_and(|v| v != previous.version) {
+                return Ok(Err(ModifyTaskError::VersionConflict(Box::new(previous))));
+            }
+
+            if let Some(category_id) = &patch.category_id {
+                if *category_id != previous.category_id
+                    && !self.has_category(user_id, category_id).await?
+                {
+                    return Ok(Err(ModifyTaskError::CategoryNotFound));
+                }
+            }
+
+            let mut expected = previous.clone();
+            patch.apply(&mut expected);
+            let activity = self.activity(user_id, task_changes(&previous, &expected));
+
+            // The task is modified only if it is still in the fetched state,
+            // so that the recorded activity and the published event describe the change correctly.
+            // Otherwise, the task has been modified concurrently, so try again.
+            let Some(task) = self
+                .tasks
+                .update_task(user_id, task_id, patch, Some(previous.version), &activity)
+                .await?
+            else {
+                continue;
+            };
+
+            let kind = if previous.category_id != task.category_id {
+                BoardEventKind::TaskMoved {
+                    task: task.clone(),
+                    from_category_id: previous.category_id,
+                }
+            } else {
+                BoardEventKind::TaskUpdated(task.clone())
+            };
+
+            let event_id = self.publish(user_id, kind);
+
+            return Ok(Ok((task, event_id)));
+        }
+
+        Err(anyhow!(
+            "task {} is modified concurrently too often",
+            task_id
+        ))
+    }
+
+    /// Applies the operations to the tasks in order, all or none of them.
+    /// Returns the state of the task after each operation, or the operation that has failed.
+    pub async fn apply_bulk_operations(
+        &self,
+        user_id: UserId,
+        operations: &[BulkOperation],
+    ) -> anyhow::Result<Result<Vec<TaskDescription>, BulkFailure>> {
+        let changes = match self
+            .tasks
+            .apply_bulk_operations(user_id, self.user_board(user_id), operations, Utc::now())
+            .await?
+        {
+            Ok(changes) => changes,
+            Err(failure) => return Ok(Err(failure)),
+        };
+
+        for (operation, change) in operations.iter().zip(&changes) {
+            if !change.is_changed() {
+                continue;
+            }
+
+            let task = &change.task;
+            let kind = match &operation.action {
+                BulkAction::Update(_) if change.previous.category_id != task.category_id => {
+                    BoardEventKind::TaskMoved {
+                        task: task.clone(),
+                        from_category_id: change.previous.category_id.clone(),
+                    }
+                }
+                BulkAction::Archive | BulkAction::Delete => BoardEventKind::TaskDeleted {
+                    task_id: task.task_id.clone(),
+                },
+                _ => BoardEventKind::TaskUpdated(task.clone()),
+            };
+
+            self.publish(user_id, kind);
+        }
+
+        Ok(Ok(changes.into_iter().map(|change| change.task).collect()))
+    }
+
+    /// Moves the task to the trash. It is permanently deleted by [`TasksService::purge_trash`].
+    pub async fn delete_task(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Result<EventId, LifecycleError>> {
+        let Some(task) = self.tasks.fetch_task(user_id, task_id).await? else {
+            return Ok(Err(LifecycleError::TaskNotFound));
+        };
+
+        let lifecycle = task.lifecycle.trashed(Utc::now());
+        let activity = self.activity(user_id, vec![ActivityKind::Deleted]);
+        if self
+            .tasks
+            .set_task_lifecycle(user_id, task_id, lifecycle, &activity)
+            .await?
+            .is_none()
+        {
+            return Ok(Err(LifecycleError::TaskNotFound));
+        }
+
+        Ok(Ok(self.publish(
+            user_id,
+            BoardEventKind::TaskDeleted {
+                task_id: task_id.to_string(),
+            },
+        )))
+    }
+
+    /// Moves the task to the archive, hiding it from the board.
+    pub async fn archive_task(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Result<TaskDescription, LifecycleError>> {
+        let Some(task) = self.tasks.fetch_task(user_id, task_id).await? else {
+            return Ok(Err(LifecycleError::TaskNotFound));
+        };
+
+        if task.lifecycle.is_archived() {
+            return Ok(Ok(task));
+        }
+
+        let lifecycle = task.lifecycle.archived(Utc::now());
+        let activity = self.activity(user_id, vec![ActivityKind::Archived]);
+        let Some(task) = self
+            .tasks
+            .set_task_lifecycle(user_id, task_id, lifecycle, &activity)
+            .await?
+        else {
+            return Ok(Err(LifecycleError::TaskNotFound));
+        };
+
+        self.publish(
+            user_id,
+            BoardEventKind::TaskDeleted {
+                task_id: task_id.to_string(),
+            },
+        );
+
+        Ok(Ok(task))
+    }
+
+    /// Returns the task from the archive or the trash to the board.
+    pub async fn restore_task(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Result<TaskDescription, LifecycleError>> {
+        let task = match self.tasks.fetch_task(user_id, task_id).await? {
+            Some(task) => task,
+            None => {
+                let trashed = self.tasks.fetch_trashed_tasks(user_id).await?;
+                let Some(task) = trashed.into_iter().find(|t| t.task_id == task_id) else {
+                    return Ok(Err(LifecycleError::TaskNotFound));
+                };
+                task
+            }
+        };
+
+        if task.lifecycle.is_active() {
+            return Ok(Ok(task));
+        }
+
+        let categories = self.tasks.fetch_categories(user_id).await?;
+        if !categories.iter().any(|c| c.category_id == task.category_id) {
+            return Ok(Err(LifecycleError::CategoryTrashed));
+        }
+
+        let activity = self.activity(user_id, vec![ActivityKind::Restored]);
+        let Some(task) = self
+            .tasks
+            .set_task_lifecycle(user_id, task_id, Lifecycle::default(), &activity)
+            .await?
+        else {
+            return Ok(Err(LifecycleError::TaskNotFound));
+        };
+
+        self.publish(user_id, BoardEventKind::TaskCreated(task.clone()));
+
+        Ok(Ok(task))
+    }
+
+    /// Changes the label of the category, returning its new state. Archived categories can be renamed too.
+    pub async fn rename_category(
+        &self,
+        user_id: UserId,
+        category_id: &str,
         label: &str,
-        description: &str,
+    ) -> anyhow::Result<Result<TaskCategoryDescription, RenameCategoryError>> {
+        let label = label.trim();
+        if label.is_empty() || label.chars().count() > MAX_CATEGORY_LABEL_LENGTH {
+            return Ok(Err(RenameCategoryError::InvalidLabel));
+        }
+
+        let Some(category) = self
+            .tasks
+            .rename_category(user_id, category_id, label)
+            .await?
+        else {
+            return Ok(Err(RenameCategoryError::CategoryNotFound));
+        };
+
+        self.publish(user_id, BoardEventKind::CategoryUpdated(category.clone()));
+
+        Ok(Ok(category))
+    }
+
+    /// Moves the category to the archive, hiding it from the board together with its tasks.
+    pub async fn archive_category(
+        &self,
+        user_id: UserId,
         category_id: &str,
-    ) -> anyhow::Result<()> {
-        self.tasks
-            .modify_task(user_id, task_id, label, description, category_id)
+    ) -> anyhow::Result<Result<TaskCategoryDescription, LifecycleError>> {
+        self.change_category_lifecycle(user_id, category_id, |lifecycle| {
+            lifecycle.archived(Utc::now())
+        })
+        .await
+    }
+
+    /// Moves the category to the trash together with its tasks.
+    pub async fn delete_category(
+        &self,
+        user_id: UserId,
+        category_id: &str,
+    ) -> anyhow::Result<Result<TaskCategoryDescription, LifecycleError>> {
+        self.change_category_lifecycle(user_id, category_id, |lifecycle| {
+            lifecycle.trashed(Utc::now())
+        })
+        .await
+    }
+
+    /// Returns the category from the archive or the trash to the board.
+    /// The tasks trashed together with the category are restored too.
+    pub async fn restore_category(
+        &self,
+        user_id: UserId,
+        category_id: &str,
+    ) -> anyhow::Result<Result<TaskCategoryDescription, LifecycleError>> {
+        self.change_category_lifecycle(user_id, category_id, |_| Lifecycle::default())
             .await
     }
 
-    pub async fn delete_task(&self, user_id: UserId, task_id: &str) -> anyhow::Result<()> {
-        self.tasks.delete_task(user_id, task_id).await
+    async fn change_category_lifecycle(
+        &self,
+        user_id: UserId,
+        category_id: &str,
+        change: impl FnOnce(Lifecycle) -> Lifecycle,
+    ) -> anyhow::Result<Result<TaskCategoryDescription, LifecycleError>> {
+        let mut categories = self.tasks.fetch_categories(user_id).await?;
+        categories.extend(self.tasks.fetch_trashed_categories(user_id).await?);
+
+        let Some(category) = categories
+            .into_iter()
+            .find(|c| c.category_id == category_id)
+        else {
+            return Ok(Err(LifecycleError::CategoryNotFound));
+        };
+
+        let lifecycle = change(category.lifecycle);
+        if lifecycle.is_active() == category.lifecycle.is_active()
+            && lifecycle.is_trashed() == category.lifecycle.is_trashed()
+        {
+            return Ok(Ok(category));
+        }
+
+        let Some(category) = self
+            .tasks
+            .set_category_lifecycle(user_id, category_id, lifecycle)
+            .await?
+        else {
+            return Ok(Err(LifecycleError::CategoryNotFound));
+        };
+
+        let kind = if lifecycle.is_active() {
+            BoardEventKind::CategoryRestored(category.clone())
+        } else {
+            BoardEventKind::CategoryRemoved {
+                category_id: category_id.to_string(),
+            }
+        };
+
+        self.publish(user_id, kind);
+
+        Ok(Ok(category))
+    }
+
+    /// Returns the archived categories and the archived tasks of the user.
+    pub async fn fetch_archive(&self, user_id: UserId) -> anyhow::Result<StoredItems> {
+        let (mut categories, mut tasks) = self.fetch_board(user_id, true, None).await?;
+
+        categories.retain(|c| c.lifecycle.is_archived());
+        tasks.retain(|t| t.lifecycle.is_archived());
+
+        Ok(StoredItems { categories, tasks })
+    }
+
+    /// Returns the trashed categories and the trashed tasks of the user.
+    pub async fn fetch_trash(&self, user_id: UserId) -> anyhow::Result<StoredItems> {
+        Ok(StoredItems {
+            categories: self.tasks.fetch_trashed_categories(user_id).await?,
+            tasks: self.tasks.fetch_trashed_tasks(user_id).await?,
+        })
+    }
+
+    /// Permanently deletes the tasks and the categories that have been in the trash
+    /// for longer than `retention`, with the attached files. Returns the number of deleted tasks.
+    pub async fn purge_trash(&self, retention: TimeDelta) -> anyhow::Result<usize> {
+        let deleted_before = Utc::now() - retention;
+
+        let expired = self.tasks.fetch_expired_tasks(deleted_before).await?;
+
+        for (user_id, task_id) in &expired {
+            self.attachments.delete_task_attachments(task_id).await?;
+            self.tasks.delete_task(*user_id, task_id).await?;
+        }
+
+        self.tasks.delete_expired_categories(deleted_before).await?;
+
+        Ok(expired.len())
+    }
+
+    /// Returns the label palette of the user's board.
+    pub async fn fetch_labels(&self, user_id: UserId) -> anyhow::Result<Vec<LabelDescription>> {
+        self.tasks.fetch_labels(user_id).await
+    }
+
+    /// Creates a task from the message in the oldest active category of the board, which is
+    /// its first column, attaching the files of the message to it. Attachments that are too large
+    /// or of an unsupported type are skipped and reported.
+    pub async fn create_task_from_email(
+        &self,
+        board_id: BoardId,
+        email: InboundEmail,
+    ) -> anyhow::Result<Result<InboundTask, InboundError>> {
+        let user_id = board_id;
+
+        // The categories are listed in the order they have been created.
+        let categories = self.tasks.fetch_categories(user_id).await?;
+        let Some(category) = categories.iter().find(|c| c.lifecycle.is_active()) else {
+            return Ok(Err(InboundError::NoCategory));
+        };
+
+        let data = TaskData {
+            label: email.label().to_string(),
+            description: email.text,
+            category_id: category.category_id.clone(),
+            start_at: None,
+            due_at: None,
+            priority: Default::default(),
+        };
+        let Ok((task, _)) = self.create_task(user_id, data).await? else {
+            return Ok(Err(InboundError::NoCategory));
+        };
+
+        let mut attachments = Vec::new();
+        let mut rejected_attachments = Vec::new();
+
+        for upload in email.attachments {
+            let file_name = upload.file_name.clone();
+
+            match self
+                .attachments
+                .upload(user_id, &task.task_id, upload)
+                .await?
+            {
+                Ok(attachment) => attachments.push(attachment),
+                Err(error) => rejected_attachments.push(RejectedAttachment { file_name, error }),
+            }
+        }
+
+        Ok(Ok(InboundTask {
+            task,
+            attachments,
+            rejected_attachments,
+        }))
+    }
+
+    /// Imports the cards of the source as tasks, creating the missing categories and labels,
+    /// and reports what has been created. In a dry run nothing is created.
+    pub async fn import_board(
+        &self,
+        user_id: UserId,
+        source: ImportSource,
+        dry_run: bool,
+    ) -> anyhow::Result<Result<ImportReport, ImportError>> {
+        let categories = self.tasks.fetch_categories(user_id).await?;
+        let labels = self.tasks.fetch_labels(user_id).await?;
+
+        let plan = match ImportPlan::new(source, &categories, &labels) {
+            Ok(plan) => plan,
+            Err(error) => return Ok(Err(error)),
+        };
+
+        if dry_run {
+            return Ok(Ok(plan.into_report(None)));
+        }
+
+        let activity = self.activity(user_id, vec![ActivityKind::Created]);
+        let items = self
+            .tasks
+            .import_board(user_id, &plan.import, &activity)
+            .await?;
+
+        for category in &items.categories {
+            self.publish(user_id, BoardEventKind::CategoryCreated(category.clone()));
+        }
+
+        for (data, label_id) in plan.import.labels.iter().zip(&items.label_ids) {
+            let label = data.clone().into_description(label_id.clone());
+            self.publish(user_id, BoardEventKind::LabelCreated(label));
+        }
+
+        self.publish(
+            user_id,
+            BoardEventKind::BoardImported {
+                category_ids: items
+                    .categories
+                    .iter()
+                    .map(|c| c.category_id.clone())
+                    .collect(),
+                task_count: items.task_ids.len(),
+            },
+        );
+
+        Ok(Ok(plan.into_report(Some(&items))))
+    }
+
+    pub async fn create_label(
+        &self,
+        user_id: UserId,
+        data: LabelData,
+    ) -> anyhow::Result<Result<LabelDescription, LabelError>> {
+        if !data.is_valid() {
+            return Ok(Err(LabelError::InvalidLabel));
+        }
+
+        let label_id = self.tasks.create_label(user_id, &data).await?;
+        let label = data.into_description(label_id);
+
+        self.publish(user_id, BoardEventKind::LabelCreated(label.clone()));
+
+        Ok(Ok(label))
+    }
+
+    pub async fn modify_label(
+        &self,
+        user_id: UserId,
+        label_id: &str,
+        data: LabelData,
+    ) -> anyhow::Result<Result<LabelDescription, LabelError>> {
+        if !data.is_valid() {
+            return Ok(Err(LabelError::InvalidLabel));
+        }
+
+        if !self.tasks.update_label(user_id, label_id, &data).await? {
+            return Ok(Err(LabelError::LabelNotFound));
+        }
+
+        let label = data.into_description(label_id.to_string());
+
+        self.publish(user_id, BoardEventKind::LabelUpdated(label.clone()));
+
+        Ok(Ok(label))
+    }
+
+    /// Deletes the label from the palette, unassigning it from the tasks.
+    pub async fn delete_label(
+        &self,
+        user_id: UserId,
+        label_id: &str,
+    ) -> anyhow::Result<Result<(), LabelError>> {
+        if !self.tasks.delete_label(user_id, label_id).await? {
+            return Ok(Err(LabelError::LabelNotFound));
+        }
+
+        self.publish(
+            user_id,
+            BoardEventKind::LabelDeleted {
+                label_id: label_id.to_string(),
+            },
+        );
+
+        Ok(Ok(()))
+    }
+
+    /// Assigns the label to the task or unassigns it, returning the new state of the task.
+    /// Assigning an assigned label or unassigning an unassigned one leaves the task unchanged.
+    pub async fn set_label_assigned(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        label_id: &str,
+        assigned: bool,
+    ) -> anyhow::Result<Result<TaskDescription, AssignLabelError>> {
+        let Some(task) = self.tasks.fetch_task(user_id, task_id).await? else {
+            return Ok(Err(AssignLabelError::TaskNotFound));
+        };
+
+        if self.tasks.fetch_label(user_id, label_id).await?.is_none() {
+            return Ok(Err(AssignLabelError::LabelNotFound));
+        }
+
+        if task.label_ids.iter().any(|id| id == label_id) == assigned {
+            return Ok(Ok(task));
+        }
+
+        let Some(task) = self
+            .tasks
+            .set_label_assigned(user_id, task_id, label_id, assigned)
+            .await?
+        else {
+            return Ok(Err(AssignLabelError::TaskNotFound));
+        };
+
+        self.publish(user_id, BoardEventKind::TaskUpdated(task.clone()));
+
+        Ok(Ok(task))
+    }
+
+    /// Returns the checklist of the task ordered by position, or `None` if there is no such task.
+    pub async fn fetch_checklist(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Option<Vec<ChecklistItem>>> {
+        if self.tasks.fetch_task(user_id, task_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(self.tasks.fetch_checklist(user_id, task_id).await?))
+    }
+
+    /// Appends the item to the checklist of the task, returning the new checklist.
+    pub async fn add_checklist_item(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        text: &str,
+    ) -> anyhow::Result<Result<Vec<ChecklistItem>, ChecklistError>> {
+        if text.trim().is_empty() {
+            return Ok(Err(ChecklistError::InvalidText));
+        }
+
+        if self.tasks.fetch_task(user_id, task_id).await?.is_none() {
+            return Ok(Err(ChecklistError::TaskNotFound));
+        }
+
+        self.tasks
+            .add_checklist_item(user_id, task_id, text)
+            .await?;
+
+        self.checklist_changed(user_id, task_id).await
+    }
+
+    /// Changes the text, the done flag or the position of the item, returning the new checklist.
+    pub async fn modify_checklist_item(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        item_id: &str,
+        patch: &ChecklistItemPatch,
+    ) -> anyhow::Result<Result<Vec<ChecklistItem>, ChecklistError>> {
+        if patch
+            .text
+            .as_ref()
+            .is_some_and(|text| text.trim().is_empty())
+        {
+            return Ok(Err(ChecklistError::InvalidText));
+        }
+
+        if self.tasks.fetch_task(user_id, task_id).await?.is_none() {
+            return Ok(Err(ChecklistError::TaskNotFound));
+        }
+
+        if !self
+            .tasks
+            .update_checklist_item(user_id, task_id, item_id, patch)
+            .await?
+        {
+            return Ok(Err(ChecklistError::ItemNotFound));
+        }
+
+        self.checklist_changed(user_id, task_id).await
+    }
+
+    /// Deletes the item from the checklist, returning the new checklist.
+    pub async fn delete_checklist_item(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        item_id: &str,
+    ) -> anyhow::Result<Result<Vec<ChecklistItem>, ChecklistError>> {
+        if self.tasks.fetch_task(user_id, task_id).await?.is_none() {
+            return Ok(Err(ChecklistError::TaskNotFound));
+        }
+
+        if !self
+            .tasks
+            .delete_checklist_item(user_id, task_id, item_id)
+            .await?
+        {
+            return Ok(Err(ChecklistError::ItemNotFound));
+        }
+
+        self.checklist_changed(user_id, task_id).await
+    }
+
+    /// Announces the new checklist progress of the task and returns its checklist.
+    async fn checklist_changed(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Result<Vec<ChecklistItem>, ChecklistError>> {
+        let Some(task) = self.tasks.fetch_task(user_id, task_id).await? else {
+            return Ok(Err(ChecklistError::TaskNotFound));
+        };
+
+        self.publish(user_id, BoardEventKind::TaskUpdated(task));
+
+        Ok(Ok(self.tasks.fetch_checklist(user_id, task_id).await?))
     }
 
-    pub async fn fetch_categories(
+    /// Returns the history of the task, newest first, or `None` if the task has never existed.
+    /// The history of a deleted task is still available.
+    pub async fn fetch_task_activity(
         &self,
         user_id: UserId,
-    ) -> anyhow::Result<Vec<TaskCategoryDescription>> {
-        self.tasks.fetch_categories(user_id).await
+        task_id: &str,
+    ) -> anyhow::Result<Option<Vec<ActivityEntry>>> {
+        let entries = self
+            .activity
+            .fetch_task_activity(self.user_board(user_id), task_id)
+            .await?;
+
+        if entries.is_empty() && self.tasks.fetch_task(user_id, task_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(entries))
+    }
+
+    /// Returns a page of the changes on the board, starting after the `cursor` of the previous page.
+    pub async fn fetch_board_activity(
+        &self,
+        board_id: BoardId,
+        cursor: Option<ActivityId>,
+        limit: i64,
+    ) -> anyhow::Result<ActivityPage> {
+        let limit = limit.clamp(1, MAX_ACTIVITY_PAGE_SIZE);
+
+        // One more entry tells whether there is a next page.
+        let mut entries = self
+            .activity
+            .fetch_board_activity(board_id, cursor, limit + 1)
+            .await?;
+
+        let next_cursor = if entries.len() as i64 > limit {
+            entries.truncate(limit as usize);
+            entries.last().map(|x| x.activity_id)
+        } else {
+            None
+        };
+
+        Ok(ActivityPage {
+            entries,
+            next_cursor,
+        })
+    }
+
+    /// Returns the changes of the tasks and the categories of the user made after the `since` cursor.
+    /// Without a cursor, or with a cursor that is not in the change log, returns the whole board.
+    pub async fn fetch_changes(
+        &self,
+        user_id: UserId,
+        since: Option<SyncSeq>,
+    ) -> anyhow::Result<SyncBatch> {
+        // The cursor is read first, so that the changes made while the board is read are sent again later.
+        let latest = self.sync.latest_seq(user_id).await?;
+
+        let Some(since) = since.filter(|since| (0..=latest).contains(since)) else {
+            return Ok(SyncBatch {
+                reset: true,
+                categories: self.tasks.fetch_categories(user_id).await?,
+                tasks: self.tasks.fetch_tasks(user_id).await?,
+                cursor: latest,
+                ..Default::default()
+            });
+        };
+
+        // One more entry tells whether there are more changes.
+        let mut changes = self
+            .sync
+            .fetch_changes(user_id, since, MAX_SYNC_CHANGES + 1)
+            .await?;
+
+        let has_more = changes.len() as i64 > MAX_SYNC_CHANGES;
+        changes.truncate(MAX_SYNC_CHANGES as usize);
+
+        let mut batch = SyncBatch {
+            cursor: changes.last().map_or(since, |x| x.seq),
+            has_more,
+            ..Default::default()
+        };
+
+        let mut seen = HashSet::new();
+        let entities: Vec<SyncEntity> = changes
+            .into_iter()
+            .map(|x| x.entity)
+            .filter(|entity| seen.insert(entity.clone()))
+            .collect();
+
+        if entities
+            .iter()
+            .any(|e| matches!(e, SyncEntity::Category(_)))
+        {
+            let mut categories: HashMap<TaskCategoryId, TaskCategoryDescription> = self
+                .tasks
+                .fetch_categories(user_id)
+                .await?
+                .into_iter()
+                .map(|c| (c.category_id.clone(), c))
+                .collect();
+
+            for entity in &entities {
+                if let SyncEntity::Category(category_id) = entity {
+                    match categories.remove(category_id) {
+                        Some(category) => batch.categories.push(category),
+                        None => batch.deleted_category_ids.push(category_id.clone()),
+                    }
+                }
+            }
+        }
+
+        if entities.iter().any(|e| matches!(e, SyncEntity::Task(_))) {
+            let mut tasks: HashMap<TaskId, TaskDescription> = self
+                .tasks
+                .fetch_tasks(user_id)
+                .await?
+                .into_iter()
+                .map(|t| (t.task_id.clone(), t))
+                .collect();
+
+            for entity in &entities {
+                if let SyncEntity::Task(task_id) = entity {
+                    match tasks.remove(task_id) {
+                        Some(task) => batch.tasks.push(task),
+                        None => batch.deleted_task_ids.push(task_id.clone()),
+                    }
+                }
+            }
+        }
+
+        Ok(batch)
+    }
+
+    /// Subscribes to the events of the board, replaying the events published after `last_event_id`.
+    pub fn subscribe(
+        &self,
+        board_id: BoardId,
+        last_event_id: Option<EventId>,
+    ) -> Result<BoardSubscription, SubscriptionError> {
+        self.events.subscribe(board_id, last_event_id)
+    }
+
+    /// Returns whether the user has the category and it is not in the trash.
+    async fn has_category(&self, user_id: UserId, category_id: &str) -> anyhow::Result<bool> {
+        let categories = self.tasks.fetch_categories(user_id).await?;
+
+        Ok(categories
+            .iter()
+            .any(|category| category.category_id == category_id))
+    }
+
+    /// Describes the changes of a task made by the user now.
+    fn activity(&self, user_id: UserId, kinds: Vec<ActivityKind>) -> ActivityRecord {
+        ActivityRecord::now(self.user_board(user_id), user_id, kinds)
+    }
+
+    fn publish(&self, user_id: UserId, kind: BoardEventKind) -> EventId {
+        self.events.publish(self.user_board(user_id), kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{TimeDelta, Utc};
+    use chrono_tz::Tz;
+
+    use crate::{
+        app::{
+            attachments::{AttachmentError, AttachmentLimits, AttachmentsService, Upload},
+            due_dates::DuePeriod,
+            events::{BoardEventKind, EventBus},
+            filters::parse_filter,
+            import::ImportSource,
+            inbound::{InboundEmail, InboundError},
+            repositories::TasksRepository,
+        },
+        model::{
+            activity::ActivityKind,
+            bulk::{BulkAction, BulkError, BulkOperation},
+            checklists::{ChecklistItemPatch, ChecklistProgress},
+            labels::LabelData,
+            tasks::{TaskCategoryDescription, TaskData, TaskPatch, TaskPriority, INITIAL_VERSION},
+            UserId,
+        },
+        storage::inmemory,
+    };
+
+    use super::{
+        AssignLabelError, ChecklistError, CreateTaskError, LabelError, LifecycleError,
+        ModifyTaskError, RenameCategoryError, TasksService,
+    };
+
+    const USER_ID: UserId = UserId::from_raw(1);
+
+    fn task_data(label: &str, category_id: &str) -> TaskData {
+        TaskData {
+            label: label.to_string(),
+            description: "description".to_string(),
+            category_id: category_id.to_string(),
+            start_at: None,
+            due_at: None,
+            priority: Default::default(),
+        }
+    }
+
+    async fn setup_tasks_service() -> anyhow::Result<(TasksService, String)> {
+        let (service, categories) = setup_tasks_service_with_categories(&["ToDo"]).await?;
+
+        Ok((service, categories[0].category_id.clone()))
+    }
+
+    async fn setup_tasks_service_with_categories(
+        labels: &[&str],
+    ) -> anyhow::Result<(TasksService, Vec<TaskCategoryDescription>)> {
+        let activity = Arc::new(inmemory::InMemoryActivity::new());
+        let sync = Arc::new(inmemory::InMemorySync::new());
+        let tasks = Arc::new(inmemory::InMemoryTasks::new(
+            activity.clone(),
+            sync.clone(),
+            Arc::new(inmemory::InMemoryWebhooks::new()),
+        ));
+        let categories = tasks.add_categories(USER_ID, labels).await?;
+
+        let attachments = AttachmentsService::new(
+            Arc::new(inmemory::InMemoryAttachments::new()),
+            tasks.clone(),
+            Arc::new(inmemory::InMemoryBlobs::new()),
+            AttachmentLimits::default(),
+        );
+
+        let service = TasksService::new(
+            tasks,
+            Arc::new(EventBus::new()),
+            Arc::new(attachments),
+            activity,
+            sync,
+        );
+
+        Ok((service, categories))
+    }
+
+    #[tokio::test]
+    async fn modify_task_increments_version() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let (task, _) = service
+            .create_task(USER_ID, task_data("label", &category_id))
+            .await?
+            .expect("failed to create task");
+        assert_eq!(task.version, INITIAL_VERSION);
+
+        let (modified, _) = service
+            .modify_task(
+                USER_ID,
+                &task.task_id,
+                task_data("new label", &category_id),
+                Some(task.version),
+            )
+            .await?
+            .expect("failed to modify task");
+
+        assert_eq!(modified.version, task.version + 1);
+        assert_eq!(modified.label, "new label");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn modify_task_version_conflict() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let (task, _) = service
+            .create_task(USER_ID, task_data("label", &category_id))
+            .await?
+            .expect("failed to create task");
+
+        // The first editor succeeds.
+        service
+            .modify_task(
+                USER_ID,
+                &task.task_id,
+                task_data("first", &category_id),
+                Some(task.version),
+            )
+            .await?
+            .expect("failed to modify task");
+
+        // The second editor based the changes on the same version.
+        let result = service
+            .modify_task(
+                USER_ID,
+                &task.task_id,
+                task_data("second", &category_id),
+                Some(task.version),
+            )
+            .await?;
+
+        let Err(ModifyTaskError::VersionConflict(current)) = result else {
+            panic!(
+                "modify succeeded although the version was outdated: {:?}",
+                result
+            );
+        };
+
+        assert_eq!(current.label, "first");
+        assert_eq!(current.version, task.version + 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_task_rejects_unknown_category() -> anyhow::Result<()> {
+        let (service, _) = setup_tasks_service().await?;
+
+        let foreign = service
+            .tasks
+            .add_categories(UserId::from_raw(2), &["Foreign"])
+            .await?;
+        let trashed = service.tasks.add_categories(USER_ID, &["Trashed"]).await?;
+        service
+            .delete_category(USER_ID, &trashed[0].category_id)
+            .await?
+            .expect("failed to delete category");
+
+        for target in ["missing", &foreign[0].category_id, &trashed[0].category_id] {
+            let result = service
+                .create_task(USER_ID, task_data("label", target))
+                .await?;
+
+            assert!(
+                matches!(result, Err(CreateTaskError::CategoryNotFound)),
+                "task created in category {}: {:?}",
+                target,
+                result
+            );
+        }
+
+        assert!(service.tasks.fetch_tasks(USER_ID).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn patch_task_changes_only_provided_fields() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let (task, _) = service
+            .create_task(USER_ID, task_data("label", &category_id))
+            .await?
+            .expect("failed to create task");
+
+        let done = service.tasks.add_categories(USER_ID, &["Done"]).await?;
+
+        let mut subscription = service
+            .subscribe(service.user_board(USER_ID), None)
+            .unwrap();
+
+        let patch = TaskPatch {
+            category_id: Some(done[0].category_id.clone()),
+            ..Default::default()
+        };
+
+        let (patched, event_id) = service
+            .patch_task(USER_ID, &task.task_id, &patch, None)
+            .await?
+            .expect("failed to patch task");
+
+        assert_eq!(patched.label, "label");
+        assert_eq!(patched.description, "description");
+        assert_eq!(patched.category_id, done[0].category_id);
+        assert_eq!(patched.version, task.version + 1);
+
+        let event = subscription.recv().await.unwrap();
+        assert_eq!(event.event_id, event_id);
+        assert!(
+            matches!(&event.kind, BoardEventKind::TaskMoved { from_category_id, .. } if *from_category_id == category_id),
+            "expected the task to be moved: {:?}",
+            event.kind
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn patch_task_rejects_unknown_category() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let (task, _) = service
+            .create_task(USER_ID, task_data("label", &category_id))
+            .await?
+            .expect("failed to create task");
+
+        let foreign = service
+            .tasks
+            .add_categories(UserId::from_raw(2), &["Foreign"])
+            .await?;
+        let trashed = service.tasks.add_categories(USER_ID, &["Trashed"]).await?;
+        service
+            .delete_category(USER_ID, &trashed[0].category_id)
+            .await?
+            .expect("failed to delete category");
+
+        for target in ["missing", &foreign[0].category_id, &trashed[0].category_id] {
+            let patch = TaskPatch {
+                category_id: Some(target.to_string()),
+                ..Default::default()
+            };
+
+            let result = service
+                .patch_task(USER_ID, &task.task_id, &patch, None)
+                .await?;
+
+            assert!(
+                matches!(result, Err(ModifyTaskError::CategoryNotFound)),
+                "task moved to category {}: {:?}",
+                target,
+                result
+            );
+        }
+
+        let current = service.fetch_task(USER_ID, &task.task_id).await?.unwrap();
+        assert_eq!(current.category_id, category_id);
+        assert_eq!(current.version, task.version);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn modify_task_not_found() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let result = service
+            .modify_task(USER_ID, "missing", task_data("label", &category_id), None)
+            .await?;
+
+        assert!(
+            matches!(result, Err(ModifyTaskError::TaskNotFound)),
+            "modify succeeded although there is no such task: {:?}",
+            result
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fetch_tasks_due_returns_overdue_tasks() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+        let now = Utc::now();
+
+        for (label, due_at) in [
+            ("later", Some(now + TimeDelta::days(30))),
+            ("yesterday", Some(now - TimeDelta::days(1))),
+            ("no due date", None),
+            ("last week", Some(now - TimeDelta::days(7))),
+        ] {
+            let data = TaskData {
+                due_at,
+                ..task_data(label, &category_id)
+            };
+            service
+                .create_task(USER_ID, data)
+                .await?
+                .expect("failed to create task");
+        }
+
+        let overdue = service
+            .fetch_tasks_due(USER_ID, DuePeriod::Overdue, Tz::UTC)
+            .await?;
+
+        let labels: Vec<&str> = overdue.iter().map(|task| task.label.as_str()).collect();
+        assert_eq!(labels, ["last week", "yesterday"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn deleting_label_unassigns_it() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let (task, _) = service
+            .create_task(USER_ID, task_data("task", &category_id))
+            .await?
+            .expect("failed to create task");
+        let label = service
+            .create_label(
+                USER_ID,
+                LabelData {
+                    name: "bug".to_string(),
+                    color: "#ff0000".to_string(),
+                },
+            )
+            .await?
+            .unwrap();
+
+        let assigned = service
+            .set_label_assigned(USER_ID, &task.task_id, &label.label_id, true)
+            .await?
+            .unwrap();
+        assert_eq!(assigned.label_ids, std::slice::from_ref(&label.label_id));
+        assert_eq!(assigned.version, INITIAL_VERSION + 1);
+
+        // Assigning again changes nothing.
+        let assigned_again = service
+            .set_label_assigned(USER_ID, &task.task_id, &label.label_id, true)
+            .await?
+            .unwrap();
+        assert_eq!(assigned_again.version, assigned.version);
+
+        service
+            .delete_label(USER_ID, &label.label_id)
+            .await?
+            .unwrap();
+
+        let task = service.fetch_task(USER_ID, &task.task_id).await?.unwrap();
+        assert!(task.label_ids.is_empty());
+        assert_eq!(task.version, assigned.version + 1);
+
+        let result = service
+            .set_label_assigned(USER_ID, &task.task_id, &label.label_id, true)
+            .await?;
+        assert!(matches!(result, Err(AssignLabelError::LabelNotFound)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn labels_of_other_users_are_not_found() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+        let other_user_id = UserId::from_raw(2);
+
+        let (task, _) = service
+            .create_task(USER_ID, task_data("task", &category_id))
+            .await?
+            .expect("failed to create task");
+        let label = service
+            .create_label(
+                other_user_id,
+                LabelData {
+                    name: "bug".to_string(),
+                    color: "#ff0000".to_string(),
+                },
+            )
+            .await?
+            .unwrap();
+
+        assert!(service.fetch_labels(USER_ID).await?.is_empty());
+
+        let result = service
+            .modify_label(
+                USER_ID,
+                &label.label_id,
+                LabelData {
+                    name: "feature".to_string(),
+                    color: "#00ff00".to_string(),
+                },
+            )
+            .await?;
+        assert!(matches!(result, Err(LabelError::LabelNotFound)));
+
+        let result = service.delete_label(USER_ID, &label.label_id).await?;
+        assert!(matches!(result, Err(LabelError::LabelNotFound)));
+
+        let result = service
+            .set_label_assigned(USER_ID, &task.task_id, &label.label_id, true)
+            .await?;
+        assert!(matches!(result, Err(AssignLabelError::LabelNotFound)));
+
+        // The label of the other user is left intact.
+        let labels = service.fetch_labels(other_user_id).await?;
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].name, "bug");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn invalid_labels_are_rejected() -> anyhow::Result<()> {
+        let (service, _) = setup_tasks_service().await?;
+
+        let label = service
+            .create_label(
+                USER_ID,
+                LabelData {
+                    name: "bug".to_string(),
+                    color: "#ff0000".to_string(),
+                },
+            )
+            .await?
+            .unwrap();
+
+        let invalid = [
+            ("bug", "red"),
+            ("bug", "ff0000"),
+            ("bug", "#ff000"),
+            ("bug", "#ff00000"),
+            ("bug", "#gg0000"),
+            ("bug", "#ff 000"),
+            ("  ", "#ff0000"),
+        ];
+
+        for (name, color) in invalid {
+            let data = || LabelData {
+                name: name.to_string(),
+                color: color.to_string(),
+            };
+
+            let result = service.create_label(USER_ID, data()).await?;
+            assert!(
+                matches!(result, Err(LabelError::InvalidLabel)),
+                "created label {:?} {:?}: {:?}",
+                name,
+                color,
+                result
+            );
+
+            let result = service
+                .modify_label(USER_ID, &label.label_id, data())
+                .await?;
+            assert!(
+                matches!(result, Err(LabelError::InvalidLabel)),
+                "modified label to {:?} {:?}: {:?}",
+                name,
+                color,
+                result
+            );
+        }
+
+        let labels = service.fetch_labels(USER_ID).await?;
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].color, "#ff0000");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn priorities_are_stored_and_unknown_values_are_rejected() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        for priority in [
+            TaskPriority::Low,
+            TaskPriority::Normal,
+            TaskPriority::High,
+            TaskPriority::Urgent,
+        ] {
+            assert_eq!(TaskPriority::parse(priority.as_str()), Some(priority));
+            assert_eq!(TaskPriority::from_rank(priority.rank()), Some(priority));
+
+            let (task, _) = service
+                .create_task(
+                    USER_ID,
+                    TaskData {
+                        priority,
+                        ..task_data("task", &category_id)
+                    },
+                )
+                .await?
+                .expect("failed to create task");
+            let task = service.fetch_task(USER_ID, &task.task_id).await?.unwrap();
+            assert_eq!(task.priority, priority);
+        }
+
+        for name in ["", "critical", "High", " low"] {
+            assert_eq!(TaskPriority::parse(name), None, "parsed {:?}", name);
+        }
+        assert_eq!(TaskPriority::from_rank(-1), None);
+        assert_eq!(TaskPriority::from_rank(4), None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn board_is_filtered() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let label = service
+            .create_label(
+                USER_ID,
+                LabelData {
+                    name: "Bug".to_string(),
+                    color: "#ff0000".to_string(),
+                },
+            )
+            .await?
+            .unwrap();
+
+        let (urgent_bug, _) = service
+            .create_task(
+                USER_ID,
+                TaskData {
+                    priority: TaskPriority::Urgent,
+                    due_at: Some(Utc::now() + TimeDelta::days(2)),
+                    ..task_data("urgent bug", &category_id)
+                },
+            )
+            .await?
+            .expect("failed to create task");
+        let (bug, _) = service
+            .create_task(USER_ID, task_data("bug", &category_id))
+            .await?
+            .expect("failed to create task");
+        let (archived_bug, _) = service
+            .create_task(
+                USER_ID,
+                TaskData {
+                    priority: TaskPriority::High,
+                    ..task_data("archived bug", &category_id)
+                },
+            )
+            .await?
+            .expect("failed to create task");
+        service
+            .create_task(USER_ID, task_data("feature", &category_id))
+            .await?
+            .expect("failed to create task");
+
+        for task in [&urgent_bug, &bug, &archived_bug] {
+            service
+                .set_label_assigned(USER_ID, &task.task_id, &label.label_id, true)
+                .await?
+                .unwrap();
+        }
+        service
+            .archive_task(USER_ID, &archived_bug.task_id)
+            .await?
+            .unwrap();
+
+        let cases = [
+            (
+                "label:bug column:todo",
+                vec![&urgent_bug, &bug, &archived_bug],
+            ),
+            ("label:bug priority:>=high -archived", vec![&urgent_bug]),
+            (
+                "label:bug (due:<7d OR archived)",
+                vec![&urgent_bug, &archived_bug],
+            ),
+            ("label:bug -due:none", vec![&urgent_bug]),
+            ("label:feature", vec![]),
+        ];
+
+        for (query, expected) in cases {
+            let filter = parse_filter(query).unwrap();
+            let (_, tasks) = service.fetch_board(USER_ID, true, Some(&filter)).await?;
+
+            let mut task_ids: Vec<&str> = tasks.iter().map(|t| t.task_id.as_str()).collect();
+            let mut expected_ids: Vec<&str> = expected.iter().map(|t| t.task_id.as_str()).collect();
+            task_ids.sort();
+            expected_ids.sort();
+            assert_eq!(task_ids, expected_ids, "{}", query);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn checklist_reorder_and_progress() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let (task, _) = service
+            .create_task(USER_ID, task_data("task", &category_id))
+            .await?
+            .expect("failed to create task");
+
+        for text in ["first", "second", "third"] {
+            service
+                .add_checklist_item(USER_ID, &task.task_id, text)
+                .await?
+                .unwrap();
+        }
+
+        let checklist = service
+            .fetch_checklist(USER_ID, &task.task_id)
+            .await?
+            .unwrap();
+        let third_id = checklist[2].item_id.clone();
+
+        let patch = ChecklistItemPatch {
+            done: Some(true),
+            position: Some(0),
+            ..Default::default()
+        };
+        let checklist = service
+            .modify_checklist_item(USER_ID, &task.task_id, &third_id, &patch)
+            .await?
+            .unwrap();
+
+        let texts: Vec<&str> = checklist.iter().map(|item| item.text.as_str()).collect();
+        assert_eq!(texts, ["third", "first", "second"]);
+        assert!(checklist
+            .iter()
+            .enumerate()
+            .all(|(i, item)| item.position == i as i32));
+
+        let task = service.fetch_task(USER_ID, &task.task_id).await?.unwrap();
+        assert_eq!(
+            task.checklist_progress,
+            ChecklistProgress { done: 1, total: 3 }
+        );
+        assert_eq!(task.version, INITIAL_VERSION + 4);
+
+        let result = service
+            .add_checklist_item(USER_ID, &task.task_id, "  ")
+            .await?;
+        assert!(matches!(result, Err(ChecklistError::InvalidText)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn activity_records_changes_and_survives_deletion() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let (task, _) = service
+            .create_task(USER_ID, task_data("label", &category_id))
+            .await?
+            .expect("failed to create task");
+
+        let patch = TaskPatch {
+            label: Some("new label".to_string()),
+            description: Some("new description".to_string()),
+            ..Default::default()
+        };
+        service
+            .patch_task(USER_ID, &task.task_id, &patch, None)
+            .await?
+            .unwrap();
+
+        service.delete_task(USER_ID, &task.task_id).await?.unwrap();
+
+        let activity = service
+            .fetch_task_activity(USER_ID, &task.task_id)
+            .await?
+            .unwrap();
+        let kinds: Vec<ActivityKind> = activity.into_iter().map(|x| x.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ActivityKind::Deleted,
+                ActivityKind::DescriptionChanged {
+                    from: "description".to_string(),
+                    to: "new description".to_string(),
+                },
+                ActivityKind::LabelChanged {
+                    from: "label".to_string(),
+                    to: "new label".to_string(),
+                },
+                ActivityKind::Created,
+            ]
+        );
+
+        assert!(service
+            .fetch_task_activity(USER_ID, "missing")
+            .await?
+            .is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn board_activity_is_paginated() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        for label in ["first", "second", "third"] {
+            service
+                .create_task(USER_ID, task_data(label, &category_id))
+                .await?
+                .expect("failed to create task");
+        }
+
+        let board_id = service.user_board(USER_ID);
+
+        let first_page = service.fetch_board_activity(board_id, None, 2).await?;
+        assert_eq!(first_page.entries.len(), 2);
+        assert!(first_page.next_cursor.is_some());
+
+        let second_page = service
+            .fetch_board_activity(board_id, first_page.next_cursor, 2)
+            .await?;
+        assert_eq!(second_page.entries.len(), 1);
+        assert!(second_page.next_cursor.is_none());
+        assert!(second_page.entries[0].activity_id < first_page.entries[1].activity_id);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn trashed_task_can_be_restored() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let (task, _) = service
+            .create_task(USER_ID, task_data("label", &category_id))
+            .await?
+            .expect("failed to create task");
+
+        service.archive_task(USER_ID, &task.task_id).await?.unwrap();
+        let (_, board_tasks) = service.fetch_board(USER_ID, false, None).await?;
+        assert!(board_tasks.is_empty());
+        assert_eq!(service.fetch_archive(USER_ID).await?.tasks.len(), 1);
+
+        service.delete_task(USER_ID, &task.task_id).await?.unwrap();
+        assert!(service.fetch_task(USER_ID, &task.task_id).await?.is_none());
+        assert!(service.fetch_archive(USER_ID).await?.tasks.is_empty());
+        assert_eq!(service.fetch_trash(USER_ID).await?.tasks.len(), 1);
+
+        let restored = service.restore_task(USER_ID, &task.task_id).await?.unwrap();
+        assert!(restored.lifecycle.is_active());
+
+        let (_, board_tasks) = service.fetch_board(USER_ID, false, None).await?;
+        assert_eq!(board_tasks.len(), 1);
+        assert!(service.fetch_trash(USER_ID).await?.tasks.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn columns_are_paginated() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let mut task_ids = Vec::new();
+        for label in ["a", "b", "c", "d", "e"] {
+            let (task, _) = service
+                .create_task(USER_ID, task_data(label, &category_id))
+                .await?
+                .expect("failed to create task");
+            task_ids.push(task.task_id);
+        }
+        service.archive_task(USER_ID, &task_ids[0]).await?.unwrap();
+
+        let mut paged = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = service
+                .fetch_category_tasks(USER_ID, &category_id, cursor.as_ref(), 2, false)
+                .await?
+                .unwrap();
+            assert!(page.tasks.len() <= 2);
+
+            paged.extend(page.tasks.into_iter().map(|t| t.task_id));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        // Ordered like the board, by creation.
+        assert_eq!(paged, task_ids[1..]);
+
+        let summary = service.fetch_board_summary(USER_ID, false).await?;
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].task_count, 4);
+
+        let summary = service.fetch_board_summary(USER_ID, true).await?;
+        assert_eq!(summary[0].task_count, 5);
+
+        assert!(service
+            .fetch_category_tasks(USER_ID, "missing", None, 2, false)
+            .await?
+            .is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn trashed_category_takes_its_tasks_and_is_purged() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let (task, _) = service
+            .create_task(USER_ID, task_data("label", &category_id))
+            .await?
+            .expect("failed to create task");
+
+        service
+            .delete_category(USER_ID, &category_id)
+            .await?
+            .unwrap();
+        assert_eq!(service.fetch_trash(USER_ID).await?.tasks.len(), 1);
+
+        let result = service.restore_task(USER_ID, &task.task_id).await?;
+        assert!(matches!(result, Err(LifecycleError::CategoryTrashed)));
+
+        // Nothing has been in the trash for a day yet.
+        assert_eq!(service.purge_trash(TimeDelta::days(1)).await?, 0);
+
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        assert_eq!(service.purge_trash(TimeDelta::zero()).await?, 1);
+
+        let trash = service.fetch_trash(USER_ID).await?;
+        assert!(trash.categories.is_empty() && trash.tasks.is_empty());
+
+        let result = service.restore_category(USER_ID, &category_id).await?;
+        assert!(matches!(result, Err(LifecycleError::CategoryNotFound)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn renamed_category_is_announced() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+        let mut subscription = service.subscribe(USER_ID, None).unwrap();
+
+        let category = service
+            .rename_category(USER_ID, &category_id, " Backlog ")
+            .await?
+            .unwrap();
+        assert_eq!(category.label, "Backlog");
+        assert_eq!(category.version, INITIAL_VERSION + 1);
+
+        let event = subscription.recv().await.unwrap();
+        assert!(matches!(&event.kind, BoardEventKind::CategoryUpdated(c) if c.label == "Backlog"));
+
+        let result = service.rename_category(USER_ID, &category_id, " ").await?;
+        assert!(matches!(result, Err(RenameCategoryError::InvalidLabel)));
+
+        service
+            .delete_category(USER_ID, &category_id)
+            .await?
+            .unwrap();
+        let result = service
+            .rename_category(USER_ID, &category_id, "Done")
+            .await?;
+        assert!(matches!(result, Err(RenameCategoryError::CategoryNotFound)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn changes_are_synced_since_cursor() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let (kept, _) = service
+            .create_task(USER_ID, task_data("kept", &category_id))
+            .await?
+            .expect("failed to create task");
+
+        let snapshot = service.fetch_changes(USER_ID, None).await?;
+        assert!(snapshot.reset);
+        assert_eq!(snapshot.categories.len(), 1);
+        assert_eq!(snapshot.tasks.len(), 1);
+
+        let (deleted, _) = service
+            .create_task(USER_ID, task_data("deleted", &category_id))
+            .await?
+            .expect("failed to create task");
+        service
+            .patch_task(
+                USER_ID,
+                &kept.task_id,
+                &TaskPatch {
+                    label: Some("renamed".to_string()),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await?
+            .unwrap();
+        service
+            .delete_task(USER_ID, &deleted.task_id)
+            .await?
+            .unwrap();
+
+        let batch = service
+            .fetch_changes(USER_ID, Some(snapshot.cursor))
+            .await?;
+        assert!(!batch.reset && !batch.has_more);
+        assert!(batch.categories.is_empty());
+        assert_eq!(batch.tasks.len(), 1);
+        assert_eq!(batch.tasks[0].label, "renamed");
+        assert_eq!(batch.deleted_task_ids, vec![deleted.task_id]);
+
+        let batch = service.fetch_changes(USER_ID, Some(batch.cursor)).await?;
+        assert!(batch.tasks.is_empty() && batch.deleted_task_ids.is_empty());
+
+        // A cursor from the future is not trusted.
+        let batch = service
+            .fetch_changes(USER_ID, Some(batch.cursor + 1))
+            .await?;
+        assert!(batch.reset);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tasks_changed_with_labels_and_categories_are_synced() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let (labelled, _) = service
+            .create_task(USER_ID, task_data("labelled", &category_id))
+            .await?
+            .expect("failed to create task");
+        let label = service
+            .create_label(
+                USER_ID,
+                LabelData {
+                    name: "bug".to_string(),
+                    color: "#ff0000".to_string(),
+                },
+            )
+            .await?
+            .unwrap();
+        let labelled = service
+            .set_label_assigned(USER_ID, &labelled.task_id, &label.label_id, true)
+            .await?
+            .unwrap();
+
+        let cursor = service.fetch_changes(USER_ID, None).await?.cursor;
+
+        // A change that is not applied is neither recorded nor synced.
+        let updated = service
+            .tasks
+            .update_task(
+                USER_ID,
+                &labelled.task_id,
+                &TaskPatch {
+                    label: Some("stale".to_string()),
+                    ..Default::default()
+                },
+                Some(labelled.version - 1),
+                &service.activity(USER_ID, vec![ActivityKind::Created]),
+            )
+            .await?;
+        assert!(updated.is_none());
+
+        let batch = service.fetch_changes(USER_ID, Some(cursor)).await?;
+        assert_eq!(batch.cursor, cursor);
+
+        let activity = service
+            .fetch_task_activity(USER_ID, &labelled.task_id)
+            .await?
+            .unwrap();
+        assert_eq!(activity.len(), 1);
+
+        service
+            .delete_label(USER_ID, &label.label_id)
+            .await?
+            .unwrap();
+
+        let batch = service.fetch_changes(USER_ID, Some(cursor)).await?;
+        assert_eq!(batch.tasks.len(), 1);
+        assert!(batch.tasks[0].label_ids.is_empty());
+
+        service
+            .delete_category(USER_ID, &category_id)
+            .await?
+            .unwrap();
+
+        let batch = service.fetch_changes(USER_ID, Some(batch.cursor)).await?;
+        assert_eq!(batch.deleted_category_ids, vec![category_id]);
+        assert_eq!(batch.deleted_task_ids, vec![labelled.task_id]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bulk_operations_are_applied_all_or_nothing() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+        let (first, _) = service
+            .create_task(USER_ID, task_data("first", &category_id))
+            .await?
+            .expect("failed to create task");
+        let (second, _) = service
+            .create_task(USER_ID, task_data("second", &category_id))
+            .await?
+            .expect("failed to create task");
+
+        let update = |task_id: &str, label: &str| BulkOperation {
+            task_id: task_id.to_string(),
+            action: BulkAction::Update(TaskPatch {
+                label: Some(label.to_string()),
+                ..Default::default()
+            }),
+        };
+
+        let cursor = service.fetch_changes(USER_ID, None).await?.cursor;
+
+        let failure = service
+            .apply_bulk_operations(
+                USER_ID,
+                &[
+                    update(&first.task_id, "changed"),
+                    BulkOperation {
+                        task_id: second.task_id.clone(),
+                        action: BulkAction::AddLabel("missing".to_string()),
+                    },
+                ],
+            )
+            .await?
+            .unwrap_err();
+        assert_eq!(failure.index, 1);
+        assert_eq!(failure.error, BulkError::LabelNotFound);
+
+        let task = service.fetch_task(USER_ID, &first.task_id).await?.unwrap();
+        assert_eq!(task.label, "first");
+        assert_eq!(task.version, first.version);
+
+        let batch = service.fetch_changes(USER_ID, Some(cursor)).await?;
+        assert_eq!(batch.cursor, cursor);
+
+        let activity = service
+            .fetch_task_activity(USER_ID, &first.task_id)
+            .await?
+            .unwrap();
+        assert_eq!(activity.len(), 1);
+
+        let results = service
+            .apply_bulk_operations(
+                USER_ID,
+                &[
+                    update(&first.task_id, "changed"),
+                    BulkOperation {
+                        task_id: second.task_id.clone(),
+                        action: BulkAction::Delete,
+                    },
+                    BulkOperation {
+                        task_id: first.task_id.clone(),
+                        action: BulkAction::Archive,
+                    },
+                ],
+            )
+            .await?
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results[1].lifecycle.is_trashed());
+        assert!(results[2].lifecycle.is_archived());
+        assert_eq!(results[2].label, "changed");
+        assert_eq!(results[2].version, first.version + 2);
+
+        assert!(service
+            .fetch_task(USER_ID, &second.task_id)
+            .await?
+            .is_none());
+
+        let activity = service
+            .fetch_task_activity(USER_ID, &first.task_id)
+            .await?
+            .unwrap();
+        let kinds: Vec<_> = activity.into_iter().map(|entry| entry.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ActivityKind::Archived,
+                ActivityKind::LabelChanged {
+                    from: "first".to_string(),
+                    to: "changed".to_string(),
+                },
+                ActivityKind::Created,
+            ]
+        );
+
+        let batch = service.fetch_changes(USER_ID, Some(cursor)).await?;
+        assert_eq!(batch.deleted_task_ids, vec![second.task_id]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn board_is_imported_after_dry_run() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        service
+            .create_label(
+                USER_ID,
+                LabelData {
+                    name: "Home".to_string(),
+                    color: "#00ff00".to_string(),
+                },
+            )
+            .await?
+            .unwrap();
+
+        let csv = "column,title,labels\n\
+            todo,Buy milk,home;errand\n\
+            Later,Paint the fence,errand\n";
+
+        let report = service
+            .import_board(USER_ID, ImportSource::from_csv(csv).unwrap(), true)
+            .await?
+            .unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.task_count, 2);
+        assert_eq!(report.columns.len(), 2);
+        assert_eq!(
+            report.columns[0].category_id.as_deref(),
+            Some(&*category_id)
+        );
+        assert_eq!(report.columns[0].label, "ToDo");
+        assert!(report.columns[1].created);
+        assert_eq!(report.columns[1].category_id, None);
+        assert!(!report.labels[0].created);
+        assert!(report.labels[1].created);
+
+        let (categories, tasks) = service.fetch_board(USER_ID, false, None).await?;
+        assert_eq!(categories.len(), 1);
+        assert!(tasks.is_empty());
+
+        let mut subscription = service.subscribe(USER_ID, None).unwrap();
+
+        let report = service
+            .import_board(USER_ID, ImportSource::from_csv(csv).unwrap(), false)
+            .await?
+            .unwrap();
+        assert!(!report.dry_run);
+
+        let (categories, tasks) = service.fetch_board(USER_ID, false, None).await?;
+        assert_eq!(categories.len(), 2);
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(service.fetch_labels(USER_ID).await?.len(), 2);
+
+        let later = report.columns[1].category_id.clone().unwrap();
+        let event = subscription.recv().await.unwrap();
+        assert!(
+            matches!(&event.kind, BoardEventKind::CategoryCreated(c) if c.category_id == later)
+        );
+
+        let fence = tasks.iter().find(|t| t.label == "Paint the fence").unwrap();
+        assert_eq!(fence.category_id, later);
+        assert_eq!(
+            fence.label_ids,
+            [report.labels[1].label_id.clone().unwrap()]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn task_is_created_from_email() -> anyhow::Result<()> {
+        let (service, category_id) = setup_tasks_service().await?;
+
+        let email = InboundEmail {
+            recipients: vec!["board@tasks.example.com".to_string()],
+            subject: "Fix the sink".to_string(),
+            text: "It leaks.".to_string(),
+            attachments: vec![
+                Upload {
+                    file_name: "notes.txt".to_string(),
+                    content_type: "text/plain".to_string(),
+                    data: b"Call the plumber".to_vec(),
+                },
+                Upload {
+                    file_name: "setup.exe".to_string(),
+                    content_type: "application/x-msdownload".to_string(),
+                    data: b"MZ".to_vec(),
+                },
+            ],
+        };
+
+        let created = service
+            .create_task_from_email(USER_ID, email)
+            .await?
+            .unwrap();
+        assert_eq!(created.task.label, "Fix the sink");
+        assert_eq!(created.task.description, "It leaks.");
+        assert_eq!(created.task.category_id, category_id);
+        assert_eq!(created.attachments.len(), 1);
+        assert_eq!(created.attachments[0].file_name, "notes.txt");
+        assert_eq!(created.rejected_attachments.len(), 1);
+        assert_eq!(created.rejected_attachments[0].file_name, "setup.exe");
+        assert!(matches!(
+            created.rejected_attachments[0].error,
+            AttachmentError::UnsupportedContentType
+        ));
+
+        let empty_board = UserId::from_raw(2);
+        let email = InboundEmail {
+            recipients: Vec::new(),
+            subject: String::new(),
+            text: String::new(),
+            attachments: Vec::new(),
+        };
+        assert_eq!(
+            service
+                .create_task_from_email(empty_board, email)
+                .await?
+                .unwrap_err(),
+            InboundError::NoCategory
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn task_from_email_goes_to_oldest_active_category() -> anyhow::Result<()> {
+        let (service, categories) =
+            setup_tasks_service_with_categories(&["Backlog", "Doing", "Done"]).await?;
+        let [backlog, doing, done] = [0, 1, 2].map(|i| categories[i].category_id.clone());
+
+        let email = || InboundEmail {
+            recipients: vec!["board@tasks.example.com".to_string()],
+            subject: "Fix the sink".to_string(),
+            text: String::new(),
+            attachments: Vec::new(),
+        };
+        let category_of_email = || async {
+            let created = service
+                .create_task_from_email(USER_ID, email())
+                .await?
+                .unwrap();
+            anyhow::Ok(created.task.category_id)
+        };
+
+        assert_eq!(category_of_email().await?, backlog);
+
+        service.archive_category(USER_ID, &backlog).await?.unwrap();
+        assert_eq!(category_of_email().await?, doing);
+
+        service.delete_category(USER_ID, &doing).await?.unwrap();
+        assert_eq!(category_of_email().await?, done);
+
+        service.restore_category(USER_ID, &backlog).await?.unwrap();
+        assert_eq!(category_of_email().await?, backlog);
+
+        Ok(())
     }
 }
@@ -0,0 +1,476 @@
+use std::{cmp::Ordering, sync::Arc};
+
+use chrono::Utc;
+
+use crate::model::{
+    tasks::{TaskDescription, TaskPriority},
+    views::{ViewData, ViewDescription, ViewGrouping, ViewSort},
+    BoardId, UserId,
+};
+
+use super::{
+    filters::{parse_filter, FilterSyntaxError},
+    repositories::{TasksRepository, ViewsRepository},
+};
+
+#[derive(Debug)]
+pub enum ViewError {
+    ViewNotFound,
+    /// Only the owner may change or delete a view.
+    NotOwner,
+    /// The name is blank or too long.
+    InvalidName,
+    InvalidFilter(FilterSyntaxError),
+}
+
+/// Tasks of a view that share the value of the grouping.
+#[derive(Debug)]
+pub struct ViewGroup {
+    /// ID of the category or the label, or the name of the priority.
+    /// `None` if the view is not grouped, or for the tasks without labels.
+    pub key: Option<String>,
+    pub tasks: Vec<TaskDescription>,
+}
+
+pub struct ViewsService {
+    views: Arc<dyn ViewsRepository>,
+    tasks: Arc<dyn TasksRepository>,
+}
+
+impl ViewsService {
+    pub fn new(views: Arc<dyn ViewsRepository>, tasks: Arc<dyn TasksRepository>) -> Self {
+        Self { views, tasks }
+    }
+
+    /// Returns the views of the board that the user owns or that are shared.
+    pub async fn fetch_views(
+        &self,
+        user_id: UserId,
+        board_id: BoardId,
+    ) -> anyhow::Result<Vec<ViewDescription>> {
+        let mut views = self.views.fetch_views(board_id).await?;
+        views.retain(|v| v.is_visible_to(user_id));
+
+        Ok(views)
+    }
+
+    pub async fn fetch_view(
+        &self,
+        user_id: UserId,
+        board_id: BoardId,
+        view_id: &str,
+    ) -> anyhow::Result<Result<ViewDescription, ViewError>> {
+        match self.views.fetch_view(view_id).await? {
+            Some(view) if view.board_id == board_id && view.is_visible_to(user_id) => Ok(Ok(view)),
+            _ => Ok(Err(ViewError::ViewNotFound)),
+        }
+    }
+
+    pub async fn create_view(
+        &self,
+        user_id: UserId,
+        board_id: BoardId,
+        data: ViewData,
+    ) -> anyhow::Result<Result<ViewDescription, ViewError>> {
+        if let Err(err) = validate(&data) {
+            return Ok(Err(err));
+        }
+
+        let view_id = self.views.create_view(board_id, user_id, &data).await?;
+
+        Ok(Ok(ViewDescription {
+            view_id,
+            board_id,
+            owner_id: user_id,
+            data,
+        }))
+    }
+
+    pub async fn modify_view(
+        &self,
+        user_id: UserId,
+        board_id: BoardId,
+        view_id: &str,
+        data: ViewData,
+    ) -> anyhow::Result<Result<ViewDescription, ViewError>> {
+        let view = match self.fetch_owned_view(user_id, board_id, view_id).await? {
+            Ok(view) => view,
+            Err(err) => return Ok(Err(err)),
+        };
+
+        if let Err(err) = validate(&data) {
+            return Ok(Err(err));
+        }
+
+        if !self.views.update_view(view_id, &data).await? {
+            return Ok(Err(ViewError::ViewNotFound));
+        }
+
+        Ok(Ok(ViewDescription { data, ..view }))
+    }
+
+    pub async fn delete_view(
+        &self,
+        user_id: UserId,
+        board_id: BoardId,
+        view_id: &str,
+    ) -> anyhow::Result<Result<(), ViewError>> {
+        if let Err(err) = self.fetch_owned_view(user_id, board_id, view_id).await? {
+            return Ok(Err(err));
+        }
+
+        if !self.views.delete_view(view_id).await? {
+            return Ok(Err(ViewError::ViewNotFound));
+        }
+
+        Ok(Ok(()))
+    }
+
+    /// Returns the view and the tasks of the board that match its filter, sorted and grouped as the view says.
+    /// Empty groups are omitted, unless the view is not grouped.
+    pub async fn run_view(
+        &self,
+        user_id: UserId,
+        board_id: BoardId,
+        view_id: &str,
+    ) -> anyhow::Result<Result<(ViewDescription, Vec<ViewGroup>), ViewError>> {
+        let view = match self.fetch_view(user_id, board_id, view_id).await? {
+            Ok(view) => view,
+            Err(err) => return Ok(Err(err)),
+        };
+
+        let filter = match parse_filter(&view.data.filter) {
+            Ok(filter) => filter,
+            Err(err) => return Ok(Err(ViewError::InvalidFilter(err))),
+        };
+
+        let mut categories = self.tasks.fetch_categories(board_id).await?;
+        let mut tasks = self
+            .tasks
+            .fetch_filtered_tasks(board_id, &filter, Utc::now())
+            .await?;
+
+        if !view.data.include_archived {
+            categories.retain(|c| c.lifecycle.is_active());
+            tasks.retain(|t| {
+                t.lifecycle.is_active() && categories.iter().any(|c| c.category_id == t.category_id)
+            });
+        }
+
+        sort_tasks(&mut tasks, view.data.sort, view.data.descending);
+
+        let keys: Vec<Option<String>> = match view.data.group_by {
+            ViewGrouping::None => return Ok(Ok((view, vec![ViewGroup { key: None, tasks }]))),
+            ViewGrouping::Column => categories
+                .into_iter()
+                .map(|c| Some(c.category_id))
+                .collect(),
+            ViewGrouping::Priority => [
+                TaskPriority::Urgent,
+                TaskPriority::High,
+                TaskPriority::Normal,
+                TaskPriority::Low,
+            ]
+            .into_iter()
+            .map(|p| Some(p.as_str().to_string()))
+            .collect(),
+            ViewGrouping::Label => {
+                let labels = self.tasks.fetch_labels(board_id).await?;
+
+                labels
+                    .into_iter()
+                    .map(|l| Some(l.label_id))
+                    .chain([None])
+                    .collect()
+            }
+        };
+
+        let groups = keys
+            .into_iter()
+            .map(|key| ViewGroup {
+                tasks: tasks
+                    .iter()
+                    .filter(|t| group_contains(view.data.group_by, key.as_deref(), t))
+                    .cloned()
+                    .collect(),
+                key,
+            })
+            .filter(|g| !g.tasks.is_empty())
+            .collect();
+
+        Ok(Ok((view, groups)))
+    }
+
+    async fn fetch_owned_view(
+        &self,
+        user_id: UserId,
+        board_id: BoardId,
+        view_id: &str,
+    ) -> anyhow::Result<Result<ViewDescription, ViewError>> {
+        match self.fetch_view(user_id, board_id, view_id).await? {
+            Ok(view) if view.owner_id != user_id => Ok(Err(ViewError::NotOwner)),
+            result => Ok(result),
+        }
+    }
+}
+
+fn validate(data: &ViewData) -> Result<(), ViewError> {
+    if !data.is_name_valid() {
+        return Err(ViewError::InvalidName);
+    }
+
+    parse_filter(&data.filter).map_err(ViewError::InvalidFilter)?;
+
+    Ok(())
+}
+
+fn sort_tasks(tasks: &mut [TaskDescription], sort: ViewSort, descending: bool) {
+    let directed = |ordering: Ordering| {
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    };
+
+    match sort {
+        ViewSort::Board => {
+            if descending {
+                tasks.reverse();
+            }
+        }
+        ViewSort::DueAt => tasks.sort_by(|a, b| match (a.due_at, b.due_at) {
+            (Some(a), Some(b)) => directed(a.cmp(&b)),
+            (a, b) => a.is_none().cmp(&b.is_none()),
+        }),
+        ViewSort::Priority => tasks.sort_by(|a, b| directed(a.priority.cmp(&b.priority))),
+        ViewSort::Label => {
+            tasks.sort_by_cached_key(|t| t.label.to_lowercase());
+            if descending {
+                tasks.reverse();
+            }
+        }
+    }
+}
+
+fn group_contains(grouping: ViewGrouping, key: Option<&str>, task: &TaskDescription) -> bool {
+    match (grouping, key) {
+        (ViewGrouping::Column, Some(category_id)) => task.category_id == category_id,
+        (ViewGrouping::Priority, Some(priority)) => task.priority.as_str() == priority,
+        (ViewGrouping::Label, Some(label_id)) => task.label_ids.iter().any(|l| l == label_id),
+        (ViewGrouping::Label, None) => task.label_ids.is_empty(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{TimeDelta, Utc};
+
+    use crate::{
+        app::repositories::TasksRepository,
+        model::{
+            activity::{ActivityKind, ActivityRecord},
+            labels::LabelData,
+            tasks::{TaskData, TaskPriority},
+            views::{ViewData, ViewDescription, ViewGrouping, ViewSort},
+            UserId,
+        },
+        storage::inmemory,
+    };
+
+    use super::{ViewError, ViewsService};
+
+    const USER_ID: UserId = UserId::from_raw(1);
+    const OTHER_USER_ID: UserId = UserId::from_raw(2);
+
+    fn view_data(name: &str, filter: &str) -> ViewData {
+        ViewData {
+            name: name.to_string(),
+            filter: filter.to_string(),
+            sort: ViewSort::Board,
+            descending: false,
+            group_by: ViewGrouping::None,
+            include_archived: false,
+            shared: false,
+        }
+    }
+
+    fn task_data(label: &str, category_id: &str, priority: TaskPriority) -> TaskData {
+        TaskData {
+            label: label.to_string(),
+            description: String::new(),
+            category_id: category_id.to_string(),
+            start_at: None,
+            due_at: None,
+            priority,
+        }
+    }
+
+    #[tokio::test]
+    async fn view_sorts_and_groups_matching_tasks() -> anyhow::Result<()> {
+        let tasks = Arc::new(inmemory::InMemoryTasks::new(
+            Arc::new(inmemory::InMemoryActivity::new()),
+            Arc::new(inmemory::InMemorySync::new()),
+            Arc::new(inmemory::InMemoryWebhooks::new()),
+        ));
+        let service = ViewsService::new(Arc::new(inmemory::InMemoryViews::new()), tasks.clone());
+
+        let categories = tasks.add_categories(USER_ID, &["ToDo", "Done"]).await?;
+        let (todo, done) = (&categories[0].category_id, &categories[1].category_id);
+        let label_id = tasks
+            .create_label(
+                USER_ID,
+                &LabelData {
+                    name: "bug".to_string(),
+                    color: "#ff0000".to_string(),
+                },
+            )
+            .await?;
+
+        let now = Utc::now();
+        let mut task_ids = Vec::new();
+        for (label, category_id, priority, due_in_days) in [
+            ("later", todo, TaskPriority::High, Some(5)),
+            ("no due date", done, TaskPriority::Urgent, None),
+            ("sooner", done, TaskPriority::Low, Some(1)),
+            ("not a bug", todo, TaskPriority::High, Some(2)),
+        ] {
+            let task_id = tasks
+                .create_task(
+                    USER_ID,
+                    &TaskData {
+                        due_at: due_in_days.map(|days| now + TimeDelta::days(days)),
+                        ..task_data(label, category_id, priority)
+                    },
+                    &ActivityRecord::now(USER_ID, USER_ID, vec![ActivityKind::Created]),
+                )
+                .await?;
+
+            if label != "not a bug" {
+                tasks
+                    .set_label_assigned(USER_ID, &task_id, &label_id, true)
+                    .await?;
+            }
+            task_ids.push(task_id);
+        }
+
+        let view = service
+            .create_view(
+                USER_ID,
+                USER_ID,
+                ViewData {
+                    sort: ViewSort::DueAt,
+                    group_by: ViewGrouping::Column,
+                    ..view_data("My bugs", "label:bug")
+                },
+            )
+            .await?
+            .unwrap();
+
+        let (_, groups) = service
+            .run_view(USER_ID, USER_ID, &view.view_id)
+            .await?
+            .unwrap();
+
+        let grouped: Vec<(Option<&str>, Vec<&str>)> = groups
+            .iter()
+            .map(|g| {
+                (
+                    g.key.as_deref(),
+                    g.tasks.iter().map(|t| t.label.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            grouped,
+            [
+                (Some(todo.as_str()), vec!["later"]),
+                (Some(done.as_str()), vec!["sooner", "no due date"]),
+            ]
+        );
+
+        let view = service
+            .modify_view(
+                USER_ID,
+                USER_ID,
+                &view.view_id,
+                ViewData {
+                    sort: ViewSort::Priority,
+                    descending: true,
+                    ..view_data("Everything", "")
+                },
+            )
+            .await?
+            .unwrap();
+
+        let (_, groups) = service
+            .run_view(USER_ID, USER_ID, &view.view_id)
+            .await?
+            .unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].tasks[0].label, "no due date");
+        assert_eq!(groups[0].tasks[3].label, "sooner");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn only_shared_views_are_visible_to_others() -> anyhow::Result<()> {
+        let service = ViewsService::new(
+            Arc::new(inmemory::InMemoryViews::new()),
+            Arc::new(inmemory::InMemoryTasks::new(
+                Arc::new(inmemory::InMemoryActivity::new()),
+                Arc::new(inmemory::InMemorySync::new()),
+                Arc::new(inmemory::InMemoryWebhooks::new()),
+            )),
+        );
+
+        let private = service
+            .create_view(USER_ID, USER_ID, view_data("Private", "due:<0d"))
+            .await?
+            .unwrap();
+        let shared = service
+            .create_view(
+                USER_ID,
+                USER_ID,
+                ViewData {
+                    shared: true,
+                    ..view_data("Shared", "priority:urgent")
+                },
+            )
+            .await?
+            .unwrap();
+
+        let names = |views: Vec<ViewDescription>| -> Vec<String> {
+            views.into_iter().map(|v| v.data.name).collect()
+        };
+        assert_eq!(
+            names(service.fetch_views(USER_ID, USER_ID).await?),
+            ["Private", "Shared"]
+        );
+        assert_eq!(
+            names(service.fetch_views(OTHER_USER_ID, USER_ID).await?),
+            ["Shared"]
+        );
+
+        let result = service
+            .fetch_view(OTHER_USER_ID, USER_ID, &private.view_id)
+            .await?;
+        assert!(matches!(result, Err(ViewError::ViewNotFound)));
+
+        let result = service
+            .delete_view(OTHER_USER_ID, USER_ID, &shared.view_id)
+            .await?;
+        assert!(matches!(result, Err(ViewError::NotOwner)));
+
+        let result = service
+            .create_view(USER_ID, USER_ID, view_data("Broken", "due:<tomorrow"))
+            .await?;
+        assert!(matches!(result, Err(ViewError::InvalidFilter(err)) if err.position == 5));
+
+        Ok(())
+    }
+}
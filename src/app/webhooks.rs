@@ -0,0 +1,818 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use sha2::Sha256;
+
+use crate::model::{
+    tasks::TaskCategoryDescription,
+    webhooks::{
+        generate_webhook_secret, DeliveryId, DeliveryStatus, WebhookData, WebhookDelivery,
+        WebhookDescription, WebhookEvent, WebhookEventKind,
+    },
+    BoardId, UserId,
+};
+
+use super::{
+    events::BoardEventKind,
+    repositories::{TasksRepository, WebhookRenderer, WebhookSender, WebhooksRepository},
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Events that webhooks can subscribe to.
+pub const WEBHOOK_EVENT_TYPES: &[&str] = &[
+    "task_created",
+    "task_updated",
+    "task_moved",
+    "task_deleted",
+    "category_created",
+    "category_updated",
+    "category_removed",
+    "category_restored",
+];
+
+/// Event sent by the "send test event" action, regardless of the event filter.
+pub const TEST_EVENT_TYPE: &str = "ping";
+
+pub const MAX_WEBHOOKS_PER_BOARD: usize = 20;
+
+const MAX_URL_LENGTH: usize = 2048;
+
+/// Number of attempts after which a delivery is dead-lettered.
+pub const MAX_ATTEMPTS: u32 = 8;
+
+/// Delay before the first retry, doubled after each failed attempt.
+const INITIAL_RETRY_DELAY: TimeDelta = TimeDelta::seconds(30);
+
+const MAX_RETRY_DELAY: TimeDelta = TimeDelta::hours(1);
+
+/// Number of deliveries claimed at once by [`WebhooksService::deliver_due`].
+const DELIVERY_BATCH_SIZE: i64 = 20;
+
+/// How long a claimed delivery is not claimed again. Longer than a request may take,
+/// so that a delivery is retried only if the worker stopped while sending it.
+const DELIVERY_LEASE: TimeDelta = TimeDelta::minutes(2);
+
+/// Number of recorded events claimed at once by [`WebhooksService::queue_recorded_events`].
+const EVENT_BATCH_SIZE: i64 = 100;
+
+/// How long a claimed event is not claimed again, so that it is claimed again
+/// only if the worker stopped before queueing its deliveries.
+const EVENT_LEASE: TimeDelta = TimeDelta::minutes(1);
+
+/// How long the delivered and dead-lettered deliveries are kept in the log.
+const DELIVERY_RETENTION: TimeDelta = TimeDelta::days(30);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum WebhookError {
+    WebhookNotFound,
+    /// The URL is not an absolute `http` or `https` URL, or its host resolves to an address
+    /// that may not be reached, e.g. of a private network.
+    InvalidUrl,
+    UnknownEventType(String),
+    TooManyWebhooks,
+}
+
+pub struct WebhooksService {
+    webhooks: Arc<dyn WebhooksRepository>,
+    tasks: Arc<dyn TasksRepository>,
+    sender: Arc<dyn WebhookSender>,
+    renderer: Arc<dyn WebhookRenderer>,
+}
+
+impl WebhooksService {
+    pub fn new(
+        webhooks: Arc<dyn WebhooksRepository>,
+        tasks: Arc<dyn TasksRepository>,
+        sender: Arc<dyn WebhookSender>,
+        renderer: Arc<dyn WebhookRenderer>,
+    ) -> Self {
+        Self {
+            webhooks,
+            tasks,
+            sender,
+            renderer,
+        }
+    }
+
+    pub async fn fetch_webhooks(
+        &self,
+        board_id: BoardId,
+    ) -> anyhow::Result<Vec<WebhookDescription>> {
+        self.webhooks.fetch_webhooks(board_id).await
+    }
+
+    /// Registers the webhook with a new secret. An empty list of event types subscribes to all the events.
+    pub async fn create_webhook(
+        &self,
+        board_id: BoardId,
+        url: &str,
+        mut event_types: Vec<String>,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Result<WebhookDescription, WebhookError>> {
+        let url = url.trim();
+        let Some(parsed_url) = parse_url(url) else {
+            return Ok(Err(WebhookError::InvalidUrl));
+        };
+
+        if let Some(unknown) = event_types
+            .iter()
+            .find(|t| !WEBHOOK_EVENT_TYPES.contains(&t.as_str()))
+        {
+            return Ok(Err(WebhookError::UnknownEventType(unknown.clone())));
+        }
+        event_types.sort();
+        event_types.dedup();
+
+        if self.webhooks.fetch_webhooks(board_id).await?.len() >= MAX_WEBHOOKS_PER_BOARD {
+            return Ok(Err(WebhookError::TooManyWebhooks));
+        }
+
+        // Checked again by the sender on each delivery, since the host may resolve differently later.
+        if !self.sender.is_allowed_url(&parsed_url).await {
+            return Ok(Err(WebhookError::InvalidUrl));
+        }
+
+        let data = WebhookData {
+            url: url.to_string(),
+            event_types,
+            secret: generate_webhook_secret(),
+            created_at: now,
+        };
+        let webhook_id = self.webhooks.create_webhook(board_id, &data).await?;
+
+        Ok(Ok(WebhookDescription {
+            webhook_id,
+            board_id,
+            data,
+        }))
+    }
+
+    /// Deletes the webhook together with its delivery log.
+    pub async fn delete_webhook(
+        &self,
+        board_id: BoardId,
+        webhook_id: &str,
+    ) -> anyhow::Result<Result<(), WebhookError>> {
+        if self
+            .fetch_board_webhook(board_id, webhook_id)
+            .await?
+            .is_none()
+        {
+            return Ok(Err(WebhookError::WebhookNotFound));
+        }
+
+        self.webhooks.delete_webhook(webhook_id).await?;
+
+        Ok(Ok(()))
+    }
+
+    /// Returns at most `limit` most recent deliveries of the webhook.
+    pub async fn fetch_deliveries(
+        &self,
+        board_id: BoardId,
+        webhook_id: &str,
+        limit: i64,
+    ) -> anyhow::Result<Result<Vec<WebhookDelivery>, WebhookError>> {
+        if self
+            .fetch_board_webhook(board_id, webhook_id)
+            .await?
+            .is_none()
+        {
+            return Ok(Err(WebhookError::WebhookNotFound));
+        }
+
+        Ok(Ok(self
+            .webhooks
+            .fetch_deliveries(webhook_id, limit)
+            .await?))
+    }
+
+    /// Queues a [`TEST_EVENT_TYPE`] delivery with the payload to the webhook.
+    pub async fn send_test_event(
+        &self,
+        board_id: BoardId,
+        webhook_id: &str,
+        payload: &str,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Result<DeliveryId, WebhookError>> {
+        if self
+            .fetch_board_webhook(board_id, webhook_id)
+            .await?
+            .is_none()
+        {
+            return Ok(Err(WebhookError::WebhookNotFound));
+        }
+
+        let delivery_id = self
+            .webhooks
+            .enqueue_delivery(webhook_id, TEST_EVENT_TYPE, payload, now)
+            .await?;
+
+        Ok(Ok(delivery_id))
+    }
+
+    /// Queues a delivery of the event to each webhook of the board that subscribes to it.
+    /// Returns the number of the queued deliveries.
+    pub async fn enqueue_event(
+        &self,
+        board_id: BoardId,
+        event_type: &str,
+        payload: &str,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<usize> {
+        let mut count = 0;
+
+        for webhook in self.webhooks.fetch_webhooks(board_id).await? {
+            if webhook.accepts(event_type) {
+                self.webhooks
+                    .enqueue_delivery(&webhook.webhook_id, event_type, payload, now)
+                    .await?;
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Queues the deliveries of the events recorded together with the changes of the boards.
+    /// The payloads describe the changed tasks and categories as they are at `now`, and the events
+    /// of those deleted since are dropped, as the deletions have events of their own.
+    /// Returns the number of the processed events.
+    pub async fn queue_recorded_events(&self, now: DateTime<Utc>) -> anyhow::Result<usize> {
+        let events = self
+            .webhooks
+            .claim_events(now, now + EVENT_LEASE, EVENT_BATCH_SIZE)
+            .await?;
+
+        for event in &events {
+            if let Some(kind) = self.board_event(event).await? {
+                let payload = self
+                    .renderer
+                    .render(event.board_id, &kind, event.created_at)?;
+
+                self.enqueue_event(event.board_id, kind.name(), &payload, now)
+                    .await?;
+            }
+
+            self.webhooks.delete_event(event.event_id).await?;
+        }
+
+        Ok(events.len())
+    }
+
+    /// Attempts the deliveries due at `now`. Returns the number of the attempts.
+    pub async fn deliver_due(&self, now: DateTime<Utc>) -> anyhow::Result<usize> {
+        let deliveries = self
+            .webhooks
+            .claim_due_deliveries(now, now + DELIVERY_LEASE, DELIVERY_BATCH_SIZE)
+            .await?;
+
+        let count = deliveries.len();
+        for delivery in deliveries {
+            self.attempt_delivery(delivery, now).await?;
+        }
+
+        Ok(count)
+    }
+
+    /// Deletes the finished deliveries that are older than the retention period.
+    pub async fn purge_deliveries(&self, now: DateTime<Utc>) -> anyhow::Result<u64> {
+        self.webhooks
+            .delete_finished_deliveries(now - DELIVERY_RETENTION)
+            .await
+    }
+
+    async fn fetch_board_webhook(
+        &self,
+        board_id: BoardId,
+        webhook_id: &str,
+    ) -> anyhow::Result<Option<WebhookDescription>> {
+        Ok(self
+            .webhooks
+            .fetch_webhook(webhook_id)
+            .await?
+            .filter(|w| w.board_id == board_id))
+    }
+
+    /// Returns the event with the current state of the changed item, or `None` if it no longer exists.
+    async fn board_event(&self, event: &WebhookEvent) -> anyhow::Result<Option<BoardEventKind>> {
+        // The board belongs to the user with the same ID.
+        let user_id = event.board_id;
+
+        let kind = match &event.kind {
+            WebhookEventKind::TaskCreated(task_id) => self
+                .tasks
+                .fetch_task(user_id, task_id)
+                .await?
+                .map(BoardEventKind::TaskCreated),
+            WebhookEventKind::TaskUpdated(task_id) => self
+                .tasks
+                .fetch_task(user_id, task_id)
+                .await?
+                .map(BoardEventKind::TaskUpdated),
+            WebhookEventKind::TaskMoved {
+                task_id,
+                from_category_id,
+            } => self.tasks.fetch_task(user_id, task_id).await?.map(|task| {
+                BoardEventKind::TaskMoved {
+                    task,
+                    from_category_id: from_category_id.clone(),
+                }
+            }),
+            WebhookEventKind::TaskDeleted(task_id) => Some(BoardEventKind::TaskDeleted {
+                task_id: task_id.clone(),
+            }),
+            WebhookEventKind::CategoryCreated(category_id) => self
+                .fetch_category(user_id, category_id)
+                .await?
+                .map(BoardEventKind::CategoryCreated),
+            WebhookEventKind::CategoryUpdated(category_id) => self
+                .fetch_category(user_id, category_id)
+                .await?
+                .map(BoardEventKind::CategoryUpdated),
+            WebhookEventKind::CategoryRemoved(category_id) => {
+                Some(BoardEventKind::CategoryRemoved {
+                    category_id: category_id.clone(),
+                })
+            }
+            WebhookEventKind::CategoryRestored(category_id) => self
+                .fetch_category(user_id, category_id)
+                .await?
+                .map(BoardEventKind::CategoryRestored),
+        };
+
+        Ok(kind)
+    }
+
+    async fn fetch_category(
+        &self,
+        user_id: UserId,
+        category_id: &str,
+    ) -> anyhow::Result<Option<TaskCategoryDescription>> {
+        Ok(self
+            .tasks
+            .fetch_categories(user_id)
+            .await?
+            .into_iter()
+            .find(|c| c.category_id == category_id))
+    }
+
+    async fn attempt_delivery(
+        &self,
+        mut delivery: WebhookDelivery,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        // The webhook has been deleted after the delivery was claimed.
+        let Some(webhook) = self.webhooks.fetch_webhook(&delivery.webhook_id).await? else {
+            return Ok(());
+        };
+
+        let timestamp = now.timestamp().to_string();
+        let headers = vec![
+            ("Content-Type".to_string(), "application/json".to_string()),
+            ("X-Webhook-Id".to_string(), webhook.webhook_id.clone()),
+            ("X-Webhook-Event".to_string(), delivery.event_type.clone()),
+            (
+                "X-Webhook-Delivery".to_string(),
+                delivery.delivery_id.clone(),
+            ),
+            ("X-Webhook-Timestamp".to_string(), timestamp.clone()),
+            (
+                "X-Webhook-Signature".to_string(),
+                format!(
+                    "sha256={}",
+                    sign(&webhook.data.secret, &timestamp, &delivery.payload)
+                ),
+            ),
+        ];
+
+        let result = self
+            .sender
+            .post(&webhook.data.url, &headers, &delivery.payload)
+            .await;
+
+        delivery.attempts += 1;
+        delivery.last_status_code = result.as_ref().ok().copied();
+        delivery.last_error = match result {
+            Ok(status) if (200..300).contains(&status) => None,
+            Ok(status) => Some(format!("receiver responded with status {}", status)),
+            Err(err) => Some(format!("{:#}", err)),
+        };
+
+        if delivery.last_error.is_none() {
+            delivery.status = DeliveryStatus::Delivered;
+            delivery.next_attempt_at = None;
+        } else if delivery.attempts >= MAX_ATTEMPTS {
+            log::warn!(
+                "Delivery {} of webhook {} is dead-lettered after {} attempts",
+                delivery.delivery_id,
+                webhook.webhook_id,
+                delivery.attempts
+            );
+            delivery.status = DeliveryStatus::DeadLetter;
+            delivery.next_attempt_at = None;
+        } else {
+            delivery.next_attempt_at = Some(now + retry_delay(delivery.attempts));
+        }
+
+        self.webhooks.update_delivery(&delivery).await
+    }
+}
+
+/// Returns the URL if it is an absolute `http` or `https` URL.
+fn parse_url(url: &str) -> Option<Url> {
+    if url.len() > MAX_URL_LENGTH {
+        return None;
+    }
+
+    Url::parse(url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https") && url.host_str().is_some())
+}
+
+/// Delay after the failed attempt: 30 seconds after the first one, doubled after each next one, at most an hour.
+fn retry_delay(attempts: u32) -> TimeDelta {
+    let factor = 1 << attempts.saturating_sub(1).min(16);
+
+    (INITIAL_RETRY_DELAY * factor).min(MAX_RETRY_DELAY)
+}
+
+/// Signs `<timestamp>.<body>` with the secret of the webhook. Receivers verify the signature
+/// and reject old timestamps, so that a captured delivery cannot be replayed.
+fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{DateTime, TimeDelta, Utc};
+    use wiremock::{
+        matchers::{header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::{
+        app::{
+            events::BoardEventKind,
+            repositories::{TasksRepository, WebhookRenderer},
+        },
+        model::{
+            activity::{ActivityKind, ActivityRecord},
+            lifecycle::Lifecycle,
+            tasks::{TaskData, TaskPatch},
+            webhooks::DeliveryStatus,
+            BoardId,
+        },
+        storage::{
+            inmemory::{InMemoryActivity, InMemorySync, InMemoryTasks, InMemoryWebhooks},
+            webhooks::HttpWebhookSender,
+        },
+    };
+
+    use super::{retry_delay, sign, WebhookError, WebhooksService, MAX_ATTEMPTS, TEST_EVENT_TYPE};
+
+    const BOARD_ID: BoardId = BoardId::from_raw(1);
+
+    /// Renders the name of the event followed by the label of the task, if any.
+    struct TestRenderer;
+
+    impl WebhookRenderer for TestRenderer {
+        fn render(
+            &self,
+            _board_id: BoardId,
+            event: &BoardEventKind,
+            _created_at: DateTime<Utc>,
+        ) -> anyhow::Result<String> {
+            Ok(match event {
+                BoardEventKind::TaskCreated(task)
+                | BoardEventKind::TaskUpdated(task)
+                | BoardEventKind::TaskMoved { task, .. } => {
+                    format!("{} {}", event.name(), task.label)
+                }
+                _ => event.name().to_string(),
+            })
+        }
+    }
+
+    fn create_service() -> (WebhooksService, Arc<InMemoryTasks>) {
+        // The receivers of the tests listen on the loopback address.
+        create_service_with_sender(HttpWebhookSender::with_address_filter(|_| true))
+    }
+
+    fn create_service_with_sender(
+        sender: HttpWebhookSender,
+    ) -> (WebhooksService, Arc<InMemoryTasks>) {
+        let webhooks = Arc::new(InMemoryWebhooks::new());
+        let tasks = Arc::new(InMemoryTasks::new(
+            Arc::new(InMemoryActivity::new()),
+            Arc::new(InMemorySync::new()),
+            webhooks.clone(),
+        ));
+
+        let service = WebhooksService::new(
+            webhooks,
+            tasks.clone(),
+            Arc::new(sender),
+            Arc::new(TestRenderer),
+        );
+
+        (service, tasks)
+    }
+
+    #[tokio::test]
+    async fn signed_event_is_delivered_to_receiver() -> anyhow::Result<()> {
+        let receiver = MockServer::start().await;
+        let (service, _) = create_service();
+        let now = Utc::now();
+
+        let webhook = service
+            .create_webhook(
+                BOARD_ID,
+                &format!("{}/hooks", receiver.uri()),
+                vec!["task_created".to_string()],
+                now,
+            )
+            .await?
+            .unwrap();
+
+        let payload = r#"{"event":"task_created"}"#;
+        let signature = format!(
+            "sha256={}",
+            sign(&webhook.data.secret, &now.timestamp().to_string(), payload)
+        );
+
+        Mock::given(method("POST"))
+            .and(path("/hooks"))
+            .and(header("x-webhook-event", "task_created"))
+            .and(header("x-webhook-signature", signature.as_str()))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&receiver)
+            .await;
+
+        // The webhook does not subscribe to the other events.
+        assert_eq!(
+            service
+                .enqueue_event(BOARD_ID, "task_deleted", "{}", now)
+                .await?,
+            0
+        );
+        assert_eq!(
+            service
+                .enqueue_event(BOARD_ID, "task_created", payload, now)
+                .await?,
+            1
+        );
+
+        assert_eq!(service.deliver_due(now).await?, 1);
+        assert_eq!(service.deliver_due(now).await?, 0);
+
+        let deliveries = service
+            .fetch_deliveries(BOARD_ID, &webhook.webhook_id, 10)
+            .await?
+            .unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].status, DeliveryStatus::Delivered);
+        assert_eq!(deliveries[0].last_status_code, Some(204));
+        assert_eq!(deliveries[0].attempts, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn failed_delivery_is_retried_then_dead_lettered() -> anyhow::Result<()> {
+        let receiver = MockServer::start().await;
+        let (service, _) = create_service();
+        let mut now = Utc::now();
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(MAX_ATTEMPTS as u64)
+            .mount(&receiver)
+            .await;
+
+        let webhook = service
+            .create_webhook(BOARD_ID, &receiver.uri(), Vec::new(), now)
+            .await?
+            .unwrap();
+        service
+            .send_test_event(BOARD_ID, &webhook.webhook_id, "{}", now)
+            .await?
+            .unwrap();
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            assert_eq!(service.deliver_due(now).await?, 1);
+
+            let delivery = service
+                .fetch_deliveries(BOARD_ID, &webhook.webhook_id, 1)
+                .await?
+                .unwrap()
+                .remove(0);
+            assert_eq!(delivery.event_type, TEST_EVENT_TYPE);
+            assert_eq!(delivery.attempts, attempt);
+            assert_eq!(delivery.last_status_code, Some(500));
+
+            if attempt < MAX_ATTEMPTS {
+                assert_eq!(delivery.status, DeliveryStatus::Pending);
+                // Not retried before the backoff delay.
+                assert_eq!(service.deliver_due(now).await?, 0);
+                now = delivery.next_attempt_at.unwrap();
+            } else {
+                assert_eq!(delivery.status, DeliveryStatus::DeadLetter);
+                assert_eq!(delivery.next_attempt_at, None);
+            }
+        }
+
+        assert_eq!(service.deliver_due(now + TimeDelta::days(1)).await?, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn webhook_is_validated() -> anyhow::Result<()> {
+        let (service, _) = create_service_with_sender(HttpWebhookSender::new());
+        let now = Utc::now();
+
+        for url in [
+            "ftp://example.com",
+            "http://127.0.0.1:8000/hooks",
+            "http://localhost/hooks",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://192.168.0.10/hooks",
+        ] {
+            assert_eq!(
+                service
+                    .create_webhook(BOARD_ID, url, Vec::new(), now)
+                    .await?
+                    .unwrap_err(),
+                WebhookError::InvalidUrl,
+                "{}",
+                url
+            );
+        }
+        assert_eq!(
+            service
+                .create_webhook(
+                    BOARD_ID,
+                    "https://1.1.1.1",
+                    vec!["label_created".to_string()],
+                    now
+                )
+                .await?
+                .unwrap_err(),
+            WebhookError::UnknownEventType("label_created".to_string())
+        );
+
+        let webhook = service
+            .create_webhook(BOARD_ID, "https://1.1.1.1", Vec::new(), now)
+            .await?
+            .unwrap();
+        assert_eq!(
+            service
+                .delete_webhook(BoardId::from_raw(2), &webhook.webhook_id)
+                .await?,
+            Err(WebhookError::WebhookNotFound)
+        );
+        assert_eq!(
+            service
+                .delete_webhook(BOARD_ID, &webhook.webhook_id)
+                .await?,
+            Ok(())
+        );
+
+        assert_eq!(retry_delay(1), TimeDelta::seconds(30));
+        assert_eq!(retry_delay(3), TimeDelta::minutes(2));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), TimeDelta::hours(1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn recorded_changes_are_queued_with_current_state() -> anyhow::Result<()> {
+        let (service, tasks) = create_service();
+        let now = Utc::now();
+        let activity = |kinds| ActivityRecord::now(BOARD_ID, BOARD_ID, kinds);
+
+        // Nothing is recorded before the board has webhooks.
+        let categories = tasks.add_categories(BOARD_ID, &["ToDo", "Done"]).await?;
+        assert_eq!(service.queue_recorded_events(now).await?, 0);
+
+        let webhook = service
+            .create_webhook(BOARD_ID, "https://1.1.1.1/hooks", Vec::new(), now)
+            .await?
+            .unwrap();
+
+        let data = |label: &str| TaskData {
+            label: label.to_string(),
+            description: String::new(),
+            category_id: categories[0].category_id.clone(),
+            start_at: None,
+            due_at: None,
+            priority: Default::default(),
+        };
+
+        let task_id = tasks
+            .create_task(
+                BOARD_ID,
+                &data("Write"),
+                &activity(vec![ActivityKind::Created]),
+            )
+            .await?;
+        let moved = ActivityKind::Moved {
+            from_category_id: categories[0].category_id.clone(),
+            to_category_id: categories[1].category_id.clone(),
+        };
+        tasks
+            .update_task(
+                BOARD_ID,
+                &task_id,
+                &TaskPatch {
+                    category_id: Some(categories[1].category_id.clone()),
+                    ..Default::default()
+                },
+                None,
+                &activity(vec![moved]),
+            )
+            .await?
+            .unwrap();
+        tasks
+            .rename_category(BOARD_ID, &categories[1].category_id, "Finished")
+            .await?
+            .unwrap();
+
+        // Trashed before its creation is queued.
+        let trashed_id = tasks
+            .create_task(
+                BOARD_ID,
+                &data("Drop"),
+                &activity(vec![ActivityKind::Created]),
+            )
+            .await?;
+        tasks
+            .set_task_lifecycle(
+                BOARD_ID,
+                &trashed_id,
+                Lifecycle::default().trashed(now),
+                &activity(vec![ActivityKind::Deleted]),
+            )
+            .await?
+            .unwrap();
+
+        // Renamed after the events were recorded.
+        tasks
+            .update_task(
+                BOARD_ID,
+                &task_id,
+                &TaskPatch {
+                    label: Some("Write docs".to_string()),
+                    ..Default::default()
+                },
+                None,
+                &activity(Vec::new()),
+            )
+            .await?
+            .unwrap();
+
+        // Changes of the other boards are not recorded for this one.
+        tasks
+            .add_categories(BoardId::from_raw(2), &["Other"])
+            .await?;
+
+        assert_eq!(service.queue_recorded_events(now).await?, 6);
+        assert_eq!(service.queue_recorded_events(now).await?, 0);
+
+        let mut deliveries = service
+            .fetch_deliveries(BOARD_ID, &webhook.webhook_id, 10)
+            .await?
+            .unwrap();
+        deliveries.reverse();
+
+        let payloads: Vec<&str> = deliveries.iter().map(|d| d.payload.as_str()).collect();
+        assert_eq!(
+            payloads,
+            [
+                "task_created Write docs",
+                "task_moved Write docs",
+                "category_updated",
+                "task_deleted",
+                "task_updated Write docs",
+            ]
+        );
+        assert!(deliveries
+            .iter()
+            .all(|d| d.status == DeliveryStatus::Pending && d.payload.starts_with(&d.event_type)));
+
+        Ok(())
+    }
+}
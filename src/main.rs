@@ -8,27 +8,98 @@ mod app;
 mod model;
 mod storage;
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use api::{initialize_api, Context};
+use api::{initialize_api, serve_smtp, Context, JsonWebhookRenderer};
 use app::{
+    attachments::{AttachmentLimits, AttachmentsService},
     auth::AuthService,
-    repositories::{SessionsRepository, TasksRepository, UsersRepositry},
+    calendar::CalendarService,
+    comments::CommentsService,
+    events::EventBus,
+    idempotency::IdempotencyService,
+    inbound::InboundEmailService,
+    presence::PresenceTracker,
+    repositories::{
+        ActivityRepository, AttachmentsRepository, BlobStore, CommentsRepository,
+        IdempotencyRepository, SearchRepository, SessionsRepository, SyncRepository,
+        TasksRepository, UsersRepositry, ViewsRepository, WebhooksRepository,
+    },
+    search::SearchService,
     tasks::TasksService,
+    views::ViewsService,
+    webhooks::WebhooksService,
 };
+use chrono::{TimeDelta, Utc};
 use storage::{
+    blobs::{LocalBlobStore, S3BlobStore, S3Config},
     db::{self, DatabaseConnection, DatabaseConnectionRef},
     inmemory,
+    webhooks::HttpWebhookSender,
 };
 
 struct Environment {
     database_url: Option<String>,
+    blobs_dir: Option<String>,
+    s3: Option<S3Config>,
+    trash_retention: TimeDelta,
+    idempotency_ttl: TimeDelta,
+    inbound_email_domain: String,
+    smtp_listen: Option<String>,
 }
 
+/// How long the trashed items are kept, unless `TRASH_RETENTION_DAYS` is set.
+const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+
+/// How long the responses to the requests with idempotency keys are replayed,
+/// unless `IDEMPOTENCY_TTL_HOURS` is set.
+const DEFAULT_IDEMPOTENCY_TTL_HOURS: i64 = 24;
+
+/// Domain of the inbound email addresses of the boards, unless `INBOUND_EMAIL_DOMAIN` is set.
+const DEFAULT_INBOUND_EMAIL_DOMAIN: &str = "localhost";
+
+/// How often the expired items are purged from the trash, and the expired idempotency keys
+/// and the old webhook deliveries are deleted.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How often the due webhook deliveries are checked.
+const WEBHOOK_DELIVERY_INTERVAL: Duration = Duration::from_secs(1);
+
 fn read_environment() -> Environment {
     let database_url = std::env::var("DATABASE").ok();
+    let blobs_dir = std::env::var("BLOBS_DIR").ok();
+
+    let s3 = std::env::var("S3_BUCKET").ok().map(|bucket| S3Config {
+        endpoint: std::env::var("S3_ENDPOINT")
+            .unwrap_or_else(|_| "https://s3.amazonaws.com".to_string()),
+        region: std::env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+        bucket,
+        access_key: std::env::var("S3_ACCESS_KEY").unwrap_or_default(),
+        secret_key: std::env::var("S3_SECRET_KEY").unwrap_or_default(),
+    });
 
-    Environment { database_url }
+    let trash_retention_days = std::env::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+
+    let idempotency_ttl_hours = std::env::var("IDEMPOTENCY_TTL_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse().ok())
+        .unwrap_or(DEFAULT_IDEMPOTENCY_TTL_HOURS);
+
+    let inbound_email_domain = std::env::var("INBOUND_EMAIL_DOMAIN")
+        .unwrap_or_else(|_| DEFAULT_INBOUND_EMAIL_DOMAIN.to_string());
+
+    Environment {
+        database_url,
+        blobs_dir,
+        s3,
+        trash_retention: TimeDelta::days(trash_retention_days),
+        idempotency_ttl: TimeDelta::hours(idempotency_ttl_hours),
+        inbound_email_domain,
+        smtp_listen: std::env::var("SMTP_LISTEN").ok(),
+    }
 }
 
 fn init_logging() {
@@ -39,32 +110,110 @@ struct Repositories {
     users: Arc<dyn UsersRepositry>,
     sessions: Arc<dyn SessionsRepository>,
     tasks: Arc<dyn TasksRepository>,
+    comments: Arc<dyn CommentsRepository>,
+    attachments: Arc<dyn AttachmentsRepository>,
+    blobs: Arc<dyn BlobStore>,
+    activity: Arc<dyn ActivityRepository>,
+    search: Arc<dyn SearchRepository>,
+    views: Arc<dyn ViewsRepository>,
+    sync: Arc<dyn SyncRepository>,
+    idempotency: Arc<dyn IdempotencyRepository>,
+    webhooks: Arc<dyn WebhooksRepository>,
 }
 
 fn create_inmemory_repositories() -> Repositories {
+    let activity = Arc::new(inmemory::InMemoryActivity::new());
+    let sync = Arc::new(inmemory::InMemorySync::new());
+    let webhooks = Arc::new(inmemory::InMemoryWebhooks::new());
+    let tasks = Arc::new(inmemory::InMemoryTasks::new(
+        activity.clone(),
+        sync.clone(),
+        webhooks.clone(),
+    ));
+    let comments = Arc::new(inmemory::InMemoryComments::new());
+
     Repositories {
         sessions: Arc::new(inmemory::InMemorySessions::new()),
         users: Arc::new(inmemory::InMemoryUsers::new()),
-        tasks: Arc::new(inmemory::InMemoryTasks::new()),
+        search: Arc::new(inmemory::InMemorySearch::new(
+            tasks.clone(),
+            comments.clone(),
+        )),
+        tasks,
+        comments,
+        attachments: Arc::new(inmemory::InMemoryAttachments::new()),
+        blobs: Arc::new(inmemory::InMemoryBlobs::new()),
+        activity,
+        views: Arc::new(inmemory::InMemoryViews::new()),
+        sync,
+        idempotency: Arc::new(inmemory::InMemoryIdempotency::new()),
+        webhooks,
     }
 }
 
-fn create_db_repositories(db: DatabaseConnectionRef) -> Repositories {
+fn create_db_repositories(db: DatabaseConnectionRef, blobs: Arc<dyn BlobStore>) -> Repositories {
     Repositories {
         sessions: Arc::new(db::DbSessions::new(db.clone())),
         users: Arc::new(db::DbUsers::new(db.clone())),
         tasks: Arc::new(db::DbTasks::new(db.clone())),
+        comments: Arc::new(db::DbComments::new(db.clone())),
+        attachments: Arc::new(db::DbAttachments::new(db.clone())),
+        blobs,
+        activity: Arc::new(db::DbActivity::new(db.clone())),
+        search: Arc::new(db::DbSearch::new(db.clone())),
+        views: Arc::new(db::DbViews::new(db.clone())),
+        sync: Arc::new(db::DbSync::new(db.clone())),
+        idempotency: Arc::new(db::DbIdempotency::new(db.clone())),
+        webhooks: Arc::new(db::DbWebhooks::new(db.clone())),
     }
 }
 
-fn create_context(repos: Repositories) -> Context {
+fn create_context(repos: Repositories, env: &Environment) -> Context {
+    let attachments = Arc::new(AttachmentsService::new(
+        repos.attachments,
+        repos.tasks.clone(),
+        repos.blobs,
+        AttachmentLimits::default(),
+    ));
+    let events = Arc::new(EventBus::new());
+
     Context {
         auth: Box::new(AuthService::new(
             repos.sessions,
+            repos.users.clone(),
+            repos.tasks.clone(),
+            events.clone(),
+        )),
+        calendar: Box::new(CalendarService::new(
+            repos.users.clone(),
+            repos.tasks.clone(),
+        )),
+        inbound: Box::new(InboundEmailService::new(
             repos.users,
+            &env.inbound_email_domain,
+        )),
+        comments: Box::new(CommentsService::new(repos.comments, repos.tasks.clone())),
+        search: Box::new(SearchService::new(repos.search)),
+        views: Box::new(ViewsService::new(repos.views, repos.tasks.clone())),
+        tasks: Box::new(TasksService::new(
             repos.tasks.clone(),
+            events,
+            attachments.clone(),
+            repos.activity,
+            repos.sync,
+        )),
+        attachments,
+        presence: Arc::new(PresenceTracker::new()),
+        idempotency: Box::new(IdempotencyService::new(
+            repos.idempotency,
+            env.idempotency_ttl,
+        )),
+        webhooks: Box::new(WebhooksService::new(
+            repos.webhooks,
+            repos.tasks,
+            Arc::new(HttpWebhookSender::new()),
+            Arc::new(JsonWebhookRenderer),
         )),
-        tasks: Box::new(TasksService::new(repos.tasks)),
     }
 }
 
@@ -76,13 +225,81 @@ async fn create_repos(env: &Environment) -> anyhow::Result<Repositories> {
                 .inspect_err(|err| log::error!("Could not connect to the database: {}", err))?,
         );
 
-        Ok(create_db_repositories(db))
+        Ok(create_db_repositories(db, create_blob_store(env)))
     } else {
         log::info!("Using in-memory repositories, since database URI is not set.");
-        Ok(create_inmemory_repositories())
+
+        let mut repos = create_inmemory_repositories();
+        if env.s3.is_some() || env.blobs_dir.is_some() {
+            repos.blobs = create_blob_store(env);
+        }
+
+        Ok(repos)
     }
 }
 
+/// Creates the store of attached files: S3 if a bucket is set, otherwise a local directory.
+fn create_blob_store(env: &Environment) -> Arc<dyn BlobStore> {
+    if let Some(config) = &env.s3 {
+        log::info!(
+            "Storing blobs in bucket {} at {}",
+            config.bucket,
+            config.endpoint
+        );
+        return Arc::new(S3BlobStore::new(config.clone()));
+    }
+
+    let dir = env.blobs_dir.as_deref().unwrap_or("blobs");
+    log::info!("Storing blobs in directory {}", dir);
+    Arc::new(LocalBlobStore::new(dir))
+}
+
+/// Periodically deletes the items that have been in the trash for longer than `retention`,
+/// the expired idempotency keys and the old webhook deliveries.
+fn spawn_purge(context: Arc<Context>, retention: TimeDelta) {
+    rocket::tokio::spawn(async move {
+        let mut interval = rocket::tokio::time::interval(PURGE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match context.tasks.purge_trash(retention).await {
+                Ok(0) => {}
+                Ok(count) => log::info!("Purged {} tasks from the trash", count),
+                Err(err) => log::error!("Could not purge the trash: {:?}", err),
+            }
+
+            if let Err(err) = context.idempotency.purge_expired().await {
+                log::error!("Could not delete the expired idempotency keys: {:?}", err);
+            }
+
+            if let Err(err) = context.webhooks.purge_deliveries(Utc::now()).await {
+                log::error!("Could not delete the old webhook deliveries: {:?}", err);
+            }
+        }
+    });
+}
+
+/// Periodically queues the deliveries of the recorded board events
+/// and sends the webhook deliveries that are due, including the retries.
+fn spawn_webhook_delivery(context: Arc<Context>) {
+    rocket::tokio::spawn(async move {
+        let mut interval = rocket::tokio::time::interval(WEBHOOK_DELIVERY_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(err) = context.webhooks.queue_recorded_events(Utc::now()).await {
+                log::error!("Could not queue the webhook events: {:?}", err);
+            }
+
+            if let Err(err) = context.webhooks.deliver_due(Utc::now()).await {
+                log::error!("Could not deliver the webhook events: {:?}", err);
+            }
+        }
+    });
+}
+
 #[launch]
 async fn rocket() -> _ {
     init_logging();
@@ -95,7 +312,28 @@ async fn rocket() -> _ {
         .await
         .expect("failed to initialize repositories");
 
-    let context = Arc::new(create_context(repos));
+    let context = Arc::new(create_context(repos, &environment));
+
+    spawn_purge(context.clone(), environment.trash_retention);
+    spawn_webhook_delivery(context.clone());
+
+    if let Some(address) = environment.smtp_listen.clone() {
+        rocket::tokio::spawn(serve_smtp(context.clone(), address));
+    }
 
     initialize_api(context)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{create_context, create_inmemory_repositories, initialize_api, read_environment};
+
+    #[rocket::async_test]
+    async fn routes_do_not_collide() {
+        let context = create_context(create_inmemory_repositories(), &read_environment());
+
+        assert!(initialize_api(Arc::new(context)).ignite().await.is_ok());
+    }
+}
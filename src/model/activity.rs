@@ -0,0 +1,142 @@
+use chrono::{DateTime, Utc};
+
+use super::{tasks::TaskDescription, BoardId, TaskCategoryId, TaskId, UserId};
+
+/// Identifier of an activity entry. Entries of later changes have greater identifiers.
+pub type ActivityId = i64;
+
+/// A change of a task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActivityKind {
+    Created,
+    LabelChanged {
+        from: String,
+        to: String,
+    },
+    DescriptionChanged {
+        from: String,
+        to: String,
+    },
+    Moved {
+        from_category_id: TaskCategoryId,
+        to_category_id: TaskCategoryId,
+    },
+    Archived,
+    /// Moved to the trash.
+    Deleted,
+    /// Restored from the archive or the trash.
+    Restored,
+}
+
+impl ActivityKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityKind::Created => "created",
+            ActivityKind::LabelChanged { .. } => "label_changed",
+            ActivityKind::DescriptionChanged { .. } => "description_changed",
+            ActivityKind::Moved { .. } => "moved",
+            ActivityKind::Archived => "archived",
+            ActivityKind::Deleted => "deleted",
+            ActivityKind::Restored => "restored",
+        }
+    }
+
+    /// Returns the values before and after the change, if the change has them.
+    pub fn values(&self) -> Option<(&str, &str)> {
+        match self {
+            ActivityKind::LabelChanged { from, to }
+            | ActivityKind::DescriptionChanged { from, to } => Some((from, to)),
+            ActivityKind::Moved {
+                from_category_id,
+                to_category_id,
+            } => Some((from_category_id, to_category_id)),
+            ActivityKind::Created
+            | ActivityKind::Archived
+            | ActivityKind::Deleted
+            | ActivityKind::Restored => None,
+        }
+    }
+
+    /// Restores the change from [`ActivityKind::as_str`] and [`ActivityKind::values`].
+    pub fn from_parts(kind: &str, from: Option<String>, to: Option<String>) -> Option<Self> {
+        match kind {
+            "created" => Some(ActivityKind::Created),
+            "archived" => Some(ActivityKind::Archived),
+            "deleted" => Some(ActivityKind::Deleted),
+            "restored" => Some(ActivityKind::Restored),
+            _ => {
+                let (from, to) = (from?, to?);
+
+                match kind {
+                    "label_changed" => Some(ActivityKind::LabelChanged { from, to }),
+                    "description_changed" => Some(ActivityKind::DescriptionChanged { from, to }),
+                    "moved" => Some(ActivityKind::Moved {
+                        from_category_id: from,
+                        to_category_id: to,
+                    }),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// Record of who changed a task, how and when.
+#[derive(Debug, Clone)]
+pub struct ActivityEntry {
+    pub activity_id: ActivityId,
+    pub board_id: BoardId,
+    pub task_id: TaskId,
+    pub actor_id: UserId,
+    pub kind: ActivityKind,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Changes of a task made at once, recorded in the same transaction as the changes themselves.
+#[derive(Debug, Clone)]
+pub struct ActivityRecord {
+    pub board_id: BoardId,
+    pub actor_id: UserId,
+    pub kinds: Vec<ActivityKind>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ActivityRecord {
+    /// Describes the changes made by the actor at the current time.
+    pub fn now(board_id: BoardId, actor_id: UserId, kinds: Vec<ActivityKind>) -> Self {
+        Self {
+            board_id,
+            actor_id,
+            kinds,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Returns the recorded changes between two states of a task.
+pub fn task_changes(previous: &TaskDescription, task: &TaskDescription) -> Vec<ActivityKind> {
+    let mut changes = Vec::new();
+
+    if previous.label != task.label {
+        changes.push(ActivityKind::LabelChanged {
+            from: previous.label.clone(),
+            to: task.label.clone(),
+        });
+    }
+
+    if previous.description != task.description {
+        changes.push(ActivityKind::DescriptionChanged {
+            from: previous.description.clone(),
+            to: task.description.clone(),
+        });
+    }
+
+    if previous.category_id != task.category_id {
+        changes.push(ActivityKind::Moved {
+            from_category_id: previous.category_id.clone(),
+            to_category_id: task.category_id.clone(),
+        });
+    }
+
+    changes
+}
@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+
+use super::{TaskId, UserId};
+
+pub type AttachmentId = String;
+
+/// File attached to a task. The content is kept in the blob store under `blob_key`.
+#[derive(Debug, Clone)]
+pub struct AttachmentDescription {
+    pub attachment_id: AttachmentId,
+    pub task_id: TaskId,
+    /// The user whose quota the file counts towards.
+    pub owner_id: UserId,
+    pub file_name: String,
+    pub content_type: String,
+    /// Size of the file in bytes.
+    pub size: i64,
+    pub blob_key: String,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,4 @@
+use super::UserId;
+
+/// Every user owns exactly one board, so a board is identified by the ID of its owner.
+pub type BoardId = UserId;
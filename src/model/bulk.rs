@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+
+use super::{
+    activity::{task_changes, ActivityKind},
+    tasks::{TaskDescription, TaskPatch},
+    LabelId, TaskId,
+};
+
+/// Change of a single task within a batch applied all at once.
+#[derive(Debug, Clone)]
+pub enum BulkAction {
+    /// Changes the provided fields. Moving the task is a change of its category.
+    Update(TaskPatch),
+    Archive,
+    /// Moves the task to the trash.
+    Delete,
+    AddLabel(LabelId),
+    RemoveLabel(LabelId),
+}
+
+#[derive(Debug, Clone)]
+pub struct BulkOperation {
+    pub task_id: TaskId,
+    pub action: BulkAction,
+}
+
+/// Reason an operation of a batch cannot be applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
+pub enum BulkError {
+    /// There is no such task, or it is in the trash.
+    TaskNotFound,
+    /// The target category does not exist or is in the trash.
+    CategoryNotFound,
+    LabelNotFound,
+}
+
+/// The operation at `index` has failed, so none of the operations of the batch have been applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulkFailure {
+    pub index: usize,
+    pub error: BulkError,
+}
+
+/// State of the task before and after an operation of a batch.
+#[derive(Debug, Clone)]
+pub struct BulkChange {
+    pub previous: TaskDescription,
+    pub task: TaskDescription,
+}
+
+impl BulkChange {
+    /// Returns whether the operation has changed the task.
+    pub fn is_changed(&self) -> bool {
+        self.previous.version != self.task.version
+    }
+}
+
+impl BulkAction {
+    /// Applies the action to the task, incrementing its version if the task changes.
+    /// The existence of the category and the label is checked by the caller.
+    pub fn apply(&self, task: &mut TaskDescription, now: DateTime<Utc>) {
+        let changed = match self {
+            BulkAction::Update(patch) => {
+                patch.apply(task);
+                true
+            }
+            BulkAction::Archive => {
+                let archived = !task.lifecycle.is_archived();
+                if archived {
+                    task.lifecycle = task.lifecycle.archived(now);
+                }
+                archived
+            }
+            BulkAction::Delete => {
+                task.lifecycle = task.lifecycle.trashed(now);
+                true
+            }
+            BulkAction::AddLabel(label_id) => match task.label_ids.binary_search(label_id) {
+                Ok(_) => false,
+                Err(pos) => {
+                    task.label_ids.insert(pos, label_id.clone());
+                    true
+                }
+            },
+            BulkAction::RemoveLabel(label_id) => match task.label_ids.binary_search(label_id) {
+                Ok(pos) => {
+                    task.label_ids.remove(pos);
+                    true
+                }
+                Err(_) => false,
+            },
+        };
+
+        if changed {
+            task.version += 1;
+        }
+    }
+
+    /// Returns the recorded changes made by the action, given the states of the task before and after it.
+    pub fn activity(&self, change: &BulkChange) -> Vec<ActivityKind> {
+        if !change.is_changed() {
+            return Vec::new();
+        }
+
+        match self {
+            BulkAction::Update(_) => task_changes(&change.previous, &change.task),
+            BulkAction::Archive => vec![ActivityKind::Archived],
+            BulkAction::Delete => vec![ActivityKind::Deleted],
+            BulkAction::AddLabel(_) | BulkAction::RemoveLabel(_) => Vec::new(),
+        }
+    }
+}
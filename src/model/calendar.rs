@@ -0,0 +1,36 @@
+use rand::Rng;
+
+/// Secret of the calendar feed of a user. Anyone who knows it can read the feed,
+/// so it is replaced when the feed is reset and dropped when the feed is revoked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarToken(String);
+
+impl CalendarToken {
+    pub fn from_str(token: &str) -> Option<CalendarToken> {
+        if !Self::is_valid_token(token) {
+            return None;
+        }
+
+        Some(Self(token.to_string()))
+    }
+
+    pub fn generate_random() -> CalendarToken {
+        let mut rng = rand::thread_rng();
+
+        let mut bytes: [u8; 20] = [0; 20];
+        bytes.iter_mut().for_each(|b| *b = rng.gen());
+
+        let token = hex::encode(bytes);
+        debug_assert!(Self::is_valid_token(&token));
+
+        Self(token)
+    }
+
+    fn is_valid_token(token: &str) -> bool {
+        token.len() == 40 && token.chars().all(|c| c.is_ascii_hexdigit())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
@@ -0,0 +1,36 @@
+pub type ChecklistItemId = String;
+
+/// Item of the checklist of a task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecklistItem {
+    pub item_id: ChecklistItemId,
+    pub text: String,
+    pub done: bool,
+    /// Position of the item in the checklist, starting at 0. Positions of the items are contiguous.
+    pub position: i32,
+}
+
+/// Changes to a checklist item. Fields that are `None` are left unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct ChecklistItemPatch {
+    pub text: Option<String>,
+    pub done: Option<bool>,
+    /// New position of the item. The items in between are shifted. Positions past the end move the item to the end.
+    pub position: Option<i32>,
+}
+
+/// Number of done items and of all items in the checklist of a task.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChecklistProgress {
+    pub done: i64,
+    pub total: i64,
+}
+
+impl ChecklistProgress {
+    pub fn of(items: &[ChecklistItem]) -> Self {
+        Self {
+            done: items.iter().filter(|item| item.done).count() as i64,
+            total: items.len() as i64,
+        }
+    }
+}
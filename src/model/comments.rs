@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+
+use super::{TaskId, UserId};
+
+pub type CommentId = String;
+
+/// Comment in the thread of a task.
+#[derive(Debug, Clone)]
+pub struct CommentDescription {
+    pub comment_id: CommentId,
+    pub task_id: TaskId,
+    pub author_id: UserId,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+    /// Time of the last edit, if the comment has been edited.
+    pub edited_at: Option<DateTime<Utc>>,
+    /// Deleted comments are kept in the thread, so that the replies still make sense.
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// Previous text of an edited comment.
+#[derive(Debug, Clone)]
+pub struct CommentRevision {
+    pub text: String,
+    /// Time the text was written.
+    pub written_at: DateTime<Utc>,
+}
@@ -0,0 +1,107 @@
+use std::cmp::Ordering;
+
+use chrono::{DateTime, Days, NaiveDate, TimeDelta, Utc};
+
+use super::tasks::TaskPriority;
+
+/// Condition on the tasks of a board, parsed from a filter query such as
+/// `label:bug priority:>=high due:<7d column:"In progress" -archived`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskFilter {
+    /// All the filters match. An empty list matches every task.
+    And(Vec<TaskFilter>),
+    /// At least one of the filters matches.
+    Or(Vec<TaskFilter>),
+    Not(Box<TaskFilter>),
+    /// The task has a label with the name, compared case-insensitively.
+    Label(String),
+    /// The task is in the category with the label, compared case-insensitively.
+    Column(String),
+    Priority(Comparison, TaskPriority),
+    Due(DueFilter),
+    Archived,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Equal => "=",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Greater => ">",
+        }
+    }
+
+    /// Returns whether the comparison holds for the ordering of the left operand relative to the right one.
+    pub fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Less => ordering.is_lt(),
+            Comparison::LessOrEqual => ordering.is_le(),
+            Comparison::Equal => ordering.is_eq(),
+            Comparison::GreaterOrEqual => ordering.is_ge(),
+            Comparison::Greater => ordering.is_gt(),
+        }
+    }
+}
+
+/// Range `[from, to)` of instants. Either end is `None` if the range is unbounded.
+pub type DueRange = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DueFilter {
+    /// The task has no due date.
+    None,
+    Compare(Comparison, DueDate),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DueDate {
+    /// The instant at the offset from the current time.
+    Relative(TimeDelta),
+    /// The day in UTC.
+    Date(NaiveDate),
+}
+
+impl DueDate {
+    /// Returns the range `[start, end)` covered by the date. The range of a relative date is a single instant.
+    fn bounds(self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        match self {
+            DueDate::Relative(offset) => (now + offset, now + offset),
+            DueDate::Date(date) => {
+                let start = date.and_time(Default::default()).and_utc();
+                let end = (date + Days::new(1)).and_time(Default::default()).and_utc();
+
+                (start, end)
+            }
+        }
+    }
+}
+
+impl DueFilter {
+    /// Returns the range of the due dates that match the comparison, or `None` for [`DueFilter::None`].
+    pub fn range(self, now: DateTime<Utc>) -> Option<DueRange> {
+        let DueFilter::Compare(comparison, date) = self else {
+            return None;
+        };
+
+        let (start, end) = date.bounds(now);
+
+        Some(match comparison {
+            Comparison::Less => (None, Some(start)),
+            Comparison::LessOrEqual => (None, Some(end)),
+            Comparison::Equal => (Some(start), Some(end)),
+            Comparison::GreaterOrEqual => (Some(start), None),
+            Comparison::Greater => (Some(end), None),
+        })
+    }
+}
@@ -0,0 +1,25 @@
+use chrono::{DateTime, Utc};
+
+use super::UserId;
+
+/// Response sent to a request with an idempotency key, replayed on the retries of the request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub etag: Option<String>,
+    /// JSON body.
+    pub body: String,
+}
+
+/// Idempotency key sent by a client, with the request it has been used for.
+#[derive(Debug, Clone)]
+pub struct IdempotencyRecord {
+    /// `None` for the requests made before logging in.
+    pub user_id: Option<UserId>,
+    pub key: String,
+    /// Hash of the request, which tells whether a retry is the same request.
+    pub fingerprint: String,
+    /// `None` while the request is being handled.
+    pub response: Option<StoredResponse>,
+    pub created_at: DateTime<Utc>,
+}
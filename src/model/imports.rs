@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+
+use super::{
+    labels::{LabelData, LabelId},
+    tasks::{TaskCategoryDescription, TaskPriority},
+    TaskCategoryId, TaskId,
+};
+
+/// Reference to a category or a label, which either exists on the board
+/// or is created by the import at the given index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportRef<Id> {
+    Existing(Id),
+    New(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedTask {
+    pub category: ImportRef<TaskCategoryId>,
+    pub label: String,
+    pub description: String,
+    pub start_at: Option<DateTime<Utc>>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub priority: TaskPriority,
+    pub labels: Vec<ImportRef<LabelId>>,
+}
+
+/// Categories, labels and tasks created on a board all at once.
+#[derive(Debug, Clone, Default)]
+pub struct BoardImport {
+    /// Labels of the new categories.
+    pub categories: Vec<String>,
+    pub labels: Vec<LabelData>,
+    pub tasks: Vec<ImportedTask>,
+}
+
+/// Items created by an import, in the order of [`BoardImport`].
+#[derive(Debug, Clone, Default)]
+pub struct ImportedItems {
+    pub categories: Vec<TaskCategoryDescription>,
+    pub label_ids: Vec<LabelId>,
+    pub task_ids: Vec<TaskId>,
+}
+
+impl ImportedItems {
+    pub fn category_id<'a>(&'a self, category: &'a ImportRef<TaskCategoryId>) -> &'a str {
+        match category {
+            ImportRef::Existing(category_id) => category_id,
+            ImportRef::New(index) => &self.categories[*index].category_id,
+        }
+    }
+
+    pub fn label_id<'a>(&'a self, label: &'a ImportRef<LabelId>) -> &'a str {
+        match label {
+            ImportRef::Existing(label_id) => label_id,
+            ImportRef::New(index) => &self.label_ids[*index],
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use rand::Rng;
+
+/// Local part of the inbound email address of a board. Anyone who knows the address
+/// can add tasks to the board, so it can be replaced with a new one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboundToken(String);
+
+impl InboundToken {
+    /// Parses the token case-insensitively, since mail servers may change the case of addresses.
+    pub fn from_str(token: &str) -> Option<InboundToken> {
+        let token = token.to_ascii_lowercase();
+        if !Self::is_valid_token(&token) {
+            return None;
+        }
+
+        Some(Self(token))
+    }
+
+    pub fn generate_random() -> InboundToken {
+        let mut rng = rand::thread_rng();
+
+        let mut bytes: [u8; 16] = [0; 16];
+        bytes.iter_mut().for_each(|b| *b = rng.gen());
+
+        let token = hex::encode(bytes);
+        debug_assert!(Self::is_valid_token(&token));
+
+        Self(token)
+    }
+
+    fn is_valid_token(token: &str) -> bool {
+        token.len() == 32 && token.chars().all(|c| c.is_ascii_hexdigit())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
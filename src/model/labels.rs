@@ -0,0 +1,41 @@
+pub type LabelId = String;
+
+/// Coloured label from the palette of a board. Labels are assigned to tasks of the board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelDescription {
+    pub label_id: LabelId,
+    pub name: String,
+    /// Colour in `#rrggbb` format.
+    pub color: String,
+}
+
+/// Editable data of a label.
+#[derive(Debug, Clone)]
+pub struct LabelData {
+    pub name: String,
+    pub color: String,
+}
+
+impl LabelData {
+    const MAX_NAME_LENGTH: usize = 64;
+
+    /// Returns true if the name is not blank and not too long, and the colour is in `#rrggbb` format.
+    pub fn is_valid(&self) -> bool {
+        let name_is_valid =
+            !self.name.trim().is_empty() && self.name.chars().count() <= Self::MAX_NAME_LENGTH;
+
+        let color_is_valid = self.color.len() == 7
+            && self.color.starts_with('#')
+            && self.color[1..].chars().all(|c| c.is_ascii_hexdigit());
+
+        name_is_valid && color_is_valid
+    }
+
+    pub fn into_description(self, label_id: LabelId) -> LabelDescription {
+        LabelDescription {
+            label_id,
+            name: self.name,
+            color: self.color,
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+
+/// Whether a task or a category is on the board, in the archive or in the trash.
+///
+/// Archived items are hidden from the board but kept. Trashed items are hidden everywhere
+/// except the trash and are purged after the retention period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Lifecycle {
+    pub archived_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Lifecycle {
+    pub fn is_active(&self) -> bool {
+        self.archived_at.is_none() && self.deleted_at.is_none()
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archived_at.is_some() && self.deleted_at.is_none()
+    }
+
+    pub fn is_trashed(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    pub fn archived(self, time: DateTime<Utc>) -> Self {
+        Self {
+            archived_at: Some(time),
+            deleted_at: None,
+        }
+    }
+
+    /// Moves the item to the trash. Restoring it later makes it active again.
+    pub fn trashed(self, time: DateTime<Utc>) -> Self {
+        Self {
+            archived_at: self.archived_at,
+            deleted_at: Some(time),
+        }
+    }
+}
@@ -1,9 +1,28 @@
+pub mod activity;
+pub mod attachments;
+mod boards;
+pub mod bulk;
+pub mod calendar;
+pub mod checklists;
+pub mod comments;
+pub mod filters;
+pub mod idempotency;
+pub mod imports;
+pub mod inbound;
+pub mod labels;
+pub mod lifecycle;
+pub mod search;
+mod sessions;
+pub mod sync;
 pub mod tasks;
 mod types;
 mod users;
-mod sessions;
+pub mod views;
+pub mod webhooks;
 
+pub use boards::BoardId;
+pub use labels::LabelId;
 pub use sessions::SessionToken;
 pub use tasks::{TaskCategoryId, TaskId};
 pub use types::UniqueId;
-pub use users::UserId;
+pub use users::{UserId, DEFAULT_TIMEZONE};
@@ -0,0 +1,15 @@
+use super::tasks::TaskDescription;
+
+/// Opening and closing markers of the matched words in [`SearchHit::snippet`].
+pub const HIGHLIGHT_START: &str = "<mark>";
+pub const HIGHLIGHT_END: &str = "</mark>";
+
+/// A task that matches a search query.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub task: TaskDescription,
+    /// Relevance of the task. Greater is more relevant.
+    pub rank: f32,
+    /// Fragment of the matched text with the matched words highlighted.
+    pub snippet: String,
+}
@@ -0,0 +1,43 @@
+use super::{TaskCategoryId, TaskId, UserId};
+
+/// Position in the change log of a user. Later changes have greater sequence numbers.
+pub type SyncSeq = i64;
+
+/// Item of a board whose changes are synchronized.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SyncEntity {
+    Task(TaskId),
+    Category(TaskCategoryId),
+}
+
+impl SyncEntity {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SyncEntity::Task(_) => "task",
+            SyncEntity::Category(_) => "category",
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            SyncEntity::Task(id) | SyncEntity::Category(id) => id,
+        }
+    }
+
+    /// Restores the entity from [`SyncEntity::kind`] and [`SyncEntity::id`].
+    pub fn from_parts(kind: &str, id: String) -> Option<Self> {
+        match kind {
+            "task" => Some(SyncEntity::Task(id)),
+            "category" => Some(SyncEntity::Category(id)),
+            _ => None,
+        }
+    }
+}
+
+/// Entry of the change log: the entity has changed, or has been deleted.
+#[derive(Debug, Clone)]
+pub struct SyncChange {
+    pub seq: SyncSeq,
+    pub user_id: UserId,
+    pub entity: SyncEntity,
+}
@@ -1,5 +1,10 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
 use rand::Rng;
 
+use super::{checklists::ChecklistProgress, labels::LabelId, lifecycle::Lifecycle};
+
 pub fn generate_random_task_id() -> String {
     let mut rng = rand::thread_rng();
 
@@ -12,16 +17,190 @@ pub fn generate_random_task_id() -> String {
 pub type TaskId = String;
 pub type TaskCategoryId = String;
 
+/// Version of a task or a category. It starts at 1 and is incremented on every modification.
+pub type Version = i64;
+
+pub const INITIAL_VERSION: Version = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum TaskPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+impl TaskPriority {
+    const ALL: [TaskPriority; 4] = [
+        TaskPriority::Low,
+        TaskPriority::Normal,
+        TaskPriority::High,
+        TaskPriority::Urgent,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskPriority::Low => "low",
+            TaskPriority::Normal => "normal",
+            TaskPriority::High => "high",
+            TaskPriority::Urgent => "urgent",
+        }
+    }
+
+    /// Parses the name returned by [`TaskPriority::as_str`].
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.as_str() == name)
+    }
+
+    /// Returns the rank of the priority, which grows with the urgency. Used to store the priority.
+    pub fn rank(self) -> i16 {
+        self as i16
+    }
+
+    pub fn from_rank(rank: i16) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.rank() == rank)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TaskDescription {
     pub task_id: TaskId,
     pub label: String,
     pub description: String,
     pub category_id: TaskCategoryId,
+    pub start_at: Option<DateTime<Utc>>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub priority: TaskPriority,
+    /// Labels assigned to the task, ordered by ID.
+    pub label_ids: Vec<LabelId>,
+    pub checklist_progress: ChecklistProgress,
+    pub lifecycle: Lifecycle,
+    pub version: Version,
+}
+
+/// Editable data of a task.
+#[derive(Debug, Clone)]
+pub struct TaskData {
+    pub label: String,
+    pub description: String,
+    pub category_id: TaskCategoryId,
+    pub start_at: Option<DateTime<Utc>>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub priority: TaskPriority,
+}
+
+impl TaskData {
+    pub fn into_description(self, task_id: TaskId) -> TaskDescription {
+        TaskDescription {
+            task_id,
+            label: self.label,
+            description: self.description,
+            category_id: self.category_id,
+            start_at: self.start_at,
+            due_at: self.due_at,
+            priority: self.priority,
+            label_ids: Vec::new(),
+            checklist_progress: ChecklistProgress::default(),
+            lifecycle: Lifecycle::default(),
+            version: INITIAL_VERSION,
+        }
+    }
+
+    /// Returns the patch that replaces all the data of a task.
+    pub fn into_patch(self) -> TaskPatch {
+        TaskPatch {
+            label: Some(self.label),
+            description: Some(self.description),
+            category_id: Some(self.category_id),
+            start_at: Some(self.start_at),
+            due_at: Some(self.due_at),
+            priority: Some(self.priority),
+        }
+    }
+}
+
+/// Position of a task in its column, whose tasks are ordered by creation, then by ID.
+/// The cursors of the pages of a column are written as `<created_seq>-<task_id>`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TaskCursor {
+    /// Sequence number given to the task on creation.
+    pub created_seq: i64,
+    pub task_id: TaskId,
+}
+
+impl TaskCursor {
+    pub fn parse(cursor: &str) -> Option<Self> {
+        let (created_seq, task_id) = cursor.split_once('-')?;
+
+        Some(Self {
+            created_seq: created_seq.parse().ok()?,
+            task_id: task_id.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for TaskCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.created_seq, self.task_id)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct TaskCategoryDescription {
     pub category_id: TaskCategoryId,
     pub label: String,
+    pub lifecycle: Lifecycle,
+    pub version: Version,
+}
+
+/// Changes to a task. Fields that are `None` are left unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct TaskPatch {
+    pub label: Option<String>,
+    pub description: Option<String>,
+    pub category_id: Option<TaskCategoryId>,
+    /// `Some(None)` removes the start date.
+    pub start_at: Option<Option<DateTime<Utc>>>,
+    /// `Some(None)` removes the due date.
+    pub due_at: Option<Option<DateTime<Utc>>>,
+    pub priority: Option<TaskPriority>,
+}
+
+impl TaskPatch {
+    pub fn is_empty(&self) -> bool {
+        self.label.is_none()
+            && self.description.is_none()
+            && self.category_id.is_none()
+            && self.start_at.is_none()
+            && self.due_at.is_none()
+            && self.priority.is_none()
+    }
+
+    /// Applies the changes to the task, without changing its version.
+    pub fn apply(&self, task: &mut TaskDescription) {
+        if let Some(label) = &self.label {
+            task.label = label.clone();
+        }
+
+        if let Some(description) = &self.description {
+            task.description = description.clone();
+        }
+
+        if let Some(category_id) = &self.category_id {
+            task.category_id = category_id.clone();
+        }
+
+        if let Some(start_at) = self.start_at {
+            task.start_at = start_at;
+        }
+
+        if let Some(due_at) = self.due_at {
+            task.due_at = due_at;
+        }
+
+        if let Some(priority) = self.priority {
+            task.priority = priority;
+        }
+    }
 }
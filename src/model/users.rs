@@ -1,3 +1,6 @@
 use super::UniqueId;
 
 pub type UserId = UniqueId;
+
+/// Timezone of the users that have not configured one.
+pub const DEFAULT_TIMEZONE: &str = "UTC";
@@ -0,0 +1,109 @@
+use super::{BoardId, UserId};
+
+pub type ViewId = String;
+
+/// Order of the tasks of a view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ViewSort {
+    /// The order of the tasks on the board.
+    #[default]
+    Board,
+    /// Tasks without a due date come last in either direction.
+    DueAt,
+    Priority,
+    Label,
+}
+
+impl ViewSort {
+    const ALL: [ViewSort; 4] = [
+        ViewSort::Board,
+        ViewSort::DueAt,
+        ViewSort::Priority,
+        ViewSort::Label,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ViewSort::Board => "board",
+            ViewSort::DueAt => "due_at",
+            ViewSort::Priority => "priority",
+            ViewSort::Label => "label",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == name)
+    }
+}
+
+/// How the tasks of a view are grouped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ViewGrouping {
+    #[default]
+    None,
+    Column,
+    Priority,
+    /// A task with several labels is in several groups.
+    Label,
+}
+
+impl ViewGrouping {
+    const ALL: [ViewGrouping; 4] = [
+        ViewGrouping::None,
+        ViewGrouping::Column,
+        ViewGrouping::Priority,
+        ViewGrouping::Label,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ViewGrouping::None => "none",
+            ViewGrouping::Column => "column",
+            ViewGrouping::Priority => "priority",
+            ViewGrouping::Label => "label",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|g| g.as_str() == name)
+    }
+}
+
+/// Editable data of a saved view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewData {
+    pub name: String,
+    /// Filter query, see [`crate::app::filters::parse_filter`].
+    pub filter: String,
+    pub sort: ViewSort,
+    pub descending: bool,
+    pub group_by: ViewGrouping,
+    pub include_archived: bool,
+    /// Shared views are visible to everyone who has access to the board.
+    pub shared: bool,
+}
+
+impl ViewData {
+    const MAX_NAME_LENGTH: usize = 64;
+
+    /// Returns true if the name is not blank and not too long.
+    pub fn is_name_valid(&self) -> bool {
+        !self.name.trim().is_empty() && self.name.chars().count() <= Self::MAX_NAME_LENGTH
+    }
+}
+
+/// Named combination of a filter, a sort order and a grouping of the tasks of a board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewDescription {
+    pub view_id: ViewId,
+    pub board_id: BoardId,
+    pub owner_id: UserId,
+    pub data: ViewData,
+}
+
+impl ViewDescription {
+    /// Returns true if the user owns the view or it is shared. Access to the board is not checked.
+    pub fn is_visible_to(&self, user_id: UserId) -> bool {
+        self.owner_id == user_id || self.data.shared
+    }
+}
@@ -0,0 +1,211 @@
+use chrono::{DateTime, Utc};
+use rand::Rng;
+
+use super::{activity::ActivityKind, BoardId, TaskCategoryId, TaskId};
+
+pub type WebhookId = String;
+
+pub type DeliveryId = String;
+
+/// Position of an event in the queue of events to send. Later events have greater identifiers.
+pub type WebhookEventId = i64;
+
+/// Generates the key that the deliveries of a webhook are signed with.
+pub fn generate_webhook_secret() -> String {
+    let mut rng = rand::thread_rng();
+
+    let mut bytes: [u8; 32] = [0; 32];
+    bytes.iter_mut().for_each(|b| *b = rng.gen());
+
+    hex::encode(bytes)
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookData {
+    pub url: String,
+    /// Names of the delivered events. All the events are delivered if it is empty.
+    pub event_types: Vec<String>,
+    /// Key of the HMAC-SHA256 signatures of the deliveries.
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookDescription {
+    pub webhook_id: WebhookId,
+    pub board_id: BoardId,
+    pub data: WebhookData,
+}
+
+impl WebhookDescription {
+    pub fn accepts(&self, event_type: &str) -> bool {
+        self.data.event_types.is_empty() || self.data.event_types.iter().any(|t| t == event_type)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    /// Waiting for the first attempt or for a retry.
+    Pending,
+    Delivered,
+    /// All the attempts failed, the delivery will not be retried.
+    DeadLetter,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::DeadLetter => "dead_letter",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "pending" => Some(DeliveryStatus::Pending),
+            "delivered" => Some(DeliveryStatus::Delivered),
+            "dead_letter" => Some(DeliveryStatus::DeadLetter),
+            _ => None,
+        }
+    }
+}
+
+/// Event queued for a webhook, with the outcome of the attempts to deliver it.
+#[derive(Debug, Clone)]
+pub struct WebhookDelivery {
+    pub delivery_id: DeliveryId,
+    pub webhook_id: WebhookId,
+    pub event_type: String,
+    /// JSON body of the request.
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    /// When the delivery is attempted next. `None` once it is delivered or dead-lettered.
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    /// Status code of the response to the last attempt, if there was a response.
+    pub last_status_code: Option<u16>,
+    /// Why the last attempt failed.
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Change of a board to send to its webhooks. The state of the changed task or category
+/// is read when the deliveries are queued.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebhookEventKind {
+    TaskCreated(TaskId),
+    TaskUpdated(TaskId),
+    TaskMoved {
+        task_id: TaskId,
+        from_category_id: TaskCategoryId,
+    },
+    /// The task has been deleted, archived or trashed.
+    TaskDeleted(TaskId),
+    CategoryCreated(TaskCategoryId),
+    CategoryUpdated(TaskCategoryId),
+    /// The category has been archived or trashed together with its tasks.
+    CategoryRemoved(TaskCategoryId),
+    CategoryRestored(TaskCategoryId),
+}
+
+impl WebhookEventKind {
+    /// Returns the event of the task changed as described by its activity.
+    /// A restored task is announced as created, like on the event stream.
+    pub fn of_task(task_id: &str, activity: &[ActivityKind]) -> Self {
+        let task_id = task_id.to_string();
+
+        for kind in activity {
+            match kind {
+                ActivityKind::Created | ActivityKind::Restored => {
+                    return WebhookEventKind::TaskCreated(task_id)
+                }
+                ActivityKind::Archived | ActivityKind::Deleted => {
+                    return WebhookEventKind::TaskDeleted(task_id)
+                }
+                ActivityKind::Moved {
+                    from_category_id, ..
+                } => {
+                    return WebhookEventKind::TaskMoved {
+                        task_id,
+                        from_category_id: from_category_id.clone(),
+                    }
+                }
+                ActivityKind::LabelChanged { .. } | ActivityKind::DescriptionChanged { .. } => {}
+            }
+        }
+
+        WebhookEventKind::TaskUpdated(task_id)
+    }
+
+    /// Name of the event, one of the webhook event types.
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEventKind::TaskCreated(_) => "task_created",
+            WebhookEventKind::TaskUpdated(_) => "task_updated",
+            WebhookEventKind::TaskMoved { .. } => "task_moved",
+            WebhookEventKind::TaskDeleted(_) => "task_deleted",
+            WebhookEventKind::CategoryCreated(_) => "category_created",
+            WebhookEventKind::CategoryUpdated(_) => "category_updated",
+            WebhookEventKind::CategoryRemoved(_) => "category_removed",
+            WebhookEventKind::CategoryRestored(_) => "category_restored",
+        }
+    }
+
+    /// ID of the changed task or category.
+    pub fn item_id(&self) -> &str {
+        match self {
+            WebhookEventKind::TaskCreated(id)
+            | WebhookEventKind::TaskUpdated(id)
+            | WebhookEventKind::TaskMoved { task_id: id, .. }
+            | WebhookEventKind::TaskDeleted(id)
+            | WebhookEventKind::CategoryCreated(id)
+            | WebhookEventKind::CategoryUpdated(id)
+            | WebhookEventKind::CategoryRemoved(id)
+            | WebhookEventKind::CategoryRestored(id) => id,
+        }
+    }
+
+    /// Category the task has been moved from.
+    pub fn moved_from(&self) -> Option<&str> {
+        match self {
+            WebhookEventKind::TaskMoved {
+                from_category_id, ..
+            } => Some(from_category_id),
+            _ => None,
+        }
+    }
+
+    /// Restores the event from [`WebhookEventKind::name`], [`WebhookEventKind::item_id`]
+    /// and [`WebhookEventKind::moved_from`].
+    pub fn from_parts(
+        name: &str,
+        item_id: String,
+        from_category_id: Option<String>,
+    ) -> Option<Self> {
+        match name {
+            "task_created" => Some(WebhookEventKind::TaskCreated(item_id)),
+            "task_updated" => Some(WebhookEventKind::TaskUpdated(item_id)),
+            "task_moved" => Some(WebhookEventKind::TaskMoved {
+                task_id: item_id,
+                from_category_id: from_category_id?,
+            }),
+            "task_deleted" => Some(WebhookEventKind::TaskDeleted(item_id)),
+            "category_created" => Some(WebhookEventKind::CategoryCreated(item_id)),
+            "category_updated" => Some(WebhookEventKind::CategoryUpdated(item_id)),
+            "category_removed" => Some(WebhookEventKind::CategoryRemoved(item_id)),
+            "category_restored" => Some(WebhookEventKind::CategoryRestored(item_id)),
+            _ => None,
+        }
+    }
+}
+
+/// Change of a board recorded in the same transaction as the change, waiting to be
+/// queued for the webhooks of the board.
+#[derive(Debug, Clone)]
+pub struct WebhookEvent {
+    pub event_id: WebhookEventId,
+    pub board_id: BoardId,
+    pub kind: WebhookEventKind,
+    pub created_at: DateTime<Utc>,
+}
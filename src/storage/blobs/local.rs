@@ -0,0 +1,106 @@
+use std::{
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use tokio::fs;
+
+use crate::app::repositories::BlobStore;
+
+/// Keeps the blobs as files in a directory. Keys may contain `/` to form subdirectories.
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> anyhow::Result<PathBuf> {
+        let relative = Path::new(key);
+
+        // Keys must not escape the root directory.
+        if key.is_empty()
+            || !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(anyhow!("invalid blob key: {}", key));
+        }
+
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl BlobStore for LocalBlobStore {
+    async fn put(&self, key: &str, _content_type: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        let path = self.path(key)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // Write to a temporary file first, so that readers never see a partially written blob.
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, data).await?;
+        fs::rename(&temp_path, &path).await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match fs::remove_file(self.path(key)?).await {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::repositories::BlobStore;
+
+    use super::LocalBlobStore;
+
+    #[tokio::test]
+    async fn put_get_delete() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = LocalBlobStore::new(dir.path());
+
+        store
+            .put("attachments/a", "text/plain", b"hello".to_vec())
+            .await?;
+        assert_eq!(
+            store.get("attachments/a").await?.as_deref(),
+            Some(&b"hello"[..])
+        );
+
+        store.delete("attachments/a").await?;
+        assert_eq!(store.get("attachments/a").await?, None);
+
+        // Deleting a missing blob is fine.
+        store.delete("attachments/a").await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejects_keys_outside_root() {
+        let store = LocalBlobStore::new("/tmp/blobs");
+
+        assert!(store.get("../etc/passwd").await.is_err());
+        assert!(store.get("/etc/passwd").await.is_err());
+    }
+}
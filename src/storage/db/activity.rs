@@ -0,0 +1,109 @@
+use anyhow::anyhow;
+use sqlx::{postgres::PgRow, PgConnection, Row};
+
+use crate::{
+    app::repositories::ActivityRepository,
+    model::{
+        activity::{ActivityEntry, ActivityId, ActivityKind, ActivityRecord},
+        BoardId, UserId,
+    },
+};
+
+use super::{DatabaseConnectionRef, DbError};
+
+pub struct DbActivity {
+    db: DatabaseConnectionRef,
+}
+
+/// Columns of `activity` table read by [`activity_from_row`].
+const ACTIVITY_COLUMNS: &str =
+    "activity_id, board_id, task_id, actor_id, kind, from_value, to_value, created_at";
+
+fn activity_from_row(row: &PgRow) -> anyhow::Result<ActivityEntry> {
+    let kind: String = row.try_get(4)?;
+
+    Ok(ActivityEntry {
+        activity_id: row.try_get(0)?,
+        board_id: BoardId::from_raw(row.try_get::<i32, _>(1)? as i64),
+        task_id: row.try_get(2)?,
+        actor_id: UserId::from_raw(row.try_get::<i32, _>(3)? as i64),
+        kind: ActivityKind::from_parts(&kind, row.try_get(5)?, row.try_get(6)?)
+            .ok_or_else(|| anyhow!("unknown activity kind {}", kind))?,
+        created_at: row.try_get(7)?,
+    })
+}
+
+/// Records the changes of the task within the transaction of the changes.
+pub(super) async fn record_activity(
+    tx: &mut PgConnection,
+    task_id: &str,
+    activity: &ActivityRecord,
+) -> Result<(), DbError> {
+    for kind in &activity.kinds {
+        let values = kind.values();
+
+        sqlx::query(
+            "INSERT INTO activity \
+            (board_id, task_id, actor_id, kind, from_value, to_value, created_at) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(activity.board_id.raw() as i32)
+        .bind(task_id)
+        .bind(activity.actor_id.raw() as i32)
+        .bind(kind.as_str())
+        .bind(values.map(|(from, _)| from))
+        .bind(values.map(|(_, to)| to))
+        .bind(activity.created_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+impl DbActivity {
+    pub fn new(db: DatabaseConnectionRef) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ActivityRepository for DbActivity {
+    async fn fetch_task_activity(
+        &self,
+        board_id: BoardId,
+        task_id: &str,
+    ) -> anyhow::Result<Vec<ActivityEntry>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM activity WHERE board_id=$1 AND task_id=$2 \
+            ORDER BY activity_id DESC",
+            ACTIVITY_COLUMNS
+        ))
+        .bind(board_id.raw() as i32)
+        .bind(task_id)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        rows.iter().map(activity_from_row).collect()
+    }
+
+    async fn fetch_board_activity(
+        &self,
+        board_id: BoardId,
+        before: Option<ActivityId>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<ActivityEntry>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM activity WHERE board_id=$1 AND ($2::BIGINT IS NULL OR activity_id < $2) \
+            ORDER BY activity_id DESC LIMIT $3",
+            ACTIVITY_COLUMNS
+        ))
+        .bind(board_id.raw() as i32)
+        .bind(before)
+        .bind(limit)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        rows.iter().map(activity_from_row).collect()
+    }
+}
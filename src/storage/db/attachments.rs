@@ -0,0 +1,158 @@
+use sqlx::{postgres::PgRow, Row};
+
+use crate::{
+    app::repositories::AttachmentsRepository,
+    model::{attachments::AttachmentDescription, UserId},
+};
+
+use super::{DatabaseConnectionRef, DbError};
+
+pub struct DbAttachments {
+    db: DatabaseConnectionRef,
+}
+
+/// Columns of `attachments` table read by [`attachment_from_row`].
+const ATTACHMENT_COLUMNS: &str =
+    "attachment_id, task_id, owner_id, file_name, content_type, size, blob_key, created_at";
+
+fn attachment_from_row(row: &PgRow) -> Result<AttachmentDescription, DbError> {
+    Ok(AttachmentDescription {
+        attachment_id: row.try_get(0)?,
+        task_id: row.try_get(1)?,
+        owner_id: UserId::from_raw(row.try_get::<i32, _>(2)? as i64),
+        file_name: row.try_get(3)?,
+        content_type: row.try_get(4)?,
+        size: row.try_get(5)?,
+        blob_key: row.try_get(6)?,
+        created_at: row.try_get(7)?,
+    })
+}
+
+impl DbAttachments {
+    pub fn new(db: DatabaseConnectionRef) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl AttachmentsRepository for DbAttachments {
+    async fn fetch_attachments(&self, task_id: &str) -> anyhow::Result<Vec<AttachmentDescription>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM attachments WHERE task_id=$1 ORDER BY created_at, attachment_id",
+            ATTACHMENT_COLUMNS
+        ))
+        .bind(task_id)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(attachment_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn fetch_attachment(
+        &self,
+        task_id: &str,
+        attachment_id: &str,
+    ) -> anyhow::Result<Option<AttachmentDescription>> {
+        let optional_row = sqlx::query(&format!(
+            "SELECT {} FROM attachments WHERE task_id=$1 AND attachment_id=$2",
+            ATTACHMENT_COLUMNS
+        ))
+        .bind(task_id)
+        .bind(attachment_id)
+        .fetch_optional(self.db.as_pool())
+        .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(None);
+        };
+
+        Ok(Some(attachment_from_row(&row)?))
+    }
+
+    async fn total_size(&self, owner_id: UserId) -> anyhow::Result<i64> {
+        let row =
+            sqlx::query("SELECT COALESCE(SUM(size), 0)::BIGINT FROM attachments WHERE owner_id=$1")
+                .bind(owner_id.raw() as i32)
+                .fetch_one(self.db.as_pool())
+                .await?;
+
+        Ok(row.try_get(0)?)
+    }
+
+    async fn create_attachment(
+        &self,
+        attachment: &AttachmentDescription,
+        quota: i64,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.db.as_pool().begin().await?;
+
+        // Serialize the uploads of the user, so that concurrent uploads cannot exceed the quota together.
+        sqlx::query("SELECT 1 FROM users WHERE user_id=$1 FOR UPDATE")
+            .bind(attachment.owner_id.raw() as i32)
+            .execute(&mut *tx)
+            .await?;
+
+        let res = sqlx::query(
+            "INSERT INTO attachments \
+            (attachment_id, task_id, owner_id, file_name, content_type, size, blob_key, created_at) \
+            SELECT $1, $2, $3, $4, $5, $6, $7, $8 \
+            WHERE (SELECT COALESCE(SUM(size), 0) FROM attachments WHERE owner_id=$3) + $6 <= $9",
+        )
+        .bind(&attachment.attachment_id)
+        .bind(&attachment.task_id)
+        .bind(attachment.owner_id.raw() as i32)
+        .bind(&attachment.file_name)
+        .bind(&attachment.content_type)
+        .bind(attachment.size)
+        .bind(&attachment.blob_key)
+        .bind(attachment.created_at)
+        .bind(quota)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn delete_attachment(
+        &self,
+        task_id: &str,
+        attachment_id: &str,
+    ) -> anyhow::Result<Option<AttachmentDescription>> {
+        let optional_row = sqlx::query(&format!(
+            "DELETE FROM attachments WHERE task_id=$1 AND attachment_id=$2 RETURNING {}",
+            ATTACHMENT_COLUMNS
+        ))
+        .bind(task_id)
+        .bind(attachment_id)
+        .fetch_optional(self.db.as_pool())
+        .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(None);
+        };
+
+        Ok(Some(attachment_from_row(&row)?))
+    }
+
+    async fn delete_task_attachments(
+        &self,
+        task_id: &str,
+    ) -> anyhow::Result<Vec<AttachmentDescription>> {
+        let rows = sqlx::query(&format!(
+            "DELETE FROM attachments WHERE task_id=$1 RETURNING {}",
+            ATTACHMENT_COLUMNS
+        ))
+        .bind(task_id)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(attachment_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+}
@@ -0,0 +1,187 @@
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, Row};
+
+use crate::{
+    app::repositories::CommentsRepository,
+    model::{
+        comments::{CommentDescription, CommentId, CommentRevision},
+        tasks::generate_random_task_id,
+        UserId,
+    },
+};
+
+use super::{DatabaseConnectionRef, DbError};
+
+pub struct DbComments {
+    db: DatabaseConnectionRef,
+}
+
+/// Columns of `comments` table read by [`comment_from_row`].
+const COMMENT_COLUMNS: &str =
+    "comment_id, task_id, author_id, text, created_at, edited_at, deleted_at";
+
+fn comment_from_row(row: &PgRow) -> Result<CommentDescription, DbError> {
+    Ok(CommentDescription {
+        comment_id: row.try_get(0)?,
+        task_id: row.try_get(1)?,
+        author_id: UserId::from_raw(row.try_get::<i32, _>(2)? as i64),
+        text: row.try_get(3)?,
+        created_at: row.try_get(4)?,
+        edited_at: row.try_get(5)?,
+        deleted_at: row.try_get(6)?,
+    })
+}
+
+impl DbComments {
+    pub fn new(db: DatabaseConnectionRef) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl CommentsRepository for DbComments {
+    async fn fetch_comments(&self, task_id: &str) -> anyhow::Result<Vec<CommentDescription>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM comments WHERE task_id=$1 ORDER BY created_at, comment_id",
+            COMMENT_COLUMNS
+        ))
+        .bind(task_id)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(comment_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn fetch_comment(
+        &self,
+        task_id: &str,
+        comment_id: &str,
+    ) -> anyhow::Result<Option<CommentDescription>> {
+        let optional_row = sqlx::query(&format!(
+            "SELECT {} FROM comments WHERE task_id=$1 AND comment_id=$2",
+            COMMENT_COLUMNS
+        ))
+        .bind(task_id)
+        .bind(comment_id)
+        .fetch_optional(self.db.as_pool())
+        .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(None);
+        };
+
+        Ok(Some(comment_from_row(&row)?))
+    }
+
+    async fn create_comment(
+        &self,
+        task_id: &str,
+        author_id: UserId,
+        text: &str,
+        created_at: DateTime<Utc>,
+    ) -> anyhow::Result<CommentId> {
+        let random_comment_id = generate_random_task_id();
+
+        sqlx::query(
+            "INSERT INTO comments (comment_id, task_id, author_id, text, created_at) \
+            VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(&random_comment_id)
+        .bind(task_id)
+        .bind(author_id.raw() as i32)
+        .bind(text)
+        .bind(created_at)
+        .execute(self.db.as_pool())
+        .await?;
+
+        Ok(random_comment_id)
+    }
+
+    async fn update_comment(
+        &self,
+        task_id: &str,
+        comment_id: &str,
+        text: &str,
+        edited_at: DateTime<Utc>,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.db.as_pool().begin().await?;
+
+        let optional_row = sqlx::query(
+            "SELECT text, COALESCE(edited_at, created_at) FROM comments \
+            WHERE task_id=$1 AND comment_id=$2 FOR UPDATE",
+        )
+        .bind(task_id)
+        .bind(comment_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(false);
+        };
+
+        let previous_text: String = row.try_get(0)?;
+        let written_at: DateTime<Utc> = row.try_get(1)?;
+
+        sqlx::query(
+            "INSERT INTO comment_revisions (comment_id, text, written_at) VALUES ($1, $2, $3)",
+        )
+        .bind(comment_id)
+        .bind(previous_text)
+        .bind(written_at)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE comments SET text=$3, edited_at=$4 WHERE task_id=$1 AND comment_id=$2")
+            .bind(task_id)
+            .bind(comment_id)
+            .bind(text)
+            .bind(edited_at)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn delete_comment(
+        &self,
+        task_id: &str,
+        comment_id: &str,
+        deleted_at: DateTime<Utc>,
+    ) -> anyhow::Result<bool> {
+        let res = sqlx::query(
+            "UPDATE comments SET deleted_at=COALESCE(deleted_at, $3) \
+            WHERE task_id=$1 AND comment_id=$2",
+        )
+        .bind(task_id)
+        .bind(comment_id)
+        .bind(deleted_at)
+        .execute(self.db.as_pool())
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn fetch_revisions(&self, comment_id: &str) -> anyhow::Result<Vec<CommentRevision>> {
+        let rows = sqlx::query(
+            "SELECT text, written_at FROM comment_revisions WHERE comment_id=$1 \
+            ORDER BY revision_id",
+        )
+        .bind(comment_id)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                Ok(CommentRevision {
+                    text: row.try_get(0)?,
+                    written_at: row.try_get(1)?,
+                })
+            })
+            .collect::<Result<_, DbError>>()?)
+    }
+}
@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Postgres, QueryBuilder};
+
+use crate::model::filters::TaskFilter;
+
+/// Appends the condition on a row of `tasks` table that matches the filter.
+pub(super) fn push_filter(
+    query: &mut QueryBuilder<'_, Postgres>,
+    filter: &TaskFilter,
+    now: DateTime<Utc>,
+) {
+    match filter {
+        TaskFilter::And(filters) => push_all(query, filters, " AND ", "TRUE", now),
+        TaskFilter::Or(filters) => push_all(query, filters, " OR ", "FALSE", now),
+        TaskFilter::Not(filter) => {
+            query.push("NOT (");
+            push_filter(query, filter, now);
+            query.push(")");
+        }
+        TaskFilter::Label(name) => {
+            query
+                .push(
+                    "EXISTS (SELECT 1 FROM task_labels JOIN labels ON labels.label_id=task_labels.label_id \
+                    WHERE task_labels.task_id=tasks.task_id AND LOWER(labels.name)=LOWER(",
+                )
+                .push_bind(name.clone())
+                .push("))");
+        }
+        TaskFilter::Column(name) => {
+            query
+                .push(
+                    "EXISTS (SELECT 1 FROM task_categories WHERE task_categories.category_id=tasks.category_id \
+                    AND LOWER(task_categories.label)=LOWER(",
+                )
+                .push_bind(name.clone())
+                .push("))");
+        }
+        TaskFilter::Priority(comparison, priority) => {
+            query
+                .push("priority")
+                .push(comparison.as_str())
+                .push_bind(priority.rank());
+        }
+        TaskFilter::Due(due) => match due.range(now) {
+            None => {
+                query.push("due_at IS NULL");
+            }
+            Some((from, to)) => {
+                query.push("(due_at IS NOT NULL");
+                if let Some(from) = from {
+                    query.push(" AND due_at>=").push_bind(from);
+                }
+                if let Some(to) = to {
+                    query.push(" AND due_at<").push_bind(to);
+                }
+                query.push(")");
+            }
+        },
+        TaskFilter::Archived => {
+            query.push("archived_at IS NOT NULL");
+        }
+    }
+}
+
+fn push_all(
+    query: &mut QueryBuilder<'_, Postgres>,
+    filters: &[TaskFilter],
+    separator: &str,
+    empty: &str,
+    now: DateTime<Utc>,
+) {
+    if filters.is_empty() {
+        query.push(empty);
+        return;
+    }
+
+    for (idx, filter) in filters.iter().enumerate() {
+        if idx > 0 {
+            query.push(separator);
+        }
+
+        query.push("(");
+        push_filter(query, filter, now);
+        query.push(")");
+    }
+}
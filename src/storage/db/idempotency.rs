@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, Row};
+
+use crate::{
+    app::repositories::IdempotencyRepository,
+    model::{
+        idempotency::{IdempotencyRecord, StoredResponse},
+        UserId,
+    },
+};
+
+use super::DatabaseConnectionRef;
+
+pub struct DbIdempotency {
+    db: DatabaseConnectionRef,
+}
+
+fn record_from_row(row: &PgRow) -> anyhow::Result<IdempotencyRecord> {
+    let status: Option<i16> = row.try_get(3)?;
+
+    Ok(IdempotencyRecord {
+        user_id: row
+            .try_get::<Option<i32>, _>(0)?
+            .map(|user_id| UserId::from_raw(user_id as i64)),
+        key: row.try_get(1)?,
+        fingerprint: row.try_get(2)?,
+        response: match status {
+            Some(status) => Some(StoredResponse {
+                status: status as u16,
+                etag: row.try_get(4)?,
+                body: row.try_get(5)?,
+            }),
+            None => None,
+        },
+        created_at: row.try_get(6)?,
+    })
+}
+
+impl DbIdempotency {
+    pub fn new(db: DatabaseConnectionRef) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for DbIdempotency {
+    async fn reserve_key(
+        &self,
+        record: &IdempotencyRecord,
+        expired_before: DateTime<Utc>,
+        abandoned_before: DateTime<Utc>,
+    ) -> anyhow::Result<Option<IdempotencyRecord>> {
+        let reserved = sqlx::query(
+            "INSERT INTO idempotency_keys (user_id, key, fingerprint, created_at) \
+            VALUES ($1, $2, $3, $4) \
+            ON CONFLICT (user_id, key) DO UPDATE \
+            SET fingerprint=EXCLUDED.fingerprint, created_at=EXCLUDED.created_at, \
+            status=NULL, etag=NULL, body=NULL \
+            WHERE idempotency_keys.created_at < $5 \
+            OR (idempotency_keys.status IS NULL AND idempotency_keys.created_at < $6)",
+        )
+        .bind(record.user_id.map(|user_id| user_id.raw() as i32))
+        .bind(&record.key)
+        .bind(&record.fingerprint)
+        .bind(record.created_at)
+        .bind(expired_before)
+        .bind(abandoned_before)
+        .execute(self.db.as_pool())
+        .await?
+        .rows_affected()
+            > 0;
+
+        if reserved {
+            return Ok(None);
+        }
+
+        let row = sqlx::query(
+            "SELECT user_id, key, fingerprint, status, etag, body, created_at \
+            FROM idempotency_keys WHERE user_id IS NOT DISTINCT FROM $1 AND key=$2",
+        )
+        .bind(record.user_id.map(|user_id| user_id.raw() as i32))
+        .bind(&record.key)
+        .fetch_one(self.db.as_pool())
+        .await?;
+
+        Ok(Some(record_from_row(&row)?))
+    }
+
+    async fn complete_key(
+        &self,
+        user_id: Option<UserId>,
+        key: &str,
+        response: &StoredResponse,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE idempotency_keys SET status=$3, etag=$4, body=$5 \
+            WHERE user_id IS NOT DISTINCT FROM $1 AND key=$2",
+        )
+        .bind(user_id.map(|user_id| user_id.raw() as i32))
+        .bind(key)
+        .bind(response.status as i16)
+        .bind(&response.etag)
+        .bind(&response.body)
+        .execute(self.db.as_pool())
+        .await?;
+
+        Ok(())
+    }
+
+    async fn release_key(&self, user_id: Option<UserId>, key: &str) -> anyhow::Result<()> {
+        sqlx::query(
+            "DELETE FROM idempotency_keys WHERE user_id IS NOT DISTINCT FROM $1 AND key=$2",
+        )
+        .bind(user_id.map(|user_id| user_id.raw() as i32))
+        .bind(key)
+        .execute(self.db.as_pool())
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_keys(&self, expired_before: DateTime<Utc>) -> anyhow::Result<u64> {
+        let res = sqlx::query("DELETE FROM idempotency_keys WHERE created_at < $1")
+            .bind(expired_before)
+            .execute(self.db.as_pool())
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+}
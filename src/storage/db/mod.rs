@@ -1,9 +1,26 @@
+mod activity;
+mod attachments;
+mod comments;
 mod database;
+mod filters;
+mod idempotency;
+mod search;
 mod sessions;
+mod sync;
 mod tasks;
 mod users;
+mod views;
+mod webhooks;
 
+pub use activity::DbActivity;
+pub use attachments::DbAttachments;
+pub use comments::DbComments;
 pub use database::{DatabaseConnection, DatabaseConnectionRef, DbError};
+pub use idempotency::DbIdempotency;
+pub use search::DbSearch;
 pub use sessions::DbSessions;
+pub use sync::DbSync;
 pub use tasks::DbTasks;
 pub use users::DbUsers;
+pub use views::DbViews;
+pub use webhooks::DbWebhooks;
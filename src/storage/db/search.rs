@@ -0,0 +1,84 @@
+use sqlx::Row;
+
+use crate::{
+    app::repositories::SearchRepository,
+    model::{
+        search::{SearchHit, HIGHLIGHT_END, HIGHLIGHT_START},
+        UserId,
+    },
+};
+
+use super::{
+    tasks::{task_from_row, TASK_COLUMNS},
+    DatabaseConnectionRef,
+};
+
+pub struct DbSearch {
+    db: DatabaseConnectionRef,
+}
+
+impl DbSearch {
+    pub fn new(db: DatabaseConnectionRef) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl SearchRepository for DbSearch {
+    async fn search_tasks(
+        &self,
+        user_id: UserId,
+        query: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<SearchHit>> {
+        let headline_options = format!(
+            "StartSel={}, StopSel={}, MinWords=5, MaxWords=20",
+            HIGHLIGHT_START, HIGHLIGHT_END
+        );
+
+        // A task is ranked by its own text and its best matching comment.
+        // The snippet is taken from the best ranked of them.
+        let rows = sqlx::query(&format!(
+            "WITH query AS (SELECT plainto_tsquery('simple', $2) AS q), \
+            task_hits AS ( \
+                SELECT t.task_id, ts_rank(t.search_vector, query.q) AS rank, \
+                    ts_headline('simple', t.label || ' ' || t.description, query.q, $4) AS snippet \
+                FROM tasks t, query \
+                WHERE t.user_id=$1 AND t.deleted_at IS NULL AND t.search_vector @@ query.q \
+            ), \
+            comment_hits AS ( \
+                SELECT DISTINCT ON (c.task_id) c.task_id, ts_rank(c.search_vector, query.q) AS rank, \
+                    ts_headline('simple', c.text, query.q, $4) AS snippet \
+                FROM comments c JOIN tasks t ON t.task_id=c.task_id, query \
+                WHERE t.user_id=$1 AND t.deleted_at IS NULL AND c.deleted_at IS NULL \
+                    AND c.search_vector @@ query.q \
+                ORDER BY c.task_id, rank DESC \
+            ), \
+            hits AS ( \
+                SELECT DISTINCT ON (task_id) task_id, snippet, \
+                    SUM(rank) OVER (PARTITION BY task_id) AS total_rank \
+                FROM (SELECT * FROM task_hits UNION ALL SELECT * FROM comment_hits) AS all_hits \
+                ORDER BY task_id, rank DESC \
+            ) \
+            SELECT {}, hits.total_rank AS search_rank, hits.snippet AS search_snippet FROM tasks JOIN hits ON hits.task_id=tasks.task_id \
+            ORDER BY hits.total_rank DESC, tasks.task_id LIMIT $3",
+            TASK_COLUMNS
+        ))
+        .bind(user_id.raw())
+        .bind(query)
+        .bind(limit)
+        .bind(headline_options)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(SearchHit {
+                    task: task_from_row(row)?,
+                    rank: row.try_get("search_rank")?,
+                    snippet: row.try_get("search_snippet")?,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::anyhow;
+use sqlx::{postgres::PgRow, PgConnection, Row};
+
+use crate::{
+    app::repositories::SyncRepository,
+    model::{
+        sync::{SyncChange, SyncEntity, SyncSeq},
+        UserId,
+    },
+};
+
+use super::{DatabaseConnectionRef, DbError};
+
+pub struct DbSync {
+    db: DatabaseConnectionRef,
+}
+
+fn change_from_row(row: &PgRow) -> anyhow::Result<SyncChange> {
+    let kind: String = row.try_get(2)?;
+
+    Ok(SyncChange {
+        seq: row.try_get(0)?,
+        user_id: UserId::from_raw(row.try_get::<i32, _>(1)? as i64),
+        entity: SyncEntity::from_parts(&kind, row.try_get(3)?)
+            .ok_or_else(|| anyhow!("unknown sync entity kind {}", kind))?,
+    })
+}
+
+/// Appends the changed entities to the log of the user within the transaction of the change.
+/// Returns the sequence number of the last entry.
+pub(super) async fn record_changes(
+    tx: &mut PgConnection,
+    user_id: UserId,
+    entities: &[SyncEntity],
+) -> Result<SyncSeq, DbError> {
+    // Reserving the numbers locks the row of the user until the commit, so the entries
+    // become visible in the order of their numbers and readers never skip one.
+    let row = sqlx::query(
+        "UPDATE users SET sync_seq = sync_seq + $2 WHERE user_id=$1 RETURNING sync_seq",
+    )
+    .bind(user_id.raw() as i32)
+    .bind(entities.len() as i64)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let last: SyncSeq = row.try_get(0)?;
+
+    let first = last - entities.len() as i64 + 1;
+
+    for (seq, entity) in (first..).zip(entities) {
+        sqlx::query(
+            "INSERT INTO sync_changes (user_id, seq, entity_kind, entity_id) \
+            VALUES ($1, $2, $3, $4)",
+        )
+        .bind(user_id.raw() as i32)
+        .bind(seq)
+        .bind(entity.kind())
+        .bind(entity.id())
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(last)
+}
+
+impl DbSync {
+    pub fn new(db: DatabaseConnectionRef) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl SyncRepository for DbSync {
+    async fn fetch_changes(
+        &self,
+        user_id: UserId,
+        since: SyncSeq,
+        limit: i64,
+    ) -> anyhow::Result<Vec<SyncChange>> {
+        let rows = sqlx::query(
+            "SELECT seq, user_id, entity_kind, entity_id FROM sync_changes \
+            WHERE user_id=$1 AND seq > $2 ORDER BY seq LIMIT $3",
+        )
+        .bind(user_id.raw() as i32)
+        .bind(since)
+        .bind(limit)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        rows.iter().map(change_from_row).collect()
+    }
+
+    async fn latest_seq(&self, user_id: UserId) -> anyhow::Result<SyncSeq> {
+        let row = sqlx::query("SELECT sync_seq FROM users WHERE user_id=$1")
+            .bind(user_id.raw() as i32)
+            .fetch_optional(self.db.as_pool())
+            .await?;
+
+        Ok(row.map(|row| row.try_get(0)).transpose()?.unwrap_or(0))
+    }
+}
@@ -1,98 +1,410 @@
+use chrono::{DateTime, Utc};
+
 use crate::{
     app::repositories::TasksRepository,
     model::{
-        tasks::{generate_random_task_id, TaskCategoryDescription, TaskDescription},
-        TaskId, UserId,
+        activity::ActivityRecord,
+        bulk::{BulkAction, BulkChange, BulkError, BulkFailure, BulkOperation},
+        checklists::{ChecklistItem, ChecklistItemId, ChecklistItemPatch, ChecklistProgress},
+        filters::TaskFilter,
+        imports::{BoardImport, ImportedItems},
+        labels::{LabelData, LabelDescription},
+        lifecycle::Lifecycle,
+        sync::SyncEntity,
+        tasks::{
+            generate_random_task_id, TaskCategoryDescription, TaskCursor, TaskData,
+            TaskDescription, TaskPatch, TaskPriority, Version, INITIAL_VERSION,
+        },
+        webhooks::WebhookEventKind,
+        BoardId, LabelId, TaskCategoryId, TaskId, UserId,
     },
 };
 
-use super::{DatabaseConnectionRef, DbError};
+use super::{
+    activity::record_activity, filters::push_filter, sync::record_changes,
+    webhooks::record_webhook_events, DatabaseConnectionRef, DbError,
+};
 
-use sqlx::Row;
+use sqlx::{postgres::PgRow, PgConnection, Postgres, QueryBuilder, Row};
 
 pub struct DbTasks {
     db: DatabaseConnectionRef,
 }
 
+/// Columns of `tasks` table read by [`task_from_row`].
+pub(super) const TASK_COLUMNS: &str = "task_id, category_id, label, description, start_at, due_at, version, priority, \
+    ARRAY(SELECT label_id FROM task_labels WHERE task_labels.task_id=tasks.task_id ORDER BY label_id), \
+    (SELECT COUNT(*) FILTER (WHERE done) FROM checklist_items WHERE checklist_items.task_id=tasks.task_id), \
+    (SELECT COUNT(*) FROM checklist_items WHERE checklist_items.task_id=tasks.task_id), \
+    archived_at, deleted_at";
+
+/// Columns of `task_categories` table read by [`category_from_row`].
+const CATEGORY_COLUMNS: &str = "category_id, label, version, archived_at, deleted_at";
+
+/// Reads a task from a row of [`TASK_COLUMNS`].
+pub(super) fn task_from_row(row: &PgRow) -> Result<TaskDescription, DbError> {
+    Ok(TaskDescription {
+        task_id: row.try_get(0)?,
+        category_id: row.try_get(1)?,
+        label: row.try_get(2)?,
+        description: row.try_get(3)?,
+        start_at: row.try_get(4)?,
+        due_at: row.try_get(5)?,
+        version: row.try_get(6)?,
+        priority: TaskPriority::from_rank(row.try_get(7)?)
+            .ok_or_else(|| DbError::Decode("unknown task priority".into()))?,
+        label_ids: row.try_get(8)?,
+        checklist_progress: ChecklistProgress {
+            done: row.try_get(9)?,
+            total: row.try_get(10)?,
+        },
+        lifecycle: Lifecycle {
+            archived_at: row.try_get(11)?,
+            deleted_at: row.try_get(12)?,
+        },
+    })
+}
+
+fn category_from_row(row: &PgRow) -> Result<TaskCategoryDescription, DbError> {
+    Ok(TaskCategoryDescription {
+        category_id: row.try_get(0)?,
+        label: row.try_get(1)?,
+        version: row.try_get(2)?,
+        lifecycle: Lifecycle {
+            archived_at: row.try_get(3)?,
+            deleted_at: row.try_get(4)?,
+        },
+    })
+}
+
+fn label_from_row(row: &PgRow) -> Result<LabelDescription, DbError> {
+    Ok(LabelDescription {
+        label_id: row.try_get(0)?,
+        name: row.try_get(1)?,
+        color: row.try_get(2)?,
+    })
+}
+
 impl DbTasks {
     pub fn new(db: DatabaseConnectionRef) -> Self {
         Self { db }
     }
 }
 
+/// Locks the task until the end of the transaction and increments its version.
+/// Fails if there is no such task.
+async fn lock_and_bump_task(
+    tx: &mut PgConnection,
+    user_id: UserId,
+    task_id: &str,
+) -> Result<(), DbError> {
+    let res = sqlx::query("UPDATE tasks SET version=version+1 WHERE user_id=$1 AND task_id=$2")
+        .bind(user_id.raw())
+        .bind(task_id)
+        .execute(tx)
+        .await?;
+
+    if res.rows_affected() == 0 {
+        return Err(DbError::RowNotFound);
+    }
+
+    Ok(())
+}
+
+async fn insert_task(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    task: &TaskData,
+) -> Result<TaskId, DbError> {
+    let random_task_id = generate_random_task_id();
+
+    sqlx::query(
+        "INSERT INTO tasks (user_id, task_id, category_id, label, description, start_at, due_at, priority) \
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(user_id.raw())
+    .bind(&random_task_id)
+    .bind(&task.category_id)
+    .bind(&task.label)
+    .bind(&task.description)
+    .bind(task.start_at)
+    .bind(task.due_at)
+    .bind(task.priority.rank())
+    .execute(conn)
+    .await?;
+
+    Ok(random_task_id)
+}
+
+async fn insert_categories(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    labels: &[&str],
+) -> Result<Vec<TaskCategoryDescription>, DbError> {
+    let descriptions: Vec<TaskCategoryDescription> = labels
+        .iter()
+        .map(|label| TaskCategoryDescription {
+            category_id: generate_random_task_id(),
+            label: label.to_string(),
+            lifecycle: Lifecycle::default(),
+            version: INITIAL_VERSION,
+        })
+        .collect();
+
+    for desc in &descriptions {
+        sqlx::query(
+            "INSERT INTO task_categories (user_id, category_id, label) VALUES ($1, $2, $3)",
+        )
+        .bind(user_id.raw())
+        .bind(&desc.category_id)
+        .bind(&desc.label)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(descriptions)
+}
+
+async fn insert_label(
+    conn: &mut PgConnection,
+    user_id: UserId,
+    label: &LabelData,
+) -> Result<LabelId, DbError> {
+    let random_label_id = generate_random_task_id();
+
+    sqlx::query("INSERT INTO labels (user_id, label_id, name, color) VALUES ($1, $2, $3, $4)")
+        .bind(user_id.raw())
+        .bind(&random_label_id)
+        .bind(&label.name)
+        .bind(&label.color)
+        .execute(conn)
+        .await?;
+
+    Ok(random_label_id)
+}
+
 #[async_trait]
 impl TasksRepository for DbTasks {
     async fn fetch_tasks(&self, user_id: UserId) -> anyhow::Result<Vec<TaskDescription>> {
-        let rows = sqlx::query(
-            "SELECT task_id, category_id, label, description FROM tasks WHERE user_id=$1",
-        )
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM tasks WHERE user_id=$1 AND deleted_at IS NULL \
+            ORDER BY created_seq, task_id",
+            TASK_COLUMNS
+        ))
+        .bind(user_id.raw())
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        Ok(rows.iter().map(task_from_row).collect::<Result<_, _>>()?)
+    }
+
+    async fn fetch_filtered_tasks(
+        &self,
+        user_id: UserId,
+        filter: &TaskFilter,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<TaskDescription>> {
+        let mut query = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {} FROM tasks WHERE user_id=",
+            TASK_COLUMNS
+        ));
+        query
+            .push_bind(user_id.raw())
+            .push(" AND deleted_at IS NULL AND (");
+        push_filter(&mut query, filter, now);
+        query.push(")");
+
+        let rows = query.build().fetch_all(self.db.as_pool()).await?;
+
+        Ok(rows.iter().map(task_from_row).collect::<Result<_, _>>()?)
+    }
+
+    async fn fetch_category_tasks(
+        &self,
+        user_id: UserId,
+        category_id: &str,
+        after: Option<&TaskCursor>,
+        limit: i64,
+        include_archived: bool,
+    ) -> anyhow::Result<Vec<(TaskCursor, TaskDescription)>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {}, created_seq FROM tasks WHERE user_id=$1 AND category_id=$2 \
+            AND deleted_at IS NULL AND ($3 OR archived_at IS NULL) \
+            AND ($4::BIGINT IS NULL OR (created_seq, task_id) > ($4, $5)) \
+            ORDER BY created_seq, task_id LIMIT $6",
+            TASK_COLUMNS
+        ))
         .bind(user_id.raw())
+        .bind(category_id)
+        .bind(include_archived)
+        .bind(after.map(|after| after.created_seq))
+        .bind(after.map(|after| after.task_id.as_str()))
+        .bind(limit)
         .fetch_all(self.db.as_pool())
         .await?;
 
         Ok(rows
-            .into_iter()
+            .iter()
             .map(|row| {
-                // TODO: use try_get instead. Otherwise, panic is possible.
-                let task_id = row.get(0);
-                let category_id = row.get(1);
-                let label = row.get(2);
-                let description = row.get(3);
-
-                TaskDescription {
-                    task_id,
-                    category_id,
-                    label,
-                    description,
-                }
+                let task = task_from_row(row)?;
+                let cursor = TaskCursor {
+                    created_seq: row.try_get("created_seq")?,
+                    task_id: task.task_id.clone(),
+                };
+                Ok((cursor, task))
             })
-            .collect())
+            .collect::<Result<_, DbError>>()?)
+    }
+
+    async fn count_category_tasks(
+        &self,
+        user_id: UserId,
+        include_archived: bool,
+    ) -> anyhow::Result<Vec<(TaskCategoryId, i64)>> {
+        let rows = sqlx::query(
+            "SELECT category_id, COUNT(*) FROM tasks WHERE user_id=$1 AND deleted_at IS NULL \
+            AND ($2 OR archived_at IS NULL) GROUP BY category_id",
+        )
+        .bind(user_id.raw())
+        .bind(include_archived)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Ok((row.try_get(0)?, row.try_get(1)?)))
+            .collect::<Result<_, DbError>>()?)
+    }
+
+    async fn fetch_task(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Option<TaskDescription>> {
+        let optional_row = sqlx::query(&format!(
+            "SELECT {} FROM tasks WHERE user_id=$1 AND task_id=$2 AND deleted_at IS NULL",
+            TASK_COLUMNS
+        ))
+        .bind(user_id.raw())
+        .bind(task_id)
+        .fetch_optional(self.db.as_pool())
+        .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(None);
+        };
+
+        Ok(Some(task_from_row(&row)?))
+    }
+
+    async fn fetch_tasks_due(
+        &self,
+        user_id: UserId,
+        from: Option<DateTime<Utc>>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<TaskDescription>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM tasks WHERE user_id=$1 AND archived_at IS NULL AND deleted_at IS NULL \
+            AND due_at < $2 AND ($3::TIMESTAMPTZ IS NULL OR due_at >= $3) \
+            ORDER BY due_at",
+            TASK_COLUMNS
+        ))
+        .bind(user_id.raw())
+        .bind(to)
+        .bind(from)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        Ok(rows.iter().map(task_from_row).collect::<Result<_, _>>()?)
     }
 
     async fn create_task(
         &self,
         user_id: UserId,
-        label: &str,
-        description: &str,
-        category_id: &str,
+        task: &TaskData,
+        activity: &ActivityRecord,
     ) -> anyhow::Result<TaskId> {
-        let random_task_id = generate_random_task_id();
+        let mut tx = self.db.as_pool().begin().await?;
 
-        sqlx::query("INSERT INTO tasks (user_id, task_id, category_id, label, description) VALUES ($1, $2, $3, $4, $5)")
-            .bind(user_id.raw())
-            .bind(&random_task_id)
-            .bind(category_id)
-            .bind(label)
-            .bind(description)
-            .execute(self.db.as_pool())
-            .await?;
+        let task_id = insert_task(&mut tx, user_id, task).await?;
+        record_activity(&mut tx, &task_id, activity).await?;
+        record_changes(&mut tx, user_id, &[SyncEntity::Task(task_id.clone())]).await?;
+        record_webhook_events(
+            &mut tx,
+            activity.board_id,
+            &[WebhookEventKind::of_task(&task_id, &activity.kinds)],
+        )
+        .await?;
 
-        Ok(random_task_id)
+        tx.commit().await?;
+        Ok(task_id)
     }
 
-    async fn modify_task(
+    async fn update_task(
         &self,
         user_id: UserId,
         task_id: &str,
-        label: &str,
-        description: &str,
-        category_id: &str,
-    ) -> anyhow::Result<()> {
-        let res = sqlx::query("UPDATE tasks SET label=$1, description=$2, category_id=$3 WHERE user_id=$4 AND task_id=$5")
-        .bind(label)
-        .bind(description)
-            .bind(category_id)
-            .bind(user_id.raw())
-            .bind(task_id)
-            .execute(self.db.as_pool())
-            .await?;
+        patch: &TaskPatch,
+        expected_version: Option<Version>,
+        activity: &ActivityRecord,
+    ) -> anyhow::Result<Option<TaskDescription>> {
+        let mut query = QueryBuilder::<Postgres>::new("UPDATE tasks SET version=version+1");
 
-        // Expected to modify at least 1 task.
-        if res.rows_affected() == 0 {
-            return Err(DbError::RowNotFound.into());
+        if let Some(label) = &patch.label {
+            query.push(", label=").push_bind(label);
         }
 
-        Ok(())
+        if let Some(description) = &patch.description {
+            query.push(", description=").push_bind(description);
+        }
+
+        if let Some(category_id) = &patch.category_id {
+            query.push(", category_id=").push_bind(category_id);
+        }
+
+        if let Some(start_at) = patch.start_at {
+            query.push(", start_at=").push_bind(start_at);
+        }
+
+        if let Some(due_at) = patch.due_at {
+            query.push(", due_at=").push_bind(due_at);
+        }
+
+        if let Some(priority) = patch.priority {
+            query.push(", priority=").push_bind(priority.rank());
+        }
+
+        query
+            .push(" WHERE user_id=")
+            .push_bind(user_id.raw())
+            .push(" AND task_id=")
+            .push_bind(task_id);
+
+        if let Some(expected_version) = expected_version {
+            query.push(" AND version=").push_bind(expected_version);
+        }
+
+        query.push(" RETURNING ").push(TASK_COLUMNS);
+
+        let mut tx = self.db.as_pool().begin().await?;
+
+        let optional_row = query.build().fetch_optional(&mut *tx).await?;
+
+        let Some(row) = optional_row else {
+            return Ok(None);
+        };
+
+        record_activity(&mut tx, task_id, activity).await?;
+        record_changes(&mut tx, user_id, &[SyncEntity::Task(task_id.to_string())]).await?;
+        record_webhook_events(
+            &mut tx,
+            activity.board_id,
+            &[WebhookEventKind::of_task(task_id, &activity.kinds)],
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(task_from_row(&row)?))
     }
 
     async fn delete_task(&self, user_id: UserId, task_id: &str) -> anyhow::Result<()> {
@@ -110,25 +422,410 @@ impl TasksRepository for DbTasks {
         Ok(())
     }
 
+    async fn set_task_lifecycle(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        lifecycle: Lifecycle,
+        activity: &ActivityRecord,
+    ) -> anyhow::Result<Option<TaskDescription>> {
+        let mut tx = self.db.as_pool().begin().await?;
+
+        let optional_row = sqlx::query(&format!(
+            "UPDATE tasks SET archived_at=$3, deleted_at=$4, version=version+1 \
+            WHERE user_id=$1 AND task_id=$2 RETURNING {}",
+            TASK_COLUMNS
+        ))
+        .bind(user_id.raw())
+        .bind(task_id)
+        .bind(lifecycle.archived_at)
+        .bind(lifecycle.deleted_at)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(None);
+        };
+
+        record_activity(&mut tx, task_id, activity).await?;
+        record_changes(&mut tx, user_id, &[SyncEntity::Task(task_id.to_string())]).await?;
+        record_webhook_events(
+            &mut tx,
+            activity.board_id,
+            &[WebhookEventKind::of_task(task_id, &activity.kinds)],
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(task_from_row(&row)?))
+    }
+
+    async fn apply_bulk_operations(
+        &self,
+        user_id: UserId,
+        board_id: BoardId,
+        operations: &[BulkOperation],
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Result<Vec<BulkChange>, BulkFailure>> {
+        let mut tx = self.db.as_pool().begin().await?;
+
+        // Tasks touched by the batch, in the order they are first touched,
+        // with their states before the batch.
+        let mut touched: Vec<(TaskDescription, TaskDescription)> = Vec::new();
+        let mut changes = Vec::with_capacity(operations.len());
+
+        for (index, operation) in operations.iter().enumerate() {
+            // Returning drops the transaction, rolling back the operations applied so far.
+            let fail = |error| Ok(Err(BulkFailure { index, error }));
+
+            let position = match touched
+                .iter()
+                .position(|(t, _)| t.task_id == operation.task_id)
+            {
+                Some(position) => position,
+                None => {
+                    let optional_row = sqlx::query(&format!(
+                        "SELECT {} FROM tasks WHERE user_id=$1 AND task_id=$2 AND deleted_at IS NULL \
+                        FOR UPDATE",
+                        TASK_COLUMNS
+                    ))
+                    .bind(user_id.raw())
+                    .bind(&operation.task_id)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+
+                    let Some(row) = optional_row else {
+                        return fail(BulkError::TaskNotFound);
+                    };
+
+                    let task = task_from_row(&row)?;
+                    touched.push((task.clone(), task));
+                    touched.len() - 1
+                }
+            };
+
+            let task = &mut touched[position].0;
+            if task.lifecycle.is_trashed() {
+                return fail(BulkError::TaskNotFound);
+            }
+
+            match &operation.action {
+                BulkAction::Update(TaskPatch {
+                    category_id: Some(category_id),
+                    ..
+                }) => {
+                    let exists = sqlx::query(
+                        "SELECT 1 FROM task_categories \
+                        WHERE user_id=$1 AND category_id=$2 AND deleted_at IS NULL",
+                    )
+                    .bind(user_id.raw())
+                    .bind(category_id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .is_some();
+
+                    if !exists {
+                        return fail(BulkError::CategoryNotFound);
+                    }
+                }
+                BulkAction::AddLabel(label_id) | BulkAction::RemoveLabel(label_id) => {
+                    let exists =
+                        sqlx::query("SELECT 1 FROM labels WHERE user_id=$1 AND label_id=$2")
+                            .bind(user_id.raw())
+                            .bind(label_id)
+                            .fetch_optional(&mut *tx)
+                            .await?
+                            .is_some();
+
+                    if !exists {
+                        return fail(BulkError::LabelNotFound);
+                    }
+                }
+                _ => {}
+            }
+
+            let previous = task.clone();
+            operation.action.apply(task, now);
+            changes.push(BulkChange {
+                previous,
+                task: task.clone(),
+            });
+        }
+
+        for (task, original) in &touched {
+            if task.version == original.version {
+                continue;
+            }
+
+            sqlx::query(
+                "UPDATE tasks SET category_id=$3, label=$4, description=$5, start_at=$6, due_at=$7, \
+                priority=$8, archived_at=$9, deleted_at=$10, version=$11 \
+                WHERE user_id=$1 AND task_id=$2",
+            )
+            .bind(user_id.raw())
+            .bind(&task.task_id)
+            .bind(&task.category_id)
+            .bind(&task.label)
+            .bind(&task.description)
+            .bind(task.start_at)
+            .bind(task.due_at)
+            .bind(task.priority.rank())
+            .bind(task.lifecycle.archived_at)
+            .bind(task.lifecycle.deleted_at)
+            .bind(task.version)
+            .execute(&mut *tx)
+            .await?;
+
+            for label_id in &original.label_ids {
+                if !task.label_ids.contains(label_id) {
+                    sqlx::query("DELETE FROM task_labels WHERE task_id=$1 AND label_id=$2")
+                        .bind(&task.task_id)
+                        .bind(label_id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+
+            for label_id in &task.label_ids {
+                if !original.label_ids.contains(label_id) {
+                    sqlx::query("INSERT INTO task_labels (task_id, label_id) VALUES ($1, $2)")
+                        .bind(&task.task_id)
+                        .bind(label_id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+        }
+
+        let mut entities = Vec::new();
+        let mut events = Vec::new();
+
+        for (operation, change) in operations.iter().zip(&changes) {
+            let activity = ActivityRecord {
+                board_id,
+                actor_id: user_id,
+                kinds: operation.action.activity(change),
+                created_at: now,
+            };
+
+            if change.is_changed() {
+                let entity = SyncEntity::Task(change.task.task_id.clone());
+                if !entities.contains(&entity) {
+                    entities.push(entity);
+                }
+
+                events.push(WebhookEventKind::of_task(
+                    &change.task.task_id,
+                    &activity.kinds,
+                ));
+            }
+
+            record_activity(&mut tx, &change.task.task_id, &activity).await?;
+        }
+
+        record_changes(&mut tx, user_id, &entities).await?;
+        record_webhook_events(&mut tx, board_id, &events).await?;
+
+        tx.commit().await?;
+        Ok(Ok(changes))
+    }
+
+    async fn fetch_trashed_tasks(&self, user_id: UserId) -> anyhow::Result<Vec<TaskDescription>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM tasks WHERE user_id=$1 AND deleted_at IS NOT NULL",
+            TASK_COLUMNS
+        ))
+        .bind(user_id.raw())
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        Ok(rows.iter().map(task_from_row).collect::<Result<_, _>>()?)
+    }
+
+    async fn fetch_expired_tasks(
+        &self,
+        deleted_before: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<(UserId, TaskId)>> {
+        let rows = sqlx::query(
+            "SELECT user_id, task_id FROM tasks WHERE deleted_at < $1 \
+            OR category_id IN (SELECT category_id FROM task_categories WHERE deleted_at < $1)",
+        )
+        .bind(deleted_before)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                Ok((
+                    UserId::from_raw(row.try_get::<i32, _>(0)? as i64),
+                    row.try_get(1)?,
+                ))
+            })
+            .collect::<Result<_, DbError>>()?)
+    }
+
     async fn fetch_categories(
         &self,
         user_id: UserId,
     ) -> anyhow::Result<Vec<TaskCategoryDescription>> {
-        let rows = sqlx::query("SELECT category_id, label FROM task_categories WHERE user_id=$1")
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM task_categories WHERE user_id=$1 AND deleted_at IS NULL \
+            ORDER BY created_seq, category_id",
+            CATEGORY_COLUMNS
+        ))
+        .bind(user_id.raw())
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(category_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn rename_category(
+        &self,
+        user_id: UserId,
+        category_id: &str,
+        label: &str,
+    ) -> anyhow::Result<Option<TaskCategoryDescription>> {
+        let mut tx = self.db.as_pool().begin().await?;
+
+        let optional_row = sqlx::query(&format!(
+            "UPDATE task_categories SET label=$3, version=version+1 \
+            WHERE user_id=$1 AND category_id=$2 AND deleted_at IS NULL RETURNING {}",
+            CATEGORY_COLUMNS
+        ))
+        .bind(user_id.raw())
+        .bind(category_id)
+        .bind(label)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(None);
+        };
+
+        let entities = [SyncEntity::Category(category_id.to_string())];
+        record_changes(&mut tx, user_id, &entities).await?;
+        let events = [WebhookEventKind::CategoryUpdated(category_id.to_string())];
+        record_webhook_events(&mut tx, user_id, &events).await?;
+
+        tx.commit().await?;
+        Ok(Some(category_from_row(&row)?))
+    }
+
+    async fn set_category_lifecycle(
+        &self,
+        user_id: UserId,
+        category_id: &str,
+        lifecycle: Lifecycle,
+    ) -> anyhow::Result<Option<TaskCategoryDescription>> {
+        let mut tx = self.db.as_pool().begin().await?;
+
+        let optional_row = sqlx::query(
+            "SELECT deleted_at FROM task_categories WHERE user_id=$1 AND category_id=$2 FOR UPDATE",
+        )
+        .bind(user_id.raw())
+        .bind(category_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(None);
+        };
+
+        let previous_deleted_at: Option<DateTime<Utc>> = row.try_get(0)?;
+
+        let row = sqlx::query(&format!(
+            "UPDATE task_categories SET archived_at=$3, deleted_at=$4, version=version+1 \
+            WHERE user_id=$1 AND category_id=$2 RETURNING {}",
+            CATEGORY_COLUMNS
+        ))
+        .bind(user_id.raw())
+        .bind(category_id)
+        .bind(lifecycle.archived_at)
+        .bind(lifecycle.deleted_at)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // The tasks already in the trash stay there when the category is restored.
+        let tasks_update = match (previous_deleted_at, lifecycle.deleted_at) {
+            (None, Some(deleted_at)) => Some((
+                "UPDATE tasks SET deleted_at=$3, version=version+1 \
+                WHERE user_id=$1 AND category_id=$2 AND deleted_at IS NULL",
+                deleted_at,
+            )),
+            (Some(deleted_at), None) => Some((
+                "UPDATE tasks SET deleted_at=NULL, version=version+1 \
+                WHERE user_id=$1 AND category_id=$2 AND deleted_at=$3",
+                deleted_at,
+            )),
+            _ => None,
+        };
+
+        if let Some((query, deleted_at)) = tasks_update {
+            sqlx::query(query)
+                .bind(user_id.raw())
+                .bind(category_id)
+                .bind(deleted_at)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        // The tasks appear and disappear together with the category.
+        let rows = sqlx::query("SELECT task_id FROM tasks WHERE user_id=$1 AND category_id=$2")
             .bind(user_id.raw())
-            .fetch_all(self.db.as_pool())
+            .bind(category_id)
+            .fetch_all(&mut *tx)
             .await?;
 
+        let mut entities = vec![SyncEntity::Category(category_id.to_string())];
+        for row in &rows {
+            entities.push(SyncEntity::Task(row.try_get(0)?));
+        }
+        record_changes(&mut tx, user_id, &entities).await?;
+
+        let event = if lifecycle.is_active() {
+            WebhookEventKind::CategoryRestored(category_id.to_string())
+        } else {
+            WebhookEventKind::CategoryRemoved(category_id.to_string())
+        };
+        record_webhook_events(&mut tx, user_id, &[event]).await?;
+
+        tx.commit().await?;
+        Ok(Some(category_from_row(&row)?))
+    }
+
+    async fn fetch_trashed_categories(
+        &self,
+        user_id: UserId,
+    ) -> anyhow::Result<Vec<TaskCategoryDescription>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM task_categories WHERE user_id=$1 AND deleted_at IS NOT NULL",
+            CATEGORY_COLUMNS
+        ))
+        .bind(user_id.raw())
+        .fetch_all(self.db.as_pool())
+        .await?;
+
         Ok(rows
-            .into_iter()
-            .map(|row| {
-                // TODO: use try_get instead. Otherwise, panic is possible.
-                let category_id = row.get(0);
-                let label = row.get(1);
+            .iter()
+            .map(category_from_row)
+            .collect::<Result<_, _>>()?)
+    }
 
-                TaskCategoryDescription { category_id, label }
-            })
-            .collect())
+    async fn delete_expired_categories(
+        &self,
+        deleted_before: DateTime<Utc>,
+    ) -> anyhow::Result<u64> {
+        let res = sqlx::query("DELETE FROM task_categories WHERE deleted_at < $1")
+            .bind(deleted_before)
+            .execute(self.db.as_pool())
+            .await?;
+
+        Ok(res.rows_affected())
     }
 
     async fn add_categories(
@@ -136,28 +833,395 @@ impl TasksRepository for DbTasks {
         user_id: UserId,
         labels: &[&str],
     ) -> anyhow::Result<Vec<TaskCategoryDescription>> {
-        let descriptions: Vec<TaskCategoryDescription> = labels
+        let mut tx = self.db.as_pool().begin().await?;
+        let descriptions = insert_categories(&mut tx, user_id, labels).await?;
+
+        let entities: Vec<SyncEntity> = descriptions
             .iter()
-            .map(|label| TaskCategoryDescription {
-                category_id: generate_random_task_id(),
-                label: label.to_string(),
-            })
+            .map(|c| SyncEntity::Category(c.category_id.clone()))
             .collect();
+        record_changes(&mut tx, user_id, &entities).await?;
+
+        let events: Vec<WebhookEventKind> = descriptions
+            .iter()
+            .map(|c| WebhookEventKind::CategoryCreated(c.category_id.clone()))
+            .collect();
+        record_webhook_events(&mut tx, user_id, &events).await?;
+
+        tx.commit().await?;
+        Ok(descriptions)
+    }
 
+    async fn import_board(
+        &self,
+        user_id: UserId,
+        import: &BoardImport,
+        activity: &ActivityRecord,
+    ) -> anyhow::Result<ImportedItems> {
         let mut tx = self.db.as_pool().begin().await?;
 
-        for desc in &descriptions {
-            sqlx::query(
-                "INSERT INTO task_categories (user_id, category_id, label) VALUES ($1, $2, $3)",
+        let labels: Vec<&str> = import.categories.iter().map(String::as_str).collect();
+
+        let mut items = ImportedItems {
+            categories: insert_categories(&mut tx, user_id, &labels).await?,
+            ..Default::default()
+        };
+
+        for label in &import.labels {
+            items
+                .label_ids
+                .push(insert_label(&mut tx, user_id, label).await?);
+        }
+
+        for task in &import.tasks {
+            let data = TaskData {
+                label: task.label.clone(),
+                description: task.description.clone(),
+                category_id: items.category_id(&task.category).to_string(),
+                start_at: task.start_at,
+                due_at: task.due_at,
+                priority: task.priority,
+            };
+
+            let task_id = insert_task(&mut tx, user_id, &data).await?;
+
+            for label in &task.labels {
+                sqlx::query(
+                    "INSERT INTO task_labels (task_id, label_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                )
+                .bind(&task_id)
+                .bind(items.label_id(label))
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            record_activity(&mut tx, &task_id, activity).await?;
+            items.task_ids.push(task_id);
+        }
+
+        let entities: Vec<SyncEntity> = items
+            .categories
+            .iter()
+            .map(|c| SyncEntity::Category(c.category_id.clone()))
+            .chain(items.task_ids.iter().cloned().map(SyncEntity::Task))
+            .collect();
+        record_changes(&mut tx, user_id, &entities).await?;
+
+        // Like on the event stream, the imported tasks are not announced one by one.
+        let events: Vec<WebhookEventKind> = items
+            .categories
+            .iter()
+            .map(|c| WebhookEventKind::CategoryCreated(c.category_id.clone()))
+            .collect();
+        record_webhook_events(&mut tx, activity.board_id, &events).await?;
+
+        tx.commit().await?;
+        Ok(items)
+    }
+
+    async fn fetch_labels(&self, user_id: UserId) -> anyhow::Result<Vec<LabelDescription>> {
+        let rows = sqlx::query("SELECT label_id, name, color FROM labels WHERE user_id=$1")
+            .bind(user_id.raw())
+            .fetch_all(self.db.as_pool())
+            .await?;
+
+        Ok(rows.iter().map(label_from_row).collect::<Result<_, _>>()?)
+    }
+
+    async fn fetch_label(
+        &self,
+        user_id: UserId,
+        label_id: &str,
+    ) -> anyhow::Result<Option<LabelDescription>> {
+        let optional_row = sqlx::query(
+            "SELECT label_id, name, color FROM labels WHERE user_id=$1 AND label_id=$2",
+        )
+        .bind(user_id.raw())
+        .bind(label_id)
+        .fetch_optional(self.db.as_pool())
+        .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(None);
+        };
+
+        Ok(Some(label_from_row(&row)?))
+    }
+
+    async fn create_label(&self, user_id: UserId, label: &LabelData) -> anyhow::Result<LabelId> {
+        let mut conn = self.db.as_pool().acquire().await?;
+        Ok(insert_label(&mut conn, user_id, label).await?)
+    }
+
+    async fn update_label(
+        &self,
+        user_id: UserId,
+        label_id: &str,
+        label: &LabelData,
+    ) -> anyhow::Result<bool> {
+        let res =
+            sqlx::query("UPDATE labels SET name=$3, color=$4 WHERE user_id=$1 AND label_id=$2")
+                .bind(user_id.raw())
+                .bind(label_id)
+                .bind(&label.name)
+                .bind(&label.color)
+                .execute(self.db.as_pool())
+                .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn delete_label(&self, user_id: UserId, label_id: &str) -> anyhow::Result<bool> {
+        let mut tx = self.db.as_pool().begin().await?;
+
+        let rows = sqlx::query(
+            "UPDATE tasks SET version=version+1 WHERE user_id=$1 \
+            AND task_id IN (SELECT task_id FROM task_labels WHERE label_id=$2) RETURNING task_id",
+        )
+        .bind(user_id.raw())
+        .bind(label_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // Assignments are deleted by cascade.
+        let res = sqlx::query("DELETE FROM labels WHERE user_id=$1 AND label_id=$2")
+            .bind(user_id.raw())
+            .bind(label_id)
+            .execute(&mut *tx)
+            .await?;
+
+        if res.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        let entities = rows
+            .iter()
+            .map(|row| Ok(SyncEntity::Task(row.try_get(0)?)))
+            .collect::<Result<Vec<_>, DbError>>()?;
+        record_changes(&mut tx, user_id, &entities).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn set_label_assigned(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        label_id: &str,
+        assigned: bool,
+    ) -> anyhow::Result<Option<TaskDescription>> {
+        let mut tx = self.db.as_pool().begin().await?;
+
+        let task_exists =
+            sqlx::query("SELECT 1 FROM tasks WHERE user_id=$1 AND task_id=$2 FOR UPDATE")
+                .bind(user_id.raw())
+                .bind(task_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .is_some();
+
+        if !task_exists {
+            return Ok(None);
+        }
+
+        let query = if assigned {
+            "INSERT INTO task_labels (task_id, label_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"
+        } else {
+            "DELETE FROM task_labels WHERE task_id=$1 AND label_id=$2"
+        };
+
+        let res = sqlx::query(query)
+            .bind(task_id)
+            .bind(label_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let changed = res.rows_affected() > 0;
+
+        // The version is incremented only if the assignment has changed.
+        let sql = if changed {
+            format!(
+                "UPDATE tasks SET version=version+1 WHERE user_id=$1 AND task_id=$2 RETURNING {}",
+                TASK_COLUMNS
+            )
+        } else {
+            format!(
+                "SELECT {} FROM tasks WHERE user_id=$1 AND task_id=$2",
+                TASK_COLUMNS
             )
+        };
+
+        let row = sqlx::query(&sql)
             .bind(user_id.raw())
-            .bind(&desc.category_id)
-            .bind(&desc.label)
+            .bind(task_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        if changed {
+            record_changes(&mut tx, user_id, &[SyncEntity::Task(task_id.to_string())]).await?;
+            let events = [WebhookEventKind::TaskUpdated(task_id.to_string())];
+            record_webhook_events(&mut tx, user_id, &events).await?;
+        }
+
+        tx.commit().await?;
+        Ok(Some(task_from_row(&row)?))
+    }
+
+    async fn fetch_checklist(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Vec<ChecklistItem>> {
+        let rows = sqlx::query(
+            "SELECT c.item_id, c.text, c.done, c.position FROM checklist_items c \
+            JOIN tasks t ON t.task_id=c.task_id WHERE t.user_id=$1 AND c.task_id=$2 \
+            ORDER BY c.position",
+        )
+        .bind(user_id.raw())
+        .bind(task_id)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                Ok(ChecklistItem {
+                    item_id: row.try_get(0)?,
+                    text: row.try_get(1)?,
+                    done: row.try_get(2)?,
+                    position: row.try_get(3)?,
+                })
+            })
+            .collect::<Result<_, DbError>>()?)
+    }
+
+    async fn add_checklist_item(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        text: &str,
+    ) -> anyhow::Result<ChecklistItemId> {
+        let random_item_id = generate_random_task_id();
+
+        let mut tx = self.db.as_pool().begin().await?;
+
+        lock_and_bump_task(&mut tx, user_id, task_id).await?;
+
+        sqlx::query(
+            "INSERT INTO checklist_items (item_id, task_id, text, done, position) \
+            VALUES ($1, $2, $3, FALSE, (SELECT COUNT(*) FROM checklist_items WHERE task_id=$2))",
+        )
+        .bind(&random_item_id)
+        .bind(task_id)
+        .bind(text)
+        .execute(&mut *tx)
+        .await?;
+
+        record_changes(&mut tx, user_id, &[SyncEntity::Task(task_id.to_string())]).await?;
+        let events = [WebhookEventKind::TaskUpdated(task_id.to_string())];
+        record_webhook_events(&mut tx, user_id, &events).await?;
+
+        tx.commit().await?;
+        Ok(random_item_id)
+    }
+
+    async fn update_checklist_item(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        item_id: &str,
+        patch: &ChecklistItemPatch,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.db.as_pool().begin().await?;
+
+        lock_and_bump_task(&mut tx, user_id, task_id).await?;
+
+        let optional_row = sqlx::query(
+            "UPDATE checklist_items SET text=COALESCE($3, text), done=COALESCE($4, done) \
+            WHERE task_id=$1 AND item_id=$2 RETURNING position",
+        )
+        .bind(task_id)
+        .bind(item_id)
+        .bind(&patch.text)
+        .bind(patch.done)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(false);
+        };
+
+        if let Some(position) = patch.position {
+            let old_position: i32 = row.try_get(0)?;
+
+            // Move the items in between by one position towards the old position of the item.
+            sqlx::query(
+                "WITH target AS ( \
+                    SELECT LEAST(GREATEST($3, 0), COUNT(*) - 1)::INT AS position \
+                    FROM checklist_items WHERE task_id=$1 \
+                ) \
+                UPDATE checklist_items SET position=CASE \
+                    WHEN item_id=$2 THEN target.position \
+                    WHEN target.position < $4 THEN checklist_items.position + 1 \
+                    ELSE checklist_items.position - 1 \
+                END \
+                FROM target WHERE task_id=$1 AND (item_id=$2 \
+                    OR checklist_items.position BETWEEN LEAST(target.position, $4) AND GREATEST(target.position, $4))",
+            )
+            .bind(task_id)
+            .bind(item_id)
+            .bind(position)
+            .bind(old_position)
             .execute(&mut *tx)
             .await?;
         }
 
+        record_changes(&mut tx, user_id, &[SyncEntity::Task(task_id.to_string())]).await?;
+        let events = [WebhookEventKind::TaskUpdated(task_id.to_string())];
+        record_webhook_events(&mut tx, user_id, &events).await?;
+
         tx.commit().await?;
-        Ok(descriptions)
+        Ok(true)
+    }
+
+    async fn delete_checklist_item(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        item_id: &str,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.db.as_pool().begin().await?;
+
+        lock_and_bump_task(&mut tx, user_id, task_id).await?;
+
+        let optional_row = sqlx::query(
+            "DELETE FROM checklist_items WHERE task_id=$1 AND item_id=$2 RETURNING position",
+        )
+        .bind(task_id)
+        .bind(item_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(false);
+        };
+
+        let position: i32 = row.try_get(0)?;
+
+        sqlx::query(
+            "UPDATE checklist_items SET position=position-1 WHERE task_id=$1 AND position > $2",
+        )
+        .bind(task_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+
+        record_changes(&mut tx, user_id, &[SyncEntity::Task(task_id.to_string())]).await?;
+        let events = [WebhookEventKind::TaskUpdated(task_id.to_string())];
+        record_webhook_events(&mut tx, user_id, &events).await?;
+
+        tx.commit().await?;
+        Ok(true)
     }
 }
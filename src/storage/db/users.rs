@@ -1,6 +1,9 @@
-use crate::{app::repositories::UsersRepositry, model::UserId};
+use crate::{
+    app::repositories::UsersRepositry,
+    model::{calendar::CalendarToken, inbound::InboundToken, UserId},
+};
 
-use super::DatabaseConnectionRef;
+use super::{DatabaseConnectionRef, DbError};
 use sqlx::Row;
 
 pub struct DbUsers {
@@ -64,4 +67,135 @@ impl UsersRepositry for DbUsers {
 
         Ok(Some((user_id, password)))
     }
+
+    async fn get_timezone(&self, user_id: UserId) -> anyhow::Result<Option<String>> {
+        let optional_row = sqlx::query("SELECT timezone FROM users WHERE user_id=$1")
+            .bind(user_id.raw() as i32)
+            .fetch_optional(self.db.as_pool())
+            .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(None);
+        };
+
+        Ok(Some(row.try_get(0)?))
+    }
+
+    async fn set_timezone(&self, user_id: UserId, timezone: &str) -> anyhow::Result<()> {
+        let res = sqlx::query("UPDATE users SET timezone=$1 WHERE user_id=$2")
+            .bind(timezone)
+            .bind(user_id.raw() as i32)
+            .execute(self.db.as_pool())
+            .await?;
+
+        // Expected to modify exactly 1 user.
+        if res.rows_affected() == 0 {
+            return Err(DbError::RowNotFound.into());
+        }
+
+        Ok(())
+    }
+
+    async fn get_calendar_token(&self, user_id: UserId) -> anyhow::Result<Option<CalendarToken>> {
+        let optional_row = sqlx::query("SELECT calendar_token FROM users WHERE user_id=$1")
+            .bind(user_id.raw() as i32)
+            .fetch_optional(self.db.as_pool())
+            .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(None);
+        };
+
+        let token: Option<String> = row.try_get(0)?;
+        Ok(token.as_deref().and_then(CalendarToken::from_str))
+    }
+
+    async fn set_calendar_token(
+        &self,
+        user_id: UserId,
+        token: Option<&CalendarToken>,
+    ) -> anyhow::Result<()> {
+        let res = sqlx::query("UPDATE users SET calendar_token=$1 WHERE user_id=$2")
+            .bind(token.map(CalendarToken::as_str))
+            .bind(user_id.raw() as i32)
+            .execute(self.db.as_pool())
+            .await?;
+
+        if res.rows_affected() == 0 {
+            return Err(DbError::RowNotFound.into());
+        }
+
+        Ok(())
+    }
+
+    async fn find_user_by_calendar_token(
+        &self,
+        token: &CalendarToken,
+    ) -> anyhow::Result<Option<UserId>> {
+        let optional_row = sqlx::query("SELECT user_id FROM users WHERE calendar_token=$1")
+            .bind(token.as_str())
+            .fetch_optional(self.db.as_pool())
+            .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(None);
+        };
+
+        let raw_user_id: i32 = row.try_get(0)?;
+        Ok(Some(UserId::from_raw(raw_user_id as i64)))
+    }
+
+    async fn init_inbound_token(
+        &self,
+        user_id: UserId,
+        token: &InboundToken,
+    ) -> anyhow::Result<InboundToken> {
+        let optional_row = sqlx::query(
+            "UPDATE users SET inbound_token=COALESCE(inbound_token, $1) WHERE user_id=$2 \
+            RETURNING inbound_token",
+        )
+        .bind(token.as_str())
+        .bind(user_id.raw() as i32)
+        .fetch_optional(self.db.as_pool())
+        .await?;
+
+        let Some(row) = optional_row else {
+            return Err(DbError::RowNotFound.into());
+        };
+
+        let token: String = row.try_get(0)?;
+        InboundToken::from_str(&token)
+            .ok_or_else(|| anyhow::anyhow!("invalid inbound token of user {}", user_id.raw()))
+    }
+
+    async fn set_inbound_token(&self, user_id: UserId, token: &InboundToken) -> anyhow::Result<()> {
+        let res = sqlx::query("UPDATE users SET inbound_token=$1 WHERE user_id=$2")
+            .bind(token.as_str())
+            .bind(user_id.raw() as i32)
+            .execute(self.db.as_pool())
+            .await?;
+
+        if res.rows_affected() == 0 {
+            return Err(DbError::RowNotFound.into());
+        }
+
+        Ok(())
+    }
+
+    async fn find_user_by_inbound_token(
+        &self,
+        token: &InboundToken,
+    ) -> anyhow::Result<Option<UserId>> {
+        let optional_row = sqlx::query("SELECT user_id FROM users WHERE inbound_token=$1")
+            .bind(token.as_str())
+            .fetch_optional(self.db.as_pool())
+            .await?;
+
+        let Some(row) = optional_row else {
+            return Ok(None);
+        };
+
+        let raw_user_id: i32 = row.try_get(0)?;
+        Ok(Some(UserId::from_raw(raw_user_id as i64)))
+    }
 }
@@ -0,0 +1,131 @@
+use anyhow::anyhow;
+use sqlx::{postgres::PgRow, Row};
+
+use crate::{
+    app::repositories::ViewsRepository,
+    model::{
+        tasks::generate_random_task_id,
+        views::{ViewData, ViewDescription, ViewGrouping, ViewId, ViewSort},
+        BoardId, UserId,
+    },
+};
+
+use super::DatabaseConnectionRef;
+
+pub struct DbViews {
+    db: DatabaseConnectionRef,
+}
+
+/// Columns of `views` table read by [`view_from_row`].
+const VIEW_COLUMNS: &str = "view_id, board_id, owner_id, name, filter_query, sort, descending, \
+    group_by, include_archived, shared";
+
+fn view_from_row(row: &PgRow) -> anyhow::Result<ViewDescription> {
+    let sort: String = row.try_get(5)?;
+    let group_by: String = row.try_get(7)?;
+
+    Ok(ViewDescription {
+        view_id: row.try_get(0)?,
+        board_id: BoardId::from_raw(row.try_get::<i32, _>(1)? as i64),
+        owner_id: UserId::from_raw(row.try_get::<i32, _>(2)? as i64),
+        data: ViewData {
+            name: row.try_get(3)?,
+            filter: row.try_get(4)?,
+            sort: ViewSort::parse(&sort).ok_or_else(|| anyhow!("unknown view sort {}", sort))?,
+            descending: row.try_get(6)?,
+            group_by: ViewGrouping::parse(&group_by)
+                .ok_or_else(|| anyhow!("unknown view grouping {}", group_by))?,
+            include_archived: row.try_get(8)?,
+            shared: row.try_get(9)?,
+        },
+    })
+}
+
+impl DbViews {
+    pub fn new(db: DatabaseConnectionRef) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ViewsRepository for DbViews {
+    async fn fetch_views(&self, board_id: BoardId) -> anyhow::Result<Vec<ViewDescription>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM views WHERE board_id=$1 ORDER BY name, view_id",
+            VIEW_COLUMNS
+        ))
+        .bind(board_id.raw())
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        rows.iter().map(view_from_row).collect()
+    }
+
+    async fn fetch_view(&self, view_id: &str) -> anyhow::Result<Option<ViewDescription>> {
+        let optional_row = sqlx::query(&format!(
+            "SELECT {} FROM views WHERE view_id=$1",
+            VIEW_COLUMNS
+        ))
+        .bind(view_id)
+        .fetch_optional(self.db.as_pool())
+        .await?;
+
+        optional_row.as_ref().map(view_from_row).transpose()
+    }
+
+    async fn create_view(
+        &self,
+        board_id: BoardId,
+        owner_id: UserId,
+        data: &ViewData,
+    ) -> anyhow::Result<ViewId> {
+        let random_view_id = generate_random_task_id();
+
+        sqlx::query(&format!(
+            "INSERT INTO views ({}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+            VIEW_COLUMNS
+        ))
+        .bind(&random_view_id)
+        .bind(board_id.raw() as i32)
+        .bind(owner_id.raw() as i32)
+        .bind(&data.name)
+        .bind(&data.filter)
+        .bind(data.sort.as_str())
+        .bind(data.descending)
+        .bind(data.group_by.as_str())
+        .bind(data.include_archived)
+        .bind(data.shared)
+        .execute(self.db.as_pool())
+        .await?;
+
+        Ok(random_view_id)
+    }
+
+    async fn update_view(&self, view_id: &str, data: &ViewData) -> anyhow::Result<bool> {
+        let res = sqlx::query(
+            "UPDATE views SET name=$2, filter_query=$3, sort=$4, descending=$5, group_by=$6, \
+            include_archived=$7, shared=$8 WHERE view_id=$1",
+        )
+        .bind(view_id)
+        .bind(&data.name)
+        .bind(&data.filter)
+        .bind(data.sort.as_str())
+        .bind(data.descending)
+        .bind(data.group_by.as_str())
+        .bind(data.include_archived)
+        .bind(data.shared)
+        .execute(self.db.as_pool())
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn delete_view(&self, view_id: &str) -> anyhow::Result<bool> {
+        let res = sqlx::query("DELETE FROM views WHERE view_id=$1")
+            .bind(view_id)
+            .execute(self.db.as_pool())
+            .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,322 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, PgConnection, Row};
+
+use crate::{
+    app::repositories::WebhooksRepository,
+    model::{
+        tasks::generate_random_task_id,
+        webhooks::{
+            DeliveryId, DeliveryStatus, WebhookData, WebhookDelivery, WebhookDescription,
+            WebhookEvent, WebhookEventId, WebhookEventKind, WebhookId,
+        },
+        BoardId,
+    },
+};
+
+use super::{DatabaseConnectionRef, DbError};
+
+pub struct DbWebhooks {
+    db: DatabaseConnectionRef,
+}
+
+/// Columns of `webhooks` table read by [`webhook_from_row`].
+const WEBHOOK_COLUMNS: &str = "webhook_id, board_id, url, event_types, secret, created_at";
+
+/// Columns of `webhook_deliveries` table read by [`delivery_from_row`].
+const DELIVERY_COLUMNS: &str = "delivery_id, webhook_id, event_type, payload, status, attempts, \
+    next_attempt_at, last_status_code, last_error, created_at";
+
+fn webhook_from_row(row: &PgRow) -> anyhow::Result<WebhookDescription> {
+    // Event names are stored comma-separated, an empty string stands for all the events.
+    let event_types: String = row.try_get(3)?;
+
+    Ok(WebhookDescription {
+        webhook_id: row.try_get(0)?,
+        board_id: BoardId::from_raw(row.try_get::<i32, _>(1)? as i64),
+        data: WebhookData {
+            url: row.try_get(2)?,
+            event_types: event_types
+                .split(',')
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .collect(),
+            secret: row.try_get(4)?,
+            created_at: row.try_get(5)?,
+        },
+    })
+}
+
+fn delivery_from_row(row: &PgRow) -> anyhow::Result<WebhookDelivery> {
+    let status: String = row.try_get(4)?;
+
+    Ok(WebhookDelivery {
+        delivery_id: row.try_get(0)?,
+        webhook_id: row.try_get(1)?,
+        event_type: row.try_get(2)?,
+        payload: row.try_get(3)?,
+        status: DeliveryStatus::parse(&status)
+            .ok_or_else(|| anyhow!("unknown delivery status {}", status))?,
+        attempts: row.try_get::<i32, _>(5)? as u32,
+        next_attempt_at: row.try_get(6)?,
+        last_status_code: row.try_get::<Option<i16>, _>(7)?.map(|code| code as u16),
+        last_error: row.try_get(8)?,
+        created_at: row.try_get(9)?,
+    })
+}
+
+fn event_from_row(row: &PgRow) -> anyhow::Result<WebhookEvent> {
+    let event_type: String = row.try_get(2)?;
+
+    Ok(WebhookEvent {
+        event_id: row.try_get(0)?,
+        board_id: BoardId::from_raw(row.try_get::<i32, _>(1)? as i64),
+        kind: WebhookEventKind::from_parts(&event_type, row.try_get(3)?, row.try_get(4)?)
+            .ok_or_else(|| anyhow!("unknown webhook event {}", event_type))?,
+        created_at: row.try_get(5)?,
+    })
+}
+
+/// Records the events for the webhooks of the board within the transaction of the change.
+/// Nothing is recorded if the board has no webhooks.
+pub(super) async fn record_webhook_events(
+    tx: &mut PgConnection,
+    board_id: BoardId,
+    events: &[WebhookEventKind],
+) -> Result<(), DbError> {
+    let now = Utc::now();
+
+    for event in events {
+        sqlx::query(
+            "INSERT INTO webhook_events \
+            (board_id, event_type, item_id, from_category_id, created_at) \
+            SELECT $1, $2, $3, $4, $5 WHERE EXISTS (SELECT 1 FROM webhooks WHERE board_id=$1)",
+        )
+        .bind(board_id.raw() as i32)
+        .bind(event.name())
+        .bind(event.item_id())
+        .bind(event.moved_from())
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+impl DbWebhooks {
+    pub fn new(db: DatabaseConnectionRef) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl WebhooksRepository for DbWebhooks {
+    async fn fetch_webhooks(&self, board_id: BoardId) -> anyhow::Result<Vec<WebhookDescription>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM webhooks WHERE board_id=$1 ORDER BY created_at, webhook_id",
+            WEBHOOK_COLUMNS
+        ))
+        .bind(board_id.raw() as i32)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        rows.iter().map(webhook_from_row).collect()
+    }
+
+    async fn fetch_webhook(&self, webhook_id: &str) -> anyhow::Result<Option<WebhookDescription>> {
+        let optional_row = sqlx::query(&format!(
+            "SELECT {} FROM webhooks WHERE webhook_id=$1",
+            WEBHOOK_COLUMNS
+        ))
+        .bind(webhook_id)
+        .fetch_optional(self.db.as_pool())
+        .await?;
+
+        optional_row.as_ref().map(webhook_from_row).transpose()
+    }
+
+    async fn create_webhook(
+        &self,
+        board_id: BoardId,
+        data: &WebhookData,
+    ) -> anyhow::Result<WebhookId> {
+        let random_webhook_id = generate_random_task_id();
+
+        sqlx::query(&format!(
+            "INSERT INTO webhooks ({}) VALUES ($1, $2, $3, $4, $5, $6)",
+            WEBHOOK_COLUMNS
+        ))
+        .bind(&random_webhook_id)
+        .bind(board_id.raw() as i32)
+        .bind(&data.url)
+        .bind(data.event_types.join(","))
+        .bind(&data.secret)
+        .bind(data.created_at)
+        .execute(self.db.as_pool())
+        .await?;
+
+        Ok(random_webhook_id)
+    }
+
+    async fn delete_webhook(&self, webhook_id: &str) -> anyhow::Result<bool> {
+        // The deliveries are deleted by the cascade.
+        let res = sqlx::query("DELETE FROM webhooks WHERE webhook_id=$1")
+            .bind(webhook_id)
+            .execute(self.db.as_pool())
+            .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn enqueue_delivery(
+        &self,
+        webhook_id: &str,
+        event_type: &str,
+        payload: &str,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<DeliveryId> {
+        let random_delivery_id = generate_random_task_id();
+
+        sqlx::query(
+            "INSERT INTO webhook_deliveries (delivery_id, webhook_id, event_type, payload, status, \
+            attempts, next_attempt_at, created_at) VALUES ($1, $2, $3, $4, $5, 0, $6, $6)",
+        )
+        .bind(&random_delivery_id)
+        .bind(webhook_id)
+        .bind(event_type)
+        .bind(payload)
+        .bind(DeliveryStatus::Pending.as_str())
+        .bind(now)
+        .execute(self.db.as_pool())
+        .await?;
+
+        Ok(random_delivery_id)
+    }
+
+    async fn claim_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<WebhookDelivery>> {
+        // Rows locked by another worker are skipped, so each delivery is claimed once.
+        // The returned rows keep the values from before the update.
+        let rows = sqlx::query(
+            "WITH due AS ( \
+                SELECT delivery_id, next_attempt_at FROM webhook_deliveries \
+                WHERE status=$1 AND next_attempt_at <= $2 \
+                ORDER BY next_attempt_at LIMIT $4 FOR UPDATE SKIP LOCKED \
+            ) \
+            UPDATE webhook_deliveries d SET next_attempt_at=$3 FROM due \
+            WHERE d.delivery_id=due.delivery_id \
+            RETURNING d.delivery_id, d.webhook_id, d.event_type, d.payload, d.status, d.attempts, \
+            due.next_attempt_at, d.last_status_code, d.last_error, d.created_at",
+        )
+        .bind(DeliveryStatus::Pending.as_str())
+        .bind(now)
+        .bind(lease_until)
+        .bind(limit)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        let mut deliveries = rows
+            .iter()
+            .map(delivery_from_row)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        deliveries.sort_by_key(|d| d.next_attempt_at);
+
+        Ok(deliveries)
+    }
+
+    async fn update_delivery(&self, delivery: &WebhookDelivery) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE webhook_deliveries SET status=$2, attempts=$3, next_attempt_at=$4, \
+            last_status_code=$5, last_error=$6 WHERE delivery_id=$1",
+        )
+        .bind(&delivery.delivery_id)
+        .bind(delivery.status.as_str())
+        .bind(delivery.attempts as i32)
+        .bind(delivery.next_attempt_at)
+        .bind(delivery.last_status_code.map(|code| code as i16))
+        .bind(&delivery.last_error)
+        .execute(self.db.as_pool())
+        .await?;
+
+        Ok(())
+    }
+
+    async fn fetch_deliveries(
+        &self,
+        webhook_id: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<WebhookDelivery>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM webhook_deliveries WHERE webhook_id=$1 \
+            ORDER BY created_at DESC, delivery_id DESC LIMIT $2",
+            DELIVERY_COLUMNS
+        ))
+        .bind(webhook_id)
+        .bind(limit)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        rows.iter().map(delivery_from_row).collect()
+    }
+
+    async fn delete_finished_deliveries(
+        &self,
+        created_before: DateTime<Utc>,
+    ) -> anyhow::Result<u64> {
+        let res =
+            sqlx::query("DELETE FROM webhook_deliveries WHERE status<>$1 AND created_at < $2")
+                .bind(DeliveryStatus::Pending.as_str())
+                .bind(created_before)
+                .execute(self.db.as_pool())
+                .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    async fn claim_events(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<WebhookEvent>> {
+        // Rows locked by another worker are skipped, like in claim_due_deliveries.
+        let rows = sqlx::query(
+            "WITH due AS ( \
+                SELECT event_id FROM webhook_events \
+                WHERE claimed_until IS NULL OR claimed_until <= $1 \
+                ORDER BY event_id LIMIT $3 FOR UPDATE SKIP LOCKED \
+            ) \
+            UPDATE webhook_events e SET claimed_until=$2 FROM due \
+            WHERE e.event_id=due.event_id \
+            RETURNING e.event_id, e.board_id, e.event_type, e.item_id, e.from_category_id, \
+            e.created_at",
+        )
+        .bind(now)
+        .bind(lease_until)
+        .bind(limit)
+        .fetch_all(self.db.as_pool())
+        .await?;
+
+        let mut events = rows
+            .iter()
+            .map(event_from_row)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        events.sort_by_key(|e| e.event_id);
+
+        Ok(events)
+    }
+
+    async fn delete_event(&self, event_id: WebhookEventId) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM webhook_events WHERE event_id=$1")
+            .bind(event_id)
+            .execute(self.db.as_pool())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::Mutex;
+
+use crate::{
+    app::repositories::ActivityRepository,
+    model::{
+        activity::{ActivityEntry, ActivityId, ActivityRecord},
+        BoardId,
+    },
+};
+
+pub struct InMemoryActivity {
+    // Entries are ordered by ID, since they are only appended.
+    entries: Mutex<Vec<ActivityEntry>>,
+}
+
+impl InMemoryActivity {
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Records the changes of the task in the given order.
+    pub(super) fn append(&self, task_id: &str, activity: &ActivityRecord) {
+        let mut entries = self.entries.lock().unwrap();
+
+        for kind in &activity.kinds {
+            let activity_id = entries.last().map_or(1, |x| x.activity_id + 1);
+
+            entries.push(ActivityEntry {
+                activity_id,
+                board_id: activity.board_id,
+                task_id: task_id.to_string(),
+                actor_id: activity.actor_id,
+                kind: kind.clone(),
+                created_at: activity.created_at,
+            });
+        }
+    }
+}
+
+#[async_trait]
+impl ActivityRepository for InMemoryActivity {
+    async fn fetch_task_activity(
+        &self,
+        board_id: BoardId,
+        task_id: &str,
+    ) -> anyhow::Result<Vec<ActivityEntry>> {
+        let entries = self.entries.lock().unwrap();
+
+        Ok(entries
+            .iter()
+            .rev()
+            .filter(|x| x.board_id == board_id && x.task_id == task_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn fetch_board_activity(
+        &self,
+        board_id: BoardId,
+        before: Option<ActivityId>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<ActivityEntry>> {
+        let entries = self.entries.lock().unwrap();
+
+        Ok(entries
+            .iter()
+            .rev()
+            .filter(|x| x.board_id == board_id && before.is_none_or(|id| x.activity_id < id))
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+}
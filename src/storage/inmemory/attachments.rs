@@ -0,0 +1,105 @@
+use std::sync::Mutex;
+
+use crate::{
+    app::repositories::AttachmentsRepository,
+    model::{attachments::AttachmentDescription, UserId},
+};
+
+pub struct InMemoryAttachments {
+    // Attachments are ordered by creation time, since they are only appended.
+    attachments: Mutex<Vec<AttachmentDescription>>,
+}
+
+impl InMemoryAttachments {
+    pub fn new() -> Self {
+        Self {
+            attachments: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl AttachmentsRepository for InMemoryAttachments {
+    async fn fetch_attachments(&self, task_id: &str) -> anyhow::Result<Vec<AttachmentDescription>> {
+        let attachments = self.attachments.lock().unwrap();
+
+        Ok(attachments
+            .iter()
+            .filter(|a| a.task_id == task_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn fetch_attachment(
+        &self,
+        task_id: &str,
+        attachment_id: &str,
+    ) -> anyhow::Result<Option<AttachmentDescription>> {
+        let attachments = self.attachments.lock().unwrap();
+
+        Ok(attachments
+            .iter()
+            .find(|a| a.task_id == task_id && a.attachment_id == attachment_id)
+            .cloned())
+    }
+
+    async fn total_size(&self, owner_id: UserId) -> anyhow::Result<i64> {
+        let attachments = self.attachments.lock().unwrap();
+
+        Ok(attachments
+            .iter()
+            .filter(|a| a.owner_id == owner_id)
+            .map(|a| a.size)
+            .sum())
+    }
+
+    async fn create_attachment(
+        &self,
+        attachment: &AttachmentDescription,
+        quota: i64,
+    ) -> anyhow::Result<bool> {
+        let mut attachments = self.attachments.lock().unwrap();
+
+        let total_size: i64 = attachments
+            .iter()
+            .filter(|a| a.owner_id == attachment.owner_id)
+            .map(|a| a.size)
+            .sum();
+
+        if total_size + attachment.size > quota {
+            return Ok(false);
+        }
+
+        attachments.push(attachment.clone());
+        Ok(true)
+    }
+
+    async fn delete_attachment(
+        &self,
+        task_id: &str,
+        attachment_id: &str,
+    ) -> anyhow::Result<Option<AttachmentDescription>> {
+        let mut attachments = self.attachments.lock().unwrap();
+
+        let Some(idx) = attachments
+            .iter()
+            .position(|a| a.task_id == task_id && a.attachment_id == attachment_id)
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(attachments.remove(idx)))
+    }
+
+    async fn delete_task_attachments(
+        &self,
+        task_id: &str,
+    ) -> anyhow::Result<Vec<AttachmentDescription>> {
+        let mut attachments = self.attachments.lock().unwrap();
+
+        let (deleted, kept) = attachments.drain(..).partition(|a| a.task_id == task_id);
+        *attachments = kept;
+
+        Ok(deleted)
+    }
+}
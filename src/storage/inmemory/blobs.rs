@@ -0,0 +1,32 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use crate::app::repositories::BlobStore;
+
+pub struct InMemoryBlobs {
+    blobs: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl InMemoryBlobs {
+    pub fn new() -> Self {
+        Self {
+            blobs: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl BlobStore for InMemoryBlobs {
+    async fn put(&self, key: &str, _content_type: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        self.blobs.lock().unwrap().insert(key.to_string(), data);
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.blobs.lock().unwrap().get(key).cloned())
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.blobs.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    app::repositories::CommentsRepository,
+    model::{
+        comments::{CommentDescription, CommentId, CommentRevision},
+        tasks, TaskId, UserId,
+    },
+};
+
+use super::search::{self, InvertedIndex, COMMENT_WEIGHT};
+
+struct CommentStorage {
+    comment_desc: CommentDescription,
+    /// Previous revisions, oldest first.
+    revisions: Vec<CommentRevision>,
+}
+
+pub struct InMemoryComments {
+    // Comments are ordered by creation time, since they are only appended.
+    comments: Mutex<Vec<CommentStorage>>,
+    /// Texts of the comments that are not deleted by comment ID. Locked after `comments`.
+    search_index: Mutex<InvertedIndex>,
+}
+
+impl InMemoryComments {
+    pub fn new() -> Self {
+        Self {
+            comments: Mutex::new(Vec::new()),
+            search_index: Mutex::new(InvertedIndex::default()),
+        }
+    }
+
+    /// Returns the comments that are not deleted and contain all the words,
+    /// as the IDs of their tasks with the ranks and the snippets of the comments.
+    pub(super) fn search(&self, words: &[String]) -> Vec<(TaskId, f32, String)> {
+        let comments = self.comments.lock().unwrap();
+        let index = self.search_index.lock().unwrap();
+
+        index
+            .search(words)
+            .into_iter()
+            .filter_map(|(comment_id, rank)| {
+                let comment = comments
+                    .iter()
+                    .find(|c| c.comment_desc.comment_id == comment_id)?;
+
+                Some((
+                    comment.comment_desc.task_id.clone(),
+                    rank,
+                    search::highlight(&comment.comment_desc.text, words),
+                ))
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl CommentsRepository for InMemoryComments {
+    async fn fetch_comments(&self, task_id: &str) -> anyhow::Result<Vec<CommentDescription>> {
+        let comments = self.comments.lock().unwrap();
+
+        Ok(comments
+            .iter()
+            .filter(|c| c.comment_desc.task_id == task_id)
+            .map(|x| x.comment_desc.clone())
+            .collect())
+    }
+
+    async fn fetch_comment(
+        &self,
+        task_id: &str,
+        comment_id: &str,
+    ) -> anyhow::Result<Option<CommentDescription>> {
+        let comments = self.comments.lock().unwrap();
+
+        Ok(comments
+            .iter()
+            .find(|c| c.comment_desc.task_id == task_id && c.comment_desc.comment_id == comment_id)
+            .map(|x| x.comment_desc.clone()))
+    }
+
+    async fn create_comment(
+        &self,
+        task_id: &str,
+        author_id: UserId,
+        text: &str,
+        created_at: DateTime<Utc>,
+    ) -> anyhow::Result<CommentId> {
+        let comment_id = tasks::generate_random_task_id();
+
+        let mut comments = self.comments.lock().unwrap();
+
+        if comments
+            .iter()
+            .any(|c| c.comment_desc.comment_id == comment_id)
+        {
+            return Err(anyhow::anyhow!("could not generate unique comment id"));
+        }
+
+        comments.push(CommentStorage {
+            comment_desc: CommentDescription {
+                comment_id: comment_id.clone(),
+                task_id: task_id.to_string(),
+                author_id,
+                text: text.to_string(),
+                created_at,
+                edited_at: None,
+                deleted_at: None,
+            },
+            revisions: Vec::new(),
+        });
+
+        self.search_index
+            .lock()
+            .unwrap()
+            .insert(&comment_id, &[(text, COMMENT_WEIGHT)]);
+
+        Ok(comment_id)
+    }
+
+    async fn update_comment(
+        &self,
+        task_id: &str,
+        comment_id: &str,
+        text: &str,
+        edited_at: DateTime<Utc>,
+    ) -> anyhow::Result<bool> {
+        let mut comments = self.comments.lock().unwrap();
+
+        let Some(comment) = comments
+            .iter_mut()
+            .find(|c| c.comment_desc.task_id == task_id && c.comment_desc.comment_id == comment_id)
+        else {
+            return Ok(false);
+        };
+
+        let desc = &mut comment.comment_desc;
+
+        comment.revisions.push(CommentRevision {
+            text: std::mem::replace(&mut desc.text, text.to_string()),
+            written_at: desc.edited_at.unwrap_or(desc.created_at),
+        });
+        desc.edited_at = Some(edited_at);
+
+        if desc.deleted_at.is_none() {
+            self.search_index
+                .lock()
+                .unwrap()
+                .insert(comment_id, &[(text, COMMENT_WEIGHT)]);
+        }
+
+        Ok(true)
+    }
+
+    async fn delete_comment(
+        &self,
+        task_id: &str,
+        comment_id: &str,
+        deleted_at: DateTime<Utc>,
+    ) -> anyhow::Result<bool> {
+        let mut comments = self.comments.lock().unwrap();
+
+        let Some(comment) = comments
+            .iter_mut()
+            .find(|c| c.comment_desc.task_id == task_id && c.comment_desc.comment_id == comment_id)
+        else {
+            return Ok(false);
+        };
+
+        comment.comment_desc.deleted_at.get_or_insert(deleted_at);
+        self.search_index.lock().unwrap().remove(comment_id);
+
+        Ok(true)
+    }
+
+    async fn fetch_revisions(&self, comment_id: &str) -> anyhow::Result<Vec<CommentRevision>> {
+        let comments = self.comments.lock().unwrap();
+
+        Ok(comments
+            .iter()
+            .find(|c| c.comment_desc.comment_id == comment_id)
+            .map(|x| x.revisions.clone())
+            .unwrap_or_default())
+    }
+}
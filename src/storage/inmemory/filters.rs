@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::model::{filters::TaskFilter, tasks::TaskDescription, LabelId, TaskCategoryId};
+
+/// Names of the labels and the categories of a board, lowercased, which the filters refer to.
+pub(super) struct FilterContext {
+    pub labels: HashMap<LabelId, String>,
+    pub categories: HashMap<TaskCategoryId, String>,
+    pub now: DateTime<Utc>,
+}
+
+pub(super) fn matches(
+    filter: &TaskFilter,
+    task: &TaskDescription,
+    context: &FilterContext,
+) -> bool {
+    match filter {
+        TaskFilter::And(filters) => filters.iter().all(|f| matches(f, task, context)),
+        TaskFilter::Or(filters) => filters.iter().any(|f| matches(f, task, context)),
+        TaskFilter::Not(filter) => !matches(filter, task, context),
+        TaskFilter::Label(name) => {
+            let name = name.to_lowercase();
+
+            task.label_ids
+                .iter()
+                .any(|label_id| context.labels.get(label_id) == Some(&name))
+        }
+        TaskFilter::Column(name) => {
+            context.categories.get(&task.category_id) == Some(&name.to_lowercase())
+        }
+        TaskFilter::Priority(comparison, priority) => comparison.holds(task.priority.cmp(priority)),
+        TaskFilter::Due(due) => match due.range(context.now) {
+            None => task.due_at.is_none(),
+            Some((from, to)) => task.due_at.is_some_and(|due_at| {
+                from.is_none_or(|from| due_at >= from) && to.is_none_or(|to| due_at < to)
+            }),
+        },
+        TaskFilter::Archived => task.lifecycle.is_archived(),
+    }
+}
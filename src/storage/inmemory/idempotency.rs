@@ -0,0 +1,89 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    app::repositories::IdempotencyRepository,
+    model::{
+        idempotency::{IdempotencyRecord, StoredResponse},
+        UserId,
+    },
+};
+
+pub struct InMemoryIdempotency {
+    records: Mutex<Vec<IdempotencyRecord>>,
+}
+
+impl InMemoryIdempotency {
+    pub fn new() -> Self {
+        Self {
+            records: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for InMemoryIdempotency {
+    async fn reserve_key(
+        &self,
+        record: &IdempotencyRecord,
+        expired_before: DateTime<Utc>,
+        abandoned_before: DateTime<Utc>,
+    ) -> anyhow::Result<Option<IdempotencyRecord>> {
+        let mut records = self.records.lock().unwrap();
+
+        match records
+            .iter_mut()
+            .find(|r| r.user_id == record.user_id && r.key == record.key)
+        {
+            Some(existing)
+                if existing.created_at >= expired_before
+                    && (existing.response.is_some() || existing.created_at >= abandoned_before) =>
+            {
+                Ok(Some(existing.clone()))
+            }
+            Some(existing) => {
+                *existing = record.clone();
+                Ok(None)
+            }
+            None => {
+                records.push(record.clone());
+                Ok(None)
+            }
+        }
+    }
+
+    async fn complete_key(
+        &self,
+        user_id: Option<UserId>,
+        key: &str,
+        response: &StoredResponse,
+    ) -> anyhow::Result<()> {
+        let mut records = self.records.lock().unwrap();
+
+        if let Some(record) = records
+            .iter_mut()
+            .find(|r| r.user_id == user_id && r.key == key)
+        {
+            record.response = Some(response.clone());
+        }
+
+        Ok(())
+    }
+
+    async fn release_key(&self, user_id: Option<UserId>, key: &str) -> anyhow::Result<()> {
+        let mut records = self.records.lock().unwrap();
+        records.retain(|r| !(r.user_id == user_id && r.key == key));
+
+        Ok(())
+    }
+
+    async fn delete_expired_keys(&self, expired_before: DateTime<Utc>) -> anyhow::Result<u64> {
+        let mut records = self.records.lock().unwrap();
+
+        let count = records.len();
+        records.retain(|r| r.created_at >= expired_before);
+
+        Ok((count - records.len()) as u64)
+    }
+}
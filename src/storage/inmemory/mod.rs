@@ -1,7 +1,26 @@
+mod activity;
+mod attachments;
+mod blobs;
+mod comments;
+mod filters;
+mod idempotency;
+mod search;
 mod sessions;
+mod sync;
 mod tasks;
 mod users;
+mod views;
+mod webhooks;
 
+pub use activity::InMemoryActivity;
+pub use attachments::InMemoryAttachments;
+pub use blobs::InMemoryBlobs;
+pub use comments::InMemoryComments;
+pub use idempotency::InMemoryIdempotency;
+pub use search::InMemorySearch;
 pub use sessions::InMemorySessions;
+pub use sync::InMemorySync;
 pub use tasks::InMemoryTasks;
 pub use users::InMemoryUsers;
+pub use views::InMemoryViews;
+pub use webhooks::InMemoryWebhooks;
@@ -0,0 +1,254 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    app::repositories::{SearchRepository, TasksRepository},
+    model::{
+        search::{SearchHit, HIGHLIGHT_END, HIGHLIGHT_START},
+        TaskId, UserId,
+    },
+};
+
+use super::{InMemoryComments, InMemoryTasks};
+
+/// Weights of the matched words, the same as the defaults of `ts_rank` for the weights
+/// the database assigns to labels (A), descriptions (B) and comments (D).
+pub(super) const LABEL_WEIGHT: f32 = 1.0;
+pub(super) const DESCRIPTION_WEIGHT: f32 = 0.4;
+pub(super) const COMMENT_WEIGHT: f32 = 0.1;
+
+/// Maximum number of words in a snippet.
+const SNIPPET_WORDS: usize = 20;
+
+/// Number of words shown before the first match in a snippet.
+const SNIPPET_CONTEXT_WORDS: usize = 5;
+
+/// Splits the text into lowercase words, like the `simple` text search configuration of the database.
+pub(super) fn tokenize(text: &str) -> Vec<String> {
+    word_spans(text)
+        .into_iter()
+        .map(|(start, end)| text[start..end].to_lowercase())
+        .collect()
+}
+
+/// Returns the byte ranges of the words of the text.
+fn word_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+
+    for (idx, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(idx),
+            (false, Some(s)) => {
+                spans.push((s, idx));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(s) = start {
+        spans.push((s, text.len()));
+    }
+
+    spans
+}
+
+/// Returns a fragment of the text around the first matched word, with the matched words highlighted.
+pub(super) fn highlight(text: &str, words: &[String]) -> String {
+    let spans = word_spans(text);
+    let is_match =
+        |&(start, end): &(usize, usize)| words.contains(&text[start..end].to_lowercase());
+
+    let first_match = spans.iter().position(is_match).unwrap_or(0);
+    let first = first_match.saturating_sub(SNIPPET_CONTEXT_WORDS);
+    let shown = &spans[first..spans.len().min(first + SNIPPET_WORDS)];
+
+    let (Some(&(from, _)), Some(&(_, to))) = (shown.first(), shown.last()) else {
+        return String::new();
+    };
+
+    let mut snippet = String::new();
+    let mut position = from;
+
+    for span in shown.iter().filter(|span| is_match(span)) {
+        snippet.push_str(&text[position..span.0]);
+        snippet.push_str(HIGHLIGHT_START);
+        snippet.push_str(&text[span.0..span.1]);
+        snippet.push_str(HIGHLIGHT_END);
+        position = span.1;
+    }
+
+    snippet.push_str(&text[position..to]);
+    snippet
+}
+
+/// Maps the words to the documents containing them.
+#[derive(Default)]
+pub(super) struct InvertedIndex {
+    /// Weighted number of occurrences of every word in every document.
+    postings: HashMap<String, HashMap<String, f32>>,
+    /// Words of every document, to remove the document from the postings.
+    words: HashMap<String, HashSet<String>>,
+}
+
+impl InvertedIndex {
+    /// Indexes the weighted texts of the document, replacing its previous texts.
+    pub(super) fn insert(&mut self, doc_id: &str, texts: &[(&str, f32)]) {
+        self.remove(doc_id);
+
+        let mut doc_words = HashSet::new();
+
+        for &(text, weight) in texts {
+            for word in tokenize(text) {
+                *self
+                    .postings
+                    .entry(word.clone())
+                    .or_default()
+                    .entry(doc_id.to_string())
+                    .or_default() += weight;
+
+                doc_words.insert(word);
+            }
+        }
+
+        self.words.insert(doc_id.to_string(), doc_words);
+    }
+
+    pub(super) fn remove(&mut self, doc_id: &str) {
+        for word in self.words.remove(doc_id).unwrap_or_default() {
+            if let Some(docs) = self.postings.get_mut(&word) {
+                docs.remove(doc_id);
+
+                if docs.is_empty() {
+                    self.postings.remove(&word);
+                }
+            }
+        }
+    }
+
+    /// Returns the documents that contain all the words, with the total weights of the words.
+    pub(super) fn search(&self, words: &[String]) -> Vec<(String, f32)> {
+        let Some((first, rest)) = words.split_first() else {
+            return Vec::new();
+        };
+
+        let Some(docs) = self.postings.get(first) else {
+            return Vec::new();
+        };
+
+        docs.iter()
+            .filter_map(|(doc_id, &weight)| {
+                let mut total = weight;
+
+                for word in rest {
+                    total += self.postings.get(word)?.get(doc_id)?;
+                }
+
+                Some((doc_id.clone(), total))
+            })
+            .collect()
+    }
+}
+
+pub struct InMemorySearch {
+    tasks: Arc<InMemoryTasks>,
+    comments: Arc<InMemoryComments>,
+}
+
+impl InMemorySearch {
+    pub fn new(tasks: Arc<InMemoryTasks>, comments: Arc<InMemoryComments>) -> Self {
+        Self { tasks, comments }
+    }
+}
+
+#[async_trait]
+impl SearchRepository for InMemorySearch {
+    async fn search_tasks(
+        &self,
+        user_id: UserId,
+        query: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<SearchHit>> {
+        let mut words = tokenize(query);
+        words.sort();
+        words.dedup();
+
+        // Like the database, a task is ranked by its own text and its best matching comment.
+        let mut hits: HashMap<TaskId, SearchHit> = self
+            .tasks
+            .search(user_id, &words)
+            .into_iter()
+            .map(|hit| (hit.task.task_id.clone(), hit))
+            .collect();
+
+        let mut comment_hits: HashMap<TaskId, (f32, String)> = HashMap::new();
+        for (task_id, rank, snippet) in self.comments.search(&words) {
+            if comment_hits
+                .get(&task_id)
+                .is_none_or(|(best, _)| rank > *best)
+            {
+                comment_hits.insert(task_id, (rank, snippet));
+            }
+        }
+
+        for (task_id, (rank, snippet)) in comment_hits {
+            if let Some(hit) = hits.get_mut(&task_id) {
+                if rank > hit.rank {
+                    hit.snippet = snippet;
+                }
+                hit.rank += rank;
+            } else if let Some(task) = self.tasks.fetch_task(user_id, &task_id).await? {
+                hits.insert(
+                    task_id,
+                    SearchHit {
+                        task,
+                        rank,
+                        snippet,
+                    },
+                );
+            }
+        }
+
+        let mut hits: Vec<SearchHit> = hits.into_values().collect();
+        hits.sort_by(|a, b| {
+            b.rank
+                .total_cmp(&a.rank)
+                .then_with(|| a.task.task_id.cmp(&b.task.task_id))
+        });
+        hits.truncate(limit.max(0) as usize);
+
+        Ok(hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{highlight, tokenize, InvertedIndex};
+
+    #[test]
+    fn highlight_marks_matched_words() {
+        let words = tokenize("deploy");
+
+        assert_eq!(
+            highlight("Deploy the app, then deploy-check it.", &words),
+            "<mark>Deploy</mark> the app, then <mark>deploy</mark>-check it"
+        );
+    }
+
+    #[test]
+    fn index_requires_all_words() {
+        let mut index = InvertedIndex::default();
+        index.insert("a", &[("Fix login page", 1.0)]);
+        index.insert("b", &[("Fix signup", 1.0), ("login is unrelated", 0.5)]);
+
+        let mut hits = index.search(&tokenize("fix login"));
+        hits.sort_by(|x, y| x.0.cmp(&y.0));
+        assert_eq!(hits, [("a".to_string(), 2.0), ("b".to_string(), 1.5)]);
+
+        index.remove("a");
+        assert_eq!(index.search(&tokenize("page")), []);
+    }
+}
@@ -1,6 +1,9 @@
 use std::{collections::HashMap, sync::Mutex};
 
-use crate::{app::repositories::SessionsRepository, model::{SessionToken, UserId}};
+use crate::{
+    app::repositories::SessionsRepository,
+    model::{SessionToken, UserId},
+};
 
 pub struct InMemorySessions {
     sessions: Mutex<HashMap<String, UserId>>,
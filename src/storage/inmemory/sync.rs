@@ -0,0 +1,72 @@
+use std::sync::Mutex;
+
+use crate::{
+    app::repositories::SyncRepository,
+    model::{
+        sync::{SyncChange, SyncEntity, SyncSeq},
+        UserId,
+    },
+};
+
+pub struct InMemorySync {
+    // Entries are ordered by sequence number within each user, since they are only appended.
+    changes: Mutex<Vec<SyncChange>>,
+}
+
+impl InMemorySync {
+    pub fn new() -> Self {
+        Self {
+            changes: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Appends the changed entities to the log of the user, returning the sequence number of the last entry.
+    pub(super) fn append(&self, user_id: UserId, entities: &[SyncEntity]) -> SyncSeq {
+        let mut changes = self.changes.lock().unwrap();
+
+        let mut seq = latest_seq(&changes, user_id);
+
+        for entity in entities {
+            seq += 1;
+
+            changes.push(SyncChange {
+                seq,
+                user_id,
+                entity: entity.clone(),
+            });
+        }
+
+        seq
+    }
+}
+
+#[async_trait]
+impl SyncRepository for InMemorySync {
+    async fn fetch_changes(
+        &self,
+        user_id: UserId,
+        since: SyncSeq,
+        limit: i64,
+    ) -> anyhow::Result<Vec<SyncChange>> {
+        let changes = self.changes.lock().unwrap();
+
+        Ok(changes
+            .iter()
+            .filter(|x| x.user_id == user_id && x.seq > since)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn latest_seq(&self, user_id: UserId) -> anyhow::Result<SyncSeq> {
+        Ok(latest_seq(&self.changes.lock().unwrap(), user_id))
+    }
+}
+
+fn latest_seq(changes: &[SyncChange], user_id: UserId) -> SyncSeq {
+    changes
+        .iter()
+        .rev()
+        .find(|x| x.user_id == user_id)
+        .map_or(0, |x| x.seq)
+}
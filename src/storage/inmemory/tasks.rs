@@ -1,36 +1,168 @@
-use std::sync::Mutex;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
 
 use crate::{
     app::repositories::TasksRepository,
     model::{
-        tasks::{self, TaskCategoryDescription, TaskDescription},
-        TaskId, UserId,
+        activity::ActivityRecord,
+        bulk::{BulkAction, BulkChange, BulkError, BulkFailure, BulkOperation},
+        checklists::{ChecklistItem, ChecklistItemId, ChecklistItemPatch, ChecklistProgress},
+        filters::TaskFilter,
+        imports::{BoardImport, ImportedItems},
+        labels::{LabelData, LabelDescription},
+        lifecycle::Lifecycle,
+        search::SearchHit,
+        sync::SyncEntity,
+        tasks::{
+            self, TaskCategoryDescription, TaskCursor, TaskData, TaskDescription, TaskPatch,
+            Version, INITIAL_VERSION,
+        },
+        webhooks::WebhookEventKind,
+        BoardId, LabelId, TaskCategoryId, TaskId, UserId,
     },
 };
 
+use super::{
+    filters::{self, FilterContext},
+    search::{self, InvertedIndex, DESCRIPTION_WEIGHT, LABEL_WEIGHT},
+    InMemoryActivity, InMemorySync, InMemoryWebhooks,
+};
+
 struct TaskCategoryStorage {
     user_id: UserId,
     category_desc: TaskCategoryDescription,
 }
 
+struct LabelStorage {
+    user_id: UserId,
+    label_desc: LabelDescription,
+}
+
 struct TaskStorage {
     user_id: UserId,
+    /// Sequence number given to the task on creation.
+    created_seq: i64,
     task_desc: TaskDescription,
+    /// Checklist items ordered by position.
+    checklist: Vec<ChecklistItem>,
+}
+
+impl TaskStorage {
+    /// Renumbers the checklist items and updates the progress, incrementing the version of the task.
+    fn checklist_changed(&mut self) {
+        for (position, item) in self.checklist.iter_mut().enumerate() {
+            item.position = position as i32;
+        }
+
+        self.task_desc.checklist_progress = ChecklistProgress::of(&self.checklist);
+        self.task_desc.version += 1;
+    }
 }
 
 pub struct InMemoryTasks {
     // TODO: use more efficient data structure
+    // Categories and tasks are only appended, so they are listed in the order they have been created.
     categories: Mutex<Vec<TaskCategoryStorage>>,
     tasks: Mutex<Vec<TaskStorage>>,
+    /// Sequence number of the next created task. Locked after `tasks`.
+    next_task_seq: Mutex<i64>,
+    labels: Mutex<Vec<LabelStorage>>,
+    /// Labels and descriptions of the tasks by task ID. Locked after `tasks`.
+    search_index: Mutex<InvertedIndex>,
+    /// History, change log and webhook events, written while the changed items are locked.
+    activity: Arc<InMemoryActivity>,
+    sync: Arc<InMemorySync>,
+    webhooks: Arc<InMemoryWebhooks>,
 }
 
 impl InMemoryTasks {
-    pub fn new() -> Self {
+    pub fn new(
+        activity: Arc<InMemoryActivity>,
+        sync: Arc<InMemorySync>,
+        webhooks: Arc<InMemoryWebhooks>,
+    ) -> Self {
         Self {
             categories: Mutex::new(Vec::new()),
             tasks: Mutex::new(Vec::new()),
+            next_task_seq: Mutex::new(1),
+            labels: Mutex::new(Vec::new()),
+            search_index: Mutex::new(InvertedIndex::default()),
+            activity,
+            sync,
+            webhooks,
         }
     }
+
+    /// Appends the task to the change log of the user and records the event for the webhooks of the board.
+    fn track_task(&self, user_id: UserId, task_id: &str, event: WebhookEventKind) {
+        self.sync
+            .append(user_id, &[SyncEntity::Task(task_id.to_string())]);
+        self.webhooks.record_events(user_id, &[event]);
+    }
+
+    fn next_task_seq(&self) -> i64 {
+        let mut next_task_seq = self.next_task_seq.lock().unwrap();
+        let created_seq = *next_task_seq;
+        *next_task_seq += 1;
+
+        created_seq
+    }
+
+    fn index_task(&self, task: &TaskDescription) {
+        self.search_index.lock().unwrap().insert(
+            &task.task_id,
+            &[
+                (&task.label, LABEL_WEIGHT),
+                (&task.description, DESCRIPTION_WEIGHT),
+            ],
+        );
+    }
+
+    /// Returns the tasks of the user, except the trashed ones, whose label and description contain all the words.
+    pub(super) fn search(&self, user_id: UserId, words: &[String]) -> Vec<SearchHit> {
+        let tasks = self.tasks.lock().unwrap();
+        let index = self.search_index.lock().unwrap();
+
+        index
+            .search(words)
+            .into_iter()
+            .filter_map(|(task_id, rank)| {
+                let task = tasks.iter().find(|t| {
+                    t.user_id == user_id
+                        && t.task_desc.task_id == task_id
+                        && !t.task_desc.lifecycle.is_trashed()
+                })?;
+
+                let text = format!("{} {}", task.task_desc.label, task.task_desc.description);
+
+                Some(SearchHit {
+                    task: task.task_desc.clone(),
+                    rank,
+                    snippet: search::highlight(&text, words),
+                })
+            })
+            .collect()
+    }
+
+    fn with_task<R>(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        f: impl FnOnce(&mut TaskStorage) -> R,
+    ) -> anyhow::Result<R> {
+        let mut tasks = self.tasks.lock().unwrap();
+
+        let task = tasks
+            .iter_mut()
+            .find(|t| t.user_id == user_id && t.task_desc.task_id == task_id)
+            .ok_or_else(|| anyhow::anyhow!("no such task"))?;
+
+        Ok(f(task))
+    }
 }
 
 #[async_trait]
@@ -40,17 +172,164 @@ impl TasksRepository for InMemoryTasks {
 
         Ok(tasks
             .iter()
-            .filter(|s| s.user_id == user_id)
+            .filter(|s| s.user_id == user_id && !s.task_desc.lifecycle.is_trashed())
             .map(|x| x.task_desc.clone())
             .collect())
     }
 
-    async fn create_task(
+    async fn fetch_filtered_tasks(
+        &self,
+        user_id: UserId,
+        filter: &TaskFilter,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<TaskDescription>> {
+        let labels = HashMap::from_iter(
+            self.labels
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|l| l.user_id == user_id)
+                .map(|l| {
+                    (
+                        l.label_desc.label_id.clone(),
+                        l.label_desc.name.to_lowercase(),
+                    )
+                }),
+        );
+
+        let categories = HashMap::from_iter(
+            self.categories
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|c| c.user_id == user_id)
+                .map(|c| {
+                    (
+                        c.category_desc.category_id.clone(),
+                        c.category_desc.label.to_lowercase(),
+                    )
+                }),
+        );
+
+        let context = FilterContext {
+            labels,
+            categories,
+            now,
+        };
+
+        let tasks = self.tasks.lock().unwrap();
+
+        Ok(tasks
+            .iter()
+            .filter(|t| {
+                t.user_id == user_id
+                    && !t.task_desc.lifecycle.is_trashed()
+                    && filters::matches(filter, &t.task_desc, &context)
+            })
+            .map(|x| x.task_desc.clone())
+            .collect())
+    }
+
+    async fn fetch_category_tasks(
         &self,
         user_id: UserId,
-        label: &str,
-        description: &str,
         category_id: &str,
+        after: Option<&TaskCursor>,
+        limit: i64,
+        include_archived: bool,
+    ) -> anyhow::Result<Vec<(TaskCursor, TaskDescription)>> {
+        let tasks = self.tasks.lock().unwrap();
+
+        let mut category_tasks: Vec<(TaskCursor, TaskDescription)> = tasks
+            .iter()
+            .filter(|t| t.user_id == user_id)
+            .map(|t| {
+                let cursor = TaskCursor {
+                    created_seq: t.created_seq,
+                    task_id: t.task_desc.task_id.clone(),
+                };
+                (cursor, &t.task_desc)
+            })
+            .filter(|(cursor, t)| {
+                t.category_id == category_id
+                    && !t.lifecycle.is_trashed()
+                    && (include_archived || t.lifecycle.is_active())
+                    && after.is_none_or(|after| cursor > after)
+            })
+            .map(|(cursor, t)| (cursor, t.clone()))
+            .collect();
+        category_tasks.sort_by(|a, b| a.0.cmp(&b.0));
+        category_tasks.truncate(limit.max(0) as usize);
+
+        Ok(category_tasks)
+    }
+
+    async fn count_category_tasks(
+        &self,
+        user_id: UserId,
+        include_archived: bool,
+    ) -> anyhow::Result<Vec<(TaskCategoryId, i64)>> {
+        let tasks = self.tasks.lock().unwrap();
+
+        let mut counts: HashMap<TaskCategoryId, i64> = HashMap::new();
+        for task in tasks.iter().filter(|t| {
+            t.user_id == user_id
+                && !t.task_desc.lifecycle.is_trashed()
+                && (include_archived || t.task_desc.lifecycle.is_active())
+        }) {
+            *counts
+                .entry(task.task_desc.category_id.clone())
+                .or_default() += 1;
+        }
+
+        Ok(counts.into_iter().collect())
+    }
+
+    async fn fetch_task(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Option<TaskDescription>> {
+        let tasks = self.tasks.lock().unwrap();
+
+        Ok(tasks
+            .iter()
+            .find(|t| {
+                t.user_id == user_id
+                    && t.task_desc.task_id == task_id
+                    && !t.task_desc.lifecycle.is_trashed()
+            })
+            .map(|x| x.task_desc.clone()))
+    }
+
+    async fn fetch_tasks_due(
+        &self,
+        user_id: UserId,
+        from: Option<DateTime<Utc>>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<TaskDescription>> {
+        let tasks = self.tasks.lock().unwrap();
+
+        let mut due: Vec<TaskDescription> = tasks
+            .iter()
+            .filter(|t| t.user_id == user_id && t.task_desc.lifecycle.is_active())
+            .filter(|t| {
+                t.task_desc
+                    .due_at
+                    .is_some_and(|due_at| due_at < to && from.is_none_or(|from| due_at >= from))
+            })
+            .map(|x| x.task_desc.clone())
+            .collect();
+
+        due.sort_by_key(|t| t.due_at);
+        Ok(due)
+    }
+
+    async fn create_task(
+        &self,
+        user_id: UserId,
+        task: &TaskData,
+        activity: &ActivityRecord,
     ) -> anyhow::Result<TaskId> {
         let task_id = tasks::generate_random_task_id();
 
@@ -64,48 +343,247 @@ impl TasksRepository for InMemoryTasks {
             return Err(anyhow::anyhow!("could not generate unique task id"));
         }
 
+        let task_desc = task.clone().into_description(task_id.clone());
+        self.index_task(&task_desc);
+
         tasks.push(TaskStorage {
             user_id,
-            task_desc: TaskDescription {
-                task_id: task_id.clone(),
-                label: label.to_string(),
-                description: description.to_string(),
-                category_id: category_id.to_string(),
-            },
+            created_seq: self.next_task_seq(),
+            task_desc,
+            checklist: Vec::new(),
         });
 
+        self.activity.append(&task_id, activity);
+        self.track_task(
+            user_id,
+            &task_id,
+            WebhookEventKind::of_task(&task_id, &activity.kinds),
+        );
+
         Ok(task_id)
     }
 
-    async fn modify_task(
+    async fn update_task(
         &self,
         user_id: UserId,
         task_id: &str,
-        label: &str,
-        description: &str,
-        category_id: &str,
-    ) -> anyhow::Result<()> {
+        patch: &TaskPatch,
+        expected_version: Option<Version>,
+        activity: &ActivityRecord,
+    ) -> anyhow::Result<Option<TaskDescription>> {
+        let mut tasks = self.tasks.lock().unwrap();
+
+        let Some(task) = tasks.iter_mut().find(|t| {
+            t.user_id == user_id
+                && t.task_desc.task_id == task_id
+                && expected_version.is_none_or(|v| t.task_desc.version == v)
+        }) else {
+            return Ok(None);
+        };
+
+        patch.apply(&mut task.task_desc);
+        task.task_desc.version += 1;
+
+        if patch.label.is_some() || patch.description.is_some() {
+            self.index_task(&task.task_desc);
+        }
+
+        self.activity.append(task_id, activity);
+        self.track_task(
+            user_id,
+            task_id,
+            WebhookEventKind::of_task(task_id, &activity.kinds),
+        );
+
+        Ok(Some(task.task_desc.clone()))
+    }
+
+    async fn delete_task(&self, user_id: UserId, task_id: &str) -> anyhow::Result<()> {
+        let mut tasks = self.tasks.lock().unwrap();
+
+        let Some(idx) = tasks
+            .iter()
+            .position(|t| t.user_id == user_id && t.task_desc.task_id == task_id)
+        else {
+            return Err(anyhow::anyhow!("no such task"));
+        };
+
+        tasks.remove(idx);
+        self.search_index.lock().unwrap().remove(task_id);
+        Ok(())
+    }
+
+    async fn set_task_lifecycle(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        lifecycle: Lifecycle,
+        activity: &ActivityRecord,
+    ) -> anyhow::Result<Option<TaskDescription>> {
         let mut tasks = self.tasks.lock().unwrap();
 
         let Some(task) = tasks
             .iter_mut()
             .find(|t| t.user_id == user_id && t.task_desc.task_id == task_id)
         else {
-            return Err(anyhow::anyhow!("no such task"));
+            return Ok(None);
         };
 
-        task.task_desc.label = label.to_string();
-        task.task_desc.description = description.to_string();
-        task.task_desc.category_id = category_id.to_string();
-        Ok(())
+        task.task_desc.lifecycle = lifecycle;
+        task.task_desc.version += 1;
+
+        self.activity.append(task_id, activity);
+        self.track_task(
+            user_id,
+            task_id,
+            WebhookEventKind::of_task(task_id, &activity.kinds),
+        );
+
+        Ok(Some(task.task_desc.clone()))
     }
 
-    async fn delete_task(&self, user_id: UserId, task_id: &str) -> anyhow::Result<()> {
+    async fn apply_bulk_operations(
+        &self,
+        user_id: UserId,
+        board_id: BoardId,
+        operations: &[BulkOperation],
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<Result<Vec<BulkChange>, BulkFailure>> {
+        let categories = self.categories.lock().unwrap();
+        let labels = self.labels.lock().unwrap();
         let mut tasks = self.tasks.lock().unwrap();
 
-        tasks.retain_mut(|t| t.user_id == user_id && t.task_desc.task_id == task_id);
+        // The operations are applied to copies, which replace the tasks only if all of them succeed.
+        let mut changed: HashMap<TaskId, TaskDescription> = HashMap::new();
+        let mut changes = Vec::with_capacity(operations.len());
 
-        Ok(())
+        for (index, operation) in operations.iter().enumerate() {
+            let fail = |error| Ok(Err(BulkFailure { index, error }));
+
+            let task = match changed.get(&operation.task_id) {
+                Some(task) => Some(task.clone()),
+                None => tasks
+                    .iter()
+                    .find(|t| t.user_id == user_id && t.task_desc.task_id == operation.task_id)
+                    .map(|t| t.task_desc.clone()),
+            };
+
+            let Some(mut task) = task.filter(|t| !t.lifecycle.is_trashed()) else {
+                return fail(BulkError::TaskNotFound);
+            };
+
+            match &operation.action {
+                BulkAction::Update(TaskPatch {
+                    category_id: Some(category_id),
+                    ..
+                }) if !categories.iter().any(|c| {
+                    c.user_id == user_id
+                        && c.category_desc.category_id == *category_id
+                        && !c.category_desc.lifecycle.is_trashed()
+                }) =>
+                {
+                    return fail(BulkError::CategoryNotFound)
+                }
+                BulkAction::AddLabel(label_id) | BulkAction::RemoveLabel(label_id)
+                    if !labels
+                        .iter()
+                        .any(|l| l.user_id == user_id && l.label_desc.label_id == *label_id) =>
+                {
+                    return fail(BulkError::LabelNotFound)
+                }
+                _ => {}
+            }
+
+            let previous = task.clone();
+            operation.action.apply(&mut task, now);
+
+            changes.push(BulkChange {
+                previous,
+                task: task.clone(),
+            });
+            changed.insert(task.task_id.clone(), task);
+        }
+
+        for stored in tasks.iter_mut() {
+            if stored.user_id != user_id {
+                continue;
+            }
+
+            if let Some(task) = changed.remove(&stored.task_desc.task_id) {
+                if task.label != stored.task_desc.label
+                    || task.description != stored.task_desc.description
+                {
+                    self.index_task(&task);
+                }
+
+                stored.task_desc = task;
+            }
+        }
+
+        let mut entities = Vec::new();
+        let mut events = Vec::new();
+
+        for (operation, change) in operations.iter().zip(&changes) {
+            let activity = ActivityRecord {
+                board_id,
+                actor_id: user_id,
+                kinds: operation.action.activity(change),
+                created_at: now,
+            };
+
+            if change.is_changed() {
+                let entity = SyncEntity::Task(change.task.task_id.clone());
+                if !entities.contains(&entity) {
+                    entities.push(entity);
+                }
+
+                events.push(WebhookEventKind::of_task(
+                    &change.task.task_id,
+                    &activity.kinds,
+                ));
+            }
+
+            self.activity.append(&change.task.task_id, &activity);
+        }
+
+        self.sync.append(user_id, &entities);
+        self.webhooks.record_events(board_id, &events);
+
+        Ok(Ok(changes))
+    }
+
+    async fn fetch_trashed_tasks(&self, user_id: UserId) -> anyhow::Result<Vec<TaskDescription>> {
+        let tasks = self.tasks.lock().unwrap();
+
+        Ok(tasks
+            .iter()
+            .filter(|t| t.user_id == user_id && t.task_desc.lifecycle.is_trashed())
+            .map(|x| x.task_desc.clone())
+            .collect())
+    }
+
+    async fn fetch_expired_tasks(
+        &self,
+        deleted_before: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<(UserId, TaskId)>> {
+        let is_expired =
+            |lifecycle: &Lifecycle| lifecycle.deleted_at.is_some_and(|t| t < deleted_before);
+
+        let categories = self.categories.lock().unwrap();
+        let tasks = self.tasks.lock().unwrap();
+
+        Ok(tasks
+            .iter()
+            .filter(|t| {
+                is_expired(&t.task_desc.lifecycle)
+                    || categories.iter().any(|c| {
+                        c.user_id == t.user_id
+                            && c.category_desc.category_id == t.task_desc.category_id
+                            && is_expired(&c.category_desc.lifecycle)
+                    })
+            })
+            .map(|t| (t.user_id, t.task_desc.task_id.clone()))
+            .collect())
     }
 
     async fn fetch_categories(
@@ -116,11 +594,126 @@ impl TasksRepository for InMemoryTasks {
 
         Ok(categories
             .iter()
-            .filter(|c| c.user_id == user_id)
+            .filter(|c| c.user_id == user_id && !c.category_desc.lifecycle.is_trashed())
             .map(|x| x.category_desc.clone())
             .collect())
     }
 
+    async fn rename_category(
+        &self,
+        user_id: UserId,
+        category_id: &str,
+        label: &str,
+    ) -> anyhow::Result<Option<TaskCategoryDescription>> {
+        let mut categories = self.categories.lock().unwrap();
+
+        let Some(category) = categories.iter_mut().find(|c| {
+            c.user_id == user_id
+                && c.category_desc.category_id == category_id
+                && !c.category_desc.lifecycle.is_trashed()
+        }) else {
+            return Ok(None);
+        };
+
+        category.category_desc.label = label.to_string();
+        category.category_desc.version += 1;
+
+        self.sync
+            .append(user_id, &[SyncEntity::Category(category_id.to_string())]);
+        self.webhooks.record_events(
+            user_id,
+            &[WebhookEventKind::CategoryUpdated(category_id.to_string())],
+        );
+
+        Ok(Some(category.category_desc.clone()))
+    }
+
+    async fn set_category_lifecycle(
+        &self,
+        user_id: UserId,
+        category_id: &str,
+        lifecycle: Lifecycle,
+    ) -> anyhow::Result<Option<TaskCategoryDescription>> {
+        let mut categories = self.categories.lock().unwrap();
+
+        let Some(category) = categories
+            .iter_mut()
+            .find(|c| c.user_id == user_id && c.category_desc.category_id == category_id)
+        else {
+            return Ok(None);
+        };
+
+        let previous = std::mem::replace(&mut category.category_desc.lifecycle, lifecycle);
+        category.category_desc.version += 1;
+
+        // The tasks already in the trash stay there when the category is restored.
+        let trash_change = match (previous.deleted_at, lifecycle.deleted_at) {
+            (None, Some(deleted_at)) => Some((None, Some(deleted_at))),
+            (Some(deleted_at), None) => Some((Some(deleted_at), None)),
+            _ => None,
+        };
+
+        let mut tasks = self.tasks.lock().unwrap();
+
+        // The tasks appear and disappear together with the category.
+        let mut entities = vec![SyncEntity::Category(category_id.to_string())];
+
+        for task in tasks
+            .iter_mut()
+            .filter(|t| t.user_id == user_id && t.task_desc.category_id == category_id)
+        {
+            if let Some((_, to)) =
+                trash_change.filter(|(from, _)| task.task_desc.lifecycle.deleted_at == *from)
+            {
+                task.task_desc.lifecycle.deleted_at = to;
+                task.task_desc.version += 1;
+            }
+
+            entities.push(SyncEntity::Task(task.task_desc.task_id.clone()));
+        }
+
+        self.sync.append(user_id, &entities);
+
+        let event = if lifecycle.is_active() {
+            WebhookEventKind::CategoryRestored(category_id.to_string())
+        } else {
+            WebhookEventKind::CategoryRemoved(category_id.to_string())
+        };
+        self.webhooks.record_events(user_id, &[event]);
+
+        Ok(Some(category.category_desc.clone()))
+    }
+
+    async fn fetch_trashed_categories(
+        &self,
+        user_id: UserId,
+    ) -> anyhow::Result<Vec<TaskCategoryDescription>> {
+        let categories = self.categories.lock().unwrap();
+
+        Ok(categories
+            .iter()
+            .filter(|c| c.user_id == user_id && c.category_desc.lifecycle.is_trashed())
+            .map(|x| x.category_desc.clone())
+            .collect())
+    }
+
+    async fn delete_expired_categories(
+        &self,
+        deleted_before: DateTime<Utc>,
+    ) -> anyhow::Result<u64> {
+        let mut categories = self.categories.lock().unwrap();
+
+        let count = categories.len();
+        categories.retain(|c| {
+            !c.category_desc
+                .lifecycle
+                .deleted_at
+                .is_some_and(|t| t < deleted_before)
+        });
+
+        Ok((count - categories.len()) as u64)
+    }
+
     async fn add_categories(
         &self,
         user_id: UserId,
@@ -131,6 +724,8 @@ impl TasksRepository for InMemoryTasks {
             .map(|label| TaskCategoryDescription {
                 category_id: tasks::generate_random_task_id(),
                 label: label.to_string(),
+                lifecycle: Lifecycle::default(),
+                version: INITIAL_VERSION,
             })
             .collect();
 
@@ -154,6 +749,362 @@ impl TasksRepository for InMemoryTasks {
             });
         }
 
+        let entities: Vec<SyncEntity> = descriptions
+            .iter()
+            .map(|c| SyncEntity::Category(c.category_id.clone()))
+            .collect();
+        self.sync.append(user_id, &entities);
+
+        let events: Vec<WebhookEventKind> = descriptions
+            .iter()
+            .map(|c| WebhookEventKind::CategoryCreated(c.category_id.clone()))
+            .collect();
+        self.webhooks.record_events(user_id, &events);
+
         Ok(descriptions)
     }
+
+    async fn import_board(
+        &self,
+        user_id: UserId,
+        import: &BoardImport,
+        activity: &ActivityRecord,
+    ) -> anyhow::Result<ImportedItems> {
+        let mut categories = self.categories.lock().unwrap();
+        let mut labels = self.labels.lock().unwrap();
+        let mut tasks = self.tasks.lock().unwrap();
+
+        let items = ImportedItems {
+            categories: import
+                .categories
+                .iter()
+                .map(|label| TaskCategoryDescription {
+                    category_id: tasks::generate_random_task_id(),
+                    label: label.clone(),
+                    lifecycle: Lifecycle::default(),
+                    version: INITIAL_VERSION,
+                })
+                .collect(),
+            label_ids: import
+                .labels
+                .iter()
+                .map(|_| tasks::generate_random_task_id())
+                .collect(),
+            task_ids: import
+                .tasks
+                .iter()
+                .map(|_| tasks::generate_random_task_id())
+                .collect(),
+        };
+
+        // Nothing is stored unless all the IDs are unique.
+        if items.categories.iter().any(|d| {
+            categories
+                .iter()
+                .any(|x| x.user_id == user_id && x.category_desc.category_id == d.category_id)
+        }) || items
+            .label_ids
+            .iter()
+            .any(|id| labels.iter().any(|l| &l.label_desc.label_id == id))
+            || items.task_ids.iter().any(|id| {
+                tasks
+                    .iter()
+                    .any(|t| t.user_id == user_id && &t.task_desc.task_id == id)
+            })
+        {
+            return Err(anyhow::anyhow!("could not generate unique ids"));
+        }
+
+        for category_desc in &items.categories {
+            categories.push(TaskCategoryStorage {
+                user_id,
+                category_desc: category_desc.clone(),
+            });
+        }
+
+        for (label, label_id) in import.labels.iter().zip(&items.label_ids) {
+            labels.push(LabelStorage {
+                user_id,
+                label_desc: label.clone().into_description(label_id.clone()),
+            });
+        }
+
+        for (task, task_id) in import.tasks.iter().zip(&items.task_ids) {
+            let mut label_ids: Vec<LabelId> = task
+                .labels
+                .iter()
+                .map(|label| items.label_id(label).to_string())
+                .collect();
+            label_ids.sort();
+            label_ids.dedup();
+
+            let data = TaskData {
+                label: task.label.clone(),
+                description: task.description.clone(),
+                category_id: items.category_id(&task.category).to_string(),
+                start_at: task.start_at,
+                due_at: task.due_at,
+                priority: task.priority,
+            };
+
+            let mut task_desc = data.into_description(task_id.clone());
+            task_desc.label_ids = label_ids;
+            self.index_task(&task_desc);
+
+            tasks.push(TaskStorage {
+                user_id,
+                created_seq: self.next_task_seq(),
+                task_desc,
+                checklist: Vec::new(),
+            });
+
+            self.activity.append(task_id, activity);
+        }
+
+        let entities: Vec<SyncEntity> = items
+            .categories
+            .iter()
+            .map(|c| SyncEntity::Category(c.category_id.clone()))
+            .chain(items.task_ids.iter().cloned().map(SyncEntity::Task))
+            .collect();
+        self.sync.append(user_id, &entities);
+
+        // Like on the event stream, the imported tasks are not announced one by one.
+        let events: Vec<WebhookEventKind> = items
+            .categories
+            .iter()
+            .map(|c| WebhookEventKind::CategoryCreated(c.category_id.clone()))
+            .collect();
+        self.webhooks.record_events(activity.board_id, &events);
+
+        Ok(items)
+    }
+
+    async fn fetch_labels(&self, user_id: UserId) -> anyhow::Result<Vec<LabelDescription>> {
+        let labels = self.labels.lock().unwrap();
+
+        Ok(labels
+            .iter()
+            .filter(|l| l.user_id == user_id)
+            .map(|x| x.label_desc.clone())
+            .collect())
+    }
+
+    async fn fetch_label(
+        &self,
+        user_id: UserId,
+        label_id: &str,
+    ) -> anyhow::Result<Option<LabelDescription>> {
+        let labels = self.labels.lock().unwrap();
+
+        Ok(labels
+            .iter()
+            .find(|l| l.user_id == user_id && l.label_desc.label_id == label_id)
+            .map(|x| x.label_desc.clone()))
+    }
+
+    async fn create_label(&self, user_id: UserId, label: &LabelData) -> anyhow::Result<LabelId> {
+        let label_id = tasks::generate_random_task_id();
+
+        let mut labels = self.labels.lock().unwrap();
+
+        if labels.iter().any(|l| l.label_desc.label_id == label_id) {
+            return Err(anyhow::anyhow!("could not generate unique label id"));
+        }
+
+        labels.push(LabelStorage {
+            user_id,
+            label_desc: label.clone().into_description(label_id.clone()),
+        });
+
+        Ok(label_id)
+    }
+
+    async fn update_label(
+        &self,
+        user_id: UserId,
+        label_id: &str,
+        label: &LabelData,
+    ) -> anyhow::Result<bool> {
+        let mut labels = self.labels.lock().unwrap();
+
+        let Some(storage) = labels
+            .iter_mut()
+            .find(|l| l.user_id == user_id && l.label_desc.label_id == label_id)
+        else {
+            return Ok(false);
+        };
+
+        storage.label_desc = label.clone().into_description(label_id.to_string());
+        Ok(true)
+    }
+
+    async fn delete_label(&self, user_id: UserId, label_id: &str) -> anyhow::Result<bool> {
+        let mut labels = self.labels.lock().unwrap();
+
+        let Some(idx) = labels
+            .iter()
+            .position(|l| l.user_id == user_id && l.label_desc.label_id == label_id)
+        else {
+            return Ok(false);
+        };
+
+        labels.remove(idx);
+
+        let mut tasks = self.tasks.lock().unwrap();
+        let mut entities = Vec::new();
+
+        for task in tasks.iter_mut().filter(|t| t.user_id == user_id) {
+            let label_ids = &mut task.task_desc.label_ids;
+
+            if let Some(pos) = label_ids.iter().position(|id| id == label_id) {
+                label_ids.remove(pos);
+                task.task_desc.version += 1;
+                entities.push(SyncEntity::Task(task.task_desc.task_id.clone()));
+            }
+        }
+
+        self.sync.append(user_id, &entities);
+
+        Ok(true)
+    }
+
+    async fn set_label_assigned(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        label_id: &str,
+        assigned: bool,
+    ) -> anyhow::Result<Option<TaskDescription>> {
+        let mut tasks = self.tasks.lock().unwrap();
+
+        let Some(task) = tasks
+            .iter_mut()
+            .find(|t| t.user_id == user_id && t.task_desc.task_id == task_id)
+        else {
+            return Ok(None);
+        };
+
+        let label_ids = &mut task.task_desc.label_ids;
+
+        let changed = match (
+            label_ids.binary_search_by(|id| id.as_str().cmp(label_id)),
+            assigned,
+        ) {
+            (Err(pos), true) => {
+                label_ids.insert(pos, label_id.to_string());
+                true
+            }
+            (Ok(pos), false) => {
+                label_ids.remove(pos);
+                true
+            }
+            _ => false,
+        };
+
+        if changed {
+            task.task_desc.version += 1;
+            self.track_task(
+                user_id,
+                task_id,
+                WebhookEventKind::TaskUpdated(task_id.to_string()),
+            );
+        }
+
+        Ok(Some(task.task_desc.clone()))
+    }
+
+    async fn fetch_checklist(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+    ) -> anyhow::Result<Vec<ChecklistItem>> {
+        self.with_task(user_id, task_id, |task| task.checklist.clone())
+    }
+
+    async fn add_checklist_item(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        text: &str,
+    ) -> anyhow::Result<ChecklistItemId> {
+        let item_id = tasks::generate_random_task_id();
+
+        self.with_task(user_id, task_id, |task| {
+            task.checklist.push(ChecklistItem {
+                item_id: item_id.clone(),
+                text: text.to_string(),
+                done: false,
+                position: 0,
+            });
+            task.checklist_changed();
+            self.track_task(
+                user_id,
+                task_id,
+                WebhookEventKind::TaskUpdated(task_id.to_string()),
+            );
+        })?;
+
+        Ok(item_id)
+    }
+
+    async fn update_checklist_item(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        item_id: &str,
+        patch: &ChecklistItemPatch,
+    ) -> anyhow::Result<bool> {
+        self.with_task(user_id, task_id, |task| {
+            let Some(idx) = task.checklist.iter().position(|i| i.item_id == item_id) else {
+                return false;
+            };
+
+            let item = &mut task.checklist[idx];
+
+            if let Some(text) = &patch.text {
+                item.text = text.clone();
+            }
+
+            if let Some(done) = patch.done {
+                item.done = done;
+            }
+
+            if let Some(position) = patch.position {
+                let item = task.checklist.remove(idx);
+                let position = (position.max(0) as usize).min(task.checklist.len());
+                task.checklist.insert(position, item);
+            }
+
+            task.checklist_changed();
+            self.track_task(
+                user_id,
+                task_id,
+                WebhookEventKind::TaskUpdated(task_id.to_string()),
+            );
+            true
+        })
+    }
+
+    async fn delete_checklist_item(
+        &self,
+        user_id: UserId,
+        task_id: &str,
+        item_id: &str,
+    ) -> anyhow::Result<bool> {
+        self.with_task(user_id, task_id, |task| {
+            let Some(idx) = task.checklist.iter().position(|i| i.item_id == item_id) else {
+                return false;
+            };
+
+            task.checklist.remove(idx);
+            task.checklist_changed();
+            self.track_task(
+                user_id,
+                task_id,
+                WebhookEventKind::TaskUpdated(task_id.to_string()),
+            );
+            true
+        })
+    }
 }
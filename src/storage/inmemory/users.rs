@@ -1,10 +1,16 @@
 use std::{collections::HashMap, sync::Mutex};
 
-use crate::{app::repositories::UsersRepositry, model::UserId};
+use crate::{
+    app::repositories::UsersRepositry,
+    model::{calendar::CalendarToken, inbound::InboundToken, UserId, DEFAULT_TIMEZONE},
+};
 
 struct UserStorage {
     username: String,
     password: String,
+    timezone: String,
+    calendar_token: Option<CalendarToken>,
+    inbound_token: Option<InboundToken>,
 }
 
 struct MutableUsersStorage {
@@ -39,6 +45,9 @@ impl InMemoryUsers {
             UserStorage {
                 username: username.to_string(),
                 password: password.to_string(),
+                timezone: DEFAULT_TIMEZONE.to_string(),
+                calendar_token: None,
+                inbound_token: None,
             },
         );
 
@@ -71,6 +80,9 @@ impl UsersRepositry for InMemoryUsers {
             UserStorage {
                 username: username.to_string(),
                 password: password.to_string(),
+                timezone: DEFAULT_TIMEZONE.to_string(),
+                calendar_token: None,
+                inbound_token: None,
             },
         );
         users.users_by_name.insert(username.to_string(), user_id);
@@ -93,4 +105,99 @@ impl UsersRepositry for InMemoryUsers {
 
         Ok(Some((user_id, user.password.clone())))
     }
+
+    async fn get_timezone(&self, user_id: UserId) -> anyhow::Result<Option<String>> {
+        let users = self.users.lock().unwrap();
+
+        Ok(users.users_by_id.get(&user_id).map(|x| x.timezone.clone()))
+    }
+
+    async fn set_timezone(&self, user_id: UserId, timezone: &str) -> anyhow::Result<()> {
+        let mut users = self.users.lock().unwrap();
+
+        let Some(user) = users.users_by_id.get_mut(&user_id) else {
+            return Err(anyhow::anyhow!("no such user"));
+        };
+
+        user.timezone = timezone.to_string();
+        Ok(())
+    }
+
+    async fn get_calendar_token(&self, user_id: UserId) -> anyhow::Result<Option<CalendarToken>> {
+        let users = self.users.lock().unwrap();
+
+        Ok(users
+            .users_by_id
+            .get(&user_id)
+            .and_then(|x| x.calendar_token.clone()))
+    }
+
+    async fn set_calendar_token(
+        &self,
+        user_id: UserId,
+        token: Option<&CalendarToken>,
+    ) -> anyhow::Result<()> {
+        let mut users = self.users.lock().unwrap();
+
+        let Some(user) = users.users_by_id.get_mut(&user_id) else {
+            return Err(anyhow::anyhow!("no such user"));
+        };
+
+        user.calendar_token = token.cloned();
+        Ok(())
+    }
+
+    async fn find_user_by_calendar_token(
+        &self,
+        token: &CalendarToken,
+    ) -> anyhow::Result<Option<UserId>> {
+        let users = self.users.lock().unwrap();
+
+        Ok(users
+            .users_by_id
+            .iter()
+            .find(|(_, user)| user.calendar_token.as_ref() == Some(token))
+            .map(|(&user_id, _)| user_id))
+    }
+
+    async fn init_inbound_token(
+        &self,
+        user_id: UserId,
+        token: &InboundToken,
+    ) -> anyhow::Result<InboundToken> {
+        let mut users = self.users.lock().unwrap();
+
+        let Some(user) = users.users_by_id.get_mut(&user_id) else {
+            return Err(anyhow::anyhow!("no such user"));
+        };
+
+        Ok(user
+            .inbound_token
+            .get_or_insert_with(|| token.clone())
+            .clone())
+    }
+
+    async fn set_inbound_token(&self, user_id: UserId, token: &InboundToken) -> anyhow::Result<()> {
+        let mut users = self.users.lock().unwrap();
+
+        let Some(user) = users.users_by_id.get_mut(&user_id) else {
+            return Err(anyhow::anyhow!("no such user"));
+        };
+
+        user.inbound_token = Some(token.clone());
+        Ok(())
+    }
+
+    async fn find_user_by_inbound_token(
+        &self,
+        token: &InboundToken,
+    ) -> anyhow::Result<Option<UserId>> {
+        let users = self.users.lock().unwrap();
+
+        Ok(users
+            .users_by_id
+            .iter()
+            .find(|(_, user)| user.inbound_token.as_ref() == Some(token))
+            .map(|(&user_id, _)| user_id))
+    }
 }
@@ -0,0 +1,83 @@
+use std::sync::Mutex;
+
+use crate::{
+    app::repositories::ViewsRepository,
+    model::{
+        tasks,
+        views::{ViewData, ViewDescription, ViewId},
+        BoardId, UserId,
+    },
+};
+
+pub struct InMemoryViews {
+    views: Mutex<Vec<ViewDescription>>,
+}
+
+impl InMemoryViews {
+    pub fn new() -> Self {
+        Self {
+            views: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl ViewsRepository for InMemoryViews {
+    async fn fetch_views(&self, board_id: BoardId) -> anyhow::Result<Vec<ViewDescription>> {
+        let views = self.views.lock().unwrap();
+
+        let mut board_views: Vec<ViewDescription> = views
+            .iter()
+            .filter(|v| v.board_id == board_id)
+            .cloned()
+            .collect();
+        board_views.sort_by(|a, b| a.data.name.cmp(&b.data.name));
+
+        Ok(board_views)
+    }
+
+    async fn fetch_view(&self, view_id: &str) -> anyhow::Result<Option<ViewDescription>> {
+        let views = self.views.lock().unwrap();
+
+        Ok(views.iter().find(|v| v.view_id == view_id).cloned())
+    }
+
+    async fn create_view(
+        &self,
+        board_id: BoardId,
+        owner_id: UserId,
+        data: &ViewData,
+    ) -> anyhow::Result<ViewId> {
+        let view_id = tasks::generate_random_task_id();
+
+        self.views.lock().unwrap().push(ViewDescription {
+            view_id: view_id.clone(),
+            board_id,
+            owner_id,
+            data: data.clone(),
+        });
+
+        Ok(view_id)
+    }
+
+    async fn update_view(&self, view_id: &str, data: &ViewData) -> anyhow::Result<bool> {
+        let mut views = self.views.lock().unwrap();
+
+        let Some(view) = views.iter_mut().find(|v| v.view_id == view_id) else {
+            return Ok(false);
+        };
+
+        view.data = data.clone();
+
+        Ok(true)
+    }
+
+    async fn delete_view(&self, view_id: &str) -> anyhow::Result<bool> {
+        let mut views = self.views.lock().unwrap();
+
+        let count = views.len();
+        views.retain(|v| v.view_id != view_id);
+
+        Ok(views.len() != count)
+    }
+}
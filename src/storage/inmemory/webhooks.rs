@@ -0,0 +1,240 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    app::repositories::WebhooksRepository,
+    model::{
+        tasks,
+        webhooks::{
+            DeliveryId, DeliveryStatus, WebhookData, WebhookDelivery, WebhookDescription,
+            WebhookEvent, WebhookEventId, WebhookEventKind, WebhookId,
+        },
+        BoardId,
+    },
+};
+
+struct RecordedEvent {
+    event: WebhookEvent,
+    claimed_until: Option<DateTime<Utc>>,
+}
+
+pub struct InMemoryWebhooks {
+    webhooks: Mutex<Vec<WebhookDescription>>,
+    /// Deliveries in the order they were queued.
+    deliveries: Mutex<Vec<WebhookDelivery>>,
+    /// Events in the order they were recorded. Locked after `webhooks`.
+    events: Mutex<Vec<RecordedEvent>>,
+    next_event_id: Mutex<WebhookEventId>,
+}
+
+impl InMemoryWebhooks {
+    pub fn new() -> Self {
+        Self {
+            webhooks: Mutex::new(Vec::new()),
+            deliveries: Mutex::new(Vec::new()),
+            events: Mutex::new(Vec::new()),
+            next_event_id: Mutex::new(1),
+        }
+    }
+
+    /// Records the events for the webhooks of the board. Nothing is recorded if the board has no webhooks.
+    pub(super) fn record_events(&self, board_id: BoardId, events: &[WebhookEventKind]) {
+        let webhooks = self.webhooks.lock().unwrap();
+        if !webhooks.iter().any(|w| w.board_id == board_id) {
+            return;
+        }
+
+        let mut recorded = self.events.lock().unwrap();
+        let mut next_event_id = self.next_event_id.lock().unwrap();
+        let now = Utc::now();
+
+        for kind in events {
+            recorded.push(RecordedEvent {
+                event: WebhookEvent {
+                    event_id: *next_event_id,
+                    board_id,
+                    kind: kind.clone(),
+                    created_at: now,
+                },
+                claimed_until: None,
+            });
+            *next_event_id += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl WebhooksRepository for InMemoryWebhooks {
+    async fn fetch_webhooks(&self, board_id: BoardId) -> anyhow::Result<Vec<WebhookDescription>> {
+        let webhooks = self.webhooks.lock().unwrap();
+
+        Ok(webhooks
+            .iter()
+            .filter(|w| w.board_id == board_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn fetch_webhook(&self, webhook_id: &str) -> anyhow::Result<Option<WebhookDescription>> {
+        let webhooks = self.webhooks.lock().unwrap();
+
+        Ok(webhooks
+            .iter()
+            .find(|w| w.webhook_id == webhook_id)
+            .cloned())
+    }
+
+    async fn create_webhook(
+        &self,
+        board_id: BoardId,
+        data: &WebhookData,
+    ) -> anyhow::Result<WebhookId> {
+        let webhook_id = tasks::generate_random_task_id();
+
+        self.webhooks.lock().unwrap().push(WebhookDescription {
+            webhook_id: webhook_id.clone(),
+            board_id,
+            data: data.clone(),
+        });
+
+        Ok(webhook_id)
+    }
+
+    async fn delete_webhook(&self, webhook_id: &str) -> anyhow::Result<bool> {
+        let mut webhooks = self.webhooks.lock().unwrap();
+
+        let count = webhooks.len();
+        webhooks.retain(|w| w.webhook_id != webhook_id);
+
+        if webhooks.len() == count {
+            return Ok(false);
+        }
+
+        let mut deliveries = self.deliveries.lock().unwrap();
+        deliveries.retain(|d| d.webhook_id != webhook_id);
+
+        Ok(true)
+    }
+
+    async fn enqueue_delivery(
+        &self,
+        webhook_id: &str,
+        event_type: &str,
+        payload: &str,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<DeliveryId> {
+        let delivery_id = tasks::generate_random_task_id();
+
+        self.deliveries.lock().unwrap().push(WebhookDelivery {
+            delivery_id: delivery_id.clone(),
+            webhook_id: webhook_id.to_string(),
+            event_type: event_type.to_string(),
+            payload: payload.to_string(),
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: Some(now),
+            last_status_code: None,
+            last_error: None,
+            created_at: now,
+        });
+
+        Ok(delivery_id)
+    }
+
+    async fn claim_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<WebhookDelivery>> {
+        let mut deliveries = self.deliveries.lock().unwrap();
+
+        let mut due: Vec<&mut WebhookDelivery> = deliveries
+            .iter_mut()
+            .filter(|d| {
+                d.status == DeliveryStatus::Pending
+                    && d.next_attempt_at.is_some_and(|next| next <= now)
+            })
+            .collect();
+        due.sort_by_key(|d| d.next_attempt_at);
+
+        Ok(due
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|delivery| {
+                let claimed = delivery.clone();
+                delivery.next_attempt_at = Some(lease_until);
+                claimed
+            })
+            .collect())
+    }
+
+    async fn update_delivery(&self, delivery: &WebhookDelivery) -> anyhow::Result<()> {
+        let mut deliveries = self.deliveries.lock().unwrap();
+
+        if let Some(existing) = deliveries
+            .iter_mut()
+            .find(|d| d.delivery_id == delivery.delivery_id)
+        {
+            *existing = delivery.clone();
+        }
+
+        Ok(())
+    }
+
+    async fn fetch_deliveries(
+        &self,
+        webhook_id: &str,
+        limit: i64,
+    ) -> anyhow::Result<Vec<WebhookDelivery>> {
+        let deliveries = self.deliveries.lock().unwrap();
+
+        Ok(deliveries
+            .iter()
+            .rev()
+            .filter(|d| d.webhook_id == webhook_id)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn delete_finished_deliveries(
+        &self,
+        created_before: DateTime<Utc>,
+    ) -> anyhow::Result<u64> {
+        let mut deliveries = self.deliveries.lock().unwrap();
+
+        let count = deliveries.len();
+        deliveries
+            .retain(|d| d.status == DeliveryStatus::Pending || d.created_at >= created_before);
+
+        Ok((count - deliveries.len()) as u64)
+    }
+
+    async fn claim_events(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<WebhookEvent>> {
+        let mut events = self.events.lock().unwrap();
+
+        Ok(events
+            .iter_mut()
+            .filter(|e| e.claimed_until.is_none_or(|until| until <= now))
+            .take(limit.max(0) as usize)
+            .map(|recorded| {
+                recorded.claimed_until = Some(lease_until);
+                recorded.event.clone()
+            })
+            .collect())
+    }
+
+    async fn delete_event(&self, event_id: WebhookEventId) -> anyhow::Result<()> {
+        let mut events = self.events.lock().unwrap();
+        events.retain(|e| e.event.event_id != event_id);
+
+        Ok(())
+    }
+}
@@ -1,2 +1,4 @@
+pub mod blobs;
 pub mod db;
 pub mod inmemory;
+pub mod webhooks;
@@ -0,0 +1,235 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect, Client, Url,
+};
+use tokio::net::lookup_host;
+
+use crate::app::repositories::WebhookSender;
+
+/// How long a receiver may take to respond before the attempt fails.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Posts the deliveries over HTTP. Redirects are not followed, so a receiver
+/// that responds with a redirection has not accepted the delivery.
+///
+/// The requests reach only the addresses accepted by the filter. The host is resolved again
+/// for each request, so that it cannot be pointed to an internal service once the webhook is created.
+pub struct HttpWebhookSender {
+    client: Client,
+    is_allowed: fn(&IpAddr) -> bool,
+}
+
+impl HttpWebhookSender {
+    /// Sender that reaches only the global addresses, not the loopback, private,
+    /// link-local (including the cloud metadata endpoint) or other special-purpose ones.
+    pub fn new() -> Self {
+        Self::with_address_filter(is_public_address)
+    }
+
+    pub fn with_address_filter(is_allowed: fn(&IpAddr) -> bool) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .redirect(redirect::Policy::none())
+                .dns_resolver(Arc::new(FilteringResolver { is_allowed }))
+                .build()
+                .expect("webhook HTTP client is configured correctly"),
+            is_allowed,
+        }
+    }
+}
+
+#[async_trait]
+impl WebhookSender for HttpWebhookSender {
+    async fn is_allowed_url(&self, url: &Url) -> bool {
+        let Some(host) = host(url) else {
+            return false;
+        };
+
+        match lookup_host((host, 0)).await {
+            Ok(addrs) => {
+                let addrs: Vec<IpAddr> = addrs.map(|addr| addr.ip()).collect();
+                !addrs.is_empty() && addrs.iter().all(self.is_allowed)
+            }
+            Err(_) => false,
+        }
+    }
+
+    async fn post(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+        body: &str,
+    ) -> anyhow::Result<u16> {
+        let url = Url::parse(url)?;
+
+        // The resolver is not asked for the addresses written in the URL.
+        if let Some(ip) = host(&url).and_then(|host| host.parse::<IpAddr>().ok()) {
+            if !(self.is_allowed)(&ip) {
+                return Err(anyhow!("address {} is not allowed", ip));
+            }
+        }
+
+        let mut request = self.client.post(url).body(body.to_string());
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+
+        let response = request.send().await?;
+
+        Ok(response.status().as_u16())
+    }
+}
+
+/// Host of the URL, without the brackets around an IPv6 address.
+fn host(url: &Url) -> Option<&str> {
+    url.host_str()
+        .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+}
+
+/// Returns whether the address is reachable on the internet, rather than being loopback,
+/// private, link-local, shared (CGNAT), unique local or reserved for another purpose.
+fn is_public_address(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_ipv4(&mapped),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: &Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+
+    !(a == 0 // "This network", including the unspecified address.
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || (a == 100 && (64..128).contains(&b)) // Shared address space (CGNAT), 100.64.0.0/10.
+        || (a == 192 && b == 0 && c == 0) // IETF protocol assignments, 192.0.0.0/24.
+        || (a == 198 && (18..20).contains(&b)) // Benchmarking, 198.18.0.0/15.
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a >= 240) // Reserved, including the broadcast address.
+}
+
+fn is_public_ipv6(ip: &Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || (first & 0xfe00) == 0xfc00 // Unique local, fc00::/7.
+        || (first & 0xffc0) == 0xfe80 // Link-local, fe80::/10.
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8) // Documentation, 2001:db8::/32.
+        || ip.is_multicast())
+}
+
+/// Resolves the hosts of the requests, leaving out the addresses that are not allowed.
+struct FilteringResolver {
+    is_allowed: fn(&IpAddr) -> bool,
+}
+
+impl Resolve for FilteringResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let is_allowed = self.is_allowed;
+
+        Box::pin(async move {
+            let addrs: Vec<_> = lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_allowed(&addr.ip()))
+                .collect();
+
+            if addrs.is_empty() {
+                return Err(format!("{} has no allowed address", name.as_str()).into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Url;
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    use crate::app::repositories::WebhookSender;
+
+    use super::HttpWebhookSender;
+
+    #[tokio::test]
+    async fn internal_addresses_are_not_allowed() -> anyhow::Result<()> {
+        let sender = HttpWebhookSender::new();
+
+        for url in [
+            "http://127.0.0.1/hooks",
+            "http://localhost:8080/hooks",
+            "http://10.0.0.1/",
+            "http://172.16.5.4/",
+            "http://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://100.64.0.1/",
+            "http://0.0.0.0/",
+            "http://[::1]/",
+            "http://[fd00::1]/",
+            "http://[fe80::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://[::ffff:10.1.2.3]/",
+            "http://198.18.0.1/",
+            "http://255.255.255.255/",
+            "http://224.0.0.1/",
+            "http://[::]/",
+            "http://[fc00::1]/",
+            "http://[ff02::1]/",
+            "http://[2001:db8::1]/",
+        ] {
+            assert!(!sender.is_allowed_url(&Url::parse(url)?).await, "{}", url);
+        }
+
+        assert!(
+            sender
+                .is_allowed_url(&Url::parse("https://1.1.1.1/hooks")?)
+                .await
+        );
+        assert!(
+            sender
+                .is_allowed_url(&Url::parse("https://[2606:4700:4700::1111]/")?)
+                .await
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn requests_to_internal_addresses_are_not_sent() -> anyhow::Result<()> {
+        let receiver = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&receiver)
+            .await;
+
+        let sender = HttpWebhookSender::new();
+        let port = receiver.address().port();
+
+        // Refused both when the address is in the URL and when it is resolved from the host.
+        assert!(sender.post(&receiver.uri(), &[], "{}").await.is_err());
+        assert!(sender
+            .post(&format!("http://localhost:{}/", port), &[], "{}")
+            .await
+            .is_err());
+
+        let sender = HttpWebhookSender::with_address_filter(|_| true);
+        assert_eq!(sender.post(&receiver.uri(), &[], "{}").await?, 204);
+
+        Ok(())
+    }
+}